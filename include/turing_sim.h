@@ -0,0 +1,66 @@
+/*
+ * A C interface to turing-sim-rs, built into its library with
+ *
+ *     cargo build --release --features capi
+ *
+ * as target/release/libturing_sim_rs.so (.dylib on macOS, .dll on
+ * Windows). See src/capi.rs.
+ *
+ * A machine in the standard format, such as "1RB1LB_1LA1RZ", becomes a
+ * handle with ts_machine_new, which runs it from a blank tape. It's run
+ * with ts_machine_run, up to a limit each time, read with the others, and
+ * given back with ts_machine_free. Every function but ts_machine_new and
+ * ts_machine_free takes a handle ts_machine_new returned that hasn't been
+ * freed yet. Handles are independent, but each must only be used from one
+ * thread at a time.
+ */
+#ifndef TURING_SIM_H
+#define TURING_SIM_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct TsMachine TsMachine;
+
+/*
+ * A handle for a machine of 1 to 6 states given in the standard format as
+ * a NUL-terminated string, or NULL if it isn't one.
+ */
+TsMachine *ts_machine_new(const char *machine);
+
+/* Gives back a handle. NULL is ignored. */
+void ts_machine_free(TsMachine *machine);
+
+/*
+ * Runs for at most limit more steps, stopping early if the machine halts,
+ * and returns the steps taken.
+ */
+uint64_t ts_machine_run(TsMachine *machine, uint64_t limit);
+
+/* Steps taken since the machine was made. */
+uint64_t ts_machine_steps(const TsMachine *machine);
+
+/* The state as its letter, 'A' for the first, or 'Z' once it's halted. */
+char ts_machine_state(const TsMachine *machine);
+
+/* The tape index of the cell under the head, 0 being where it started. */
+int64_t ts_machine_head(const TsMachine *machine);
+
+/* The ones on the tape. */
+uint64_t ts_machine_ones(const TsMachine *machine);
+
+/*
+ * Writes the length cells from tape index start on to cells, 0 or 1 each,
+ * which must have room for them. Cells the head hasn't been to are 0.
+ */
+void ts_machine_tape(const TsMachine *machine, int64_t start, size_t length, uint8_t *cells);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
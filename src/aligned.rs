@@ -0,0 +1,182 @@
+use std::alloc::Layout;
+use std::ops::Deref;
+use std::ops::DerefMut;
+use std::ptr::NonNull;
+
+// The words of a chunk of tape, in memory aligned to a given number of bytes.
+// A Vec can only be aligned to its element type, which for tapes of tens of
+// gigabytes leaves every chunk straddling pages, and the kernel only backs
+// the 2 MiB aligned stretches of a mapping with huge pages. This is as much
+// of a Vec as a chunk needs, pushing, growing and being read and written as
+// a slice, over an allocation with the alignment asked for.
+pub struct AlignedVec<T: Copy> {
+    ptr: NonNull<T>,
+    len: usize,
+    capacity: usize,
+    align: usize,
+    huge_pages: bool,
+}
+
+// Owned like a Vec's buffer
+unsafe impl<T: Copy + Send> Send for AlignedVec<T> {}
+unsafe impl<T: Copy + Sync> Sync for AlignedVec<T> {}
+
+// What the kernel uses for transparent huge pages on x86-64 and most of
+// aarch64
+pub const HUGE_PAGE: usize = 2 << 20;
+
+impl<T: Copy> AlignedVec<T> {
+    // Room for `capacity` words at `align` bytes, or T's own alignment if
+    // that's more. With `huge_pages`, the kernel is asked to back the
+    // allocation with them where it can, see `advise_huge_pages`.
+    pub fn with_capacity(capacity: usize, align: usize, huge_pages: bool) -> AlignedVec<T> {
+        assert!(size_of::<T>() > 0);
+        let align = align.max(align_of::<T>());
+        let mut vec = AlignedVec {
+            ptr: NonNull::dangling(),
+            len: 0,
+            capacity: 0,
+            align,
+            huge_pages,
+        };
+        vec.reallocate(capacity);
+        vec
+    }
+
+    pub fn from_slice(words: &[T], align: usize, huge_pages: bool) -> AlignedVec<T> {
+        let mut vec = AlignedVec::with_capacity(words.len(), align, huge_pages);
+        for &word in words {
+            vec.push(word);
+        }
+        vec
+    }
+
+    // Allocations smaller than the alignment, such as a first chunk that has
+    // only just started growing, are only aligned to T, so as not to waste
+    // most of an aligned block on them
+    fn layout(&self, capacity: usize) -> Layout {
+        let layout = Layout::array::<T>(capacity).expect("chunk too large");
+        match layout.size() >= self.align {
+            true => layout.align_to(self.align).expect("chunk too large"),
+            false => layout,
+        }
+    }
+
+    // Moves the words to an allocation of `capacity`, which holds them all
+    fn reallocate(&mut self, capacity: usize) {
+        assert!(capacity >= self.len);
+        if capacity == self.capacity {
+            return;
+        }
+        let ptr = if capacity == 0 {
+            NonNull::dangling()
+        } else {
+            let layout = self.layout(capacity);
+            // Safety: the layout isn't zero sized
+            let ptr = unsafe { std::alloc::alloc(layout) } as *mut T;
+            let Some(ptr) = NonNull::new(ptr) else {
+                std::alloc::handle_alloc_error(layout);
+            };
+            if self.huge_pages {
+                advise_huge_pages(ptr.as_ptr() as *mut u8, layout.size());
+            }
+            // Safety: both hold at least `len` words and are separate
+            unsafe { std::ptr::copy_nonoverlapping(self.ptr.as_ptr(), ptr.as_ptr(), self.len) };
+            ptr
+        };
+        self.free();
+        self.ptr = ptr;
+        self.capacity = capacity;
+    }
+
+    fn free(&mut self) {
+        if self.capacity > 0 {
+            // Safety: allocated in `reallocate` with this layout
+            unsafe {
+                std::alloc::dealloc(self.ptr.as_ptr() as *mut u8, self.layout(self.capacity))
+            };
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn reserve_exact(&mut self, more: usize) {
+        let needed = self.len.checked_add(more).expect("chunk too large");
+        if needed > self.capacity {
+            self.reallocate(needed);
+        }
+    }
+
+    // Doubles the allocation when it's full, as a Vec does
+    pub fn push(&mut self, value: T) {
+        if self.len == self.capacity {
+            let doubled = self.capacity.checked_mul(2).expect("chunk too large");
+            self.reallocate(doubled.max(1));
+        }
+        // Safety: within the allocation, past the words written so far
+        unsafe { self.ptr.as_ptr().add(self.len).write(value) };
+        self.len += 1;
+    }
+}
+
+impl<T: Copy> Drop for AlignedVec<T> {
+    fn drop(&mut self) {
+        self.free();
+    }
+}
+
+impl<T: Copy> Clone for AlignedVec<T> {
+    fn clone(&self) -> Self {
+        let mut vec = AlignedVec::with_capacity(self.capacity, self.align, self.huge_pages);
+        vec.len = self.len;
+        // Safety: the new allocation holds as many words and is separate
+        unsafe { std::ptr::copy_nonoverlapping(self.ptr.as_ptr(), vec.ptr.as_ptr(), self.len) };
+        vec
+    }
+}
+
+impl<T: Copy> Deref for AlignedVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        // Safety: the first `len` words have been written
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T: Copy> DerefMut for AlignedVec<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        // Safety: as for `deref`, and the words are only reachable through
+        // this
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+// Asks the kernel to back the whole huge pages within `len` bytes from
+// `start` with huge pages, which for a tape that's swept over and over cuts
+// the TLB misses of each sweep and the page faults of growing it. It's only
+// advice and does nothing where transparent huge pages are off, and nothing
+// at all but on Linux.
+#[cfg(target_os = "linux")]
+fn advise_huge_pages(start: *mut u8, len: usize) {
+    extern "C" {
+        fn madvise(addr: *mut u8, len: usize, advice: i32) -> i32;
+    }
+    const MADV_HUGEPAGE: i32 = 14;
+    // madvise wants whole pages, and only whole huge pages can be backed
+    let offset = start.align_offset(HUGE_PAGE);
+    if offset >= len {
+        return;
+    }
+    let len = (len - offset) / HUGE_PAGE * HUGE_PAGE;
+    if len > 0 {
+        // Safety: the range is within an allocation of ours, and the advice
+        // doesn't change its contents
+        unsafe { madvise(start.add(offset), len, MADV_HUGEPAGE) };
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn advise_huge_pages(_start: *mut u8, _len: usize) {}
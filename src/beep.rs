@@ -0,0 +1,143 @@
+use crate::counter::CounterAbstraction;
+use crate::decider::Decider;
+use crate::decider::Proof;
+use crate::decider::TranslatedCycler;
+use crate::decider::Verdict;
+use crate::enumerate::PartialMachine;
+use crate::holdouts::Chain;
+use crate::holdouts::ChainRun;
+use crate::standard::state_letter;
+
+// Beeping busy beavers: a machine beeps whenever it takes a marked
+// transition, and quasi-halts once one of them has fired for the last time.
+// That can happen while it runs on forever, so unlike halting it can't be
+// seen by running the machine, only proven. Here that's done by finding a
+// point after which the machine only repeats itself, either as a translated
+// cycler, by a counter abstraction rule, or by sweeping into blank tape. A
+// marked transition that hasn't fired since that point never will again.
+pub enum Beeps {
+    Halted {
+        steps: u128,
+        sigma: u64,
+    },
+    // The first marked transition to fall silent, and the step it last fired
+    QuasiHalted {
+        steps: u128,
+        transition: (usize, u8),
+    },
+    // Every marked transition that has fired keeps firing
+    Forever,
+    Undecided,
+}
+
+pub struct BeepRun {
+    pub outcome: Beeps,
+    // The step each marked transition last fired at, if it ever did
+    pub last: Vec<((usize, u8), Option<u128>)>,
+}
+
+pub fn format_transition((state, read): (usize, u8)) -> String {
+    format!("{}{}", state_letter(state), read)
+}
+
+// Transitions written as a state letter and the symbol read, such as B1
+pub fn parse_transitions(s: &str, states: usize) -> Result<Vec<(usize, u8)>, String> {
+    s.split(',')
+        .map(|transition| match transition.as_bytes() {
+            &[letter @ b'A'..=b'Z', read @ (b'0' | b'1')]
+                if ((letter - b'A') as usize) < states =>
+            {
+                Ok(((letter - b'A') as usize, read - b'0'))
+            }
+            _ => Err(format!("bad transition {}", transition)),
+        })
+        .collect()
+}
+
+// Runs the machine for up to `limit` steps, beeping on the marked
+// transitions
+pub fn run_beeping<const N: usize>(
+    machine: &PartialMachine<N>,
+    marked: &[(usize, u8)],
+    limit: u64,
+) -> BeepRun {
+    // Where the machine starts repeating itself and how often, in steps or
+    // chain steps
+    let cycler = TranslatedCycler { limit }.decide(machine);
+    let counter = CounterAbstraction {
+        limit,
+        max_period: 1_000,
+    };
+    let repeats = match cycler {
+        Verdict::NonHalting(Proof::TranslatedCycler { start, period, .. }) => {
+            Some((start, period, false))
+        }
+        _ => match counter.decide(machine) {
+            Verdict::NonHalting(Proof::CounterAbstraction { start, period, .. }) => {
+                Some((start, period, true))
+            }
+            _ => None,
+        },
+    };
+
+    let mut run = ChainRun::new(machine);
+    let mut last: Vec<((usize, u8), Option<u128>)> = marked.iter().map(|&t| (t, None)).collect();
+    // The chain step each transition last fired at
+    let mut last_chain = vec![0; marked.len()];
+    let mut escaped = None;
+    let outcome = loop {
+        // Everything a marked transition does after the start happens
+        // within the first period of it
+        let passed = match repeats {
+            Some((start, period, false)) => run.steps >= (start + period) as u128,
+            Some((start, period, true)) => run.chain_steps >= start + period,
+            None => run.steps >= limit as u128,
+        };
+        if passed || escaped.is_some() {
+            break None;
+        }
+        let transition = (run.state, run.tape.head);
+        let result = run.step();
+        if matches!(result, Chain::Escaped) {
+            // The same transition over and over from here on
+            escaped = Some(transition);
+            continue;
+        }
+        for (i, (marked, fired)) in last.iter_mut().enumerate() {
+            if *marked == transition {
+                *fired = Some(run.steps);
+                last_chain[i] = run.chain_steps;
+            }
+        }
+        match result {
+            Chain::Running | Chain::Escaped => {}
+            Chain::Halted => {
+                break Some(Beeps::Halted {
+                    steps: run.steps,
+                    sigma: run.tape.ones(),
+                })
+            }
+        }
+    };
+    let outcome = outcome.unwrap_or_else(|| {
+        // Which marked transitions have gone silent for good
+        let silent = |i: usize| match (escaped, repeats) {
+            (Some(transition), _) => last[i].0 != transition,
+            (None, Some((start, _, false))) => last[i].1.is_some_and(|step| step <= start as u128),
+            (None, Some((start, _, true))) => last_chain[i] <= start,
+            (None, None) => false,
+        };
+        let first = (0..last.len())
+            .filter(|&i| last[i].1.is_some() && silent(i))
+            .min_by_key(|&i| last[i].1);
+        match first {
+            Some(i) => Beeps::QuasiHalted {
+                steps: last[i].1.unwrap(),
+                transition: last[i].0,
+            },
+            None if escaped.is_some() || repeats.is_some() => Beeps::Forever,
+            None => Beeps::Undecided,
+        }
+    });
+    BeepRun { outcome, last }
+}
@@ -0,0 +1,180 @@
+use std::time::Duration;
+use std::time::Instant;
+
+use num_traits::PrimInt;
+use num_traits::Unsigned;
+
+use crate::enumerate::PartialMachine;
+use crate::reachable::Reachability;
+use crate::CompiledTuringMachine;
+use crate::DisplayOptions;
+use crate::State::*;
+use crate::Tape;
+
+// A fixed set of machines to time the simulator on, so that changes to the
+// step loop, the tape or the LUT can be compared run to run. Each is run for
+// its number of steps, or until it halts if that's sooner, and the kinds
+// cover the shapes of run that stress different parts: champions that halt
+// after a long time, cyclers that stay put, bouncers and translated cyclers
+// that sweep the tape and grow it, and counters that grow it slowly.
+pub struct Benchmark {
+    pub name: String,
+    pub kind: String,
+    pub machine: String,
+    pub steps: u64,
+}
+
+// A line per machine, of its name, kind, the machine and the steps to run
+pub const CORPUS: &str = "\
+bb2 champion 1RB1LB_1LA1RZ 6
+bb3 champion 1RB1RZ_1LB0RC_1LC1LA 21
+bb4 champion 1RB1LB_1LA0LC_1RZ1LD_1RD0RA 107
+bb5 champion 1RB1LC_1RC1RB_1RD0LE_1LA1LD_1RZ0LA 47176870
+cycler oscillator 1RB0RB_1LA1LA 10000000
+translated oscillator 1RB0LA_1LA1RB 10000000
+bouncer oscillator 1RB0LB_1LA0RA 10000000
+binary counter 1RB1LA_0LA0RB 10000000
+";
+
+// Reads a corpus in the format of `CORPUS`, skipping blank lines and ones
+// starting with #
+pub fn load_corpus(text: &str) -> Result<Vec<Benchmark>, String> {
+    let mut corpus = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.split_whitespace().collect::<Vec<_>>()[..] {
+            [name, kind, machine, steps] => corpus.push(Benchmark {
+                name: name.to_string(),
+                kind: kind.to_string(),
+                machine: machine.to_string(),
+                steps: steps
+                    .parse()
+                    .map_err(|_| format!("line {}: bad step count {}", i + 1, steps))?,
+            }),
+            _ => {
+                return Err(format!(
+                    "line {}: expected a name, kind, machine and step count",
+                    i + 1
+                ))
+            }
+        }
+    }
+    Ok(corpus)
+}
+
+pub fn corpus() -> Vec<Benchmark> {
+    load_corpus(CORPUS).expect("the built in corpus parses")
+}
+
+// Repeats `run` until it's taken at least `at_least`, and returns the number
+// of times it ran and how long that took, so that short runs are timed over
+// enough of them to be measured
+pub fn time(at_least: Duration, mut run: impl FnMut()) -> (u64, Duration) {
+    let start = Instant::now();
+    let mut runs = 0;
+    loop {
+        run();
+        runs += 1;
+        let elapsed = start.elapsed();
+        if elapsed >= at_least {
+            return (runs, elapsed);
+        }
+    }
+}
+
+// Steps one at a time through `TuringMachine::step`, on a tape of words of
+// T, returning the steps taken
+pub fn run_stepwise<T: Unsigned + PrimInt, const N: usize>(
+    machine: &PartialMachine<N>,
+    steps: u64,
+) -> u64 {
+    let mut tm = machine.to_machine();
+    let mut tape = Tape::<T>::new();
+    let mut taken = 0;
+    while let Index(state) = tm.state {
+        if taken == steps {
+            break;
+        }
+        tm.step(&mut tape, state);
+        taken += 1;
+    }
+    taken
+}
+
+// Runs through the flattened table, as headless runs do
+pub fn run_flat<T: Unsigned + PrimInt, const N: usize>(
+    machine: &PartialMachine<N>,
+    steps: u64,
+) -> u64 {
+    let mut tm = machine.to_machine();
+    let mut tape = Tape::<T>::new();
+    tm.run_limited(&mut tape, steps).unwrap_or(steps)
+}
+
+// Runs a compiled machine from the start on a blank tape, a word at a time,
+// for about `steps` of them
+pub fn run_lut<T: Unsigned + PrimInt, const N: usize>(
+    compiled: &mut CompiledTuringMachine<T, N>,
+    steps: u64,
+) {
+    compiled.tm.state = Index(0);
+    let mut tape = Tape::<T>::new();
+    std::hint::black_box(compiled.run_limited(&mut tape, steps));
+}
+
+// Whether a run through the LUT for `steps` ends in the same state, with the
+// same tape and head, as one through the step loop, for a halting machine
+pub fn lut_agrees<T: Unsigned + PrimInt, const N: usize>(
+    machine: &PartialMachine<N>,
+    compiled: &mut CompiledTuringMachine<T, N>,
+    steps: u64,
+) -> bool {
+    let mut tm = machine.to_machine();
+    let mut tape = Tape::<T>::new();
+    tm.run_limited(&mut tape, steps);
+    compiled.tm.state = Index(0);
+    let mut words = Tape::<T>::new();
+    compiled.run_limited(&mut words, steps);
+    tm.state == compiled.tm.state
+        && tape.head_index() == words.head_index()
+        && tape.right == words.right
+        && tape.left == words.left
+}
+
+// Builds the LUT for words of T
+pub fn build_lut<T: Unsigned + PrimInt, const N: usize>(machine: &PartialMachine<N>) {
+    std::hint::black_box(machine.to_machine().compile::<T>());
+}
+
+// Verbose runs print a frame per step, so they're only timed over the first
+// VERBOSE_STEPS of each machine, into a sink so only the formatting counts
+pub const VERBOSE_STEPS: u64 = 100_000;
+
+// Runs with the verbose output, as main prints it, returning the steps
+// taken
+pub fn run_verbose<T: Unsigned + PrimInt, const N: usize>(
+    machine: &PartialMachine<N>,
+    steps: u64,
+    options: &DisplayOptions,
+) -> u64 {
+    let mut tm = machine.to_machine();
+    let mut tape = Tape::<T>::new();
+    tm.run_verbose_into(
+        &mut std::io::sink(),
+        &mut tape,
+        options,
+        steps.min(VERBOSE_STEPS),
+    )
+    .unwrap()
+}
+
+// Builds only the entries of the LUT reachable from a blank tape, returning
+// how many that was out of how many there are
+pub fn build_reachable_lut<T: Unsigned + PrimInt, const N: usize>(
+    machine: &PartialMachine<N>,
+) -> Reachability {
+    std::hint::black_box(machine.to_machine().compile_reachable::<T>()).1
+}
@@ -0,0 +1,201 @@
+use std::collections::VecDeque;
+
+use crate::wide::WideMachine;
+use crate::wide::WideStep;
+
+// Brainfuck, compiled to a Turing machine over 256 symbols with a cell of
+// the tape for each cell of the program's. There's no input, and output is
+// only kept by running the program directly, so the machine has to be
+// compared with it by the cells it leaves.
+#[derive(Clone, Copy)]
+pub enum Op {
+    // The runs of + and - in a row, added up
+    Add(u8),
+    Move(bool),
+    // Where the matching bracket is
+    Open(usize),
+    Close(usize),
+    Output,
+}
+
+pub fn parse_bf(text: &str) -> Result<Vec<Op>, String> {
+    let mut ops: Vec<Op> = Vec::new();
+    let mut open = Vec::new();
+    for c in text.chars() {
+        let op = match c {
+            '+' | '-' => {
+                let add = if c == '+' { 1 } else { u8::MAX };
+                if let Some(Op::Add(sum)) = ops.last_mut() {
+                    *sum = sum.wrapping_add(add);
+                    continue;
+                }
+                Op::Add(add)
+            }
+            '>' => Op::Move(true),
+            '<' => Op::Move(false),
+            '[' => {
+                open.push(ops.len());
+                Op::Open(0)
+            }
+            ']' => {
+                let start = open.pop().ok_or("a ] without a [")?;
+                ops[start] = Op::Open(ops.len());
+                Op::Close(start)
+            }
+            '.' => Op::Output,
+            ',' => return Err(String::from("programs can't read input")),
+            _ => continue,
+        };
+        ops.push(op);
+    }
+    match open.is_empty() {
+        true => Ok(ops),
+        false => Err(String::from("a [ without a ]")),
+    }
+}
+
+// The result of running a program directly
+pub struct BfRun {
+    pub cells: Vec<u8>,
+    pub output: Vec<u8>,
+    pub steps: u64,
+}
+
+// Runs a program for up to `limit` operations, or None if it's still going
+pub fn run_bf(ops: &[Op], limit: u64) -> Result<Option<BfRun>, String> {
+    let mut run = BfRun {
+        cells: vec![0],
+        output: Vec::new(),
+        steps: 0,
+    };
+    let mut pointer = 0;
+    let mut at = 0;
+    while at < ops.len() {
+        if run.steps == limit {
+            return Ok(None);
+        }
+        run.steps += 1;
+        let cell = run.cells[pointer];
+        at = match ops[at] {
+            Op::Add(add) => {
+                run.cells[pointer] = cell.wrapping_add(add);
+                at + 1
+            }
+            Op::Move(true) => {
+                pointer += 1;
+                if pointer == run.cells.len() {
+                    run.cells.push(0);
+                }
+                at + 1
+            }
+            Op::Move(false) => {
+                pointer = pointer
+                    .checked_sub(1)
+                    .ok_or("the program moves left of the first cell")?;
+                at + 1
+            }
+            Op::Open(close) if cell == 0 => close + 1,
+            Op::Close(open) if cell != 0 => open + 1,
+            Op::Open(_) | Op::Close(_) => at + 1,
+            Op::Output => {
+                run.output.push(cell);
+                at + 1
+            }
+        };
+    }
+    Ok(Some(run))
+}
+
+// Compiles a program into a Turing machine that halts by going to the state
+// past the last, the same way as `wang::compile`: a state for each
+// operation, moving right if the operation doesn't move and then left again
+// in a state for where it goes next. Adding followed by moving is one step.
+pub fn compile_bf(ops: &[Op]) -> WideMachine {
+    const SYMBOLS: usize = 256;
+    // The end, at ops.len(), stops by moving right and back
+    let end = ops.len();
+    let halt = end + 1;
+    let mut back = vec![None; halt + 1];
+    let mut states = end + 1;
+    let mut back_to = |to: usize| {
+        *back[to].get_or_insert_with(|| {
+            states += 1;
+            states - 1
+        })
+    };
+    let mut transitions = Vec::new();
+    for at in 0..=end {
+        for read in 0..=u8::MAX {
+            let (print, right, next) = match ops.get(at) {
+                Some(&Op::Add(add)) => match ops.get(at + 1) {
+                    Some(&Op::Move(right)) => (read.wrapping_add(add), right, at + 2),
+                    _ => (read.wrapping_add(add), true, back_to(at + 1)),
+                },
+                Some(&Op::Move(right)) => (read, right, at + 1),
+                Some(&Op::Open(close)) if read == 0 => (read, true, back_to(close + 1)),
+                Some(&Op::Close(open)) if read != 0 => (read, true, back_to(open + 1)),
+                Some(_) => (read, true, back_to(at + 1)),
+                None => (read, true, back_to(halt)),
+            };
+            transitions.push(Some(WideStep { print, right, next }));
+        }
+    }
+    transitions.resize(states * SYMBOLS, None);
+    for (to, state) in back.iter().enumerate() {
+        let Some(state) = *state else {
+            continue;
+        };
+        let next = if to == halt { states } else { to };
+        for read in 0..SYMBOLS {
+            transitions[state * SYMBOLS + read] = Some(WideStep {
+                print: read as u8,
+                right: false,
+                next,
+            });
+        }
+    }
+    WideMachine {
+        symbols: SYMBOLS,
+        transitions,
+    }
+}
+
+// The cells of a tape from where the machine started, without the blank
+// ones at the end
+pub fn tape_cells(tape: &VecDeque<u8>) -> Vec<u8> {
+    let mut cells: Vec<u8> = tape.iter().copied().collect();
+    while cells.last() == Some(&0) {
+        cells.pop();
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::WideRun;
+
+    // The usual Hello World!, which leaves the codes of some of the letters
+    // it printed in the cells
+    const HELLO: &str = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+    const CELLS: [u8; 7] = [0, 0, 72, 100, 87, 33, 10];
+
+    #[test]
+    fn hello_compiles_to_a_machine_leaving_the_same_cells() {
+        let ops = parse_bf(HELLO).unwrap();
+        let program = run_bf(&ops, 10_000).unwrap().expect("the program stops");
+        assert_eq!(program.output, b"Hello World!\n");
+        assert_eq!(program.cells, CELLS);
+
+        let machine = compile_bf(&ops);
+        let mut run = WideRun::new(&machine, VecDeque::from([0]));
+        // Every operation takes the machine one or two steps, as does stopping
+        let mut steps = 0;
+        while !run.halted() {
+            assert!(steps <= 2 * program.steps + 2, "still running after {} steps", steps);
+            run.step().unwrap();
+            steps += 1;
+        }
+        assert_eq!(tape_cells(&run.tape), CELLS);
+    }
+}
@@ -0,0 +1,194 @@
+use std::ffi::c_char;
+use std::ffi::CStr;
+
+use crate::enumerate::PartialMachine;
+use crate::standard::state_letter;
+use crate::Bit::*;
+use crate::State::*;
+use crate::Tape;
+use crate::TuringMachine;
+
+// A C interface to the simulator, for C, C++ or Julia tooling to run
+// machines without a simulator of their own. It's built into the library
+// with the capi feature, and declared in include/turing_sim.h, which also
+// says what each function expects of its pointers, so that isn't repeated
+// here.
+//
+// A machine in the standard format becomes a handle with `ts_machine_new`,
+// which runs it from a blank tape. It's stepped with `ts_machine_run`, up
+// to a limit each time, read with the other functions, and given back with
+// `ts_machine_free`. Handles are independent, and each is only ever used
+// from one thread at a time.
+
+// A run of a machine of any number of states, behind the one type that
+// handles point to
+trait Run {
+    fn run(&mut self, limit: u64) -> u64;
+    fn state(&self) -> Option<usize>;
+    fn head(&self) -> i64;
+    fn cell(&self, index: i64) -> u8;
+    fn ones(&self) -> u64;
+}
+
+struct Machine<const N: usize> {
+    tm: TuringMachine<N>,
+    tape: Tape<u64>,
+}
+
+impl<const N: usize> Machine<N> {
+    fn parse(text: &str) -> Option<Box<dyn Run>> {
+        let machine: PartialMachine<N> = text.parse().ok()?;
+        Some(Box::new(Machine {
+            tm: machine.to_machine(),
+            tape: Tape::new(),
+        }))
+    }
+}
+
+impl<const N: usize> Run for Machine<N> {
+    fn run(&mut self, limit: u64) -> u64 {
+        let mut taken = 0;
+        while let Index(state) = self.tm.state {
+            if taken == limit {
+                break;
+            }
+            self.tm.step(&mut self.tape, state);
+            taken += 1;
+        }
+        taken
+    }
+
+    fn state(&self) -> Option<usize> {
+        match self.tm.state {
+            Index(state) => Some(state),
+            HALT => None,
+        }
+    }
+
+    fn head(&self) -> i64 {
+        self.tape.head_index()
+    }
+
+    fn cell(&self, index: i64) -> u8 {
+        match self.tape.cell_at(index) {
+            Zero => 0,
+            One => 1,
+        }
+    }
+
+    fn ones(&self) -> u64 {
+        self.tape.ones()
+    }
+}
+
+pub struct TsMachine {
+    run: Box<dyn Run>,
+    steps: u64,
+}
+
+// A handle for the machine, or null if it isn't a machine of 1 to 6 states
+// in the standard format
+#[no_mangle]
+pub unsafe extern "C" fn ts_machine_new(machine: *const c_char) -> *mut TsMachine {
+    let Ok(text) = CStr::from_ptr(machine).to_str() else {
+        return std::ptr::null_mut();
+    };
+    let text = text.trim();
+    let run = match text.split('_').count() {
+        1 => Machine::<1>::parse(text),
+        2 => Machine::<2>::parse(text),
+        3 => Machine::<3>::parse(text),
+        4 => Machine::<4>::parse(text),
+        5 => Machine::<5>::parse(text),
+        6 => Machine::<6>::parse(text),
+        _ => None,
+    };
+    match run {
+        Some(run) => Box::into_raw(Box::new(TsMachine { run, steps: 0 })),
+        None => std::ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ts_machine_free(machine: *mut TsMachine) {
+    if !machine.is_null() {
+        drop(Box::from_raw(machine));
+    }
+}
+
+// Runs for at most `limit` more steps, stopping early if it halts, and
+// returns the steps taken
+#[no_mangle]
+pub unsafe extern "C" fn ts_machine_run(machine: *mut TsMachine, limit: u64) -> u64 {
+    let machine = &mut *machine;
+    let taken = machine.run.run(limit);
+    machine.steps += taken;
+    taken
+}
+
+// Steps taken since the machine was made
+#[no_mangle]
+pub unsafe extern "C" fn ts_machine_steps(machine: *const TsMachine) -> u64 {
+    (*machine).steps
+}
+
+// The state as its letter, A for the first, or Z once it's halted
+#[no_mangle]
+pub unsafe extern "C" fn ts_machine_state(machine: *const TsMachine) -> c_char {
+    match (*machine).run.state() {
+        Some(state) => state_letter(state) as c_char,
+        None => b'Z' as c_char,
+    }
+}
+
+// The tape index of the cell under the head, where 0 is the cell it
+// started on
+#[no_mangle]
+pub unsafe extern "C" fn ts_machine_head(machine: *const TsMachine) -> i64 {
+    (*machine).run.head()
+}
+
+// The ones on the tape
+#[no_mangle]
+pub unsafe extern "C" fn ts_machine_ones(machine: *const TsMachine) -> u64 {
+    (*machine).run.ones()
+}
+
+// Writes the `length` cells from tape index `start` on to `cells`, a 0 or
+// a 1 each. The tape is blank past the cells the head has been to.
+#[no_mangle]
+pub unsafe extern "C" fn ts_machine_tape(
+    machine: *const TsMachine,
+    start: i64,
+    length: usize,
+    cells: *mut u8,
+) {
+    let cells = std::slice::from_raw_parts_mut(cells, length);
+    for (index, cell) in (start..).zip(cells) {
+        *cell = (*machine).run.cell(index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_handle_runs_the_machine() {
+        unsafe {
+            let machine = ts_machine_new(c"1RB1LB_1LA1RZ".as_ptr());
+            assert!(!machine.is_null());
+            assert_eq!(ts_machine_run(machine, 4), 4);
+            assert_eq!(ts_machine_state(machine), b'A' as c_char);
+            assert_eq!(ts_machine_run(machine, 100), 2);
+            assert_eq!(ts_machine_steps(machine), 6);
+            assert_eq!(ts_machine_state(machine), b'Z' as c_char);
+            assert_eq!(ts_machine_ones(machine), 4);
+            let mut cells = [9; 6];
+            ts_machine_tape(machine, ts_machine_head(machine) - 3, 6, cells.as_mut_ptr());
+            assert_eq!(cells, [0, 1, 1, 1, 1, 0]);
+            ts_machine_free(machine);
+            assert!(ts_machine_new(c"1RB1LB_1LA".as_ptr()).is_null());
+        }
+    }
+}
@@ -0,0 +1,445 @@
+use std::io::Write;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+use std::time::Instant;
+
+use num_traits::PrimInt;
+use num_traits::Unsigned;
+
+use crate::format_state;
+use crate::terminal::Key;
+use crate::terminal::Raw;
+use crate::DisplayOptions;
+use crate::State::*;
+use crate::Tape;
+use crate::TapeSnapshot;
+use crate::TuringMachine;
+
+// asciicast v2 wants JSON strings, and the frames are full of escape codes
+fn json_string(s: &str) -> String {
+    let mut output = String::with_capacity(s.len() + 2);
+    output.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+fn frame<const N: usize, T: Unsigned + PrimInt>(
+    tm: &TuringMachine<N>,
+    tape: &Tape<T>,
+    options: &DisplayOptions,
+) -> String {
+    let text = format!(
+        "{}\n{}",
+        tape.render(options),
+        format_state(tm, tape, options)
+    );
+    // A raw terminal does not return the carriage on its own.
+    // Tabs are expanded so the header's width is the width actually drawn.
+    text.replace('\t', "    ").replace('\n', "\r\n")
+}
+
+// The escape codes that turn a terminal showing `shown` into one showing
+// `next`, both frames with their top left corner at the terminal's. Only the
+// stretch of each line between the first and last cells that differ is
+// written, so a step costs the cell it wrote, the head moving and the state
+// changing, rather than the whole window.
+pub fn redraw(shown: &str, next: &str, out: &mut String) {
+    let (shown, next): (Vec<&str>, Vec<&str>) =
+        (shown.split("\r\n").collect(), next.split("\r\n").collect());
+    for row in 0..shown.len().max(next.len()) {
+        let old: Vec<char> = shown
+            .get(row)
+            .map_or(Vec::new(), |line| line.chars().collect());
+        let new: Vec<char> = next
+            .get(row)
+            .map_or(Vec::new(), |line| line.chars().collect());
+        if old == new {
+            continue;
+        }
+        let first = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+        // The ends of the lines only line up if they're the same length
+        let same = match old.len() == new.len() {
+            true => old[first..]
+                .iter()
+                .rev()
+                .zip(new[first..].iter().rev())
+                .take_while(|(a, b)| a == b)
+                .count(),
+            false => 0,
+        };
+        out.push_str(&format!("\x1b[{};{}H", row + 1, first + 1));
+        out.extend(&new[first..new.len() - same]);
+        if new.len() < old.len() {
+            out.push_str("\x1b[K");
+        }
+    }
+}
+
+// Frames drawn at most this often when playback is faster, with the steps
+// in between still written as they go
+const FRAME: Duration = Duration::from_millis(16);
+
+// Where a watched run has been, so it can be taken back to any step it's
+// reached and on to any it hasn't. The machine and tape are kept every
+// `SNAPSHOT` steps, sharing the chunks that didn't change in between, see
+// `Tape::snapshot`, and a step between two is reached by running on from
+// the one before it.
+struct Timeline<const N: usize, T: Unsigned + PrimInt> {
+    // One for each multiple of `SNAPSHOT` reached, in order
+    snapshots: Vec<(TuringMachine<N>, TapeSnapshot<T>)>,
+    furthest: u64,
+}
+
+const SNAPSHOT: u64 = 1024;
+
+impl<const N: usize, T: Unsigned + PrimInt> Timeline<N, T> {
+    fn new(tm: &TuringMachine<N>, tape: &mut Tape<T>) -> Timeline<N, T> {
+        Timeline {
+            snapshots: vec![(tm.clone(), tape.snapshot())],
+            furthest: 0,
+        }
+    }
+
+    // Notes that the run has been taken to `steps`, keeping a snapshot if
+    // it's the first time there and one is due
+    fn reached(&mut self, steps: u64, tm: &TuringMachine<N>, tape: &mut Tape<T>) {
+        if steps.is_multiple_of(SNAPSHOT) && steps / SNAPSHOT == self.snapshots.len() as u64 {
+            self.snapshots.push((tm.clone(), tape.snapshot()));
+        }
+        self.furthest = self.furthest.max(steps);
+    }
+
+    // Takes the run to `target` steps from the last snapshot at or before
+    // it, or as far as it goes if it halts first, returning the steps taken
+    fn goto(&mut self, target: u64, tm: &mut TuringMachine<N>, tape: &mut Tape<T>) -> u64 {
+        let index = (target / SNAPSHOT).min(self.snapshots.len() as u64 - 1);
+        let (machine, snapshot) = &self.snapshots[index as usize];
+        *tm = machine.clone();
+        *tape = Tape::restore(snapshot);
+        let mut steps = index * SNAPSHOT;
+        while let Index(state) = tm.state {
+            if steps == target {
+                break;
+            }
+            tm.step(tape, state);
+            steps += 1;
+            self.reached(steps, tm, tape);
+        }
+        steps
+    }
+}
+
+// What a key asks `watch` to do
+enum Action {
+    Goto(u64),
+    Pause,
+    Quit,
+}
+
+// Cells in the scrub bar
+const BAR: u64 = 40;
+
+// Watching from a terminal, where the keys are read as they're pressed:
+//
+//     space    pause or play
+//     , .      a step back or forward
+//     <- ->    a second of playback back or forward
+//     :goto N  jump to step N
+//     q        stop
+//
+// A scrub bar under the frame shows the step on view against the furthest
+// the run has been taken.
+struct Controls<const N: usize, T: Unsigned + PrimInt> {
+    keys: Receiver<Key>,
+    timeline: Timeline<N, T>,
+    // The command being typed after a :, if there is one
+    prompt: Option<String>,
+    // What was wrong with the last command, shown until the next key
+    message: String,
+    // Dropped last, to put the terminal back
+    _raw: Raw,
+}
+
+impl<const N: usize, T: Unsigned + PrimInt> Controls<N, T> {
+    // What `key` asks for, with the run at `steps` and `stride` steps a
+    // second of playback
+    fn press(&mut self, key: Key, steps: u64, stride: u64) -> Option<Action> {
+        self.message.clear();
+        if let Some(prompt) = &mut self.prompt {
+            match key {
+                Key::Char(c) => prompt.push(c),
+                Key::Backspace if prompt.pop().is_none() => self.prompt = None,
+                Key::Backspace | Key::Left | Key::Right => {}
+                Key::Enter => {
+                    let command = self.prompt.take().unwrap_or_default();
+                    let step = command.strip_prefix("goto ").map(|n| n.trim().parse());
+                    match step {
+                        Some(Ok(step)) => return Some(Action::Goto(step)),
+                        Some(Err(_)) => self.message = format!("bad step in :{}", command),
+                        None => self.message = format!("unknown command :{}", command),
+                    }
+                }
+                Key::Interrupt => return Some(Action::Quit),
+            }
+            return None;
+        }
+        match key {
+            Key::Char(':') => self.prompt = Some(String::new()),
+            Key::Char(' ') => return Some(Action::Pause),
+            Key::Char(',') => return Some(Action::Goto(steps.saturating_sub(1))),
+            Key::Char('.') => return Some(Action::Goto(steps + 1)),
+            Key::Left => return Some(Action::Goto(steps.saturating_sub(stride))),
+            Key::Right => return Some(Action::Goto(steps.saturating_add(stride))),
+            Key::Char('q') | Key::Interrupt => return Some(Action::Quit),
+            _ => {}
+        }
+        None
+    }
+
+    // The scrub bar and the line with the prompt or message, to go under
+    // the frame for the run at `steps`
+    fn lines(&self, steps: u64, playing: bool) -> String {
+        let furthest = self.timeline.furthest.max(1);
+        let filled = (steps as u128 * BAR as u128 / furthest as u128) as usize;
+        format!(
+            "\r\n[{}{}] step {} of {}{}\r\n{}",
+            "=".repeat(filled),
+            " ".repeat(BAR as usize - filled),
+            steps,
+            self.timeline.furthest,
+            if playing { "" } else { ", paused" },
+            match &self.prompt {
+                Some(prompt) => format!(":{}", prompt),
+                None => self.message.clone(),
+            }
+        )
+    }
+}
+
+impl<const N: usize> TuringMachine<N> {
+    // Plays a run in the terminal at `speed` steps a second, for at most
+    // `limit` steps, returning the steps taken to the one last shown.
+    // After the first frame only what each step changes is written, see
+    // `redraw`, so playback isn't held up by the terminal until it's very
+    // fast, and past the rate frames can be shown at it just keeps output
+    // flowing. From a terminal the run can be paused and scrubbed through,
+    // see `Controls`, and it waits to be stopped at the end rather than
+    // stopping when the machine does.
+    pub fn watch<T: Unsigned + PrimInt, W: Write>(
+        &mut self,
+        tape: &mut Tape<T>,
+        out: &mut W,
+        speed: f64,
+        limit: u64,
+        options: &DisplayOptions,
+    ) -> std::io::Result<u64> {
+        let mut controls = Raw::enter().map(|raw| Controls {
+            keys: raw.keys(),
+            timeline: Timeline::new(self, tape),
+            prompt: None,
+            message: String::new(),
+            _raw: raw,
+        });
+        let stride = (speed as u64).max(1);
+        let mut steps = 0;
+        let mut playing = true;
+        let mut shown = frame(self, tape, options);
+        if let Some(controls) = &controls {
+            shown.push_str(&controls.lines(steps, playing));
+        }
+        write!(out, "\x1b[2J\x1b[H{}", shown)?;
+        out.flush()?;
+        // When playback last started, and from which step
+        let mut start = (Instant::now(), steps);
+        let mut flushed = start.0;
+        let mut changes = String::new();
+        'play: loop {
+            let finished = steps == limit || matches!(self.state, HALT);
+            if let Some(controls) = &mut controls {
+                let mut actions = Vec::new();
+                // Waiting for a key when there's nothing to play, rather
+                // than spinning
+                if finished || !playing {
+                    if let Ok(key) = controls.keys.recv_timeout(FRAME) {
+                        actions.extend(controls.press(key, steps, stride));
+                    }
+                }
+                while let Ok(key) = controls.keys.try_recv() {
+                    actions.extend(controls.press(key, steps, stride));
+                }
+                for action in actions {
+                    match action {
+                        Action::Goto(target) => {
+                            steps = controls.timeline.goto(target.min(limit), self, tape)
+                        }
+                        Action::Pause => playing = !playing,
+                        Action::Quit => break 'play,
+                    }
+                    start = (Instant::now(), steps);
+                }
+            } else if finished {
+                break;
+            }
+            let state = match self.state {
+                Index(state) if playing && steps != limit => Some(state),
+                _ => None,
+            };
+            if let Some(state) = state {
+                self.step(tape, state);
+                steps += 1;
+            }
+            let mut next = frame(self, tape, options);
+            if let Some(controls) = &mut controls {
+                controls.timeline.reached(steps, self, tape);
+                next.push_str(&controls.lines(steps, playing));
+            }
+            changes.clear();
+            redraw(&shown, &next, &mut changes);
+            out.write_all(changes.as_bytes())?;
+            shown = next;
+            if state.is_none() {
+                out.flush()?;
+                continue;
+            }
+
+            let due = start.0 + Duration::from_secs_f64((steps - start.1) as f64 / speed);
+            let now = Instant::now();
+            if due > now {
+                out.flush()?;
+                std::thread::sleep(due - now);
+                flushed = due;
+            } else if now - flushed >= FRAME {
+                out.flush()?;
+                flushed = now;
+            }
+        }
+        // The cursor is left under the last frame
+        write!(out, "\x1b[{}H", shown.matches("\r\n").count() + 1)?;
+        out.flush()?;
+        Ok(steps)
+    }
+
+    // Writes the verbose display of a run as an asciinema (asciicast v2)
+    // recording, one frame per step, `delay` apart, returning the steps
+    // taken. Stops after `limit` steps if the machine has not halted by
+    // then. Every frame after the first is written as its changes, see
+    // `redraw`, and as soon as it's drawn, so a long recording isn't held
+    // in memory.
+    pub fn write_cast<T: Unsigned + PrimInt, W: Write>(
+        &mut self,
+        tape: &mut Tape<T>,
+        out: &mut W,
+        delay: Duration,
+        limit: u64,
+        options: &DisplayOptions,
+    ) -> std::io::Result<u64> {
+        // The header has to come first, so the terminal size is taken from
+        // the largest frame of a run through that draws them only to
+        // measure them, before the run is taken again from the start
+        let (start, snapshot) = (self.clone(), tape.snapshot());
+        let (mut width, mut height) = (0, 0);
+        self.cast_frames(tape, limit, options, |_, frame| {
+            width = frame
+                .split("\r\n")
+                .map(|line| line.chars().count())
+                .fold(width, usize::max);
+            height = height.max(frame.matches("\r\n").count());
+            Ok(())
+        })?;
+        *self = start;
+        *tape = Tape::restore(&snapshot);
+
+        writeln!(
+            out,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}}}",
+            width.max(1),
+            height.max(1)
+        )?;
+        let mut shown = String::new();
+        let mut changes = String::new();
+        self.cast_frames(tape, limit, options, |step, frame| {
+            // Clear the screen and home the cursor before the first frame
+            changes.clear();
+            match step {
+                0 => changes.push_str(&format!("\x1b[2J\x1b[H{}", frame)),
+                _ => redraw(&shown, &frame, &mut changes),
+            }
+            shown = frame;
+            writeln!(
+                out,
+                "[{:.6}, \"o\", {}]",
+                delay.as_secs_f64() * step as f64,
+                json_string(&changes)
+            )
+        })
+    }
+
+    // Runs for at most `limit` steps, handing `each` the frame for the start
+    // and for every step taken along with its number, and returns the steps
+    // taken
+    fn cast_frames<T: Unsigned + PrimInt>(
+        &mut self,
+        tape: &mut Tape<T>,
+        limit: u64,
+        options: &DisplayOptions,
+        mut each: impl FnMut(u64, String) -> std::io::Result<()>,
+    ) -> std::io::Result<u64> {
+        each(0, frame(self, tape, options))?;
+        let mut steps = 0;
+        while let Index(state) = self.state {
+            if steps == limit {
+                break;
+            }
+            self.step(tape, state);
+            steps += 1;
+            each(steps, frame(self, tape, options))?;
+        }
+        Ok(steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enumerate::PartialMachine;
+
+    const BB5: &str = "1RB1LC_1RC1RB_1RD0LE_1LA1LD_1RZ0LA";
+
+    // The machine and tape `steps` into a run straight from the start
+    fn straight(steps: u64) -> (TuringMachine<5>, Tape<u8>) {
+        let machine: PartialMachine<5> = BB5.parse().unwrap();
+        let (mut tm, mut tape) = (machine.to_machine(), Tape::new());
+        for _ in 0..steps {
+            let Index(state) = tm.state else { break };
+            tm.step(&mut tape, state);
+        }
+        (tm, tape)
+    }
+
+    #[test]
+    fn going_anywhere_on_the_timeline_matches_a_straight_run() {
+        let options = DisplayOptions::default();
+        let (mut tm, mut tape) = straight(0);
+        let mut timeline = Timeline::new(&tm, &mut tape);
+        for target in [3000, 1500, SNAPSHOT, SNAPSHOT - 1, 0, 2999, 5000] {
+            assert_eq!(timeline.goto(target, &mut tm, &mut tape), target);
+            let (expected, cells) = straight(target);
+            assert!(tm.state == expected.state, "wrong state at step {}", target);
+            assert_eq!(tape.render(&options), cells.render(&options));
+            assert_eq!(tape.head_index(), cells.head_index());
+        }
+        assert_eq!(timeline.furthest, 5000);
+        assert_eq!(timeline.snapshots.len() as u64, 5000 / SNAPSHOT + 1);
+    }
+}
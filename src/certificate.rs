@@ -0,0 +1,561 @@
+use std::collections::VecDeque;
+
+use crate::counter::proves_rule;
+use crate::counter::run_to;
+use crate::ctl::explore;
+use crate::ctl::format_ngram;
+use crate::ctl::parse_ngram;
+use crate::ctl::NGrams;
+use crate::decider::predecessors;
+use crate::decider::Decider;
+use crate::decider::HaltUnreachable;
+use crate::decider::Proof;
+use crate::decider::Verdict;
+use crate::enumerate::PartialMachine;
+use crate::far;
+use crate::far::Automata;
+use crate::rwl;
+use crate::search::simulate;
+use crate::search::Outcome;
+use crate::standard::state_letter;
+use crate::symmetry::mirror;
+use crate::State::*;
+use crate::TapeMotion::*;
+use crate::TuringStep;
+
+// Verdicts written compactly enough to store next to a machine, one word
+// for the kind of verdict followed by its numbers:
+//
+//     halts 21 6                 steps, sigma
+//     halt-unreachable
+//     translated-cycler 1 4 2    start, period, offset
+//     backward-reasoning 12      depth
+//     closed-tape-language 2 00,10 00,01,11
+//                                radius, then the left and right n-grams,
+//                                each read outwards from the head
+//     counter-abstraction 5 2 - 1,1
+//                                start and period in chain steps, then
+//                                the growth of the left and right runs,
+//                                each side from the bottom
+//     repeated-word-list 2 3     cells to a word, repeat threshold
+//     finite-automata-reduction left 0,1,1,0 2,8,1,10,10 4,0,20,0,10
+//                                the side the DFA reads from, its
+//                                transitions on 0 and 1 from each state,
+//                                then the NFA's on 0 and on 1 from each
+//                                state, as sets of states in hex bits
+pub fn format_certificate(verdict: &Verdict) -> Option<String> {
+    Some(match verdict {
+        Verdict::Halts { steps, sigma } => format!("halts {} {}", steps, sigma),
+        Verdict::NonHalting(Proof::HaltUnreachable) => String::from("halt-unreachable"),
+        Verdict::NonHalting(Proof::TranslatedCycler {
+            start,
+            period,
+            offset,
+        }) => format!("translated-cycler {} {} {}", start, period, offset),
+        Verdict::NonHalting(Proof::BackwardReasoning { depth }) => {
+            format!("backward-reasoning {}", depth)
+        }
+        Verdict::NonHalting(Proof::ClosedTapeLanguage {
+            radius,
+            left,
+            right,
+        }) => {
+            let grams = |set: &[u32]| {
+                set.iter()
+                    .map(|&gram| format_ngram(gram, *radius))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            };
+            format!(
+                "closed-tape-language {} {} {}",
+                radius,
+                grams(left),
+                grams(right)
+            )
+        }
+        Verdict::NonHalting(Proof::CounterAbstraction {
+            start,
+            period,
+            left,
+            right,
+        }) => {
+            let growth = |runs: &[u64]| match runs.is_empty() {
+                true => String::from("-"),
+                false => runs
+                    .iter()
+                    .map(|n| n.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            };
+            format!(
+                "counter-abstraction {} {} {} {}",
+                start,
+                period,
+                growth(left),
+                growth(right)
+            )
+        }
+        Verdict::NonHalting(Proof::RepeatedWordList { block, threshold }) => {
+            format!("repeated-word-list {} {}", block, threshold)
+        }
+        Verdict::NonHalting(Proof::FiniteAutomataReduction { mirrored, dfa, nfa }) => {
+            let sets = |sets: &[u64]| {
+                sets.iter()
+                    .map(|set| format!("{:x}", set))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            };
+            format!(
+                "finite-automata-reduction {} {} {} {}",
+                if *mirrored { "right" } else { "left" },
+                dfa.iter()
+                    .flatten()
+                    .map(|q| q.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+                sets(&nfa[0]),
+                sets(&nfa[1])
+            )
+        }
+        Verdict::Undecided => return None,
+    })
+}
+
+pub fn parse_certificate(s: &str) -> Result<Verdict, String> {
+    let bad = || format!("bad certificate {:?}", s);
+    let words: Vec<&str> = s.split_whitespace().collect();
+    let number = |i: usize| words[i].parse().map_err(|_| bad());
+    Ok(match words[..] {
+        ["halts", steps, _] => Verdict::Halts {
+            steps: steps.parse().map_err(|_| bad())?,
+            sigma: number(2)?,
+        },
+        ["halt-unreachable"] => Verdict::NonHalting(Proof::HaltUnreachable),
+        ["translated-cycler", _, _, offset] => Verdict::NonHalting(Proof::TranslatedCycler {
+            start: number(1)?,
+            period: number(2)?,
+            offset: offset.parse().map_err(|_| bad())?,
+        }),
+        ["backward-reasoning", depth] => Verdict::NonHalting(Proof::BackwardReasoning {
+            depth: depth.parse().map_err(|_| bad())?,
+        }),
+        ["closed-tape-language", radius, left, right] => {
+            let radius: usize = radius.parse().map_err(|_| bad())?;
+            let grams = |list: &str| {
+                list.split(',')
+                    .map(|gram| match gram.len() == radius {
+                        true => parse_ngram(gram).ok_or_else(bad),
+                        false => Err(bad()),
+                    })
+                    .collect::<Result<Vec<u32>, String>>()
+            };
+            if radius == 0 || radius > 16 {
+                return Err(bad());
+            }
+            Verdict::NonHalting(Proof::ClosedTapeLanguage {
+                radius,
+                left: grams(left)?,
+                right: grams(right)?,
+            })
+        }
+        ["counter-abstraction", _, _, left, right] => {
+            let growth = |list: &str| match list {
+                "-" => Ok(Vec::new()),
+                _ => list
+                    .split(',')
+                    .map(|n| n.parse().map_err(|_| bad()))
+                    .collect::<Result<Vec<u64>, String>>(),
+            };
+            Verdict::NonHalting(Proof::CounterAbstraction {
+                start: number(1)?,
+                period: number(2)?,
+                left: growth(left)?,
+                right: growth(right)?,
+            })
+        }
+        ["repeated-word-list", block, threshold] => Verdict::NonHalting(Proof::RepeatedWordList {
+            block: block.parse().map_err(|_| bad())?,
+            threshold: threshold.parse().map_err(|_| bad())?,
+        }),
+        ["finite-automata-reduction", side, dfa, zero, one] => {
+            let mirrored = match side {
+                "left" => false,
+                "right" => true,
+                _ => return Err(bad()),
+            };
+            let dfa = dfa
+                .split(',')
+                .map(|q| q.parse().map_err(|_| bad()))
+                .collect::<Result<Vec<usize>, String>>()?;
+            if dfa.len() % 2 != 0 {
+                return Err(bad());
+            }
+            let sets = |list: &str| {
+                list.split(',')
+                    .map(|set| u64::from_str_radix(set, 16).map_err(|_| bad()))
+                    .collect::<Result<Vec<u64>, String>>()
+            };
+            Verdict::NonHalting(Proof::FiniteAutomataReduction {
+                mirrored,
+                dfa: dfa.chunks(2).map(|pair| [pair[0], pair[1]]).collect(),
+                nfa: [sets(zero)?, sets(one)?],
+            })
+        }
+        _ => return Err(bad()),
+    })
+}
+
+// A plain simulation that keeps track of which cells have been visited
+struct Run<'a, const N: usize> {
+    machine: &'a PartialMachine<N>,
+    // The visited cells, the first of them at position `low`
+    tape: VecDeque<u8>,
+    low: i64,
+    head: i64,
+    state: usize,
+    steps: u64,
+}
+
+impl<'a, const N: usize> Run<'a, N> {
+    fn new(machine: &'a PartialMachine<N>) -> Run<'a, N> {
+        Run {
+            machine,
+            tape: VecDeque::from([0]),
+            low: 0,
+            head: 0,
+            state: 0,
+            steps: 0,
+        }
+    }
+
+    fn cell(&self, position: i64) -> u8 {
+        usize::try_from(position - self.low)
+            .ok()
+            .and_then(|i| self.tape.get(i).copied())
+            .unwrap_or(0)
+    }
+
+    // Takes a step, returning which side the head went past the visited
+    // cells on (1 for the right, -1 for the left), 0 if it didn't, or None
+    // if the machine halted instead
+    fn step(&mut self) -> Option<i64> {
+        let i = (self.head - self.low) as usize;
+        let Some(TuringStep {
+            print,
+            motion,
+            next_state: Index(next),
+        }) = self.machine.transitions[self.state][self.tape[i] as usize]
+        else {
+            return None;
+        };
+        self.tape[i] = print as u8;
+        self.state = next;
+        self.steps += 1;
+        self.head += match motion {
+            Right => 1,
+            Left => -1,
+        };
+        if self.head < self.low {
+            self.tape.push_front(0);
+            self.low -= 1;
+            Some(-1)
+        } else if self.head == self.low + self.tape.len() as i64 {
+            self.tape.push_back(0);
+            Some(1)
+        } else {
+            Some(0)
+        }
+    }
+}
+
+fn verify_translated_cycler<const N: usize>(
+    machine: &PartialMachine<N>,
+    start: u64,
+    period: u64,
+    offset: i64,
+    limit: u64,
+) -> Result<(), String> {
+    let halted = || String::from("the machine halts during the cycle");
+    let direction = offset.signum();
+    if period == 0 || direction == 0 {
+        return Err(String::from("the cycle has to move the head"));
+    }
+    within(start.checked_add(period), limit, "steps")?;
+    let mut run = Run::new(machine);
+    let mut side = 0;
+    while run.steps < start {
+        side = run.step().ok_or_else(halted)?;
+    }
+    if start == 0 || side != direction {
+        return Err(format!("step {} is not at a new furthest cell", start));
+    }
+    let (first, first_head, first_state) = (run.tape.clone(), run.head, run.state);
+    let first_low = run.low;
+
+    // The nearest the head gets to the other side during the cycle
+    let mut extreme = run.head;
+    while run.steps < start + period {
+        side = run.step().ok_or_else(halted)?;
+        extreme = match direction {
+            1 => extreme.min(run.head),
+            _ => extreme.max(run.head),
+        };
+    }
+    if side != direction || run.state != first_state || run.head - first_head != offset {
+        return Err(format!(
+            "step {} is not in the same state at a new furthest cell {} along",
+            start + period,
+            offset
+        ));
+    }
+    let behind = (first_head - extreme) * direction;
+    let before = |position: i64| {
+        usize::try_from(position - first_low)
+            .ok()
+            .and_then(|i| first.get(i).copied())
+            .unwrap_or(0)
+    };
+    if (0..=behind)
+        .any(|i| before(first_head - direction * i) != run.cell(run.head - direction * i))
+    {
+        return Err(String::from("the tape behind the head does not repeat"));
+    }
+    Ok(())
+}
+
+// Searches a certificate has redone give up after this many configurations,
+// well past what the deciders look at themselves
+const MAX_CONFIGURATIONS: usize = 1_000_000;
+
+// Backward reasoning keeps the cells known at every step back, so a chain
+// costs the square of its depth. The decider goes 30 deep.
+const MAX_DEPTH: usize = 1_000;
+
+// Whether a count a certificate gives, None if it overflowed, is within the
+// limit on it
+fn within(count: Option<u64>, limit: u64, what: &str) -> Result<(), String> {
+    match count {
+        Some(count) if count <= limit => Ok(()),
+        _ => Err(format!(
+            "the certificate needs more than the limit of {} {}",
+            limit, what
+        )),
+    }
+}
+
+// Checks a verdict from scratch, by simulation for halting and cycling and
+// by redoing the search for backward reasoning, without trusting whatever
+// decider produced it. Every number in a certificate is the verifier's to
+// act on, so the steps simulated are held to `limit` and the searches to
+// `MAX_CONFIGURATIONS`, and a certificate that needs more is turned down
+// rather than followed.
+pub fn verify_certificate<const N: usize>(
+    machine: &PartialMachine<N>,
+    verdict: &Verdict,
+    limit: u64,
+) -> Result<(), String> {
+    match *verdict {
+        Verdict::Halts { steps, sigma } => {
+            within(steps.try_into().ok(), limit, "steps")?;
+            match simulate(machine, steps as u64) {
+                Outcome::Halted {
+                    steps: ran,
+                    sigma: left,
+                } if ran == steps && left == sigma => Ok(()),
+                Outcome::Halted { steps, sigma } => Err(format!(
+                    "the machine halts after {} steps leaving {} ones",
+                    steps, sigma
+                )),
+                Outcome::Undecided => Err(format!(
+                    "the machine is still running after {} steps",
+                    steps
+                )),
+            }
+        }
+        Verdict::NonHalting(Proof::HaltUnreachable) => match HaltUnreachable.decide(machine) {
+            Verdict::NonHalting(_) => Ok(()),
+            _ => Err(String::from("a halting transition is reachable")),
+        },
+        Verdict::NonHalting(Proof::TranslatedCycler {
+            start,
+            period,
+            offset,
+        }) => verify_translated_cycler(machine, start, period, offset, limit),
+        Verdict::NonHalting(Proof::BackwardReasoning { depth }) => {
+            within(Some(depth as u64), MAX_DEPTH as u64, "steps back")?;
+            let mut budget = MAX_CONFIGURATIONS;
+            for (state, slots) in machine.transitions.iter().enumerate() {
+                for (read, step) in slots.iter().enumerate() {
+                    if step.is_some_and(|step| step.next_state != HALT) {
+                        continue;
+                    }
+                    let cells = [(0, read as u8)].into();
+                    if predecessors(machine, state, 0, &cells, depth + 1, &mut budget)
+                        .is_none_or(|deepest| deepest > depth)
+                    {
+                        return Err(format!(
+                            "the halting transition {}{} can be reached from further back than {} steps",
+                            state_letter(state),
+                            read,
+                            depth
+                        ));
+                    }
+                }
+            }
+            Ok(())
+        }
+        Verdict::NonHalting(Proof::ClosedTapeLanguage {
+            radius,
+            ref left,
+            ref right,
+        }) => {
+            let mut ngrams = NGrams {
+                radius,
+                left: left.iter().copied().collect(),
+                right: right.iter().copied().collect(),
+            };
+            explore(machine, &mut ngrams, false, MAX_CONFIGURATIONS).map(|_| ())
+        }
+        Verdict::NonHalting(Proof::CounterAbstraction {
+            start,
+            period,
+            ref left,
+            ref right,
+        }) => {
+            within(start.checked_add(period), limit, "chain steps")?;
+            let run = run_to(machine, start)
+                .ok_or_else(|| format!("the machine stops before chain step {}", start))?;
+            if left.len() != run.tape.left.len() || right.len() != run.tape.right.len() {
+                return Err(format!(
+                    "chain step {} does not have {} left and {} right runs",
+                    start,
+                    left.len(),
+                    right.len()
+                ));
+            }
+            let growth: Vec<u64> = left.iter().chain(right).copied().collect();
+            match proves_rule(machine, run.state, &run.tape, period, &growth) {
+                true => Ok(()),
+                false => Err(format!(
+                    "the runs at chain step {} do not grow that way every {} chain steps",
+                    start, period
+                )),
+            }
+        }
+        Verdict::NonHalting(Proof::RepeatedWordList { block, threshold }) => {
+            rwl::explore(machine, block, threshold, MAX_CONFIGURATIONS).map(|_| ())
+        }
+        Verdict::NonHalting(Proof::FiniteAutomataReduction {
+            mirrored,
+            ref dfa,
+            ref nfa,
+        }) => {
+            let automata = Automata {
+                dfa: dfa.clone(),
+                nfa: nfa.clone(),
+            };
+            match mirrored {
+                true => far::check(&mirror(machine), &automata),
+                false => far::check(machine, &automata),
+            }
+        }
+        Verdict::Undecided => Err(String::from("an undecided verdict proves nothing")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: u64 = 1_000_000;
+
+    fn verify<const N: usize>(machine: &str, certificate: &str) -> Result<(), String> {
+        let machine: PartialMachine<N> = machine.parse().unwrap();
+        verify_certificate(&machine, &parse_certificate(certificate)?, LIMIT)
+    }
+
+    #[test]
+    fn certificates_the_deciders_write_check_out() {
+        for (machine, certificate) in [
+            ("1RB1LB_1LA1RZ", "halts 6 4"),
+            ("1RB0RA_1LA---", "translated-cycler 1 4 2"),
+            ("1RB0RA_1LA---", "closed-tape-language 2 00,10 00,10"),
+            ("1RB0RA_1LA---", "counter-abstraction 1 3 0 -"),
+            ("1RB0RA_1LA---", "repeated-word-list 1 2"),
+            (
+                "1RB0RA_1LA---",
+                "finite-automata-reduction left 0,1,0,0 8,11,2,1,10 1,10,1,10,10",
+            ),
+            ("1RB1LA_0LA0RB", "backward-reasoning 0"),
+            ("1RB1LA_0LA0RB", "halt-unreachable"),
+        ] {
+            let verified = verify::<2>(machine, certificate);
+            assert!(
+                verified.is_ok(),
+                "{} {}: {:?}",
+                machine,
+                certificate,
+                verified
+            );
+        }
+    }
+
+    #[test]
+    fn forged_certificates_are_turned_down() {
+        for (machine, certificate) in [
+            ("1RB1LB_1LA1RZ", "halts 7 4"),
+            ("1RB1LB_1LA1RZ", "halts 6 5"),
+            ("1RB1LB_1LA1RZ", "halt-unreachable"),
+            ("1RB1LB_1LA1RZ", "translated-cycler 1 4 2"),
+            ("1RB1LB_1LA1RZ", "backward-reasoning 3"),
+            ("1RB1LB_1LA1RZ", "closed-tape-language 1 0,1 0,1"),
+            ("1RB1LB_1LA1RZ", "repeated-word-list 2 2"),
+            ("1RB0RA_1LA---", "translated-cycler 1 3 2"),
+            ("1RB0RA_1LA---", "counter-abstraction 1 3 1 -"),
+            (
+                "1RB0RA_1LA---",
+                "finite-automata-reduction left 0,0 2,1,4 2,1,4",
+            ),
+        ] {
+            assert!(
+                verify::<2>(machine, certificate).is_err(),
+                "{} {}",
+                machine,
+                certificate
+            );
+        }
+    }
+
+    #[test]
+    fn certificates_asking_for_too_much_are_turned_down() {
+        // Each of these would keep the verifier going for as good as ever,
+        // or until it ran out of memory, if it did as it was told
+        for (machine, certificate) in [
+            ("1RB0LB_1LA0RA", "halts 99999999999999999999 1"),
+            ("1RB0LB_1LA0RA", "halts 1000001 1"),
+            (
+                "1RB0LB_1LA0RA",
+                "translated-cycler 18446744073709551615 1 1",
+            ),
+            (
+                "1RB0LB_1LA0RA",
+                "translated-cycler 1 18446744073709551615 1",
+            ),
+            (
+                "1RB0RA_1LA---",
+                "counter-abstraction 18446744073709551615 3 0 -",
+            ),
+        ] {
+            let verified = verify::<2>(machine, certificate);
+            assert!(
+                verified.as_ref().is_err_and(|e| e.contains("limit")),
+                "{} {}: {:?}",
+                machine,
+                certificate,
+                verified
+            );
+        }
+        let verified = verify::<1>("1RA---", "backward-reasoning 10000000");
+        assert!(verified.is_err_and(|e| e.contains("limit")));
+    }
+}
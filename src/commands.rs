@@ -0,0 +1,3698 @@
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::fs;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::beep::format_transition;
+use crate::beep::parse_transitions;
+use crate::beep::run_beeping;
+use crate::beep::Beeps;
+use crate::bench::build_lut;
+use crate::bench::build_reachable_lut;
+use crate::bench::corpus;
+use crate::bench::load_corpus;
+use crate::bench::lut_agrees;
+use crate::bench::run_flat;
+use crate::bench::run_lut;
+use crate::bench::run_stepwise;
+use crate::bench::run_verbose;
+use crate::bench::time;
+use crate::bench::Benchmark;
+use crate::bf::compile_bf;
+use crate::bf::parse_bf;
+use crate::bf::run_bf;
+use crate::bf::tape_cells;
+use crate::certificate::format_certificate;
+use crate::certificate::parse_certificate;
+use crate::certificate::verify_certificate;
+use crate::compare::run_side_by_side;
+use crate::chunked::Growth;
+use crate::counter::CounterAbstraction;
+#[cfg(feature = "crossval")]
+use crate::crossval::cross_validate;
+#[cfg(feature = "crossval")]
+use crate::crossval::Simulator;
+use crate::ctl::ClosedTapeLanguage;
+use crate::cursor::Checkpoints;
+use crate::cursor::Cursor;
+use crate::decider::attempt;
+use crate::decider::BackwardReasoning;
+use crate::decider::Decision;
+use crate::decider::HaltUnreachable;
+use crate::decider::Pipeline;
+use crate::decider::Proof;
+use crate::decider::Simulation;
+use crate::decider::TranslatedCycler;
+use crate::decider::Verdict;
+use crate::enumerate::PartialMachine;
+use crate::enumerate::Tnf;
+use crate::equivalence::bisimulate;
+use crate::equivalence::check_window;
+use crate::equivalence::equivalent_up_to;
+use crate::equivalence::pad;
+use crate::equivalence::Bisimulation;
+use crate::equivalence::MAX_CONFIGURATIONS;
+use crate::evolve::evolve;
+use crate::evolve::Evolution;
+use crate::evolve::Random;
+use crate::evolve::Score;
+use crate::external::parse_answer;
+use crate::external::External;
+use crate::far::FiniteAutomataReduction;
+use crate::filter::Filter;
+use crate::fuzz;
+use crate::golden::diff;
+use crate::golden::load;
+use crate::golden::trace;
+use crate::history::History;
+use crate::holdout_list::HoldoutList;
+use crate::holdouts::format_configuration;
+use crate::holdouts::holdout;
+use crate::holdouts::Chain;
+use crate::holdouts::ChainRun;
+use crate::interop::from_csv;
+use crate::interop::from_turingmachine_io;
+use crate::interop::to_html;
+use crate::interop::to_morphett;
+use crate::interop::to_turingmachinesimulator;
+use crate::json::Json;
+use crate::known::KNOWN;
+use crate::modelcheck::to_nusmv;
+use crate::modelcheck::to_tla;
+use crate::models::counter_tape;
+use crate::models::counters_to_turing;
+use crate::models::godel_number;
+use crate::models::read_counters;
+use crate::models::split_tape;
+use crate::models::tag_to_turing;
+use crate::models::to_two_counters;
+use crate::models::CounterEncoding;
+use crate::models::Instruction;
+use crate::models::TagEncoding;
+use crate::models::WideRun;
+use crate::packed::decode;
+use crate::packed::encode;
+use crate::packed::encoded_states;
+use crate::packed::write_container;
+use crate::packed::Container;
+use crate::parallel::enumerate_parallel;
+use crate::parallel::Counters;
+use crate::parallel::Parallel;
+use crate::parse_display_option;
+use crate::parse_display_options;
+use crate::progress::Progress;
+use crate::reachable::Reachability;
+use crate::rpc::serve;
+use crate::rpc::RpcError;
+use crate::rpc::INVALID_PARAMS;
+use crate::rpc::METHOD_NOT_FOUND;
+use crate::rwl::RepeatedWordList;
+use crate::sample::sample;
+use crate::sample::Sampling;
+use crate::search::simulate;
+use crate::search::Batch;
+use crate::search::Champion;
+use crate::search::Leaderboard;
+use crate::search::Outcome;
+use crate::seed::write_database;
+use crate::seed::SeedDatabase;
+use crate::seed::SEED_STATES;
+use crate::selftest;
+use crate::selftest::input_tape;
+use crate::selftest::Engine;
+use crate::sha256::hex;
+use crate::sha256::hmac_sha256;
+use crate::sha256::sha256;
+use crate::shard;
+use crate::shard::Shard;
+use crate::sliced;
+use crate::sliced::Lane;
+use crate::sliced::SlicedRun;
+use crate::smt::to_smtlib;
+use crate::spacetime::Aggregate;
+use crate::spacetime::SpaceTime;
+use crate::standard::format_step;
+use crate::standard::state_letter;
+use crate::strategy::run_auto;
+use crate::strategy::run_with;
+use crate::strategy::Strategy;
+use crate::symmetry::canonicalize_tnf;
+use crate::symmetry::Symmetries;
+use crate::tree::SimulatedTree;
+use crate::utm::decode_machine;
+use crate::utm::encode_machine;
+use crate::utm::universal;
+use crate::verify::Verify;
+use crate::wang::compile;
+use crate::wang::format_compiled;
+use crate::wang::parse_program;
+use crate::wang::run_program;
+use crate::wide::WideTree;
+use crate::Bit;
+use crate::Bit::*;
+use crate::DisplayOptions;
+use crate::State::*;
+use crate::Tape;
+use crate::TapeMotion::*;
+
+// Machines are sized at compile time, so a state count read from the command
+// line has to be matched to one of the sizes built in
+macro_rules! with_states {
+    ($n:expr, $f:ident ( $($arg:expr),* )) => {
+        match $n {
+            1 => $f::<1>($($arg),*),
+            2 => $f::<2>($($arg),*),
+            3 => $f::<3>($($arg),*),
+            4 => $f::<4>($($arg),*),
+            5 => $f::<5>($($arg),*),
+            6 => $f::<6>($($arg),*),
+            n => Err(format!("machines with {} states are not supported", n)),
+        }
+    };
+}
+
+// Why a command failed: its arguments, for which the usage is printed after
+// the message, or something that went wrong running it, for which it isn't.
+// Argument parsing says which with `usage`, `unknown` and `parse_number`,
+// and anything else a command returns is taken as an error running it.
+pub enum Failure {
+    Usage(String),
+    Error(String),
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Failure {
+        Failure::Error(message)
+    }
+}
+
+fn usage(message: &str) -> Failure {
+    Failure::Usage(message.to_string())
+}
+
+fn unknown(arg: &str) -> Failure {
+    Failure::Usage(format!("unknown argument {}", arg))
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, Failure> {
+    let value = value.ok_or(Failure::Usage(format!("{} needs a value", flag)))?;
+    value
+        .parse()
+        .map_err(|_| Failure::Usage(format!("bad value for {}: {}", flag, value)))
+}
+
+struct EnumerateArgs {
+    limit: u64,
+    leaderboard: String,
+    symmetries: Symmetries,
+    filter: Filter,
+    threads: usize,
+    shard: Option<Shard>,
+    dir: String,
+    cursor: Option<PathBuf>,
+    resume: bool,
+    checkpoint_every: u64,
+    // Seconds between progress reports, if there are to be any
+    progress: Option<u64>,
+    progress_log: Option<PathBuf>,
+    // Generate machines by running them, see `tree::SimulatedTree`, rather
+    // than filling in the table in order, see `enumerate::Tnf`
+    early_halt: bool,
+    // Whether the table enumeration was asked for, where it would otherwise
+    // be the tree
+    table: bool,
+    symbols: usize,
+}
+
+// The flags given that a tree enumeration can't honour. The tree is only
+// known as it is run, so it can't be split up or picked up again part of the
+// way through.
+fn tree_conflict(args: &EnumerateArgs) -> Option<&'static str> {
+    [
+        (!args.filter.is_empty(), "--filter"),
+        (args.threads > 1, "--threads"),
+        (args.shard.is_some(), "--shard"),
+        (args.cursor.is_some(), "--cursor"),
+    ]
+    .into_iter()
+    .find(|&(set, _)| set)
+    .map(|(_, flag)| flag)
+}
+
+// More than two symbols are always enumerated as a tree
+fn enumerate_wide(states: usize, args: &EnumerateArgs) -> Result<(), Failure> {
+    let progress = args.progress.is_some() || args.progress_log.is_some();
+    if let Some(flag) = tree_conflict(args)
+        .or(progress.then_some("--progress"))
+        .or(args.table.then_some("--static"))
+    {
+        return Err(Failure::Usage(format!("--symbols can't be used with {}", flag)));
+    }
+    let mut leaderboard = Leaderboard::create(&args.leaderboard).map_err(|e| e.to_string())?;
+    let mut tried = 0;
+    for ((machine, outcome), index) in
+        WideTree::new(states, args.symbols, args.limit, args.symmetries.mirror).zip(0..)
+    {
+        if let Outcome::Halted { steps, sigma } = outcome {
+            leaderboard
+                .offer(Champion {
+                    machine: machine.halting(),
+                    steps,
+                    sigma,
+                    found_at: index,
+                })
+                .map_err(|e| e.to_string())?;
+        }
+        tried = index + 1;
+    }
+    leaderboard.finish(tried).map_err(|e| e.to_string())?;
+    println!("{} machines tried", tried);
+    Ok(())
+}
+
+fn enumerate_states<const N: usize>(args: &EnumerateArgs) -> Result<(), String> {
+    // A shard's leaderboard goes in the directory shared by the whole search
+    let (path, subtrees) = match args.shard {
+        Some(shard) => (
+            shard.path(Path::new(&args.dir)),
+            shard.subtrees::<N>(args.symmetries),
+        ),
+        None => (
+            PathBuf::from(&args.leaderboard),
+            Tnf::<N>::subtrees(args.symmetries, 4),
+        ),
+    };
+    let mut subtrees: Vec<Tnf<N>> = subtrees
+        .into_iter()
+        .map(|subtree| subtree.with_filter(args.filter.clone()))
+        .collect();
+
+    let mut start = 0;
+    // The last machine tried, which the cursor carries on after
+    let mut last: Option<PartialMachine<N>> = None;
+    let mut leaderboard = match (&args.cursor, args.resume) {
+        (Some(cursor), true) => {
+            let cursor = Cursor::read(cursor)?;
+            if let Some(states) = cursor.states.filter(|&states| states != N) {
+                return Err(format!(
+                    "the cursor is from a search of {} state machines, not {}",
+                    states, N
+                ));
+            }
+            if let Some(limit) = cursor.limit.filter(|&limit| limit != args.limit) {
+                return Err(format!(
+                    "the cursor is from a search with a limit of {} steps, not {}",
+                    limit, args.limit
+                ));
+            }
+            if cursor.symmetries != args.symmetries {
+                return Err(String::from(
+                    "the cursor is from a search with different symmetries",
+                ));
+            }
+            if cursor.filter != args.filter {
+                return Err(String::from(
+                    "the cursor is from a search with a different filter",
+                ));
+            }
+            if let Some(machine) = cursor.last {
+                let machine = machine.parse()?;
+                subtrees = Tnf::resume_after(subtrees, &machine)?;
+                last = Some(machine);
+            }
+            start = cursor.tried;
+            Leaderboard::reopen(&path)?
+        }
+        _ => Leaderboard::create(&path).map_err(|e| e.to_string())?,
+    };
+    let mut checkpoints = args
+        .cursor
+        .clone()
+        .map(|path| Checkpoints::new(path, Duration::from_secs(args.checkpoint_every)));
+    // A log on its own is written every minute
+    let mut progress = match (args.progress, &args.progress_log) {
+        (None, None) => None,
+        (every, log) => Some(
+            Progress::new(
+                &subtrees,
+                Duration::from_secs(every.unwrap_or(60)),
+                log.as_deref(),
+            )
+            .map_err(|e| e.to_string())?,
+        ),
+    };
+
+    let counters = Arc::<Counters>::default();
+    if let (Some(progress), false, true) = (&mut progress, args.early_halt, args.threads > 1) {
+        progress.watch(counters.clone());
+    }
+
+    let mut tried = start;
+    let cursor = |tried: u64, last: &Option<PartialMachine<N>>| Cursor {
+        states: Some(N),
+        limit: Some(args.limit),
+        symmetries: args.symmetries,
+        filter: args.filter.clone(),
+        tried,
+        last: last.as_ref().map(|last| last.to_string()),
+    };
+    // Takes the machines from position `first` on
+    let mut emit = |first: u64, batch: Batch<N>| {
+        leaderboard.offer_batch(first, &batch)?;
+        if let Some(progress) = &mut progress {
+            progress.passed(
+                &batch,
+                leaderboard.best_sigma.as_ref(),
+                leaderboard.best_steps.as_ref(),
+            )?;
+        }
+        tried = first + batch.machines;
+        if batch.last.is_some() {
+            last = batch.last;
+        }
+        match &mut checkpoints {
+            Some(checkpoints) => checkpoints.passed(|| cursor(tried, &last)),
+            None => Ok(()),
+        }
+    };
+    let result: std::io::Result<()> = if args.early_halt {
+        SimulatedTree::<N>::new(args.limit, args.symmetries.mirror)
+            .zip(start..)
+            .try_for_each(|((machine, outcome), index)| emit(index, Batch::of(&machine, outcome)))
+    } else if args.threads > 1 {
+        let config = Parallel {
+            threads: args.threads,
+            limit: args.limit,
+            counters,
+        };
+        let mut result = Ok(());
+        enumerate_parallel(subtrees, &config, |index, batch| {
+            if result.is_ok() {
+                result = emit(start + index, batch);
+            }
+        });
+        result
+    } else {
+        subtrees
+            .into_iter()
+            .flatten()
+            .zip(start..)
+            .try_for_each(|(machine, index)| {
+                let outcome = simulate(&machine, args.limit);
+                emit(index, Batch::of(&machine, outcome))
+            })
+    };
+    result.map_err(|e| e.to_string())?;
+    if let Some(progress) = &mut progress {
+        progress
+            .finish(
+                leaderboard.best_sigma.as_ref(),
+                leaderboard.best_steps.as_ref(),
+            )
+            .map_err(|e| e.to_string())?;
+    }
+    leaderboard.finish(tried).map_err(|e| e.to_string())?;
+    if let Some(checkpoints) = &mut checkpoints {
+        checkpoints.finish(cursor(tried, &last)).map_err(|e| e.to_string())?;
+    }
+    println!("{} machines tried", tried);
+    Ok(())
+}
+
+// enumerate <states> [--limit N] [--leaderboard FILE] [--mirror]
+//     [--filter CLAUSES] [--threads N] [--shard K/N [--dir DIR]]
+//     [--cursor FILE | --resume-cursor FILE] [--checkpoint-every SECONDS]
+//     [--progress SECONDS] [--progress-log FILE] [--early-halt | --static]
+// enumerate <states> --symbols 3..10 [--limit N] [--leaderboard FILE] [--mirror]
+//
+// Machines are generated by running them, which is Tree Normal Form proper,
+// unless a flag needs the table enumeration, which can be split up,
+// filtered and resumed, or --static asks for it. --early-halt insists on
+// the tree and refuses those flags. Machines of more symbols are only
+// enumerated as the tree on one thread, so --symbols refuses them too, as
+// well as --progress, and the deciders, which all take two symbols, can't
+// be run on what it finds.
+pub fn enumerate(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let states: usize = parse_number("states", args.next())?;
+    let mut parsed = EnumerateArgs {
+        limit: 1000,
+        leaderboard: String::from("leaderboard.tsv"),
+        symmetries: Symmetries::NONE,
+        filter: Filter::default(),
+        threads: 1,
+        shard: None,
+        dir: String::from("."),
+        cursor: None,
+        resume: false,
+        checkpoint_every: 60,
+        progress: None,
+        progress_log: None,
+        early_halt: false,
+        table: false,
+        symbols: 2,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--limit" => parsed.limit = parse_number(&arg, args.next())?,
+            "--leaderboard" => {
+                parsed.leaderboard = args.next().ok_or(usage("--leaderboard needs a value"))?
+            }
+            "--mirror" => parsed.symmetries.mirror = true,
+            // A machine and the one with 0 and 1 swapped don't run the same
+            // from a blank tape, so treating them as one would drop machines
+            // the leaderboard needs
+            "--swap-symbols" => {
+                return Err(usage(
+                    "--swap-symbols can't be used with enumerate, a machine with 0 and 1 swapped runs differently from a blank tape, so records would be missed",
+                ))
+            }
+            "--filter" => {
+                parsed.filter = args
+                    .next()
+                    .ok_or(usage("--filter needs a value"))?
+                    .parse()
+                    .map_err(Failure::Usage)?
+            }
+            "--threads" => parsed.threads = parse_number(&arg, args.next())?,
+            "--shard" => {
+                let shard = args.next().ok_or(usage("--shard needs a value"))?;
+                parsed.shard = Some(shard.parse().map_err(Failure::Usage)?)
+            }
+            "--dir" => parsed.dir = args.next().ok_or(usage("--dir needs a value"))?,
+            "--cursor" | "--resume-cursor" => {
+                let path = args
+                    .next()
+                    .ok_or(Failure::Usage(format!("{} needs a value", arg)))?;
+                parsed.cursor = Some(PathBuf::from(path));
+                parsed.resume = arg == "--resume-cursor";
+            }
+            "--checkpoint-every" => parsed.checkpoint_every = parse_number(&arg, args.next())?,
+            "--progress" => parsed.progress = Some(parse_number(&arg, args.next())?),
+            "--progress-log" => {
+                parsed.progress_log =
+                    Some(args.next().ok_or(usage("--progress-log needs a value"))?.into())
+            }
+            "--early-halt" => parsed.early_halt = true,
+            "--static" => parsed.table = true,
+            "--symbols" => parsed.symbols = parse_number(&arg, args.next())?,
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    let conflict = tree_conflict(&parsed);
+    parsed.early_halt = match (parsed.early_halt, parsed.table, conflict) {
+        (true, true, _) => return Err(usage("--early-halt can't be used with --static")),
+        (true, false, Some(flag)) => {
+            return Err(Failure::Usage(format!(
+                "--early-halt can't be used with {}",
+                flag
+            )))
+        }
+        (true, false, None) => true,
+        // Progress is reported by the subtrees of the table
+        (false, false, None) => parsed.progress.is_none() && parsed.progress_log.is_none(),
+        (false, _, _) => false,
+    };
+    match parsed.symbols {
+        2 => Ok(with_states!(states, enumerate_states(&parsed))?),
+        3..=10 if (1..=26).contains(&states) => enumerate_wide(states, &parsed),
+        _ => Err(Failure::Usage(format!(
+            "can't enumerate {} states with {} symbols",
+            states, parsed.symbols
+        ))),
+    }
+}
+
+// merge <dir>
+pub fn merge(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let dir = args
+        .next()
+        .ok_or(usage("merge needs the directory the shards wrote to"))?;
+    let merged = shard::merge(Path::new(&dir))?;
+    for line in [merged.best_sigma, merged.best_steps].into_iter().flatten() {
+        println!("{}", line);
+    }
+    println!("{} machines tried", merged.tried);
+    Ok(())
+}
+
+// seed <file> [index... | --all]
+//
+// Summarises a seed database, see `seed`, or lists the machines at the
+// indices given, or every machine with --all
+pub fn seed(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let path = args.next().ok_or(usage("seed needs the database file"))?;
+    let mut database = SeedDatabase::open(&path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut indices = Vec::new();
+    let mut all = false;
+    for arg in args {
+        match arg.as_str() {
+            "--all" => all = true,
+            _ => indices.push(parse_number("index", Some(arg))?),
+        }
+    }
+    if all {
+        if !indices.is_empty() {
+            return Err(usage("seed takes indices or --all, not both"));
+        }
+        let out = &mut BufWriter::new(std::io::stdout().lock());
+        for entry in database.iter().map_err(|e| format!("{}: {}", path, e))? {
+            let (index, machine) = entry.map_err(|e| format!("{}: {}", path, e))?;
+            writeln!(out, "{}\t{}", index, machine).map_err(|e| e.to_string())?;
+        }
+        out.flush().map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+    if indices.is_empty() {
+        let header = &database.header;
+        println!(
+            "{} machines, {} undecided by time and {} by space{}",
+            database.len(),
+            header.undecided_time,
+            header.undecided_space,
+            if header.sorted { ", sorted" } else { "" }
+        );
+        if header.total as u64 != database.len() {
+            println!("but the header counts {} machines", header.total);
+        }
+    }
+    for index in indices {
+        let machine = database.get(index).map_err(|e| e.to_string())?;
+        println!("{}\t{}", index, machine);
+    }
+    Ok(())
+}
+
+fn pack_states<const N: usize>(machines: &[String], out: &str) -> Result<u64, String> {
+    let machines = machines
+        .iter()
+        .map(|machine| machine.parse::<PartialMachine<N>>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut file = BufWriter::new(File::create(out).map_err(|e| format!("{}: {}", out, e))?);
+    write_container(&mut file, machines).map_err(|e| format!("{}: {}", out, e))
+}
+
+fn encode_states<const N: usize>(machine: &str) -> Result<Vec<u8>, String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    Ok(encode(&machine))
+}
+
+// pack <machine> --out FILE | --in FILE --out FILE
+//
+// Writes a machine in the binary format of `packed`, or the machines listed
+// in a file, a line each and all with the same number of states, to a
+// container in it
+pub fn pack(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let mut machine = None;
+    let mut input = None;
+    let mut output = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--in" => input = Some(args.next().ok_or(usage("--in needs a value"))?),
+            "--out" => output = Some(args.next().ok_or(usage("--out needs a value"))?),
+            _ if !arg.starts_with("--") && machine.is_none() => machine = Some(arg),
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    let output = output.ok_or(usage("pack needs --out"))?;
+    let input = match (machine, input) {
+        (Some(machine), None) => {
+            let states = machine.trim().split('_').count();
+            let bytes = with_states!(states, encode_states(&machine))?;
+            return Ok(fs::write(&output, bytes).map_err(|e| format!("{}: {}", output, e))?);
+        }
+        (None, Some(input)) => input,
+        _ => return Err(usage("pack needs a machine or --in, but not both")),
+    };
+    let text = fs::read_to_string(&input).map_err(|e| format!("{}: {}", input, e))?;
+    let machines: Vec<String> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect();
+    let states = machines
+        .first()
+        .ok_or(String::from("there are no machines"))?
+        .split('_')
+        .count();
+    if let Some(other) = machines.iter().find(|m| m.split('_').count() != states) {
+        return Err(format!(
+            "{} doesn't have {} states like the first",
+            other, states
+        )
+        .into());
+    }
+    let count = with_states!(states, pack_states(&machines, &output))?;
+    eprintln!("{} machines", count);
+    Ok(())
+}
+
+fn unpack_states<const N: usize>(container: &Container, indices: &[u64]) -> Result<(), String> {
+    for &index in indices {
+        println!("{}\t{}", index, container.get::<N>(index)?);
+    }
+    if indices.is_empty() {
+        for entry in container.iter::<N>() {
+            let (index, machine) = entry?;
+            println!("{}\t{}", index, machine);
+        }
+    }
+    Ok(())
+}
+
+fn decode_states<const N: usize>(bytes: &[u8]) -> Result<(), String> {
+    println!("{}", decode::<N>(bytes)?);
+    Ok(())
+}
+
+// unpack <file> [index...] [--count]
+//
+// Checks a container written by `pack` and prints its machines, or those at
+// the indices given, or with --count just how many there are. A machine
+// packed on its own is just printed.
+pub fn unpack(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let path = args.next().ok_or(usage("unpack needs a file"))?;
+    let mut indices = Vec::new();
+    let mut count = false;
+    for arg in args {
+        match arg.as_str() {
+            "--count" => count = true,
+            _ => indices.push(parse_number("index", Some(arg))?),
+        }
+    }
+    // Read whole, though a container could as well be memory mapped
+    let bytes = fs::read(&path).map_err(|e| format!("{}: {}", path, e))?;
+    if !bytes.starts_with(b"TMBC") {
+        let states = encoded_states(&bytes).map_err(|e| format!("{}: {}", path, e))?;
+        return Ok(
+            with_states!(states, decode_states(&bytes)).map_err(|e| format!("{}: {}", path, e))?,
+        );
+    }
+    let container = Container::new(&bytes).map_err(|e| format!("{}: {}", path, e))?;
+    container.verify().map_err(|e| format!("{}: {}", path, e))?;
+    if count {
+        println!(
+            "{} machines with {} states",
+            container.len, container.states
+        );
+        return Ok(());
+    }
+    Ok(with_states!(container.states, unpack_states(&container, &indices))?)
+}
+
+fn bench_states<const N: usize>(
+    benchmark: &Benchmark,
+    at_least: Duration,
+    options: &DisplayOptions,
+) -> Result<(), String> {
+    let machine: PartialMachine<N> = benchmark.machine.parse()?;
+    let show = |strategy: &str, result: String| {
+        println!(
+            "{}\t{}\t{}\t{}",
+            benchmark.name, benchmark.kind, strategy, result
+        );
+    };
+    type Run<'a, const N: usize> = &'a dyn Fn(&PartialMachine<N>, u64) -> u64;
+    let verbose =
+        |machine: &PartialMachine<N>, steps| run_verbose::<u64, N>(machine, steps, options);
+    let runs: [(&str, Run<N>); 11] = [
+        ("step u8", &run_stepwise::<u8, N>),
+        ("step u16", &run_stepwise::<u16, N>),
+        ("step u32", &run_stepwise::<u32, N>),
+        ("step u64", &run_stepwise::<u64, N>),
+        ("step u128", &run_stepwise::<u128, N>),
+        ("flat u8", &run_flat::<u8, N>),
+        ("flat u16", &run_flat::<u16, N>),
+        ("flat u32", &run_flat::<u32, N>),
+        ("flat u64", &run_flat::<u64, N>),
+        ("flat u128", &run_flat::<u128, N>),
+        ("verbose u64", &verbose),
+    ];
+    for (strategy, run) in runs {
+        let steps = run(&machine, benchmark.steps);
+        let (runs, elapsed) = time(at_least, || {
+            std::hint::black_box(run(&machine, benchmark.steps));
+        });
+        show(
+            strategy,
+            format!(
+                "{:.0} steps/s",
+                (steps * runs) as f64 / elapsed.as_secs_f64()
+            ),
+        );
+    }
+    // Building the LUT runs each word until the head leaves it, and only
+    // gives up on one the machine cycles inside after more steps than the
+    // word has configurations, which is slow for the machines that do, so
+    // it's only timed on the champions. Wider words would need 2^33 entries
+    // or more.
+    if benchmark.kind != "champion" {
+        return Ok(());
+    }
+    for (strategy, build) in [
+        ("lut build u8", build_lut::<u8, N> as fn(&PartialMachine<N>)),
+        ("lut build u16", build_lut::<u16, N>),
+    ] {
+        let (runs, elapsed) = time(at_least, || build(&machine));
+        show(
+            strategy,
+            format!("{:.3} ms", elapsed.as_secs_f64() * 1000.0 / runs as f64),
+        );
+    }
+    for (strategy, build) in [
+        (
+            "lut reachable u8",
+            build_reachable_lut::<u8, N> as fn(&PartialMachine<N>) -> Reachability,
+        ),
+        ("lut reachable u16", build_reachable_lut::<u16, N>),
+    ] {
+        let reach = build(&machine);
+        let (runs, elapsed) = time(at_least, || {
+            build(&machine);
+        });
+        show(
+            strategy,
+            format!(
+                "{:.3} ms, {} of {} entries, {:.1}x fewer",
+                elapsed.as_secs_f64() * 1000.0 / runs as f64,
+                reach.reachable,
+                reach.total,
+                reach.ratio()
+            ),
+        );
+    }
+    // Word at a time, with the rate in the machine's own steps
+    let steps = run_flat::<u64, N>(&machine, benchmark.steps);
+    let rate = |(runs, elapsed): (u64, Duration)| {
+        format!(
+            "{:.0} steps/s",
+            (steps * runs) as f64 / elapsed.as_secs_f64()
+        )
+    };
+    let mut compiled = machine.to_machine().compile::<u8>();
+    let mut wide = machine.to_machine().compile::<u16>();
+    for result in [
+        compiled.verify(Verify::for_word::<u8>()),
+        wide.verify(Verify::for_word::<u16>()),
+    ] {
+        result.map_err(|e| format!("the LUT differs from the step loop: {}", e))?;
+    }
+    let mut reachable = machine.to_machine().compile_reachable::<u16>().0;
+    if !lut_agrees(&machine, &mut compiled, steps)
+        || !lut_agrees(&machine, &mut wide, steps)
+        || !lut_agrees(&machine, &mut reachable, steps)
+    {
+        return Err(String::from(
+            "the run through the LUT differs from the step loop",
+        ));
+    }
+    let kib = compiled.memory_usage() / 1024;
+    let result = rate(time(at_least, || run_lut(&mut compiled, benchmark.steps)));
+    show("lut u8", format!("{}, {} KiB of LUT", result, kib));
+    let mut compiled = wide;
+    let kib = compiled.memory_usage() / 1024;
+    let result = rate(time(at_least, || run_lut(&mut compiled, benchmark.steps)));
+    show("lut u16", format!("{}, {} KiB of LUT", result, kib));
+    Ok(())
+}
+
+// bench [--corpus FILE] [--only NAME,..] [--time SECONDS]
+//     [--glyphs 01[ ^] | --blocks] [--group N] [--separator C]
+//
+// Times each machine of the corpus, the built in one or one in its format,
+// under each way of running it, see `bench`. Each figure is taken over
+// runs adding up to at least --time. The verbose runs draw the tape as the
+// display flags say.
+pub fn bench(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let mut benchmarks = corpus();
+    let mut only = None;
+    let mut seconds = 0.5;
+    let mut options = DisplayOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--corpus" => {
+                let path = args.next().ok_or(usage("--corpus needs a value"))?;
+                let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+                benchmarks = load_corpus(&text).map_err(|e| format!("{}: {}", path, e))?;
+            }
+            "--only" => only = Some(args.next().ok_or(usage("--only needs a value"))?),
+            "--time" => seconds = parse_number(&arg, args.next())?,
+            _ if parse_display_option(&mut options, &arg, &mut args).map_err(Failure::Usage)? => {}
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    if let Some(only) = &only {
+        let names: Vec<&str> = only.split(',').collect();
+        if let Some(name) = names
+            .iter()
+            .find(|name| !benchmarks.iter().any(|b| b.name == **name))
+        {
+            return Err(Failure::Usage(format!("there's no benchmark {}", name)));
+        }
+        benchmarks.retain(|b| names.contains(&b.name.as_str()));
+    }
+    let at_least = Duration::try_from_secs_f64(seconds)
+        .ok()
+        .filter(|time| !time.is_zero())
+        .ok_or(Failure::Usage(format!("bad --time {}", seconds)))?;
+    println!("machine\tkind\tstrategy\tresult");
+    for benchmark in &benchmarks {
+        let states = benchmark.machine.split('_').count();
+        with_states!(states, bench_states(benchmark, at_least, &options))
+            .map_err(|e| format!("{}: {}", benchmark.name, e))?;
+    }
+    Ok(())
+}
+
+// What `decide` and `classify` run unless told otherwise, cheapest first
+const DEFAULT_DECIDERS: &str =
+    "halt-unreachable,backward-reasoning,translated-cycler,closed-tape-language,counter-abstraction,repeated-word-list,simulation,finite-automata-reduction";
+
+// A pipeline of the deciders named, separated by commas, with
+// external:PATH for a program (see `external::External`). `limit` is the
+// step limit of plain simulation, and of checking what a program answers.
+fn build_pipeline<const N: usize>(names: &str, limit: u64) -> Result<Pipeline<N>, String> {
+    let mut pipeline = Pipeline::new();
+    for name in names.split(',') {
+        pipeline = match name {
+            "halt-unreachable" => pipeline.with(HaltUnreachable),
+            "backward-reasoning" => pipeline.with(BackwardReasoning {
+                depth: 30,
+                budget: 100_000,
+            }),
+            "translated-cycler" => pipeline.with(TranslatedCycler { limit: 10_000 }),
+            "closed-tape-language" => pipeline.with(ClosedTapeLanguage {
+                max_radius: 4,
+                max_contexts: 10_000,
+            }),
+            "counter-abstraction" => pipeline.with(CounterAbstraction {
+                limit: 10_000,
+                max_period: 1_000,
+            }),
+            "repeated-word-list" => pipeline.with(RepeatedWordList {
+                max_block: 4,
+                threshold: 2,
+                max_configs: 10_000,
+            }),
+            "finite-automata-reduction" => pipeline.with(FiniteAutomataReduction { max_states: 4 }),
+            "simulation" => pipeline.with(Simulation { limit }),
+            _ if name.starts_with("external:") => {
+                pipeline.with(External::new(&name["external:".len()..], limit))
+            }
+            _ => return Err(format!("unknown decider {}", name)),
+        };
+    }
+    Ok(pipeline)
+}
+
+// An unknown decider in --deciders as a usage error, caught before anything
+// is run or written
+fn check_deciders(names: &str) -> Result<(), Failure> {
+    build_pipeline::<1>(names, 0)
+        .map(|_| ())
+        .map_err(Failure::Usage)
+}
+
+fn decide_states<const N: usize>(
+    machine: &str,
+    deciders: &str,
+    limit: u64,
+    certificate: bool,
+) -> Result<(), String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    let decision = build_pipeline(deciders, limit)?.decide(&machine);
+    match decision.decider {
+        Some(decider) => println!("{} ({})", decision.verdict, decider),
+        None => println!("{}", decision.verdict),
+    }
+    if certificate {
+        if let Some(text) = format_certificate(&decision.verdict) {
+            verify_certificate(&machine, &decision.verdict, limit)
+                .map_err(|e| format!("the certificate does not check out: {}", e))?;
+            println!("{}", text);
+        }
+    }
+    Ok(())
+}
+
+// decide <machine> [--deciders a,b,..] [--limit N] [--certificate]
+pub fn decide(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let machine = args.next().ok_or(usage("decide needs a machine"))?;
+    let mut deciders = String::from(DEFAULT_DECIDERS);
+    let mut limit = 1_000_000;
+    let mut certificate = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--deciders" => deciders = args.next().ok_or(usage("--deciders needs a value"))?,
+            "--limit" => limit = parse_number(&arg, args.next())?,
+            "--certificate" => certificate = true,
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    check_deciders(&deciders)?;
+    let states = machine.trim().split('_').count();
+    Ok(with_states!(
+        states,
+        decide_states(&machine, &deciders, limit, certificate)
+    )?)
+}
+
+fn verify_cert_states<const N: usize>(
+    machine: &str,
+    verdict: &Verdict,
+    limit: u64,
+) -> Result<(), String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    verify_certificate(&machine, verdict, limit)
+}
+
+// An argument that names a file stands for what's in it
+fn text_or_file(arg: String) -> Result<String, String> {
+    match Path::new(&arg).is_file() {
+        true => fs::read_to_string(&arg).map_err(|e| format!("{}: {}", arg, e)),
+        false => Ok(arg),
+    }
+}
+
+// verify-cert <machine> <certificate> [--limit N]: checks a certificate,
+// written as `certificate::format_certificate` does or as an external
+// decider's JSON answer, from scratch. Either can be given in a file. One
+// that needs more than --limit steps simulated is turned down.
+pub fn verify_cert(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let machine = text_or_file(args.next().ok_or(usage("verify-cert needs a machine"))?)?;
+    let certificate = text_or_file(
+        args.next()
+            .ok_or(usage("verify-cert needs a certificate"))?,
+    )?;
+    let mut limit = 100_000_000;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--limit" => limit = parse_number(&arg, args.next())?,
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    let machine = machine.trim();
+    let certificate = certificate.trim();
+    let verdict = match certificate.starts_with('{') {
+        true => parse_answer(certificate)?,
+        false => parse_certificate(certificate)?,
+    };
+    let states = machine.split('_').count();
+    with_states!(states, verify_cert_states(machine, &verdict, limit))
+        .map_err(|e| format!("the certificate does not check out: {}", e))?;
+    println!("{}: {}", machine, verdict);
+    Ok(())
+}
+
+fn classify_states<const N: usize>(
+    machine: &str,
+    deciders: &str,
+    limit: u64,
+) -> Result<Decision, String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    Ok(build_pipeline(deciders, limit)?.decide(&machine))
+}
+
+// Quotes a CSV field if it needs it
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// Writes the machines left undecided, one per line in the standard format
+// and, for 5-state machines, as a seed database
+fn write_holdouts(
+    holdouts: &[&str],
+    text: Option<&String>,
+    seed: Option<&String>,
+) -> Result<(), String> {
+    if let Some(path) = text {
+        let lines: String = holdouts
+            .iter()
+            .map(|machine| format!("{}\n", machine))
+            .collect();
+        fs::write(path, lines).map_err(|e| format!("{}: {}", path, e))?;
+    }
+    if let Some(path) = seed {
+        let machines = holdouts
+            .iter()
+            .map(|machine| machine.parse::<PartialMachine<SEED_STATES>>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("only 5-state machines fit a seed database: {}", e))?;
+        let mut out = BufWriter::new(File::create(path).map_err(|e| format!("{}: {}", path, e))?);
+        write_database(&mut out, &machines).map_err(|e| format!("{}: {}", path, e))?;
+    }
+    Ok(())
+}
+
+// Simulation limits for a staged classification: from `first`, ten times
+// more each stage, up to `limit`
+fn stage_budgets(first: u64, limit: u64) -> Vec<u64> {
+    let mut budgets = vec![first];
+    while budgets[budgets.len() - 1] < limit {
+        budgets.push(budgets[budgets.len() - 1].saturating_mul(10).min(limit));
+    }
+    budgets
+}
+
+// Settles the machines in stages rather than one at a time. Each stage
+// simulates the machines still left for the next budget, then runs the next
+// decider of the pipeline on them; once there are no more deciders only the
+// budget grows, and once the budgets reach the limit only deciders are left.
+// Most machines halt or are caught early, so few run anywhere near the
+// limit, and as the budgets grow tenfold each time, rerunning a machine from
+// the start in every stage costs little more than one run at the last.
+fn classify_staged(
+    machines: &[(usize, &str)],
+    deciders: &str,
+    first: u64,
+    limit: u64,
+    input: &str,
+) -> Result<Vec<Decision>, String> {
+    let budgets = stage_budgets(first, limit);
+    let others: Vec<&str> = deciders
+        .split(',')
+        .filter(|&name| name != "simulation")
+        .collect();
+    let mut decisions: Vec<Decision> = machines
+        .iter()
+        .map(|_| Decision {
+            verdict: Verdict::Undecided,
+            decider: None,
+        })
+        .collect();
+    let mut left: Vec<usize> = (0..machines.len()).collect();
+    for stage in 0..budgets.len().max(others.len()) {
+        let names = match (budgets.get(stage), others.get(stage)) {
+            (Some(_), Some(decider)) => format!("simulation,{}", decider),
+            (Some(_), None) => String::from("simulation"),
+            (None, Some(decider)) => decider.to_string(),
+            (None, None) => unreachable!(),
+        };
+        let budget = budgets.get(stage).copied().unwrap_or(limit);
+        let mut still_left = Vec::new();
+        for index in left {
+            let (number, machine) = machines[index];
+            let states = machine.split('_').count();
+            let decision = with_states!(states, classify_states(machine, &names, budget))
+                .map_err(|e| format!("{} line {}: {}", input, number + 1, e))?;
+            match decision.decider {
+                Some(_) => decisions[index] = decision,
+                None => still_left.push(index),
+            }
+        }
+        left = still_left;
+        eprintln!(
+            "stage {}: {} (limit {}), {} left",
+            stage + 1,
+            names,
+            budget,
+            left.len()
+        );
+        if left.is_empty() {
+            break;
+        }
+    }
+    Ok(decisions)
+}
+
+// classify --in FILE [--out FILE] [--deciders a,b,..] [--limit N]
+//     [--holdouts FILE] [--holdouts-seed FILE] [--staged FIRST]
+//
+// Runs the decider pipeline on every machine in the input, one per line in
+// the standard format, and writes a CSV of the verdicts. With --staged, the
+// machines are settled in stages with simulation limits from FIRST up to
+// the limit, see `classify_staged`.
+pub fn classify(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let mut input = None;
+    let mut output = None;
+    let mut holdouts_text = None;
+    let mut holdouts_seed = None;
+    let mut deciders = String::from(DEFAULT_DECIDERS);
+    let mut limit = 1_000_000;
+    let mut first = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--in" => input = Some(args.next().ok_or(usage("--in needs a value"))?),
+            "--out" => output = Some(args.next().ok_or(usage("--out needs a value"))?),
+            "--holdouts" => {
+                holdouts_text = Some(args.next().ok_or(usage("--holdouts needs a value"))?)
+            }
+            "--holdouts-seed" => {
+                holdouts_seed = Some(args.next().ok_or(usage("--holdouts-seed needs a value"))?)
+            }
+            "--deciders" => deciders = args.next().ok_or(usage("--deciders needs a value"))?,
+            "--limit" => limit = parse_number(&arg, args.next())?,
+            "--staged" => first = Some(parse_number(&arg, args.next())?),
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    let input = input.ok_or(usage("classify needs --in"))?;
+    if first.is_some_and(|first| first == 0 || first > limit) {
+        return Err(usage("--staged needs a first limit from 1 to --limit"));
+    }
+    check_deciders(&deciders)?;
+    let text = fs::read_to_string(&input).map_err(|e| format!("{}: {}", input, e))?;
+    let mut out: Box<dyn Write> = match &output {
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).map_err(|e| format!("{}: {}", path, e))?,
+        )),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let write_error = |e: std::io::Error| e.to_string();
+
+    // How many machines each decider settled, in pipeline order
+    let mut settled: Vec<(&str, u64)> = deciders.split(',').map(|name| (name, 0)).collect();
+    if first.is_some() && !settled.iter().any(|&(name, _)| name == "simulation") {
+        settled.push(("simulation", 0));
+    }
+    let machines: Vec<(usize, &str)> = text
+        .lines()
+        .map(str::trim)
+        .enumerate()
+        .filter(|(_, machine)| !machine.is_empty() && !machine.starts_with('#'))
+        .collect();
+    let mut staged = match first {
+        Some(first) => {
+            Some(classify_staged(&machines, &deciders, first, limit, &input)?.into_iter())
+        }
+        None => None,
+    };
+    let mut holdouts = Vec::new();
+    writeln!(out, "machine,verdict,decider,steps,certificate").map_err(write_error)?;
+    for &(number, machine) in &machines {
+        let decision = match &mut staged {
+            Some(decisions) => decisions.next().unwrap(),
+            None => {
+                let states = machine.split('_').count();
+                with_states!(states, classify_states(machine, &deciders, limit))
+                    .map_err(|e| format!("{} line {}: {}", input, number + 1, e))?
+            }
+        };
+        let (verdict, steps) = match decision.verdict {
+            Verdict::Halts { steps, .. } => ("halts", steps.to_string()),
+            Verdict::NonHalting(Proof::TranslatedCycler { start, period, .. }) => {
+                ("non-halting", (start + period).to_string())
+            }
+            Verdict::NonHalting(_) => ("non-halting", String::new()),
+            Verdict::Undecided => ("undecided", limit.to_string()),
+        };
+        match decision.decider {
+            Some(decider) => {
+                if let Some(entry) = settled.iter_mut().find(|(name, _)| *name == decider) {
+                    entry.1 += 1;
+                }
+            }
+            None => holdouts.push(machine),
+        }
+        writeln!(
+            out,
+            "{},{},{},{},{}",
+            machine,
+            verdict,
+            decision.decider.unwrap_or(""),
+            steps,
+            csv_field(&format_certificate(&decision.verdict).unwrap_or_default())
+        )
+        .map_err(write_error)?;
+    }
+    out.flush().map_err(write_error)?;
+    drop(out);
+
+    for (name, count) in settled {
+        eprintln!("{:>10}  {}", count, name);
+    }
+    eprintln!("{:>10}  undecided", holdouts.len());
+    Ok(write_holdouts(
+        &holdouts,
+        holdouts_text.as_ref(),
+        holdouts_seed.as_ref(),
+    )?)
+}
+
+// known
+pub fn known(_args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    println!("states\tsymbols\tsteps\tsigma\tsteps champion\tsigma champion");
+    for known in KNOWN {
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            known.states,
+            known.symbols,
+            known.steps,
+            known.sigma,
+            known.steps_champion,
+            known.sigma_champion
+        );
+    }
+    Ok(())
+}
+
+fn evolve_states<const N: usize>(config: &Evolution, leaderboard: &str) -> Result<(), String> {
+    let mut leaderboard = Leaderboard::create(leaderboard).map_err(|e| e.to_string())?;
+    let scored = evolve::<N, _>(config, &mut leaderboard).map_err(|e| e.to_string())?;
+    leaderboard.finish(scored).map_err(|e| e.to_string())?;
+    println!("{} machines scored", scored);
+    Ok(())
+}
+
+// evolve <states> [--population N] [--generations N] [--seed N] [--limit N]
+//     [--score sigma|steps] [--leaderboard FILE]
+pub fn evolve_command(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let states: usize = parse_number("states", args.next())?;
+    let mut config = Evolution {
+        population: 200,
+        generations: 100,
+        seed: 1,
+        limit: 10_000,
+        score: Score::Sigma,
+    };
+    let mut leaderboard = String::from("leaderboard.tsv");
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--population" => config.population = parse_number(&arg, args.next())?,
+            "--generations" => config.generations = parse_number(&arg, args.next())?,
+            "--seed" => config.seed = parse_number(&arg, args.next())?,
+            "--limit" => config.limit = parse_number(&arg, args.next())?,
+            "--score" => {
+                config.score = match args.next().as_deref() {
+                    Some("sigma") => Score::Sigma,
+                    Some("steps") => Score::Steps,
+                    _ => return Err(usage("--score is sigma or steps")),
+                }
+            }
+            "--leaderboard" => {
+                leaderboard = args.next().ok_or(usage("--leaderboard needs a value"))?
+            }
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    Ok(with_states!(states, evolve_states(&config, &leaderboard))?)
+}
+
+fn sample_states<const N: usize>(sampling: &Sampling, deciders: &str) -> Result<(), String> {
+    let statistics = sample(sampling, &build_pipeline::<N>(deciders, 0)?);
+    print!("{}", statistics);
+    Ok(())
+}
+
+// sample <states> [--count N] [--seed N] [--budgets 100,10000,..]
+//     [--deciders a,b,..]
+pub fn sample_command(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let states: usize = parse_number("states", args.next())?;
+    let mut sampling = Sampling {
+        count: 10_000,
+        seed: 1,
+        budgets: vec![100, 10_000, 1_000_000],
+    };
+    // Plain simulation is what the budgets are for
+    let mut deciders: String = DEFAULT_DECIDERS
+        .split(',')
+        .filter(|&name| name != "simulation")
+        .collect::<Vec<_>>()
+        .join(",");
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--count" => sampling.count = parse_number(&arg, args.next())?,
+            "--seed" => sampling.seed = parse_number(&arg, args.next())?,
+            "--budgets" => {
+                let budgets = args.next().ok_or(usage("--budgets needs a value"))?;
+                sampling.budgets = budgets
+                    .split(',')
+                    .map(|budget| parse_number(&arg, Some(budget.to_string())))
+                    .collect::<Result<_, _>>()?;
+                if sampling.budgets.is_empty() || !sampling.budgets.is_sorted() {
+                    return Err(usage("--budgets must be increasing"));
+                }
+            }
+            "--deciders" => deciders = args.next().ok_or(usage("--deciders needs a value"))?,
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    check_deciders(&deciders)?;
+    Ok(with_states!(states, sample_states(&sampling, &deciders))?)
+}
+
+fn analyze_states<const N: usize>(machine: &str, steps: u128, records: usize) -> Result<(), String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    let mut run = ChainRun::new(&machine);
+    let (mut low, mut high) = (0, 0);
+    let mut shown = 0;
+    let result = loop {
+        if run.steps >= steps {
+            break "still running";
+        }
+        match run.step() {
+            Chain::Running => {}
+            Chain::Halted => break "halted",
+            Chain::Escaped => break "never halts, it runs off into blank tape",
+        }
+        // Configurations where the tape has just grown show its structure
+        // at comparable moments
+        if run.head < low || run.head > high {
+            low = low.min(run.head);
+            high = high.max(run.head);
+            if shown < records {
+                println!(
+                    "{:>12}  {}",
+                    run.steps,
+                    format_configuration(run.state, &run.tape)
+                );
+                shown += 1;
+            }
+        }
+    };
+    println!(
+        "{} after {} steps, taken as {} chain steps",
+        result, run.steps, run.chain_steps
+    );
+    println!(
+        "{:>12}  {}",
+        run.steps,
+        format_configuration(run.state, &run.tape)
+    );
+    Ok(())
+}
+
+// analyze <machine or holdout name> [--steps N] [--records N]
+pub fn analyze(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let name = args.next().ok_or(usage("analyze needs a machine"))?;
+    let machine = holdout(&name).map(String::from).unwrap_or(name);
+    let mut steps = 100_000_000;
+    let mut records = 40;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--steps" => steps = parse_number(&arg, args.next())?,
+            "--records" => records = parse_number(&arg, args.next())?,
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    let states = machine.trim().split('_').count();
+    Ok(with_states!(states, analyze_states(&machine, steps, records))?)
+}
+
+fn beep_states<const N: usize>(
+    machine: &str,
+    marked: Option<&str>,
+    limit: u64,
+) -> Result<(), String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    // Every transition beeps unless told otherwise
+    let marked = match marked {
+        Some(marked) => parse_transitions(marked, N)?,
+        None => (0..N).flat_map(|state| [(state, 0), (state, 1)]).collect(),
+    };
+    let run = run_beeping(&machine, &marked, limit);
+    for (transition, fired) in &run.last {
+        match fired {
+            Some(step) => println!(
+                "{} last fired at step {}",
+                format_transition(*transition),
+                step
+            ),
+            None => println!("{} never fired", format_transition(*transition)),
+        }
+    }
+    match run.outcome {
+        Beeps::Halted { steps, sigma } => {
+            println!("halts after {} steps leaving {} ones", steps, sigma)
+        }
+        Beeps::QuasiHalted { steps, transition } => println!(
+            "quasi-halts after {} steps, when {} fires for the last time",
+            steps,
+            format_transition(transition)
+        ),
+        Beeps::Forever => println!("never halts and keeps beeping"),
+        Beeps::Undecided => println!("undecided after {} steps", limit),
+    }
+    Ok(())
+}
+
+pub fn beep(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let machine = args.next().ok_or(usage("beep needs a machine"))?;
+    let mut marked = None;
+    let mut limit = 1_000_000;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--beep" => marked = Some(args.next().ok_or(usage("--beep needs a value"))?),
+            "--limit" => limit = parse_number(&arg, args.next())?,
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    let states = machine.trim().split('_').count();
+    Ok(with_states!(states, beep_states(&machine, marked.as_deref(), limit))?)
+}
+
+// Reruns a machine with chain steps far enough to settle a claim that it
+// halts after `steps` steps leaving `sigma` ones. Gives the result with the
+// steps and ones actually seen, the steps alone if it was still running.
+fn verify_claim_states<const N: usize>(
+    machine: &str,
+    steps: u128,
+    sigma: u64,
+    limit: u64,
+) -> Result<(&'static str, String, String), String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    let mut run = ChainRun::new(&machine);
+    while run.steps <= steps {
+        if run.chain_steps >= limit {
+            return Ok(("unsettled", run.steps.to_string(), String::new()));
+        }
+        match run.step() {
+            Chain::Running => {}
+            Chain::Halted => {
+                let result = match run.steps == steps && run.tape.ones() == sigma {
+                    true => "confirmed",
+                    false => "refuted",
+                };
+                return Ok((result, run.steps.to_string(), run.tape.ones().to_string()));
+            }
+            // Never halts
+            Chain::Escaped => return Ok(("refuted", String::new(), String::new())),
+        }
+    }
+    Ok(("refuted", run.steps.to_string(), String::new()))
+}
+
+pub fn verify_claims(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let input = args
+        .next()
+        .ok_or(usage("verify-claims needs a file of claims"))?;
+    let mut output = None;
+    let mut limit = 1_000_000_000;
+    let mut key = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => output = Some(args.next().ok_or(usage("--out needs a value"))?),
+            "--limit" => limit = parse_number(&arg, args.next())?,
+            "--key" => {
+                let path = args.next().ok_or(usage("--key needs a value"))?;
+                key = Some(fs::read(&path).map_err(|e| format!("{}: {}", path, e))?);
+            }
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    let text = fs::read_to_string(&input).map_err(|e| format!("{}: {}", input, e))?;
+
+    // Each claim is machine,steps,sigma. A header line is skipped.
+    let mut report = String::from("machine,claimed steps,claimed sigma,result,steps,sigma\n");
+    let mut counts = [0; 3];
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let bad = |e: String| format!("{} line {}: {}", input, number + 1, e);
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [machine, steps, sigma] = fields[..] else {
+            return Err(bad(String::from("expected machine,steps,sigma")).into());
+        };
+        let (Ok(claimed_steps), Ok(claimed_sigma)) = (steps.parse(), sigma.parse()) else {
+            if number == 0 {
+                continue;
+            }
+            return Err(bad(String::from("steps and sigma have to be numbers")).into());
+        };
+        let states = machine.split('_').count();
+        let (result, steps_seen, sigma_seen) = with_states!(
+            states,
+            verify_claim_states(machine, claimed_steps, claimed_sigma, limit)
+        )
+        .map_err(bad)?;
+        counts[["confirmed", "refuted", "unsettled"]
+            .iter()
+            .position(|&r| r == result)
+            .unwrap()] += 1;
+        report += &format!(
+            "{},{},{},{},{},{}\n",
+            machine, claimed_steps, claimed_sigma, result, steps_seen, sigma_seen
+        );
+    }
+    report += &format!(
+        "# {} claims, {} confirmed, {} refuted, {} unsettled\n",
+        counts.iter().sum::<u64>(),
+        counts[0],
+        counts[1],
+        counts[2]
+    );
+    // Signs everything above it, with the key if there is one so that the
+    // report can't be redone by someone without it
+    report += &match &key {
+        Some(key) => format!(
+            "# hmac-sha256 {}\n",
+            hex(&hmac_sha256(key, report.as_bytes()))
+        ),
+        None => format!("# sha256 {}\n", hex(&sha256(report.as_bytes()))),
+    };
+    match &output {
+        Some(path) => fs::write(path, &report).map_err(|e| format!("{}: {}", path, e))?,
+        None => print!("{}", report),
+    }
+    Ok(())
+}
+
+// The canonical form, then for each original state the letter it became,
+// then which symmetries were applied
+fn canonicalize_states<const N: usize>(
+    machine: &str,
+    symmetries: Symmetries,
+) -> Result<(String, String), String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    let canonical = canonicalize_tnf(&machine, symmetries);
+    let mut applied: String = canonical.states.iter().map(|&s| state_letter(s)).collect();
+    if canonical.mirrored {
+        applied += " mirrored";
+    }
+    if canonical.swapped {
+        applied += " swapped";
+    }
+    Ok((canonical.machine.to_string(), applied))
+}
+
+pub fn canonicalize(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let mut machines = Vec::new();
+    let mut symmetries = Symmetries::NONE;
+    let mut unique = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--in" => {
+                let path = args.next().ok_or(usage("--in needs a value"))?;
+                let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+                machines.extend(
+                    text.lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                        .map(String::from),
+                );
+            }
+            "--mirror" => symmetries.mirror = true,
+            "--swap-symbols" => symmetries.swap_symbols = true,
+            "--unique" => unique = true,
+            _ if !arg.starts_with("--") => machines.push(arg),
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    if machines.is_empty() {
+        return Err(usage("canonicalize needs machines"));
+    }
+    let mut seen = HashSet::new();
+    for machine in &machines {
+        let states = machine.split('_').count();
+        let (canonical, applied) = with_states!(states, canonicalize_states(machine, symmetries))?;
+        if unique && !seen.insert(canonical.clone()) {
+            continue;
+        }
+        println!("{}\t{}", canonical, applied);
+    }
+    Ok(())
+}
+
+// Tries the pipeline's deciders on a held out machine, skipping those
+// already attempted on it with the same limits. Returns the decider that
+// settled it and the verdict, if one did.
+fn retry_holdout_states<const N: usize>(
+    machine: &str,
+    deciders: &str,
+    limit: u64,
+    attempts: &mut Vec<String>,
+) -> Result<Option<(&'static str, Verdict)>, String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    for decider in build_pipeline::<N>(deciders, limit)?.deciders {
+        let tried = attempt(decider.as_ref());
+        if attempts.contains(&tried) {
+            continue;
+        }
+        attempts.push(tried);
+        let verdict = decider.decide(&machine);
+        if !matches!(verdict, Verdict::Undecided) {
+            return Ok(Some((decider.name(), verdict)));
+        }
+    }
+    Ok(None)
+}
+
+// holdouts import <list> <file...>: adds the machines in the files, either
+// one per line or as classify output, where only the undecided ones count
+fn import_holdouts(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let path = PathBuf::from(args.next().ok_or(usage("holdouts import needs a list"))?);
+    let mut list = HoldoutList::read(&path)?;
+    let mut added = 0;
+    for file in args {
+        let text = fs::read_to_string(&file).map_err(|e| format!("{}: {}", file, e))?;
+        let machines = text.lines().filter_map(|line| {
+            let line = line.trim();
+            match line.split(',').collect::<Vec<_>>()[..] {
+                _ if line.is_empty() || line.starts_with('#') => None,
+                [machine] => Some(machine),
+                [machine, "undecided", ..] => Some(machine),
+                _ => None,
+            }
+        });
+        added += list.import(machines);
+    }
+    list.write(&path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    eprintln!("{} new holdouts, {} in all", added, list.entries.len());
+    Ok(())
+}
+
+// holdouts run <list> [--deciders a,b,..] [--limit N] [--report FILE]:
+// drops the machines that get decided, reporting them as tab separated
+// lines of the machine, the decider and the verdict
+fn run_holdouts(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let path = PathBuf::from(args.next().ok_or(usage("holdouts run needs a list"))?);
+    let mut deciders = String::from(DEFAULT_DECIDERS);
+    let mut limit = 1_000_000;
+    let mut report = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--deciders" => deciders = args.next().ok_or(usage("--deciders needs a value"))?,
+            "--limit" => limit = parse_number(&arg, args.next())?,
+            "--report" => report = Some(args.next().ok_or(usage("--report needs a value"))?),
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    check_deciders(&deciders)?;
+    let mut list = HoldoutList::read(&path)?;
+    let mut out: Box<dyn Write> = match &report {
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).map_err(|e| format!("{}: {}", path, e))?,
+        )),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let write_error = |e: std::io::Error| e.to_string();
+
+    let before = list.entries.len();
+    let mut remaining = Vec::new();
+    for mut entry in list.entries {
+        let states = entry.machine.split('_').count();
+        let decided = with_states!(
+            states,
+            retry_holdout_states(&entry.machine, &deciders, limit, &mut entry.attempts)
+        )
+        .map_err(|e| format!("{}: {}", entry.machine, e))?;
+        match decided {
+            Some((decider, verdict)) => {
+                writeln!(out, "{}\t{}\t{}", entry.machine, decider, verdict).map_err(write_error)?
+            }
+            None => remaining.push(entry),
+        }
+    }
+    out.flush().map_err(write_error)?;
+    list.entries = remaining;
+    list.write(&path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    eprintln!(
+        "{} decided, {} still held out",
+        before - list.entries.len(),
+        list.entries.len()
+    );
+    Ok(())
+}
+
+// holdouts diff <old list> <new list>: the machines only in the old list
+// with -, and those only in the new one with +
+fn diff_holdouts(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let old = HoldoutList::read(Path::new(
+        &args.next().ok_or(usage("holdouts diff needs two lists"))?,
+    ))?;
+    let new = HoldoutList::read(Path::new(
+        &args.next().ok_or(usage("holdouts diff needs two lists"))?,
+    ))?;
+    let machines = |list: &HoldoutList| -> HashSet<String> {
+        list.entries
+            .iter()
+            .map(|entry| entry.machine.clone())
+            .collect()
+    };
+    let (in_old, in_new) = (machines(&old), machines(&new));
+    for entry in old
+        .entries
+        .iter()
+        .filter(|entry| !in_new.contains(&entry.machine))
+    {
+        println!("-{}", entry.machine);
+    }
+    for entry in new
+        .entries
+        .iter()
+        .filter(|entry| !in_old.contains(&entry.machine))
+    {
+        println!("+{}", entry.machine);
+    }
+    Ok(())
+}
+
+pub fn holdouts(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    match args.next().as_deref() {
+        Some("import") => import_holdouts(args),
+        Some("run") => run_holdouts(args),
+        Some("diff") => diff_holdouts(args),
+        _ => Err(usage("holdouts needs import, run or diff")),
+    }
+}
+
+fn string_param<'a>(params: &'a Json, key: &str) -> Result<&'a str, String> {
+    params
+        .get(key)
+        .and_then(Json::as_str)
+        .ok_or_else(|| format!("{} needs to be a string", key))
+}
+
+fn number_param(params: &Json, key: &str, default: u64) -> Result<u64, String> {
+    match params.get(key) {
+        None => Ok(default),
+        Some(value) => value
+            .as_u64()
+            .ok_or_else(|| format!("{} needs to be a whole number", key)),
+    }
+}
+
+fn rpc_parse<const N: usize>(machine: &str) -> Result<Json, String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    Ok(Json::object([
+        ("machine", Json::string(machine.to_string())),
+        ("states", Json::number(N)),
+        ("undefined", Json::number(machine.undefined())),
+    ]))
+}
+
+// The configuration after `steps` steps, or when the machine halts if
+// that's sooner, with the tape `radius` cells either side of the head
+fn rpc_step<const N: usize>(machine: &str, steps: u64, radius: u64) -> Result<Json, String> {
+    let mut tm = machine.parse::<PartialMachine<N>>()?.to_machine();
+    let mut tape = Tape::<u64>::new();
+    let mut taken = 0;
+    while let Index(state) = tm.state {
+        if taken == steps {
+            break;
+        }
+        tm.step(&mut tape, state);
+        taken += 1;
+    }
+    let head = tape.head_index();
+    let radius = radius.min(1 << 16) as i64;
+    let cells: String = (head - radius..=head + radius)
+        .map(|index| match tape.cell_at(index) {
+            Zero => '0',
+            One => '1',
+        })
+        .collect();
+    Ok(Json::object([
+        ("steps", Json::number(taken)),
+        ("halted", Json::Bool(matches!(tm.state, HALT))),
+        (
+            "state",
+            Json::string(match tm.state {
+                Index(state) => state_letter(state).to_string(),
+                HALT => String::from("Z"),
+            }),
+        ),
+        ("head", Json::number(head)),
+        ("start", Json::number(head - radius)),
+        ("tape", Json::string(cells)),
+        ("sigma", Json::number(tape.ones())),
+    ]))
+}
+
+fn rpc_run<const N: usize>(machine: &str, limit: u64) -> Result<Json, String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    Ok(match simulate(&machine, limit) {
+        Outcome::Halted { steps, sigma } => Json::object([
+            ("halted", Json::Bool(true)),
+            ("steps", Json::number(steps)),
+            ("sigma", Json::number(sigma)),
+        ]),
+        Outcome::Undecided => Json::object([
+            ("halted", Json::Bool(false)),
+            ("steps", Json::number(limit)),
+        ]),
+    })
+}
+
+fn rpc_describe_transition<const N: usize>(
+    machine: &str,
+    state: &str,
+    read: u64,
+) -> Result<Json, String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    let index = match state.as_bytes() {
+        &[letter @ b'A'..=b'Y'] if ((letter - b'A') as usize) < N => (letter - b'A') as usize,
+        _ => return Err(format!("the machine has no state {:?}", state)),
+    };
+    if read > 1 {
+        return Err(format!("cells hold 0 or 1, not {}", read));
+    }
+    let (transition, halts, description) = match machine.transitions[index][read as usize] {
+        None => (
+            String::from("---"),
+            true,
+            String::from("undefined, so the machine halts"),
+        ),
+        Some(step) => {
+            let motion = match step.motion {
+                Left => "left",
+                Right => "right",
+            };
+            let next = match step.next_state {
+                Index(next) => format!("go to {}", state_letter(next)),
+                HALT => String::from("halt"),
+            };
+            (
+                format_step(&step),
+                matches!(step.next_state, HALT),
+                format!("print {}, move {} and {}", step.print, motion, next),
+            )
+        }
+    };
+    Ok(Json::object([
+        ("transition", Json::string(transition)),
+        ("halts", Json::Bool(halts)),
+        ("description", Json::string(description)),
+    ]))
+}
+
+fn rpc_validate_machine<const N: usize>(machine: &str) -> Result<(), String> {
+    machine.parse::<PartialMachine<N>>().map(|_| ())
+}
+
+// Diagnostics for a file of machines, one per line, with lines and columns
+// counted from 0 as editors do
+fn rpc_validate(text: &str) -> Json {
+    let mut diagnostics = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let machine = line.trim();
+        if machine.is_empty() || machine.starts_with('#') {
+            continue;
+        }
+        let states = machine.split('_').count();
+        if let Err(e) = with_states!(states, rpc_validate_machine(machine)) {
+            let start = line.len() - line.trim_start().len();
+            diagnostics.push(Json::object([
+                ("line", Json::number(number)),
+                ("start", Json::number(start)),
+                ("end", Json::number(start + machine.len())),
+                ("message", Json::string(e)),
+            ]));
+        }
+    }
+    Json::object([("diagnostics", Json::Array(diagnostics))])
+}
+
+fn rpc_method(method: &str, params: &Json) -> Result<Json, RpcError> {
+    let machine = || {
+        let machine = string_param(params, "machine")?;
+        Ok::<_, String>((machine, machine.split('_').count()))
+    };
+    let result = match method {
+        "parse" => machine().and_then(|(machine, states)| with_states!(states, rpc_parse(machine))),
+        "validate" => string_param(params, "text").map(rpc_validate),
+        "step" => machine().and_then(|(machine, states)| {
+            let steps = number_param(params, "steps", 1)?;
+            let radius = number_param(params, "radius", 16)?;
+            with_states!(states, rpc_step(machine, steps, radius))
+        }),
+        "run" => machine().and_then(|(machine, states)| {
+            let limit = number_param(params, "limit", 1_000_000)?;
+            with_states!(states, rpc_run(machine, limit))
+        }),
+        "describe-transition" => machine().and_then(|(machine, states)| {
+            let state = string_param(params, "state")?;
+            let read = number_param(params, "read", 0)?;
+            with_states!(states, rpc_describe_transition(machine, state, read))
+        }),
+        _ => {
+            return Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("no method {:?}", method),
+            ))
+        }
+    };
+    result.map_err(|e| RpcError::new(INVALID_PARAMS, e))
+}
+
+// rpc
+//
+// Serves JSON-RPC on standard input and output for editors, see `rpc`. The
+// methods, with their params:
+//
+//     parse {machine}                    the machine, states, undefined
+//     validate {text}                    diagnostics, for a file of machines
+//     step {machine, steps?, radius?}    the configuration after some steps
+//     run {machine, limit?}              halted, steps, sigma
+//     describe-transition {machine, state, read}
+pub fn rpc_command(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    if let Some(arg) = args.next() {
+        return Err(unknown(&arg));
+    }
+    Ok(serve(
+        std::io::stdin().lock(),
+        std::io::stdout().lock(),
+        rpc_method,
+    )?)
+}
+
+// Tag words are unary, so they grow exponentially with the tape
+const MAX_TAG_WORD: usize = 1 << 22;
+
+// Steps of the machine running a tag system to check, which takes a pass
+// over the word for each tag step
+const MAX_TAG_MACHINE_STEPS: u64 = 100_000_000;
+
+fn tag_states<const N: usize>(machine: &str, limit: u64, productions: bool) -> Result<(), String> {
+    let partial: PartialMachine<N> = machine.parse()?;
+    let encoding = TagEncoding::new(&partial);
+    let system = &encoding.system;
+    if productions {
+        print!("{}", system);
+    }
+    println!(
+        "{}: a 2-tag system of {} symbols",
+        partial,
+        system.names.len()
+    );
+
+    // The machine against the tag system, a step of the machine at a time
+    let mut tm = partial.to_machine();
+    let mut tape = Tape::<u64>::new();
+    let mut word = encoding.start();
+    let mut steps = 0;
+    let mut tag_steps = 0;
+    let ending = loop {
+        let configuration = encoding.decode(&word).unwrap();
+        let state = match tm.state {
+            Index(state) => Some(state),
+            HALT => None,
+        };
+        if configuration.state != state
+            || split_tape(&tape) != Some((configuration.m, configuration.n))
+        {
+            return Err(format!(
+                "the tag system differs from the machine after {} steps",
+                steps
+            ));
+        }
+        let Some(state) = state else {
+            break String::from("the halt");
+        };
+        if steps == limit {
+            break String::from("the limit");
+        }
+        if word.len() > MAX_TAG_WORD {
+            break format!("a word of {} symbols", word.len());
+        }
+        loop {
+            if !system.step(&mut word) {
+                return Err(format!(
+                    "the tag system halted while the machine was at step {}",
+                    steps
+                ));
+            }
+            tag_steps += 1;
+            if encoding.decode(&word).is_some() {
+                break;
+            }
+        }
+        tm.step(&mut tape, state);
+        steps += 1;
+    };
+    println!(
+        "the machine and the tag system agree for {} steps, up to {} ({} tag steps)",
+        steps, ending, tag_steps
+    );
+
+    // And the tag system against a machine running it, a tag step at a time,
+    // as far as the halt if the tag system got there
+    let halted = matches!(tm.state, HALT);
+    let turing = tag_to_turing(system)?;
+    let mut word = encoding.start();
+    let mut run = WideRun::new(&turing, word.iter().map(|&s| s as u8 + 1).collect());
+    let mut machine_steps = 0;
+    let mut checked = 0;
+    let ending = loop {
+        let on_tape = run.tape.iter().filter(|&&cell| cell != 0);
+        if !on_tape
+            .map(|&cell| cell as usize - 1)
+            .eq(word.iter().copied())
+        {
+            return Err(format!(
+                "the machine running the tag system differs from it after {} tag steps",
+                checked
+            ));
+        }
+        if run.halted() {
+            break String::from("the halt");
+        }
+        if (checked == tag_steps && !halted) || machine_steps > MAX_TAG_MACHINE_STEPS {
+            break String::from("the limit");
+        }
+        let going = system.step(&mut word);
+        loop {
+            run.step()?;
+            machine_steps += 1;
+            if run.state == 0 || run.halted() {
+                break;
+            }
+        }
+        if going == run.halted() {
+            return Err(format!(
+                "the machine running the tag system halts differently after {} tag steps",
+                checked
+            ));
+        }
+        checked += going as u64;
+    };
+    println!(
+        "the tag system and a machine of {} states and {} symbols running it agree for {} tag steps, up to {} ({} steps)",
+        turing.transitions.len() / turing.symbols,
+        turing.symbols,
+        checked,
+        ending,
+        machine_steps
+    );
+    Ok(())
+}
+
+// tag <machine> [--limit N] [--productions]
+//
+// Turns the machine into a 2-tag system and that into a machine again, see
+// `models`, and checks that each runs the same as the one it came from
+pub fn tag(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let machine = args.next().ok_or(usage("tag needs a machine"))?;
+    let mut limit = 1_000;
+    let mut productions = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--limit" => limit = parse_number(&arg, args.next())?,
+            "--productions" => productions = true,
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    let states = machine.split('_').count();
+    Ok(with_states!(states, tag_states(&machine, limit, productions))?)
+}
+
+// Steps to take of each machine running another, which take far more steps
+// than the one they run
+const MAX_COUNTER_STEPS: u64 = 100_000_000;
+
+fn counter_states<const N: usize>(machine: &str, limit: u64, program: bool) -> Result<(), String> {
+    let partial: PartialMachine<N> = machine.parse()?;
+    let encoding = CounterEncoding::new(&partial);
+    let counters = &encoding.machine;
+    if program {
+        print!("{}", counters);
+    }
+    println!(
+        "{}: a 4-counter machine of {} instructions",
+        partial,
+        counters.program.len()
+    );
+
+    // The machine against the counter machine, a step of the machine at a
+    // time
+    let mut tm = partial.to_machine();
+    let mut tape = Tape::<u64>::new();
+    let mut values = vec![0; counters.counters];
+    let mut at = 0;
+    let mut steps = 0;
+    let mut counter_steps = 0;
+    let ending = loop {
+        let state = match tm.state {
+            Index(state) => Some(state),
+            HALT => None,
+        };
+        if encoding.starts[state.unwrap_or(N)] != at
+            || split_tape(&tape) != Some((values[0], values[1]))
+        {
+            return Err(format!(
+                "the counter machine differs from the machine after {} steps",
+                steps
+            ));
+        }
+        let Some(state) = state else {
+            break String::from("the halt");
+        };
+        if steps == limit {
+            break String::from("the limit");
+        }
+        if counter_steps > MAX_COUNTER_STEPS {
+            break String::from("the counter machine's limit");
+        }
+        loop {
+            at = counters.step(&mut values, at).ok_or_else(|| {
+                format!(
+                    "the counter machine halted while the machine was at step {}",
+                    steps
+                )
+            })?;
+            counter_steps += 1;
+            if encoding.starts.contains(&at) {
+                break;
+            }
+        }
+        tm.step(&mut tape, state);
+        steps += 1;
+    };
+    println!(
+        "the machine and the counter machine agree for {} steps, up to {} ({} counter steps)",
+        steps, ending, counter_steps
+    );
+
+    // The counter machine against machines running it, an instruction at a
+    // time, as far as it got above
+    let (two, starts) = to_two_counters(counters)?;
+    let halts = |at: usize| matches!(counters.program[at], Instruction::Halt);
+    let mut values = vec![0; counters.counters];
+    let mut at = 0;
+    // The first step sets up the 2-counter machine's number
+    let mut two_values = vec![0; 2];
+    let mut two_at = two.step(&mut two_values, 0).unwrap();
+    let mut two_steps = 1;
+    let mut checked = 0;
+    let ending = loop {
+        let Some(number) = godel_number(&values) else {
+            break String::from("a number past 64 bits");
+        };
+        if two_at != starts[at] || two_values != [number, 0] {
+            return Err(format!(
+                "the 2-counter machine differs from the 4-counter machine after {} steps",
+                checked
+            ));
+        }
+        if halts(at) {
+            break String::from("the halt");
+        }
+        if checked == counter_steps || two_steps > MAX_COUNTER_STEPS {
+            break String::from("the limit");
+        }
+        at = counters.step(&mut values, at).unwrap();
+        loop {
+            two_at = two.step(&mut two_values, two_at).unwrap();
+            two_steps += 1;
+            if starts.contains(&two_at) {
+                break;
+            }
+        }
+        checked += 1;
+    };
+    println!(
+        "the 4-counter machine and a 2-counter machine of {} instructions running it agree for {} steps, up to {} ({} steps)",
+        two.program.len(),
+        checked,
+        ending,
+        two_steps
+    );
+
+    let (turing, starts) = counters_to_turing(counters);
+    let mut values = vec![0; counters.counters];
+    let mut at = 0;
+    let mut run = WideRun::new(&turing, counter_tape(counters.counters));
+    run.state = starts[0];
+    let mut turing_steps = 0;
+    let mut checked = 0;
+    let ending = loop {
+        if run.state != starts[at] || read_counters(&run.tape) != values {
+            return Err(format!(
+                "the machine running the counter machine differs from it after {} steps",
+                checked
+            ));
+        }
+        if halts(at) {
+            break String::from("the halt");
+        }
+        if checked == counter_steps || turing_steps > MAX_COUNTER_STEPS {
+            break String::from("the limit");
+        }
+        at = counters.step(&mut values, at).unwrap();
+        loop {
+            run.step()?;
+            turing_steps += 1;
+            if run.halted() || starts.contains(&run.state) {
+                break;
+            }
+        }
+        checked += 1;
+    };
+    println!(
+        "the 4-counter machine and a machine of {} states and {} symbols running it agree for {} steps, up to {} ({} steps)",
+        turing.transitions.len() / turing.symbols,
+        turing.symbols,
+        checked,
+        ending,
+        turing_steps
+    );
+    Ok(())
+}
+
+// counter <machine> [--limit N] [--program]
+//
+// Turns the machine into a 4-counter machine, and that into a 2-counter
+// machine and into a machine again, see `models`, and checks that each
+// runs the same as the one it came from
+pub fn counter(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let machine = args.next().ok_or(usage("counter needs a machine"))?;
+    let mut limit = 1_000;
+    let mut program = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--limit" => limit = parse_number(&arg, args.next())?,
+            "--program" => program = true,
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    let states = machine.split('_').count();
+    Ok(with_states!(states, counter_states(&machine, limit, program))?)
+}
+
+// The cells holding 1s, counted from the head
+fn ones_from_head(tape: &VecDeque<u8>, head: usize) -> Vec<isize> {
+    (0..tape.len())
+        .filter(|&i| tape[i] != 0)
+        .map(|i| i as isize - head as isize)
+        .collect()
+}
+
+// wang <program> [--limit N]
+//
+// Compiles a B-machine program, given in a file or as text, into a Turing
+// machine, see `wang`, and runs both to check they leave the same tape
+pub fn wang_command(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let program = parse_program(&text_or_file(
+        args.next().ok_or(usage("wang needs a program"))?,
+    )?)?;
+    let mut limit = 1_000_000;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--limit" => limit = parse_number(&arg, args.next())?,
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    let machine = compile(&program);
+    let states = machine.transitions.len() / 2;
+    match format_compiled(&machine) {
+        Ok(standard) => println!(
+            "{} instructions compile to {} states: {}",
+            program.len(),
+            states,
+            standard
+        ),
+        Err(e) => println!(
+            "{} instructions compile to {} states ({})",
+            program.len(),
+            states,
+            e
+        ),
+    }
+
+    let mut tape = VecDeque::from([0]);
+    let mut head = 0;
+    let Some(instructions) = run_program(&program, &mut tape, &mut head, limit) else {
+        println!("the program is still running after {} instructions", limit);
+        return Ok(());
+    };
+    // Every instruction takes the machine one or two steps, and stopping two
+    let mut run = WideRun::new(&machine, VecDeque::from([0]));
+    let mut steps = 0;
+    while !run.halted() {
+        if steps > 2 * instructions + 2 {
+            return Err(format!(
+                "the machine is still running after {} steps",
+                steps
+            )
+            .into());
+        }
+        run.step()?;
+        steps += 1;
+    }
+    let ones = ones_from_head(&tape, head);
+    if ones != ones_from_head(&run.tape, run.head) {
+        return Err(String::from("the machine leaves a different tape from the program").into());
+    }
+    println!(
+        "the program stops after {} instructions and the machine after {} steps, with the same {} ones",
+        instructions,
+        steps,
+        ones.len()
+    );
+    Ok(())
+}
+
+// bf <program> [--limit N]
+//
+// Compiles a Brainfuck program, given in a file or as text, into a Turing
+// machine, see `bf`, and runs both to check they leave the same cells
+pub fn bf_command(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let ops = parse_bf(&text_or_file(
+        args.next().ok_or(usage("bf needs a program"))?,
+    )?)?;
+    let mut limit = 100_000_000;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--limit" => limit = parse_number(&arg, args.next())?,
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    let machine = compile_bf(&ops);
+    println!(
+        "{} operations compile to {} states over {} symbols",
+        ops.len(),
+        machine.transitions.len() / machine.symbols,
+        machine.symbols
+    );
+
+    let Some(program) = run_bf(&ops, limit)? else {
+        println!("the program is still running after {} operations", limit);
+        return Ok(());
+    };
+    if !program.output.is_empty() {
+        println!("output: {}", String::from_utf8_lossy(&program.output));
+    }
+    // Every operation takes the machine one or two steps, as does stopping
+    let mut run = WideRun::new(&machine, VecDeque::from([0]));
+    let mut steps = 0;
+    while !run.halted() {
+        if steps > 2 * program.steps + 2 {
+            return Err(format!(
+                "the machine is still running after {} steps",
+                steps
+            )
+            .into());
+        }
+        run.step()?;
+        steps += 1;
+    }
+    let mut cells = program.cells;
+    while cells.last() == Some(&0) {
+        cells.pop();
+    }
+    if cells != tape_cells(&run.tape) {
+        return Err(String::from("the machine leaves different cells from the program").into());
+    }
+    let values: Vec<String> = cells.iter().map(u8::to_string).collect();
+    println!(
+        "the program stops after {} operations and the machine after {} steps, leaving {}",
+        program.steps,
+        steps,
+        match values.is_empty() {
+            true => String::from("a blank tape"),
+            false => format!("the cells {}", values.join(" ")),
+        }
+    );
+    Ok(())
+}
+
+fn history_states<const N: usize>(
+    machine: &str,
+    steps: u64,
+    out: &mut Box<dyn Write>,
+    rule: Option<&str>,
+) -> Result<(), String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    let mut tm = machine.to_machine();
+    let mut tape = Tape::<u8>::new();
+    let mut history = History::new(N);
+    let taken = tm.history(&mut tape, steps, &mut history);
+    history.write_rle(out, rule).map_err(|e| e.to_string())?;
+    eprintln!(
+        "{} rows from {} steps, {}, using {} cell states, {} KiB of history and {} KiB of tape",
+        history.len(),
+        taken,
+        match tm.state {
+            HALT => "halted",
+            Index(_) => "still running",
+        },
+        history.cell_states(),
+        history.memory_usage() / 1024,
+        tape.memory_usage() / 1024
+    );
+    Ok(())
+}
+
+// history <machine> [--steps N] [--out FILE] [--rule NAME]
+//
+// Writes a run as a one-dimensional cellular automaton history in Golly's
+// RLE format, see `history`
+pub fn history(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let machine = args.next().ok_or(usage("history needs a machine"))?;
+    let mut steps = 1_000;
+    let mut output = None;
+    let mut rule = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--steps" => steps = parse_number(&arg, args.next())?,
+            "--out" => output = Some(args.next().ok_or(usage("--out needs a value"))?),
+            "--rule" => rule = Some(args.next().ok_or(usage("--rule needs a value"))?),
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    let mut out: Box<dyn Write> = match &output {
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).map_err(|e| format!("{}: {}", path, e))?,
+        )),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let states = machine.trim().split('_').count();
+    Ok(with_states!(
+        states,
+        history_states(&machine, steps, &mut out, rule.as_deref())
+    )?)
+}
+
+fn trace_states<const N: usize>(machine: &str, options: &DisplayOptions) -> Result<(), String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    let mut tape = Tape::<u64>::new();
+    machine.to_machine().run_verbose_with(&mut tape, options);
+    Ok(())
+}
+
+// trace <machine> [--step-numbers] [--ruler N] [--mark-steps ..] [--trace-every N]
+//     [--table] [--glyphs 01[ ^] | --blocks] [--group N] [--separator C]
+//
+// Prints a machine's run from a blank tape as the demo does, with the same
+// flags. With --trace-every a run of any length can be watched, a frame at
+// a time, see `run_verbose_into`, and with --table the whole transition
+// table follows each frame with the transition about to be taken marked.
+pub fn trace_command(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let machine = args.next().ok_or(usage("trace needs a machine"))?;
+    let options = parse_display_options(args).map_err(Failure::Usage)?;
+    let states = machine.trim().split('_').count();
+    Ok(with_states!(states, trace_states(&machine, &options))?)
+}
+
+fn spacetime_states<const N: usize>(
+    machine: &str,
+    limit: u64,
+    mut diagram: SpaceTime,
+    out: Option<(&str, Aggregate)>,
+    options: &DisplayOptions,
+) -> Result<(), String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    let mut tm = machine.to_machine();
+    let mut tape = Tape::<u64>::new();
+    let steps = tm.space_time(&mut tape, limit, &mut diagram);
+    match out {
+        Some((path, aggregate)) => {
+            let file = File::create(path).map_err(|e| format!("{}: {}", path, e))?;
+            let mut out = BufWriter::new(file);
+            match path.ends_with(".svg") {
+                true => diagram.write_svg(&mut out, aggregate, 2),
+                false => diagram.write_pgm(&mut out, aggregate),
+            }
+            .and_then(|_| out.flush())
+            .map_err(|e| format!("{}: {}", path, e))?;
+        }
+        None => print!("{}", diagram.render(options)),
+    }
+    eprintln!(
+        "{} after {} steps, {} KiB of diagram",
+        match tm.state {
+            HALT => "halted",
+            Index(_) => "still running",
+        },
+        steps,
+        diagram.memory_usage() / 1024
+    );
+    Ok(())
+}
+
+// spacetime <machine> [--limit N] [--width W] [--rows R] [--out FILE]
+//     [--aggregate any|density] [--step-numbers] [--ruler N] [--mark-steps ..]
+//     [--glyphs 01[ ^] | --blocks] [--group N] [--separator C]
+//
+// Draws a machine's run from a blank tape as a space-time diagram, a row of
+// the tape for each step, in at most --rows rows of --width columns however
+// long the run, see `spacetime`. It's printed as text, or written to --out
+// as a PGM image, or an SVG one if the name ends in .svg, where the cells
+// that share a pixel are shown as whether any is a one or by how many are.
+pub fn spacetime(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let machine = args.next().ok_or(usage("spacetime needs a machine"))?;
+    let mut limit = 1_000_000;
+    let mut width: usize = 128;
+    let mut rows: usize = 512;
+    let mut out = None;
+    let mut aggregate = Aggregate::Any;
+    let mut options = DisplayOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--limit" => limit = parse_count(&arg, args.next())?,
+            "--width" => width = parse_number(&arg, args.next())?,
+            "--rows" => rows = parse_number(&arg, args.next())?,
+            "--out" => out = Some(args.next().ok_or(usage("--out needs a value"))?),
+            "--aggregate" => {
+                aggregate = match args
+                    .next()
+                    .ok_or(usage("--aggregate needs a value"))?
+                    .as_str()
+                {
+                    "any" | "max" => Aggregate::Any,
+                    "density" => Aggregate::Density,
+                    other => return Err(Failure::Usage(format!("bad --aggregate {}", other))),
+                }
+            }
+            _ if parse_display_option(&mut options, &arg, &mut args).map_err(Failure::Usage)? => {}
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    // Halving the window has to land on columns, and thinning out on rows
+    if width < 4 || !width.is_multiple_of(4) {
+        return Err(Failure::Usage(format!(
+            "--width {} isn't a multiple of 4",
+            width
+        )));
+    }
+    if rows < 2 {
+        return Err(usage("--rows needs to be at least 2"));
+    }
+    let diagram = SpaceTime::new(width, rows);
+    let out = out.as_deref().map(|path| (path, aggregate));
+    let states = machine.trim().split('_').count();
+    Ok(with_states!(
+        states,
+        spacetime_states(&machine, limit, diagram, out, &options)
+    )?)
+}
+
+fn compare_states<const N: usize>(
+    a: &str,
+    b: &str,
+    limit: u64,
+    options: &DisplayOptions,
+) -> Result<(), String> {
+    let a: PartialMachine<N> = pad(a, N).parse()?;
+    let b: PartialMachine<N> = pad(b, N).parse()?;
+    let (mut tape_a, mut tape_b) = (Tape::<u64>::new(), Tape::<u64>::new());
+    let (mut a, mut b) = (a.to_machine(), b.to_machine());
+    match run_side_by_side(&mut a, &mut tape_a, &mut b, &mut tape_b, limit, options) {
+        Some(step) => println!("their configurations first differ at step {}", step),
+        None => println!("their configurations are the same at every step"),
+    }
+    Ok(())
+}
+
+// compare <machine> <machine> [--limit N] [--step-numbers] [--ruler N] [--mark-steps ..]
+//     [--glyphs 01[ ^] | --blocks] [--group N] [--separator C]
+//
+// Traces two machines side by side from a blank tape, for at most --limit
+// steps, and says the first step their configurations differ at, see
+// `compare`. `equivalent` is the one to ask whether they behave the same.
+pub fn compare(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let a = args.next().ok_or(usage("compare needs two machines"))?;
+    let b = args.next().ok_or(usage("compare needs two machines"))?;
+    let mut limit = 100;
+    let mut options = DisplayOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--limit" => limit = parse_count(&arg, args.next())?,
+            _ if parse_display_option(&mut options, &arg, &mut args).map_err(Failure::Usage)? => {}
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    let states = a.trim().split('_').count().max(b.trim().split('_').count());
+    Ok(with_states!(states, compare_states(&a, &b, limit, &options))?)
+}
+
+// How `run` lays out the tape when told to, rather than leaving it to the
+// strategy, see `chunked`
+struct TapeLayout {
+    growth: Growth,
+    // Cells set aside either side of cell 0 before the run starts
+    reserve: usize,
+}
+
+fn run_states<const N: usize>(
+    machine: &str,
+    strategy: Option<Strategy>,
+    progress: Option<u64>,
+    layout: Option<&TapeLayout>,
+    limit: u64,
+) -> Result<(), String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    let outcome = match (strategy, progress, layout) {
+        (Some(strategy), _, _) => run_with(&machine, strategy, limit),
+        (None, None, None) => run_auto(&machine, limit),
+        (None, progress, layout) => {
+            let mut tape = Tape::<u64>::with_growth(
+                layout.map_or_else(Growth::default, |layout| layout.growth),
+            );
+            if let Some(layout) = layout {
+                tape.reserve(layout.reserve);
+            }
+            let mut tm = machine.to_machine();
+            let halted = match progress {
+                Some(seconds) => {
+                    tm.run_with_progress(&mut tape, limit, Duration::from_secs(seconds))
+                }
+                None => tm.run_limited(&mut tape, limit),
+            };
+            match halted {
+                Some(steps) => Outcome::Halted {
+                    steps: steps as u128,
+                    sigma: tape.ones(),
+                },
+                None => Outcome::Undecided,
+            }
+        }
+    };
+    match outcome {
+        Outcome::Halted { steps, sigma } => {
+            println!("halts after {} steps leaving {} ones", steps, sigma)
+        }
+        Outcome::Undecided => println!("undecided after {} steps", limit),
+    }
+    Ok(())
+}
+
+// How the halves of the tape grow, as comma separated parts: double to
+// double the first chunk each time it fills, as a Vec would, +N to add N
+// words instead, chunk=BITS for chunks of 2^BITS words, and huge for chunks
+// that are each a whole huge page of u64 words, aligned to one and backed by
+// one where the kernel can, see `Growth::huge`, which the parts after it
+// can change
+fn parse_growth(value: Option<String>) -> Result<Growth, Failure> {
+    let value = value.ok_or(usage("--growth needs a value"))?;
+    let bad = || Failure::Usage(format!("bad --growth {}", value));
+    let mut growth = Growth::default();
+    for part in value.split(',') {
+        if part == "double" {
+            growth.increment = None;
+        } else if part == "huge" {
+            growth = Growth::huge::<u64>();
+        } else if let Some(words) = part.strip_prefix('+') {
+            growth.increment = Some(
+                words
+                    .parse()
+                    .ok()
+                    .filter(|&words| words > 0)
+                    .ok_or_else(bad)?,
+            );
+        } else if let Some(bits) = part.strip_prefix("chunk=") {
+            growth.chunk_bits = bits
+                .parse()
+                .ok()
+                .filter(|bits| (1..=32).contains(bits))
+                .ok_or_else(bad)?;
+        } else {
+            return Err(bad());
+        }
+    }
+    Ok(growth)
+}
+
+// run <machine> [--limit N] [--strategy auto|flat|lut|chain | --progress SECONDS]
+//     [--reserve CELLS] [--growth double|+N|chunk=BITS|huge]
+//
+// Runs a machine from a blank tape, by default under whichever strategy a
+// short probe of each finds fastest, see `strategy`. With --progress it runs
+// through the flattened table, keeping a status line on stderr up to date
+// every so many seconds. So it does with --reserve, which allocates room for
+// that many cells either side of the start before the run, or --growth,
+// which says how the tape grows past what it has, see `parse_growth`.
+pub fn run_command(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let machine = args.next().ok_or(usage("run needs a machine"))?;
+    let mut limit = 100_000_000;
+    let mut strategy = None;
+    let mut strategy_given = false;
+    let mut progress = None;
+    let mut growth = None;
+    let mut reserve = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--limit" => limit = parse_number(&arg, args.next())?,
+            "--strategy" => {
+                strategy_given = true;
+                strategy = match args
+                    .next()
+                    .ok_or(usage("--strategy needs a value"))?
+                    .as_str()
+                {
+                    "auto" => None,
+                    name => Some(name.parse().map_err(Failure::Usage)?),
+                }
+            }
+            "--progress" => progress = Some(parse_number(&arg, args.next())?),
+            "--reserve" => reserve = Some(parse_count(&arg, args.next())? as usize),
+            "--growth" => growth = Some(parse_growth(args.next())?),
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    let layout = match (growth, reserve) {
+        (None, None) => None,
+        (growth, reserve) => Some(TapeLayout {
+            growth: growth.unwrap_or_default(),
+            reserve: reserve.unwrap_or(0),
+        }),
+    };
+    if let (true, Some(flag)) = (
+        strategy_given,
+        progress
+            .map(|_| "--progress")
+            .or(reserve.map(|_| "--reserve"))
+            .or(growth.map(|_| "--growth")),
+    ) {
+        return Err(Failure::Usage(format!(
+            "{} can't be used with --strategy",
+            flag
+        )));
+    }
+    let states = machine.trim().split('_').count();
+    Ok(with_states!(
+        states,
+        run_states(&machine, strategy, progress, layout.as_ref(), limit)
+    )?)
+}
+
+// The inputs given, then every input of each length in `all`, which are
+// made a batch at a time from a counter since there can be billions
+fn sweep_states<const N: usize>(
+    machine: &str,
+    inputs: &[Vec<Bit>],
+    all: &[usize],
+    limit: u64,
+) -> Result<(), String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    let machine = machine.to_machine();
+    let (mut swept, mut halted, mut fell) = (0u64, 0u64, 0u64);
+    let mut sweep_batch = |batch: &[Vec<Bit>]| {
+        let mut run = SlicedRun::new(&machine, batch);
+        run.run(limit);
+        swept += batch.len() as u64;
+        for (input, lane) in batch.iter().zip(&run.lanes) {
+            let input: String = input
+                .iter()
+                .map(|&bit| (b'0' + bit as u8) as char)
+                .collect();
+            let input = if input.is_empty() { "blank" } else { &input };
+            match *lane {
+                Lane::Halted { steps, sigma } => {
+                    halted += 1;
+                    println!("{}\thalts after {} steps leaving {} ones", input, steps, sigma)
+                }
+                Lane::FellOff { steps, side } => {
+                    fell += 1;
+                    println!(
+                        "{}\tfalls off the {} after {} steps",
+                        input,
+                        if side == Left { "left" } else { "right" },
+                        steps
+                    )
+                }
+                Lane::Running => println!("{}\tundecided after {} steps", input, limit),
+            }
+        }
+    };
+    for batch in inputs.chunks(sliced::LANES) {
+        sweep_batch(batch);
+    }
+    for &cells in all {
+        let count = 1u64 << cells;
+        for first in (0..count).step_by(sliced::LANES) {
+            let batch: Vec<Vec<Bit>> = (first..count.min(first + sliced::LANES as u64))
+                .map(|input| {
+                    (0..cells)
+                        .map(|i| if input >> i & 1 == 0 { Zero } else { One })
+                        .collect()
+                })
+                .collect();
+            sweep_batch(&batch);
+        }
+    }
+    eprintln!(
+        "{} inputs, {} halt, {} fall off the tape, {} undecided",
+        swept,
+        halted,
+        fell,
+        swept - halted - fell
+    );
+    Ok(())
+}
+
+// sweep <machine> (--input BITS.. | --all K) [--limit N]
+//
+// Runs a machine on each input, given with --input as often as needed, or
+// every input of K cells with --all, 64 at a time on tapes of
+// `sliced::WIDTH` cells, see `sliced`. The head starts on the first cell of
+// the input, halfway along the tape.
+pub fn sweep(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let machine = args.next().ok_or(usage("sweep needs a machine"))?;
+    let mut inputs = Vec::new();
+    let mut all = Vec::new();
+    let mut limit = 1_000_000;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" => inputs.push(parse_input(args.next())?),
+            "--all" => {
+                let cells: usize = parse_number(&arg, args.next())?;
+                if cells > sliced::WIDTH - sliced::START {
+                    return Err(Failure::Usage(format!("--all {} doesn't fit on the tape", cells)));
+                }
+                all.push(cells);
+            }
+            "--limit" => limit = parse_number(&arg, args.next())?,
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    if inputs.is_empty() && all.is_empty() {
+        return Err(usage("sweep needs --input or --all"));
+    }
+    if inputs
+        .iter()
+        .any(|input| input.len() > sliced::WIDTH - sliced::START)
+    {
+        return Err(usage("an --input doesn't fit on the tape"));
+    }
+    let states = machine.trim().split('_').count();
+    Ok(with_states!(
+        states,
+        sweep_states(&machine, &inputs, &all, limit)
+    )?)
+}
+
+fn watch_states<const N: usize>(
+    machine: &str,
+    speed: f64,
+    limit: u64,
+    cast: Option<&str>,
+    options: &DisplayOptions,
+) -> Result<(), String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    let mut tm = machine.to_machine();
+    let mut tape = Tape::<u64>::new();
+    let steps = match cast {
+        Some(path) => {
+            let file = File::create(path).map_err(|e| format!("{}: {}", path, e))?;
+            let out = &mut BufWriter::new(file);
+            let delay = Duration::from_secs_f64(1.0 / speed);
+            tm.write_cast(&mut tape, out, delay, limit, options)
+                .and_then(|steps| out.flush().map(|_| steps))
+                .map_err(|e| format!("{}: {}", path, e))?
+        }
+        None => {
+            let out = &mut BufWriter::new(std::io::stdout().lock());
+            tm.watch(&mut tape, out, speed, limit, options)
+                .map_err(|e| e.to_string())?
+        }
+    };
+    eprintln!(
+        "{} after {} steps",
+        match tm.state {
+            HALT => "halted",
+            Index(_) => "still running",
+        },
+        steps
+    );
+    Ok(())
+}
+
+// watch <machine> [--speed STEPS] [--limit N] [--cast FILE]
+//     [--glyphs 01[ ^] | --blocks] [--group N] [--separator C]
+//
+// Plays a machine's run from a blank tape in the terminal, --speed steps a
+// second (at least 0.01), see `cast`, where it can be paused, scrubbed
+// through and taken to any step with :goto N. With --cast the run is
+// recorded to an asciinema file at that speed instead, by default for at
+// most 10000 steps.
+pub fn watch(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let machine = args.next().ok_or(usage("watch needs a machine"))?;
+    let mut speed: f64 = 20.0;
+    let mut limit = None;
+    let mut cast = None;
+    let mut options = DisplayOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--speed" => speed = parse_number(&arg, args.next())?,
+            "--limit" => limit = Some(parse_number(&arg, args.next())?),
+            "--cast" => cast = Some(args.next().ok_or(usage("--cast needs a value"))?),
+            _ if parse_display_option(&mut options, &arg, &mut args).map_err(Failure::Usage)? => {}
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    // A step every 100 seconds at the slowest, so that when each step is
+    // due always fits in a Duration
+    if speed.is_nan() || speed < 0.01 {
+        return Err(Failure::Usage(format!("bad --speed {}", speed)));
+    }
+    let states = machine.trim().split('_').count();
+    // A recording that never ends fills the disk rather than the screen
+    let limit = limit.unwrap_or(if cast.is_some() { 10_000 } else { u64::MAX });
+    Ok(with_states!(
+        states,
+        watch_states(&machine, speed, limit, cast.as_deref(), &options)
+    )?)
+}
+
+// The cells of a tape split as `TagEncoding` does, with the head bracketed
+fn format_split(m: u64, n: u64) -> String {
+    let bits = |x: u64| (u64::BITS - x.leading_zeros()) as usize;
+    let left: String = (0..bits(m))
+        .rev()
+        .map(|i| char::from(b'0' + (m >> i & 1) as u8))
+        .collect();
+    let right: String = (1..bits(n))
+        .map(|i| char::from(b'0' + (n >> i & 1) as u8))
+        .collect();
+    format!("{}[{}]{}", left, n & 1, right)
+}
+
+// The cells of an --input, from the head rightwards
+fn parse_input(value: Option<String>) -> Result<Vec<Bit>, Failure> {
+    let bits = value.ok_or(usage("--input needs a value"))?;
+    bits.chars()
+        .map(|c| match c {
+            '0' => Ok(Zero),
+            '1' => Ok(One),
+            _ => Err(Failure::Usage(format!("bad --input {}", bits))),
+        })
+        .collect()
+}
+
+fn utm_states<const N: usize>(machine: &str, input: &[Bit], limit: u64) -> Result<(), String> {
+    let partial: PartialMachine<N> = machine.parse()?;
+    let universal = universal();
+    let (encoding, tape) = encode_machine(&partial, input)?;
+    println!(
+        "{} becomes a tag system of {} symbols, written on {} cells for a universal machine of {} states and {} symbols",
+        partial,
+        encoding.system.names.len(),
+        tape.len(),
+        universal.transitions.len() / universal.symbols,
+        universal.symbols
+    );
+    let mut run = WideRun::new(&universal, tape);
+    let mut steps = 0;
+    while !run.halted() {
+        if steps == limit {
+            println!(
+                "the universal machine is still running after {} steps",
+                limit
+            );
+            return Ok(());
+        }
+        run.step()?;
+        steps += 1;
+    }
+    let configuration = decode_machine(&encoding, &run.tape)
+        .ok_or("the universal machine halted without a configuration on its tape")?;
+
+    // And the machine itself on the same input
+    let mut tm = partial.to_machine();
+    let mut tape = Tape::<u64>::new();
+    for bit in input {
+        tape.set(*bit);
+        tape.move_tape(Right);
+    }
+    for _ in input {
+        tape.move_tape(Left);
+    }
+    let Some(direct) = tm.run_limited(&mut tape, steps) else {
+        return Err(String::from(
+            "the universal machine halted but the machine doesn't",
+        ));
+    };
+    if configuration.state.is_some()
+        || split_tape(&tape) != Some((configuration.m, configuration.n))
+    {
+        return Err(String::from(
+            "the universal machine leaves a different tape from the machine",
+        ));
+    }
+    println!(
+        "the universal machine halts after {} steps and the machine after {}, leaving {}",
+        steps,
+        direct,
+        format_split(configuration.m, configuration.n)
+    );
+    Ok(())
+}
+
+// utm <machine> [--input BITS] [--limit N] [--table]
+//
+// Runs a machine on the universal machine, see `utm`, by way of a 2-tag
+// system, and reads the tape it halts with back off the universal machine's
+pub fn utm(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let machine = args.next().ok_or(usage("utm needs a machine"))?;
+    let mut input = Vec::new();
+    let mut limit = 1_000_000_000;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" => input = parse_input(args.next())?,
+            "--limit" => limit = parse_number(&arg, args.next())?,
+            "--table" => println!("{}", universal()),
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    let states = machine.trim().split('_').count();
+    Ok(with_states!(states, utm_states(&machine, &input, limit))?)
+}
+
+struct ExportArgs {
+    format: String,
+    input: Vec<Bit>,
+    // Steps to unroll the run for, for formats that check or draw it
+    unroll: Option<u64>,
+    // Cells either side of the start kept by the model checker formats
+    window: Option<usize>,
+}
+
+const EXPORT_FORMATS: [&str; 7] = [
+    "morphett",
+    "turingmachinesimulator",
+    "smtlib",
+    "nusmv",
+    "tla",
+    "html",
+    "svg",
+];
+
+fn export_states<const N: usize>(machine: &str, args: &ExportArgs) -> Result<(), String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    let input = &args.input;
+    match args.format.as_str() {
+        "morphett" => print!("{}", to_morphett(&machine, input)),
+        "turingmachinesimulator" => print!("{}", to_turingmachinesimulator(&machine, input)),
+        "smtlib" => print!("{}", to_smtlib(&machine, input, args.unroll)),
+        "nusmv" => print!("{}", to_nusmv(&machine, input, args.window.unwrap_or(16))),
+        "tla" => print!("{}", to_tla(&machine, input, args.window.unwrap_or(16))),
+        "html" => print!("{}", to_html(&machine, input)),
+        "svg" => {
+            let mut tm = machine.to_machine();
+            let mut tape = input_tape::<u64>(input);
+            let mut diagram = SpaceTime::new(128, 512);
+            tm.space_time(&mut tape, args.unroll.unwrap_or(1_000_000), &mut diagram);
+            diagram
+                .write_svg(&mut std::io::stdout().lock(), Aggregate::Density, 2)
+                .map_err(|e| e.to_string())?;
+        }
+        format => return Err(format!("unknown format {}", format)),
+    }
+    Ok(())
+}
+
+// export <machine> --format morphett|turingmachinesimulator|smtlib|nusmv|tla|html|svg
+//     [--input BITS] [--unroll STEPS] [--window CELLS]
+//
+// Writes a machine, and the input to start it on, for another simulator,
+// see `interop`, for an SMT solver, see `smt`, or for a model checker, see
+// `modelcheck`. For notebooks and web pages, html writes its transitions
+// as a table and svg the first --unroll steps of its run, a million by
+// default, as a space-time diagram, see `spacetime`.
+pub fn export(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let machine = args.next().ok_or(usage("export needs a machine"))?;
+    let mut format = None;
+    let mut input = Vec::new();
+    let mut unroll = None;
+    let mut window = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => format = Some(args.next().ok_or(usage("--format needs a value"))?),
+            "--input" => input = parse_input(args.next())?,
+            "--unroll" => unroll = Some(parse_number(&arg, args.next())?),
+            "--window" => window = Some(parse_number(&arg, args.next())?),
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    let args = ExportArgs {
+        format: format.ok_or(usage("export needs --format"))?,
+        input,
+        unroll,
+        window,
+    };
+    if !EXPORT_FORMATS.contains(&args.format.as_str()) {
+        return Err(Failure::Usage(format!("unknown format {}", args.format)));
+    }
+    if args.unroll.is_some() && !["smtlib", "svg"].contains(&args.format.as_str()) {
+        return Err(Failure::Usage(format!(
+            "--unroll doesn't apply to {}",
+            args.format
+        )));
+    }
+    if args.window.is_some() && !["nusmv", "tla"].contains(&args.format.as_str()) {
+        return Err(Failure::Usage(format!(
+            "--window doesn't apply to {}",
+            args.format
+        )));
+    }
+    if args.input.len() > args.window.unwrap_or(16) + 1 {
+        if let "nusmv" | "tla" = args.format.as_str() {
+            return Err(usage("the input doesn't fit in the window"));
+        }
+    }
+    let states = machine.trim().split('_').count();
+    Ok(with_states!(states, export_states(&machine, &args))?)
+}
+
+// import <file> --format turingmachine.io|csv [--limit N]
+//
+// Reads a machine written for another simulator, see `interop`, prints it
+// in the standard format and runs it on its input
+pub fn import(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let path = args.next().ok_or(usage("import needs a file"))?;
+    let mut format = None;
+    let mut limit = 1_000_000;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => format = Some(args.next().ok_or(usage("--format needs a value"))?),
+            "--limit" => limit = parse_number(&arg, args.next())?,
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    let format = format.ok_or(usage("import needs --format"))?;
+    let parse = match format.as_str() {
+        "turingmachine.io" => from_turingmachine_io,
+        "csv" => from_csv,
+        format => return Err(Failure::Usage(format!("unknown format {}", format))),
+    };
+    let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+    let imported = parse(&text)?;
+    if imported.symbols.len() > 10 {
+        return Err(format!(
+            "{} symbols are too many for the standard format",
+            imported.symbols.len()
+        )
+        .into());
+    }
+    println!("{}", format_compiled(&imported.machine)?);
+    for (i, symbol) in imported.symbols.iter().enumerate() {
+        println!("{} is {:?}", i, symbol);
+    }
+    for (i, state) in imported.states.iter().enumerate() {
+        println!("{} is {}", state_letter(i), state);
+    }
+    if !imported.halting.is_empty() {
+        println!("Z is {}", imported.halting.join(", "));
+    }
+
+    let mut tape = imported.input.clone();
+    if tape.is_empty() {
+        tape.push_back(0);
+    }
+    let mut run = WideRun::new(&imported.machine, tape);
+    let mut steps = 0;
+    let ending = loop {
+        if run.halted() {
+            break String::from("halts");
+        }
+        if run.stuck() {
+            break format!("stops in {}", imported.states[run.state]);
+        }
+        if steps == limit {
+            break String::from("is still running");
+        }
+        run.step()?;
+        steps += 1;
+    };
+    // The tape as the simulator would show it, without the blanks either side
+    let show = |range: std::ops::Range<usize>| -> String {
+        range
+            .map(|i| imported.symbols[run.tape[i] as usize])
+            .collect()
+    };
+    let used: Vec<usize> = (0..run.tape.len()).filter(|&i| run.tape[i] != 0).collect();
+    let low = used.first().map_or(run.head, |&i| i.min(run.head));
+    let high = used.last().map_or(run.head, |&i| i.max(run.head)) + 1;
+    println!(
+        "on its input it {} after {} steps, leaving {}[{}]{}",
+        ending,
+        steps,
+        show(low..run.head),
+        show(run.head..run.head + 1),
+        show(run.head + 1..high)
+    );
+    Ok(())
+}
+
+fn golden_states<const N: usize>(
+    machine: &str,
+    path: &str,
+    input: &[Bit],
+    steps: u64,
+    update: bool,
+) -> Result<(), String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    let actual = trace(&machine, input, steps);
+    let expected = match fs::read_to_string(path) {
+        Ok(text) if !update => text,
+        Err(e) if !update && e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(format!("{}: {}", path, e))
+        }
+        // Nothing to check against yet, or told to replace it
+        _ => {
+            let mut text = actual.join("\n");
+            text.push('\n');
+            fs::write(path, text).map_err(|e| format!("{}: {}", path, e))?;
+            println!("wrote {} lines to {}", actual.len(), path);
+            return Ok(());
+        }
+    };
+    let expected = load(&expected, &machine).map_err(|e| format!("{}: {}", path, e))?;
+    let expected: Vec<&str> = expected.iter().map(String::as_str).collect();
+    match diff(&expected, &actual) {
+        None => {
+            println!("{} lines match {}", actual.len(), path);
+            Ok(())
+        }
+        Some(diff) => {
+            print!("{}", diff);
+            Err(format!(
+                "the trace differs from {}, rerun with --update if that's intended",
+                path
+            ))
+        }
+    }
+}
+
+fn selftest_states<const N: usize>(case: &selftest::Case) -> Result<bool, String> {
+    let machine: PartialMachine<N> = case.machine.parse()?;
+    match selftest::check(&machine, &case.input, case.limit) {
+        Ok(result) => {
+            if let Some(mismatch) = case.mismatch(&result) {
+                println!("{}\tWRONG\t{}", case.name, machine);
+                println!("    {}", mismatch);
+                return Ok(false);
+            }
+            let outcome = match result {
+                Some(ending) => format!("halts after {} steps", ending.steps),
+                None => String::from("runs to the limit"),
+            };
+            println!("{}\tagree\t{}", case.name, outcome);
+            Ok(true)
+        }
+        Err(results) => {
+            println!("{}\tDIFFER\t{}", case.name, machine);
+            print!("{}", selftest::diff(&results));
+            Ok(false)
+        }
+    }
+}
+
+// selftest [<machine>..] [--input BITS] [--limit N] [--corpus FILE]
+//
+// Runs machines under every engine and checks they agree, see `selftest`.
+// Machines given are run from --input for --limit steps. With none given
+// it's the known-answer corpus, the built in one or one from --corpus, and
+// each machine must also do what the corpus says it does.
+pub fn selftest_command(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let mut machines = Vec::new();
+    let mut input = Vec::new();
+    let mut limit = 1_000_000;
+    let mut corpus = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" => input = parse_input(args.next())?,
+            "--limit" => limit = parse_number(&arg, args.next())?,
+            "--corpus" => corpus = Some(args.next().ok_or(usage("--corpus needs a value"))?),
+            _ if arg.starts_with("--") => return Err(unknown(&arg)),
+            _ => machines.push(arg),
+        }
+    }
+    let cases = match corpus {
+        Some(_) if !machines.is_empty() => {
+            return Err(usage("--corpus can't be used with machines to check"))
+        }
+        Some(path) => {
+            let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+            let cases = selftest::load_corpus(&text).map_err(|e| format!("{}: {}", path, e))?;
+            if cases.is_empty() {
+                return Err(format!("{}: there are no machines in the corpus", path).into());
+            }
+            cases
+        }
+        None if machines.is_empty() => selftest::corpus(),
+        None => machines
+            .into_iter()
+            .map(|machine| selftest::Case {
+                name: machine.clone(),
+                machine,
+                input: input.clone(),
+                limit,
+                expected: None,
+            })
+            .collect(),
+    };
+    let mut failed = 0;
+    for case in &cases {
+        let states = case.machine.trim().split('_').count();
+        if !with_states!(states, selftest_states(case))? {
+            failed += 1;
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        _ => Err(format!(
+            "{} of {} machines ran differently under different engines or from what was expected",
+            failed,
+            cases.len()
+        )
+        .into()),
+    }
+}
+
+// A step count, which can also be written like 1e6
+fn parse_count(flag: &str, value: Option<String>) -> Result<u64, Failure> {
+    let value = value.ok_or(Failure::Usage(format!("{} needs a value", flag)))?;
+    value
+        .parse()
+        .ok()
+        .or_else(|| {
+            let float: f64 = value.parse().ok()?;
+            (float >= 0.0 && float.fract() == 0.0 && float < u64::MAX as f64).then_some(float as u64)
+        })
+        .ok_or(Failure::Usage(format!("bad value for {}: {}", flag, value)))
+}
+
+// What `assert` was asked to check
+struct Expectations {
+    halts: Option<bool>,
+    steps: Option<u128>,
+    sigma: Option<u64>,
+}
+
+fn assert_states<const N: usize>(
+    machine: &str,
+    input: &[Bit],
+    within: u64,
+    expected: &Expectations,
+) -> Result<Vec<String>, String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    let ending = selftest::run(&machine, input, Engine::Compiled, within);
+    let mut failures = Vec::new();
+    match &ending {
+        Some(ending) => {
+            println!(
+                "halts after {} steps leaving {} ones",
+                ending.steps,
+                ending.ones.len()
+            );
+            if expected.halts == Some(false) {
+                failures.push(format!(
+                    "expected it to run past {} steps, but it halts after {}",
+                    within, ending.steps
+                ));
+            }
+            if let Some(steps) = expected.steps.filter(|&steps| steps != ending.steps) {
+                failures.push(format!(
+                    "expected {} steps, but it takes {}",
+                    steps, ending.steps
+                ));
+            }
+            if let Some(sigma) = expected.sigma.filter(|&sigma| sigma != ending.ones.len() as u64) {
+                failures.push(format!(
+                    "expected {} ones, but it leaves {}",
+                    sigma,
+                    ending.ones.len()
+                ));
+            }
+        }
+        None => {
+            println!("still running after {} steps", within);
+            if expected.halts == Some(true) {
+                failures.push(format!("expected it to halt within {} steps", within));
+            }
+        }
+    }
+    Ok(failures)
+}
+
+// assert <machine> [--halts | --runs] [--steps N] [--sigma N] [--within N]
+//     [--input BITS]
+//
+// Runs a machine, given or in a file, for up to --within steps and checks
+// it does what's expected: halts, or is still running at the end, and
+// halts after exactly --steps leaving --sigma ones, either of which implies
+// --halts. Counts can be written like 1e6. Each expectation that fails is
+// printed on stderr, and the exit status is then 1, so that a script can
+// tell that apart from a bad command line, which exits with 2.
+pub fn assert_command(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let machine = text_or_file(args.next().ok_or(usage("assert needs a machine"))?)?;
+    let mut expected = Expectations {
+        halts: None,
+        steps: None,
+        sigma: None,
+    };
+    let mut within = 10_000_000;
+    let mut input = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--halts" => expected.halts = Some(true),
+            "--runs" => expected.halts = Some(false),
+            "--steps" => expected.steps = Some(parse_count(&arg, args.next())? as u128),
+            "--sigma" => expected.sigma = Some(parse_count(&arg, args.next())?),
+            "--within" => within = parse_count(&arg, args.next())?,
+            "--input" => input = parse_input(args.next())?,
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    if expected.steps.is_some() || expected.sigma.is_some() {
+        if expected.halts == Some(false) {
+            return Err(usage("--runs can't be used with --steps or --sigma"));
+        }
+        expected.halts = Some(true);
+    }
+    if expected.steps.is_some_and(|steps| steps > within as u128) {
+        return Err(usage("--steps is more than --within"));
+    }
+    let machine = machine.trim();
+    let states = machine.split('_').count();
+    let failures = with_states!(states, assert_states(machine, &input, within, &expected))?;
+    if !failures.is_empty() {
+        for failure in failures {
+            eprintln!("assertion failed: {}", failure);
+        }
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn equivalent_states<const N: usize>(
+    a: &str,
+    b: &str,
+    steps: u64,
+    window: u32,
+    inputs: &[Vec<Bit>],
+) -> Result<bool, String> {
+    let a: PartialMachine<N> = pad(a, N).parse()?;
+    let b: PartialMachine<N> = pad(b, N).parse()?;
+    let halts = match equivalent_up_to(&a, &b, steps, inputs) {
+        Ok(halts) => halts,
+        Err(divergence) => {
+            println!("they differ {}", divergence.describe());
+            return Ok(false);
+        }
+    };
+    match halts[0] {
+        Some(halted) => println!("they agree from a blank tape, both halting after {} steps", halted),
+        None => println!("they agree from a blank tape for {} steps, neither halting", steps),
+    }
+    if halts.len() > 1 {
+        let inputs = halts.len() - 1;
+        let halting = halts[1..].iter().filter(|halted| halted.is_some()).count();
+        match inputs - halting {
+            0 => println!("and from each of {} inputs, both halting", inputs),
+            running => println!(
+                "and from each of {} inputs, both halting on {} and still running after {} steps on {}",
+                inputs, halting, steps, running
+            ),
+        }
+    }
+    match bisimulate(&a, &b, window, inputs)? {
+        Bisimulation::Equivalent { configurations } => println!(
+            "and on all {} configurations they reach within {} cells, which they never leave, so they agree forever",
+            configurations, window
+        ),
+        Bisimulation::Escapes {
+            configurations,
+            escaped,
+        } => println!(
+            "and on all {} configurations they reach within {} cells, but they leave those cells {} ways, past which nothing is known",
+            configurations, window, escaped
+        ),
+        Bisimulation::Differ(divergence) => {
+            println!("but they differ {}", divergence.describe());
+            return Ok(false);
+        }
+        Bisimulation::TooMany => println!(
+            "and gave up on the configurations they reach within {} cells after {}",
+            window, MAX_CONFIGURATIONS
+        ),
+    }
+    Ok(true)
+}
+
+// equivalent <machine> <machine> [--steps N] [--input BITS].. [--all K] [--window CELLS]
+//
+// Checks that two machines, such as one and a state reduction of it, behave
+// the same, see `equivalence`: run in lockstep for --steps from a blank tape
+// and from each input, and then over every configuration they reach with
+// the head inside a window of cells, which settles it for good if they
+// never leave. Either machine can be a file holding it. Exits with 1 if
+// they differ, so scripts can tell that from bad arguments.
+pub fn equivalent(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let a = text_or_file(args.next().ok_or(usage("equivalent needs two machines"))?)?;
+    let b = text_or_file(args.next().ok_or(usage("equivalent needs two machines"))?)?;
+    let mut steps = 1_000_000;
+    let mut window = 32;
+    let mut inputs = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--steps" => steps = parse_count(&arg, args.next())?,
+            "--window" => window = parse_number(&arg, args.next())?,
+            "--input" => inputs.push(parse_input(args.next())?),
+            "--all" => {
+                let cells: usize = parse_number(&arg, args.next())?;
+                if cells > 16 {
+                    return Err(Failure::Usage(format!("--all {} is too many inputs", cells)));
+                }
+                inputs.extend((1..1u64 << cells).map(|input| {
+                    (0..cells)
+                        .map(|i| if input >> i & 1 == 0 { Zero } else { One })
+                        .collect()
+                }));
+            }
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    check_window(window, &inputs).map_err(Failure::Usage)?;
+    let states = a.trim().split('_').count().max(b.trim().split('_').count());
+    if !with_states!(states, equivalent_states(&a, &b, steps, window, &inputs))? {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+// fuzz parsers|tape [<file>..] [--runs N] [--seed N]
+//
+// Runs a fuzz target, see `fuzz`, on each file given, or on --runs random
+// inputs with none. The input of the first that fails is printed, and can
+// be saved to a file to run again.
+pub fn fuzz_command(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let target: fn(&[u8]) = match args.next().as_deref() {
+        Some("parsers") => fuzz::parsers,
+        Some("tape") => fuzz::tape,
+        _ => return Err(usage("fuzz needs a target, parsers or tape")),
+    };
+    let mut files = Vec::new();
+    let mut runs: u64 = 100_000;
+    let mut seed = 1;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--runs" => runs = parse_number(&arg, args.next())?,
+            "--seed" => seed = parse_number(&arg, args.next())?,
+            _ if arg.starts_with("--") => return Err(unknown(&arg)),
+            _ => files.push(arg),
+        }
+    }
+    let inputs: Box<dyn Iterator<Item = Result<Vec<u8>, String>>> = match files.is_empty() {
+        true => {
+            let mut random = Random(seed);
+            Box::new((0..runs).map(move |_| Ok(fuzz::random_input(&mut random))))
+        }
+        false => Box::new(
+            files
+                .into_iter()
+                .map(|path| fs::read(&path).map_err(|e| format!("{}: {}", path, e))),
+        ),
+    };
+    let mut tried = 0;
+    for input in inputs {
+        let input = input?;
+        if std::panic::catch_unwind(|| target(&input)).is_err() {
+            return Err(format!(
+                "failed on input {} after {} passed",
+                input.escape_ascii(),
+                tried
+            )
+            .into());
+        }
+        tried += 1;
+    }
+    println!("{} inputs passed", tried);
+    Ok(())
+}
+
+// golden <machine> <file> [--input BITS] [--steps N] [--update]
+//
+// Checks a run against a trace kept in a file, see `golden`, writing the
+// file if it isn't there yet or --update is given
+pub fn golden(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let machine = args.next().ok_or(usage("golden needs a machine"))?;
+    let path = args.next().ok_or(usage("golden needs a file"))?;
+    let mut input = Vec::new();
+    let mut steps = 10_000;
+    let mut update = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" => input = parse_input(args.next())?,
+            "--steps" => steps = parse_number(&arg, args.next())?,
+            "--update" => update = true,
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    let states = machine.trim().split('_').count();
+    Ok(with_states!(
+        states,
+        golden_states(&machine, &path, &input, steps, update)
+    )?)
+}
+
+#[cfg(feature = "crossval")]
+fn crossval_states<const N: usize>(
+    simulator: &Simulator,
+    machine: &str,
+    limit: u64,
+) -> Result<Option<String>, String> {
+    let machine: PartialMachine<N> = machine.parse()?;
+    cross_validate(simulator, &machine, limit)
+}
+
+// crossval --in FILE --sim COMMAND [--limit N]
+//
+// Runs every machine in a file, one to a line, here and in another
+// simulator, see `crossval`, and reports where they differ
+#[cfg(feature = "crossval")]
+pub fn crossval(mut args: impl Iterator<Item = String>) -> Result<(), Failure> {
+    let mut input = None;
+    let mut command = None;
+    let mut limit = 1_000_000;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--in" => input = Some(args.next().ok_or(usage("--in needs a value"))?),
+            "--sim" => command = Some(args.next().ok_or(usage("--sim needs a value"))?),
+            "--limit" => limit = parse_number(&arg, args.next())?,
+            _ => return Err(unknown(&arg)),
+        }
+    }
+    let input = input.ok_or(usage("crossval needs --in"))?;
+    let simulator = Simulator::new(&command.ok_or(usage("crossval needs --sim"))?)?;
+    let text = fs::read_to_string(&input).map_err(|e| format!("{}: {}", input, e))?;
+    let mut checked = 0;
+    let mut differences = 0;
+    for (number, machine) in text.lines().map(str::trim).enumerate() {
+        if machine.is_empty() || machine.starts_with('#') {
+            continue;
+        }
+        let states = machine.split('_').count();
+        let difference = with_states!(states, crossval_states(&simulator, machine, limit))
+            .map_err(|e| format!("{} line {}: {}", input, number + 1, e))?;
+        if let Some(difference) = difference {
+            println!("{}", difference);
+            differences += 1;
+        }
+        checked += 1;
+    }
+    println!(
+        "{} of {} machines differ, with a limit of {} steps",
+        differences, checked, limit
+    );
+    match differences {
+        0 => Ok(()),
+        _ => Err(String::from("the simulator disagrees with this crate").into()),
+    }
+}
@@ -0,0 +1,105 @@
+use num_traits::PrimInt;
+use num_traits::Unsigned;
+
+use crate::format_state;
+use crate::DisplayOptions;
+use crate::State::*;
+use crate::Tape;
+use crate::TuringMachine;
+
+fn frame_lines<const N: usize, T: Unsigned + PrimInt>(
+    tm: &TuringMachine<N>,
+    tape: &Tape<T>,
+    options: &DisplayOptions,
+) -> Vec<String> {
+    // Tabs would throw off the padding between the two columns
+    format!("{}\n{}", tape.render(options), format_state(tm, tape, options))
+        .replace('\t', "    ")
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+// Same state, same head position and the same symbols on the tape, however
+// much of it each side happens to have allocated
+fn same_configuration<const N: usize, const M: usize, T, U>(
+    a: &TuringMachine<N>,
+    tape_a: &Tape<T>,
+    b: &TuringMachine<M>,
+    tape_b: &Tape<U>,
+) -> bool
+where
+    T: Unsigned + PrimInt,
+    U: Unsigned + PrimInt,
+{
+    let (low_a, high_a) = tape_a.bounds();
+    let (low_b, high_b) = tape_b.bounds();
+    a.state == b.state
+        && tape_a.head_index() == tape_b.head_index()
+        && (low_a.min(low_b)..high_a.max(high_b)).all(|i| tape_a.cell_at(i) == tape_b.cell_at(i))
+}
+
+// Steps two machines in lockstep, printing their displays side by side with a
+// line of stats under each pair. A machine that halts first stays frozen while
+// the other carries on. Stops once both have halted or after `limit` steps,
+// and returns the first step at which the two configurations differed.
+pub fn run_side_by_side<const N: usize, const M: usize, T, U>(
+    a: &mut TuringMachine<N>,
+    tape_a: &mut Tape<T>,
+    b: &mut TuringMachine<M>,
+    tape_b: &mut Tape<U>,
+    limit: u64,
+    options: &DisplayOptions,
+) -> Option<u64>
+where
+    T: Unsigned + PrimInt,
+    U: Unsigned + PrimInt,
+{
+    let mut diverged = None;
+    let mut steps = 0;
+    // Only ever widens, so the divider doesn't wander from frame to frame
+    let mut width = 0;
+    loop {
+        if diverged.is_none() && !same_configuration(a, tape_a, b, tape_b) {
+            diverged = Some(steps);
+        }
+
+        let left = frame_lines(a, tape_a, options);
+        let right = frame_lines(b, tape_b, options);
+        width = left
+            .iter()
+            .map(|l| l.chars().count())
+            .fold(width, usize::max);
+        for i in 0..left.len().max(right.len()) {
+            let l = left.get(i).map(String::as_str).unwrap_or("");
+            let r = right.get(i).map(String::as_str).unwrap_or("");
+            println!("{:width$} | {}", l, r);
+        }
+        println!(
+            "step {} \ta: {} @{} ones {} \tb: {} @{} ones {}{}",
+            steps,
+            a.state,
+            tape_a.head_index(),
+            tape_a.ones(),
+            b.state,
+            tape_b.head_index(),
+            tape_b.ones(),
+            match diverged {
+                Some(step) => format!(" \tdiverged at step {}", step),
+                None => String::new(),
+            }
+        );
+
+        if steps == limit || (matches!(a.state, HALT) && matches!(b.state, HALT)) {
+            break;
+        }
+        if let Index(state) = a.state {
+            a.step(tape_a, state);
+        }
+        if let Index(state) = b.state {
+            b.step(tape_b, state);
+        }
+        steps += 1;
+    }
+    diverged
+}
@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use crate::decider::Decider;
+use crate::decider::Proof;
+use crate::decider::Verdict;
+use crate::enumerate::PartialMachine;
+use crate::holdouts::Chain;
+use crate::holdouts::ChainRun;
+use crate::holdouts::RunTape;
+use crate::State::*;
+use crate::TapeMotion::*;
+use crate::TuringStep;
+
+// Proves non-halting by treating the lengths of the runs on the tape as
+// counters. The machine is run with chain steps, and whenever it comes back
+// to a configuration with the same state and the same runs in the same
+// order, only with some of them longer, the runs that grew are replaced by
+// unknowns n + their length, and the configuration is simulated
+// symbolically for as many chain steps as it took. If that leads back to the
+// same configuration with every n grown by the same amount again, it does so
+// for any n, and so the counters grow forever.
+//
+// This catches machines that count upwards in unary, bouncers that push
+// runs further out each sweep, and any other growth that takes the same
+// chain steps at every size. Binary counters and Collatz-like iterations
+// change the shape of the tape as they go and are beyond it.
+pub struct CounterAbstraction {
+    // Chain steps to simulate before giving up
+    pub limit: u64,
+    // The most chain steps to simulate symbolically when trying a rule
+    pub max_period: u64,
+}
+
+// A run length, known to be constant plus some multiples of the unknowns,
+// each of which can be any number from zero on
+#[derive(Clone, PartialEq, Eq)]
+struct Count {
+    unknowns: BTreeMap<usize, u64>,
+    constant: u64,
+}
+
+impl Count {
+    fn known(constant: u64) -> Count {
+        Count {
+            unknowns: BTreeMap::new(),
+            constant,
+        }
+    }
+
+    fn add(&mut self, other: &Count) {
+        for (&unknown, &times) in &other.unknowns {
+            *self.unknowns.entry(unknown).or_insert(0) += times;
+        }
+        self.constant += other.constant;
+    }
+}
+
+struct Symbolic {
+    state: usize,
+    head: u8,
+    left: Vec<(u8, Count)>,
+    right: Vec<(u8, Count)>,
+}
+
+// Symbolic versions of the run tape's push and pop. Runs of unknown length
+// always keep at least one cell, so they can be told apart from the ones
+// around them.
+fn push(side: &mut Vec<(u8, Count)>, symbol: u8, count: Count) {
+    match side.last_mut() {
+        None if symbol == 0 => {}
+        Some((top, n)) if *top == symbol => n.add(&count),
+        _ => side.push((symbol, count)),
+    }
+}
+
+fn pop(side: &mut Vec<(u8, Count)>) -> Option<u8> {
+    let Some((symbol, n)) = side.last_mut() else {
+        return Some(0);
+    };
+    let symbol = *symbol;
+    n.constant -= 1;
+    if n.constant == 0 {
+        if !n.unknowns.is_empty() {
+            return None;
+        }
+        side.pop();
+    }
+    Some(symbol)
+}
+
+// One chain step, as in `ChainRun::step`. None if the machine halts, runs
+// off into blank tape, or the step depends on what the unknowns are.
+fn step<const N: usize>(machine: &PartialMachine<N>, config: &mut Symbolic) -> Option<()> {
+    let read = config.head;
+    let TuringStep {
+        print,
+        motion,
+        next_state: Index(next),
+    } = machine.transitions[config.state][read as usize]?
+    else {
+        return None;
+    };
+    let (behind, ahead) = match motion {
+        Right => (&mut config.left, &mut config.right),
+        Left => (&mut config.right, &mut config.left),
+    };
+    let mut run = Count::known(1);
+    if next == config.state {
+        match ahead.last() {
+            Some((symbol, count)) if *symbol == read => {
+                run.add(count);
+                ahead.pop();
+            }
+            None if read == 0 => return None,
+            _ => {}
+        }
+    }
+    push(behind, print as u8, run);
+    config.head = pop(ahead)?;
+    config.state = next;
+    Some(())
+}
+
+// Whether from this configuration, with each run given a nonzero growth
+// replaced by an unknown n plus its length, `period` chain steps always lead
+// to the same configuration with every n gone up by its growth. `growth`
+// lists the left runs then the right ones, each side from the bottom.
+pub fn proves_rule<const N: usize>(
+    machine: &PartialMachine<N>,
+    state: usize,
+    tape: &RunTape,
+    period: u64,
+    growth: &[u64],
+) -> bool {
+    if growth.len() != tape.left.len() + tape.right.len() {
+        return false;
+    }
+    let runs: Vec<(u8, u64)> = tape.left.iter().chain(&tape.right).copied().collect();
+    let generalise = |(i, &(symbol, count)): (usize, &(u8, u64)), grown: bool| {
+        let mut n = Count::known(count);
+        if growth[i] > 0 {
+            n.unknowns.insert(i, 1);
+            if grown {
+                n.constant += growth[i];
+            }
+        }
+        (symbol, n)
+    };
+    let config = |grown: bool| {
+        let mut sides = runs.iter().enumerate().map(|run| generalise(run, grown));
+        let left: Vec<_> = sides.by_ref().take(tape.left.len()).collect();
+        Symbolic {
+            state,
+            head: tape.head,
+            left,
+            right: sides.collect(),
+        }
+    };
+    let mut now = config(false);
+    for _ in 0..period {
+        if step(machine, &mut now).is_none() {
+            return false;
+        }
+    }
+    let then = config(true);
+    now.state == then.state
+        && now.head == then.head
+        && now.left == then.left
+        && now.right == then.right
+}
+
+// The state, the symbol under the head and the symbols of the runs, which
+// is all of a configuration but the run lengths
+type Shape = (usize, u8, Vec<u8>, Vec<u8>);
+
+fn shape(state: usize, tape: &RunTape) -> Shape {
+    let symbols = |side: &[(u8, u64)]| side.iter().map(|&(symbol, _)| symbol).collect();
+    (state, tape.head, symbols(&tape.left), symbols(&tape.right))
+}
+
+// Brings a fresh run to the point `chain_steps` chain steps in, to check a
+// proof from. None if it halts or escapes before then.
+pub fn run_to<const N: usize>(
+    machine: &PartialMachine<N>,
+    chain_steps: u64,
+) -> Option<ChainRun<'_, N>> {
+    let mut run = ChainRun::new(machine);
+    while run.chain_steps < chain_steps {
+        if !matches!(run.step(), Chain::Running) {
+            return None;
+        }
+    }
+    Some(run)
+}
+
+// Configurations with more runs than this aren't remembered
+const MAX_RUNS: usize = 32;
+
+impl<const N: usize> Decider<N> for CounterAbstraction {
+    fn name(&self) -> &'static str {
+        "counter-abstraction"
+    }
+
+    fn budget(&self) -> String {
+        format!("limit={},period={}", self.limit, self.max_period)
+    }
+
+    fn decide(&self, machine: &PartialMachine<N>) -> Verdict {
+        let mut run = ChainRun::new(machine);
+        // Where each shape was last seen, with the run lengths then
+        let mut seen: HashMap<Shape, (u64, RunTape)> = HashMap::new();
+        while run.chain_steps < self.limit {
+            if !matches!(run.step(), Chain::Running) {
+                // Halting is left to simulation, and escaping is already
+                // proven by translated cyclers
+                return Verdict::Undecided;
+            }
+            if run.tape.left.len() + run.tape.right.len() > MAX_RUNS {
+                continue;
+            }
+            let tape = RunTape {
+                left: run.tape.left.clone(),
+                head: run.tape.head,
+                right: run.tape.right.clone(),
+            };
+            let key = shape(run.state, &tape);
+            if let Some((start, earlier)) = seen.get(&key) {
+                let period = run.chain_steps - start;
+                let lengths = |tape: &RunTape| -> Vec<u64> {
+                    tape.left
+                        .iter()
+                        .chain(&tape.right)
+                        .map(|&(_, n)| n)
+                        .collect()
+                };
+                let growth: Option<Vec<u64>> = lengths(earlier)
+                    .iter()
+                    .zip(lengths(&tape))
+                    .map(|(&then, now)| now.checked_sub(then))
+                    .collect();
+                if let Some(growth) = growth {
+                    if period <= self.max_period
+                        && proves_rule(machine, run.state, earlier, period, &growth)
+                    {
+                        let (left, right) = growth.split_at(earlier.left.len());
+                        return Verdict::NonHalting(Proof::CounterAbstraction {
+                            start: *start,
+                            period,
+                            left: left.to_vec(),
+                            right: right.to_vec(),
+                        });
+                    }
+                }
+            }
+            seen.insert(key, (run.chain_steps, tape));
+        }
+        Verdict::Undecided
+    }
+}
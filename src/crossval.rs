@@ -0,0 +1,99 @@
+use std::io::Write;
+use std::process::Command;
+use std::process::Stdio;
+
+use crate::decider::Verdict;
+use crate::enumerate::PartialMachine;
+use crate::external::parse_answer;
+use crate::search::simulate;
+use crate::search::Outcome;
+
+// Another simulator to check runs against, given as a command line. It's
+// run once for each machine, gets the machine in the standard format and
+// the step limit on a line of its standard input,
+//
+//     1RB1LB_1LA1RZ 1000000
+//
+// and answers in the JSON of `external`, with halts or undecided:
+//
+//     {"verdict": "halts", "steps": 6, "sigma": 4}
+//
+// Unlike a decider's, its answers aren't checked, since they're what's
+// being compared.
+pub struct Simulator {
+    program: String,
+    args: Vec<String>,
+}
+
+impl Simulator {
+    pub fn new(command: &str) -> Result<Simulator, String> {
+        let mut words = command.split_whitespace().map(String::from);
+        Ok(Simulator {
+            program: words.next().ok_or("the simulator needs a command")?,
+            args: words.collect(),
+        })
+    }
+
+    pub fn run<const N: usize>(
+        &self,
+        machine: &PartialMachine<N>,
+        limit: u64,
+    ) -> Result<Outcome, String> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("{}: {}", self.program, e))?;
+        let mut stdin = child.stdin.take().unwrap();
+        stdin
+            .write_all(format!("{} {}\n", machine, limit).as_bytes())
+            .map_err(|e| e.to_string())?;
+        drop(stdin);
+        let output = child.wait_with_output().map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(format!("exited with {}", output.status));
+        }
+        match parse_answer(&String::from_utf8_lossy(&output.stdout))? {
+            Verdict::Halts { steps, sigma } => Ok(Outcome::Halted { steps, sigma }),
+            Verdict::Undecided => Ok(Outcome::Undecided),
+            Verdict::NonHalting(_) => Err(String::from(
+                "a simulator can only answer halts or undecided",
+            )),
+        }
+    }
+}
+
+fn describe(outcome: Outcome) -> String {
+    match outcome {
+        Outcome::Halted { steps, sigma } => {
+            format!("halts after {} steps with {} ones", steps, sigma)
+        }
+        Outcome::Undecided => String::from("undecided"),
+    }
+}
+
+// What's wrong, if the simulator differs from this crate on a machine
+pub fn cross_validate<const N: usize>(
+    simulator: &Simulator,
+    machine: &PartialMachine<N>,
+    limit: u64,
+) -> Result<Option<String>, String> {
+    let ours = simulate(machine, limit);
+    let theirs = simulator.run(machine, limit)?;
+    let same = match (ours, theirs) {
+        (Outcome::Halted { steps, sigma }, Outcome::Halted { steps: s, sigma: o }) => {
+            steps == s && sigma == o
+        }
+        (Outcome::Undecided, Outcome::Undecided) => true,
+        _ => false,
+    };
+    Ok((!same).then(|| {
+        format!(
+            "{}: this crate says it {}, the simulator that it {}",
+            machine,
+            describe(ours),
+            describe(theirs)
+        )
+    }))
+}
@@ -0,0 +1,174 @@
+use std::collections::BTreeSet;
+
+use crate::decider::Decider;
+use crate::decider::Proof;
+use crate::decider::Verdict;
+use crate::enumerate::PartialMachine;
+use crate::standard::state_letter;
+use crate::State::*;
+use crate::TapeMotion::*;
+use crate::TuringStep;
+
+// Proves non-halting with a closed tape language: a set of configurations
+// that holds the start, holds the successor of everything in it and holds
+// nothing about to halt.
+//
+// The languages tried are given by n-grams. Reading outwards from the head,
+// each side of the tape has to consist of n-grams (windows of `radius`
+// cells) from a set for that side, and the state with the cells within
+// `radius` of the head has to be one of the contexts found to be reachable.
+// That's a regular language, recognised by an automaton whose states are
+// the last n-1 cells read. Starting from blank tape on both sides, the sets
+// are grown until stepping any context only ever leads to contexts and
+// n-grams already in them. If that happens without running into a halting
+// transition, it is the language.
+pub struct ClosedTapeLanguage {
+    // The largest n-gram length to try
+    pub max_radius: usize,
+    // How many contexts to allow at each length before giving up on it
+    pub max_contexts: usize,
+}
+
+// N-grams are kept as bits, the cell next to the head in the lowest
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Context {
+    state: usize,
+    left: u32,
+    head: u8,
+    right: u32,
+}
+
+// The n-grams allowed on each side. The language's automaton.
+pub struct NGrams {
+    pub radius: usize,
+    pub left: BTreeSet<u32>,
+    pub right: BTreeSet<u32>,
+}
+
+impl NGrams {
+    pub fn blank(radius: usize) -> NGrams {
+        NGrams {
+            radius,
+            left: BTreeSet::from([0]),
+            right: BTreeSet::from([0]),
+        }
+    }
+}
+
+// The cells of an n-gram, the one next to the head first
+pub fn format_ngram(gram: u32, radius: usize) -> String {
+    (0..radius)
+        .map(|i| if gram >> i & 1 == 0 { '0' } else { '1' })
+        .collect()
+}
+
+pub fn parse_ngram(s: &str) -> Option<u32> {
+    s.chars().rev().try_fold(0, |gram, c| match c {
+        '0' => Some(gram << 1),
+        '1' => Some(gram << 1 | 1),
+        _ => None,
+    })
+}
+
+// Finds every context reachable from the start within the language. With
+// `grow`, n-grams that turn up are added to it, otherwise they mean the
+// language isn't closed. Fails if a context can halt or there get to be
+// more than `max_contexts` of them.
+pub fn explore<const N: usize>(
+    machine: &PartialMachine<N>,
+    ngrams: &mut NGrams,
+    grow: bool,
+    max_contexts: usize,
+) -> Result<usize, String> {
+    let mask = (1u32 << ngrams.radius) - 1;
+    let start = Context {
+        state: 0,
+        left: 0,
+        head: 0,
+        right: 0,
+    };
+    if !ngrams.left.contains(&0) || !ngrams.right.contains(&0) {
+        return Err(String::from("blank tape is not in the language"));
+    }
+    let mut contexts = BTreeSet::from([start]);
+    loop {
+        let before = (contexts.len(), ngrams.left.len(), ngrams.right.len());
+        for context in contexts.clone() {
+            let Some(TuringStep {
+                print,
+                motion,
+                next_state: Index(next),
+            }) = machine.transitions[context.state][context.head as usize]
+            else {
+                return Err(format!(
+                    "a reachable context halts in state {}",
+                    state_letter(context.state)
+                ));
+            };
+            // The side the head moves away from gets the written cell, the
+            // side it moves into loses its first cell to the head and is
+            // extended by any n-gram that fits
+            let (behind, ahead, behind_set, ahead_set) = match motion {
+                Right => (context.left, context.right, &mut ngrams.left, &ngrams.right),
+                Left => (context.right, context.left, &mut ngrams.right, &ngrams.left),
+            };
+            let behind = ((behind << 1) | print as u32) & mask;
+            if !behind_set.contains(&behind) {
+                if !grow {
+                    return Err(format!(
+                        "the language is missing the n-gram {}",
+                        format_ngram(behind, ngrams.radius)
+                    ));
+                }
+                behind_set.insert(behind);
+            }
+            let extensions: Vec<u32> = ahead_set
+                .iter()
+                .copied()
+                .filter(|gram| gram & (mask >> 1) == ahead >> 1)
+                .collect();
+            for extension in extensions {
+                let (left, right) = match motion {
+                    Right => (behind, extension),
+                    Left => (extension, behind),
+                };
+                contexts.insert(Context {
+                    state: next,
+                    left,
+                    head: (ahead & 1) as u8,
+                    right,
+                });
+            }
+            if contexts.len() > max_contexts {
+                return Err(String::from("too many contexts"));
+            }
+        }
+        if (contexts.len(), ngrams.left.len(), ngrams.right.len()) == before {
+            return Ok(contexts.len());
+        }
+    }
+}
+
+impl<const N: usize> Decider<N> for ClosedTapeLanguage {
+    fn name(&self) -> &'static str {
+        "closed-tape-language"
+    }
+
+    fn budget(&self) -> String {
+        format!("radius={},contexts={}", self.max_radius, self.max_contexts)
+    }
+
+    fn decide(&self, machine: &PartialMachine<N>) -> Verdict {
+        for radius in 1..=self.max_radius {
+            let mut ngrams = NGrams::blank(radius);
+            if explore(machine, &mut ngrams, true, self.max_contexts).is_ok() {
+                return Verdict::NonHalting(Proof::ClosedTapeLanguage {
+                    radius,
+                    left: ngrams.left.into_iter().collect(),
+                    right: ngrams.right.into_iter().collect(),
+                });
+            }
+        }
+        Verdict::Undecided
+    }
+}
@@ -0,0 +1,212 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::filter::Filter;
+use crate::header::fingerprint;
+use crate::header::header;
+use crate::header::parse_header;
+use crate::symmetry::Symmetries;
+
+// How far an enumeration has got: the number of machines it has been
+// through and the last of them, in the standard format. Carrying on right
+// after that machine (see `Tnf::resume_after`) continues the enumeration
+// without running anything twice. One is written at the end of the search
+// too, so a finished search says so in its cursor as well as its
+// leaderboard.
+//
+// Saved as a header (see `header`) and tab separated lines like
+//
+//     turing-sim-rs cursor 3 5d1f0e9a3c7b2a48
+//     states      3
+//     limit       1000
+//     symmetries  mirror
+//     filter      A0:1RB,halts=1
+//     tried       10523
+//     last        1RB---_0LC1RA_1LA---
+//
+// with the filter line left out when there is none. The fingerprint is of
+// the states, limit, symmetries and filter lines, the search the cursor
+// belongs to, so a cursor edited by hand into one for another search is
+// caught.
+//
+// Version 1 had no header or states line. Those are still read, taking the
+// number of states from the last machine. Version 2 had no limit line, and
+// those are read as fitting a search with any limit. Either is written back
+// out as version 3 at the next checkpoint.
+pub const VERSION: u32 = 3;
+
+pub struct Cursor {
+    // None only for a version 1 cursor that hadn't tried anything, which
+    // fits a search of any size
+    pub states: Option<usize>,
+    // The step limit machines were run for, None for a cursor from before
+    // version 3, which fits a search with any limit
+    pub limit: Option<u64>,
+    pub symmetries: Symmetries,
+    pub filter: Filter,
+    pub tried: u64,
+    // None if nothing had been tried yet
+    pub last: Option<String>,
+}
+
+fn format_symmetries(symmetries: Symmetries) -> String {
+    let mut names = Vec::new();
+    if symmetries.mirror {
+        names.push("mirror");
+    }
+    if symmetries.swap_symbols {
+        names.push("swap-symbols");
+    }
+    if names.is_empty() {
+        names.push("none");
+    }
+    names.join(",")
+}
+
+fn parse_symmetries(s: &str) -> Result<Symmetries, String> {
+    let mut symmetries = Symmetries::NONE;
+    for name in s.split(',') {
+        match name {
+            "mirror" => symmetries.mirror = true,
+            "swap-symbols" => symmetries.swap_symbols = true,
+            "none" => {}
+            _ => return Err(format!("unknown symmetry {:?}", name)),
+        }
+    }
+    Ok(symmetries)
+}
+
+impl Cursor {
+    pub fn read(path: &Path) -> Result<Cursor, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut lines = text.lines().peekable();
+        let found = match lines.peek() {
+            Some(line) => parse_header(line, "cursor", VERSION)
+                .map_err(|e| format!("{}: {}", path.display(), e))?,
+            None => None,
+        };
+        if found.is_some() {
+            lines.next();
+        }
+        let mut states = None;
+        let mut limit = None;
+        let mut symmetries = None;
+        let mut filter = Filter::default();
+        let mut tried = None;
+        let mut last: Option<String> = None;
+        for line in lines {
+            let bad = || format!("{}: bad line {:?}", path.display(), line);
+            match line.split_once('\t').ok_or_else(bad)? {
+                ("states", value) if found.is_some() => {
+                    states = Some(value.parse().map_err(|_| bad())?)
+                }
+                ("limit", value) if found.as_ref().is_some_and(|(version, _)| *version >= 3) => {
+                    limit = Some(value.parse().map_err(|_| bad())?)
+                }
+                ("symmetries", value) => symmetries = Some(parse_symmetries(value)?),
+                ("filter", value) => filter = value.parse()?,
+                ("tried", value) => tried = Some(value.parse().map_err(|_| bad())?),
+                ("last", value) => last = Some(value.to_string()),
+                _ => return Err(bad()),
+            }
+        }
+        let incomplete = || format!("{} is not a complete cursor", path.display());
+        let states = match found {
+            Some((version, _)) => {
+                if version >= 3 && limit.is_none() {
+                    return Err(incomplete());
+                }
+                Some(states.ok_or_else(incomplete)?)
+            }
+            // Version 1, a state per _ separated part of the last machine
+            None => last.as_ref().map(|last| last.split('_').count()),
+        };
+        let cursor = Cursor {
+            states,
+            limit,
+            symmetries: symmetries.ok_or_else(incomplete)?,
+            filter,
+            tried: tried.ok_or_else(incomplete)?,
+            last,
+        };
+        if let Some((_, expected)) = found {
+            if cursor.fingerprint() != expected {
+                return Err(format!(
+                    "{}: the cursor doesn't match its fingerprint, it was changed after it was written",
+                    path.display()
+                ));
+            }
+        }
+        Ok(cursor)
+    }
+
+    // The lines that say which search this is a cursor for
+    fn search(&self) -> String {
+        let mut text = String::new();
+        if let Some(states) = self.states {
+            text += &format!("states\t{}\n", states);
+        }
+        if let Some(limit) = self.limit {
+            text += &format!("limit\t{}\n", limit);
+        }
+        text += &format!("symmetries\t{}\n", format_symmetries(self.symmetries));
+        if !self.filter.is_empty() {
+            text += &format!("filter\t{}\n", self.filter);
+        }
+        text
+    }
+
+    fn fingerprint(&self) -> String {
+        fingerprint(&self.search())
+    }
+
+    // Replaces the file in one go, so killing the search part way through
+    // writing leaves the previous cursor in place
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let mut text = format!("{}\n", header("cursor", VERSION, &self.fingerprint()));
+        text += &self.search();
+        text += &format!("tried\t{}\n", self.tried);
+        if let Some(last) = &self.last {
+            text += &format!("last\t{}\n", last);
+        }
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, text)?;
+        fs::rename(&temporary, path)
+    }
+}
+
+// Writes the cursor out at most once every `interval`
+pub struct Checkpoints {
+    path: PathBuf,
+    interval: Duration,
+    written: Instant,
+}
+
+impl Checkpoints {
+    pub fn new(path: PathBuf, interval: Duration) -> Checkpoints {
+        Checkpoints {
+            path,
+            interval,
+            written: Instant::now(),
+        }
+    }
+
+    // Called after each machine, with everything up to it already recorded
+    pub fn passed(&mut self, cursor: impl FnOnce() -> Cursor) -> std::io::Result<()> {
+        if self.written.elapsed() < self.interval {
+            return Ok(());
+        }
+        cursor().write(&self.path)?;
+        self.written = Instant::now();
+        Ok(())
+    }
+
+    // Called once the search is over, writing the cursor however recently
+    // the last one was
+    pub fn finish(&mut self, cursor: Cursor) -> std::io::Result<()> {
+        cursor.write(&self.path)
+    }
+}
@@ -0,0 +1,462 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::enumerate::PartialMachine;
+use crate::search::simulate;
+use crate::search::Outcome;
+use crate::State::*;
+use crate::TapeMotion::*;
+use crate::TuringStep;
+
+// Why a machine never halts, in enough detail to check
+#[derive(Clone)]
+pub enum Proof {
+    // None of the states that can be reached from A has a halting transition
+    HaltUnreachable,
+    // From step `start` on, every `period` steps the machine is back in the
+    // same state at a new furthest cell, `offset` cells along, with the same
+    // tape behind it as far back as it will ever look (see
+    // `TranslatedCycler`)
+    TranslatedCycler {
+        start: u64,
+        period: u64,
+        offset: i64,
+    },
+    // Running backwards from every halting transition, no chain of
+    // predecessors gets further than `depth` steps, and none of them passes
+    // through the start (see `BackwardReasoning`)
+    BackwardReasoning {
+        depth: usize,
+    },
+    // Both sides of the tape only ever consist of these n-grams, read
+    // outwards from the head (see `ClosedTapeLanguage`)
+    ClosedTapeLanguage {
+        radius: usize,
+        left: Vec<u32>,
+        right: Vec<u32>,
+    },
+    // After `start` chain steps, every `period` chain steps the machine is
+    // back in the same configuration with the runs grown by these amounts,
+    // the left runs then the right ones, each side from the bottom (see
+    // `CounterAbstraction`)
+    CounterAbstraction {
+        start: u64,
+        period: u64,
+        left: Vec<u64>,
+        right: Vec<u64>,
+    },
+    // The tape, cut into words of `block` cells with repeat counts capped
+    // at `threshold`, only reaches finitely many shapes, none of them about
+    // to halt (see `RepeatedWordList`)
+    RepeatedWordList {
+        block: usize,
+        threshold: u32,
+    },
+    // A DFA reading the tape left of the head and an NFA reading the rest
+    // recognise everything that leads to a halt but not the start, reading
+    // from the right instead if `mirrored` (see `FiniteAutomataReduction`)
+    FiniteAutomataReduction {
+        mirrored: bool,
+        dfa: Vec<[usize; 2]>,
+        nfa: [Vec<u64>; 2],
+    },
+}
+
+#[derive(Clone)]
+pub enum Verdict {
+    Halts { steps: u128, sigma: u64 },
+    NonHalting(Proof),
+    Undecided,
+}
+
+impl Display for Proof {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Proof::HaltUnreachable => f.write_str("no halting transition is reachable"),
+            Proof::TranslatedCycler {
+                start,
+                period,
+                offset,
+            } => f.write_fmt(format_args!(
+                "translated cycler from step {} with period {}, moving {} cells",
+                start, period, offset
+            )),
+            Proof::BackwardReasoning { depth } => f.write_fmt(format_args!(
+                "no way to reach a halting transition, every path back dies within {} steps",
+                depth
+            )),
+            Proof::ClosedTapeLanguage {
+                radius,
+                left,
+                right,
+            } => f.write_fmt(format_args!(
+                "the tape stays within a closed language of {} left and {} right {}-grams",
+                left.len(),
+                right.len(),
+                radius
+            )),
+            Proof::CounterAbstraction {
+                start,
+                period,
+                left,
+                right,
+            } => f.write_fmt(format_args!(
+                "from chain step {} the run lengths grow by {} every {} chain steps",
+                start,
+                left.iter()
+                    .chain(right)
+                    .map(|n| n.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+                period
+            )),
+            Proof::RepeatedWordList { block, threshold } => f.write_fmt(format_args!(
+                "the tape stays a closed list of {}-cell words, repeats counted up to {}",
+                block, threshold
+            )),
+            Proof::FiniteAutomataReduction { mirrored, dfa, nfa } => f.write_fmt(format_args!(
+                "a {}-state DFA reading from the {} and a {}-state NFA recognise every way to halt, but not the start",
+                dfa.len(),
+                if *mirrored { "right" } else { "left" },
+                nfa[0].len()
+            )),
+        }
+    }
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Halts { steps, sigma } => f.write_fmt(format_args!(
+                "halts after {} steps leaving {} ones",
+                steps, sigma
+            )),
+            Verdict::NonHalting(proof) => f.write_fmt(format_args!("never halts: {}", proof)),
+            Verdict::Undecided => f.write_str("undecided"),
+        }
+    }
+}
+
+// Something that can settle whether some machines halt from a blank tape,
+// with undefined transitions counting as halting ones. Deciders are free to
+// give up on any machine by answering Undecided, but must never be wrong.
+pub trait Decider<const N: usize> {
+    fn name(&self) -> &'static str;
+    fn decide(&self, machine: &PartialMachine<N>) -> Verdict;
+
+    // The limits it runs with, like limit=1000, so that attempts with
+    // different limits can be told apart
+    fn budget(&self) -> String {
+        String::new()
+    }
+}
+
+// A decider with its limits, as recorded in holdout lists
+pub fn attempt<const N: usize>(decider: &dyn Decider<N>) -> String {
+    match decider.budget() {
+        budget if budget.is_empty() => decider.name().to_string(),
+        budget => format!("{}({})", decider.name(), budget),
+    }
+}
+
+// Runs the machine for up to `limit` steps and reports it if it halts
+pub struct Simulation {
+    pub limit: u64,
+}
+
+impl<const N: usize> Decider<N> for Simulation {
+    fn name(&self) -> &'static str {
+        "simulation"
+    }
+
+    fn budget(&self) -> String {
+        format!("limit={}", self.limit)
+    }
+
+    fn decide(&self, machine: &PartialMachine<N>) -> Verdict {
+        match simulate(machine, self.limit) {
+            Outcome::Halted { steps, sigma } => Verdict::Halts { steps, sigma },
+            Outcome::Undecided => Verdict::Undecided,
+        }
+    }
+}
+
+// Follows the transition graph from A. The machine can only ever be in the
+// states found, so if none of them can halt, the machine doesn't.
+pub struct HaltUnreachable;
+
+impl<const N: usize> Decider<N> for HaltUnreachable {
+    fn name(&self) -> &'static str {
+        "halt-unreachable"
+    }
+
+    fn decide(&self, machine: &PartialMachine<N>) -> Verdict {
+        let mut seen = vec![0];
+        let mut i = 0;
+        while i < seen.len() {
+            for step in &machine.transitions[seen[i]] {
+                match step.map(|step| step.next_state) {
+                    None | Some(HALT) => return Verdict::Undecided,
+                    Some(Index(next)) => {
+                        if !seen.contains(&next) {
+                            seen.push(next);
+                        }
+                    }
+                }
+            }
+            i += 1;
+        }
+        Verdict::NonHalting(Proof::HaltUnreachable)
+    }
+}
+
+// Looks for a machine that keeps repeating the same thing further and
+// further along the tape.
+//
+// Take two times the head reaches a cell it has never visited before, on
+// the same side of the tape and in the same state, and let e be the nearest
+// the head got to the other side between them. If the tape from the first
+// of those cells back to e is repeated, shifted, behind the second, the
+// second is in exactly the position the first was, with blank tape ahead of
+// it, and it will do the same thing again forever.
+pub struct TranslatedCycler {
+    // Steps to simulate before giving up
+    pub limit: u64,
+}
+
+// A time the head reached a new furthest cell
+struct Record {
+    step: u64,
+    state: usize,
+    head: i64,
+    // The tape then, with the position of its first cell
+    tape: Vec<u8>,
+    low: i64,
+    // The nearest the head got to the other side since the previous record
+    extreme: i64,
+}
+
+fn cell(tape: &[u8], low: i64, position: i64) -> u8 {
+    usize::try_from(position - low)
+        .ok()
+        .and_then(|i| tape.get(i).copied())
+        .unwrap_or(0)
+}
+
+impl<const N: usize> Decider<N> for TranslatedCycler {
+    fn name(&self) -> &'static str {
+        "translated-cycler"
+    }
+
+    fn budget(&self) -> String {
+        format!("limit={}", self.limit)
+    }
+
+    fn decide(&self, machine: &PartialMachine<N>) -> Verdict {
+        // The cells visited so far, starting at position `low`
+        let mut tape: Vec<u8> = vec![0];
+        let mut low: i64 = 0;
+        let mut head: i64 = 0;
+        let mut state = 0;
+        // Records on the right and left, and how far towards the other side
+        // the head has been since the latest of each
+        let mut records: [Vec<Record>; 2] = [Vec::new(), Vec::new()];
+        let mut extremes = [0, 0];
+        for step in 1..=self.limit {
+            let i = (head - low) as usize;
+            let Some(TuringStep {
+                print,
+                motion,
+                next_state: Index(next),
+            }) = machine.transitions[state][tape[i] as usize]
+            else {
+                return Verdict::Undecided;
+            };
+            tape[i] = print as u8;
+            state = next;
+            head += match motion {
+                Right => 1,
+                Left => -1,
+            };
+            extremes = [extremes[0].min(head), extremes[1].max(head)];
+
+            let side = if head < low {
+                tape.insert(0, 0);
+                low -= 1;
+                1
+            } else if head == low + tape.len() as i64 {
+                tape.push(0);
+                0
+            } else {
+                continue;
+            };
+            // 1 for rightwards, -1 for leftwards
+            let direction = 1 - 2 * side as i64;
+            let mut extreme = extremes[side];
+            for record in records[side].iter().rev() {
+                let behind = (record.head - extreme) * direction;
+                if record.state == state
+                    && (0..=behind).all(|i| {
+                        cell(&record.tape, record.low, record.head - direction * i)
+                            == cell(&tape, low, head - direction * i)
+                    })
+                {
+                    return Verdict::NonHalting(Proof::TranslatedCycler {
+                        start: record.step,
+                        period: step - record.step,
+                        offset: head - record.head,
+                    });
+                }
+                extreme = match side {
+                    0 => extreme.min(record.extreme),
+                    _ => extreme.max(record.extreme),
+                };
+            }
+            records[side].push(Record {
+                step,
+                state,
+                head,
+                tape: tape.clone(),
+                low,
+                extreme: extremes[side],
+            });
+            extremes[side] = head;
+        }
+        Verdict::Undecided
+    }
+}
+
+// Works backwards from the configurations just before a halt. A
+// configuration is only as much of the tape as it needs: the machine's state,
+// the head position and the cells whose values are pinned down. Each
+// transition into its state gives a possible predecessor, with the head one
+// cell back, provided the cell it would have written agrees with what's
+// known. If every chain of predecessors dies out without ever reaching
+// something the start (state A on blank cells) fits, no run from a blank
+// tape can halt.
+pub struct BackwardReasoning {
+    // How many steps back to follow a chain before giving up on the machine
+    pub depth: usize,
+    // How many configurations to look at in all before giving up
+    pub budget: usize,
+}
+
+// How deep the predecessors of the configuration go if every chain of them
+// dies out, or None if one reaches the start, goes past `depth` or runs
+// out of `budget`
+pub fn predecessors<const N: usize>(
+    machine: &PartialMachine<N>,
+    state: usize,
+    head: i64,
+    cells: &BTreeMap<i64, u8>,
+    depth: usize,
+    budget: &mut usize,
+) -> Option<usize> {
+    if state == 0 && cells.values().all(|&symbol| symbol == 0) {
+        return None;
+    }
+    if depth == 0 || *budget == 0 {
+        return None;
+    }
+    *budget -= 1;
+    let mut deepest = 0;
+    for (from, slots) in machine.transitions.iter().enumerate() {
+        for (read, step) in slots.iter().enumerate() {
+            let Some(step) = step else { continue };
+            if step.next_state != Index(state) {
+                continue;
+            }
+            let before = head
+                - match step.motion {
+                    Right => 1,
+                    Left => -1,
+                };
+            if cells
+                .get(&before)
+                .is_some_and(|&known| known != step.print as u8)
+            {
+                continue;
+            }
+            let mut earlier = cells.clone();
+            earlier.insert(before, read as u8);
+            let depth = predecessors(machine, from, before, &earlier, depth - 1, budget)?;
+            deepest = deepest.max(depth + 1);
+        }
+    }
+    Some(deepest)
+}
+
+impl<const N: usize> Decider<N> for BackwardReasoning {
+    fn name(&self) -> &'static str {
+        "backward-reasoning"
+    }
+
+    fn budget(&self) -> String {
+        format!("depth={},budget={}", self.depth, self.budget)
+    }
+
+    fn decide(&self, machine: &PartialMachine<N>) -> Verdict {
+        let mut deepest = 0;
+        let mut budget = self.budget;
+        for (state, slots) in machine.transitions.iter().enumerate() {
+            for (read, step) in slots.iter().enumerate() {
+                if step.is_some_and(|step| step.next_state != HALT) {
+                    continue;
+                }
+                let cells = BTreeMap::from([(0, read as u8)]);
+                match predecessors(machine, state, 0, &cells, self.depth, &mut budget) {
+                    Some(depth) => deepest = deepest.max(depth),
+                    None => return Verdict::Undecided,
+                }
+            }
+        }
+        Verdict::NonHalting(Proof::BackwardReasoning { depth: deepest })
+    }
+}
+
+pub struct Decision {
+    pub verdict: Verdict,
+    // Name of the decider that settled the machine, if any did
+    pub decider: Option<&'static str>,
+}
+
+// Deciders to try one after another, stopping at the first that settles the
+// machine. Cheap deciders should go first.
+pub struct Pipeline<const N: usize> {
+    pub deciders: Vec<Box<dyn Decider<N>>>,
+}
+
+impl<const N: usize> Pipeline<N> {
+    pub fn new() -> Pipeline<N> {
+        Pipeline {
+            deciders: Vec::new(),
+        }
+    }
+
+    pub fn with(mut self, decider: impl Decider<N> + 'static) -> Pipeline<N> {
+        self.deciders.push(Box::new(decider));
+        self
+    }
+
+    pub fn decide(&self, machine: &PartialMachine<N>) -> Decision {
+        for decider in &self.deciders {
+            let verdict = decider.decide(machine);
+            if !matches!(verdict, Verdict::Undecided) {
+                return Decision {
+                    verdict,
+                    decider: Some(decider.name()),
+                };
+            }
+        }
+        Decision {
+            verdict: Verdict::Undecided,
+            decider: None,
+        }
+    }
+}
+
+impl<const N: usize> Default for Pipeline<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,315 @@
+use std::fmt::Display;
+
+use crate::filter::Filter;
+use crate::standard::format_step;
+use crate::symmetry::is_canonical;
+use crate::symmetry::Symmetries;
+use crate::Bit::*;
+use crate::State::*;
+use crate::TapeMotion::*;
+use crate::TuringMachine;
+use crate::TuringState;
+use crate::TuringStep;
+
+// What an undefined transition becomes in a finished machine. Writing a one
+// on the way out is the usual convention, since it can only add to the score.
+pub const HALT_STEP: TuringStep = TuringStep {
+    print: One,
+    motion: Right,
+    next_state: HALT,
+};
+
+// A machine whose transitions may be left undefined, with reaching one
+// meaning the machine halts. Written in the standard format with "---" for
+// the undefined ones.
+#[derive(Clone)]
+pub struct PartialMachine<const N: usize> {
+    pub transitions: [[Option<TuringStep>; 2]; N],
+}
+
+impl<const N: usize> PartialMachine<N> {
+    pub fn to_machine(&self) -> TuringMachine<N> {
+        TuringMachine {
+            states: self.transitions.map(|[zero, one]| TuringState {
+                zero: zero.unwrap_or(HALT_STEP),
+                one: one.unwrap_or(HALT_STEP),
+            }),
+            state: 0.into(),
+        }
+    }
+
+    pub fn undefined(&self) -> usize {
+        self.transitions.iter().flatten().filter(|t| t.is_none()).count()
+    }
+}
+
+impl<const N: usize> Display for PartialMachine<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, state) in self.transitions.iter().enumerate() {
+            if i > 0 {
+                f.write_str("_")?;
+            }
+            for t in state {
+                match t {
+                    Some(step) => f.write_str(&format_step(step))?,
+                    None => f.write_str("---")?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Enumerates the machines of up to N states in a static normal form, the
+// table enumeration. This isn't Tree Normal Form proper, which defines
+// transitions in the order a run first needs them and is what
+// `tree::SimulatedTree` generates, but it doesn't need anything run to know
+// what comes next, so it can be split into subtrees, filtered and resumed.
+// It also generates machines that differ only in transitions no run from a
+// blank tape gets to, so its counts and positions don't match the tree's.
+//
+// The transition table is filled in slot by slot, A0 A1 B0 B1 and so on.
+// A slot can only be defined once its state has been reached, meaning some
+// earlier slot goes to it (A is reached from the start). A slot may only go
+// to a state that has already been reached or to the lowest one that hasn't,
+// so states are numbered in order of first use and every relabelling of a
+// machine is generated exactly once. Once a state turns out to be
+// unreachable, so are all the states after it, and the rest of the table
+// stays undefined.
+//
+// Machines without an undefined transition can never halt, so they are
+// skipped.
+//
+// With symmetries turned on, only the canonical machine of each class of
+// equivalent machines is generated (see `canonicalize`). With a filter, only
+// the machines it passes, leaving out whole subtrees as soon as their first
+// slots rule them out.
+#[derive(Clone)]
+pub struct Tnf<const N: usize> {
+    // For each slot, which of its options is currently picked. Option 0 is
+    // undefined, the rest are decoded by `decode`.
+    choices: [[usize; 2]; N],
+    started: bool,
+    symmetries: Symmetries,
+    // Slots before this one are held where they are, so the enumeration
+    // only covers the subtree under them
+    fixed: usize,
+    filter: Filter,
+    // Whether the choices have already been moved on to the next machine to
+    // look at
+    skipped: bool,
+}
+
+impl<const N: usize> Default for Tnf<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The options at a slot given how many states have been reached before it
+fn options(state: usize, reached: usize, n: usize) -> usize {
+    if state >= reached {
+        1
+    } else {
+        1 + 4 * (reached.min(n - 1) + 1)
+    }
+}
+
+// Numbers the possible transitions, undefined first, then by next state,
+// symbol written and direction, rightwards first
+pub fn encode(step: Option<TuringStep>) -> usize {
+    match step {
+        None => 0,
+        Some(step) => {
+            let next = match step.next_state {
+                Index(i) => i,
+                HALT => panic!("halting transitions are encoded as undefined"),
+            };
+            let print = match step.print {
+                Zero => 0,
+                One => 1,
+            };
+            let motion = match step.motion {
+                Right => 0,
+                Left => 1,
+            };
+            1 + (next << 2 | print << 1 | motion)
+        }
+    }
+}
+
+pub fn decode(choice: usize) -> Option<TuringStep> {
+    if choice == 0 {
+        return None;
+    }
+    let c = choice - 1;
+    Some(TuringStep {
+        print: if (c >> 1) & 1 == 0 { Zero } else { One },
+        motion: if c & 1 == 0 { Right } else { Left },
+        next_state: Index(c >> 2),
+    })
+}
+
+impl<const N: usize> Tnf<N> {
+    pub fn new() -> Tnf<N> {
+        Self::with_symmetries(Symmetries::NONE)
+    }
+
+    pub fn with_symmetries(symmetries: Symmetries) -> Tnf<N> {
+        Tnf {
+            choices: [[0; 2]; N],
+            started: false,
+            symmetries,
+            fixed: 0,
+            filter: Filter::default(),
+            skipped: false,
+        }
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> Tnf<N> {
+        self.filter = filter;
+        self
+    }
+
+    // Splits the enumeration into independent subtrees, one for each way of
+    // filling the first `depth` slots. Running them one after another in the
+    // order returned gives exactly the machines of the whole enumeration, in
+    // the same order.
+    pub fn subtrees(symmetries: Symmetries, depth: usize) -> Vec<Tnf<N>> {
+        let depth = depth.min(2 * N);
+        let mut prefix = Tnf::with_symmetries(symmetries);
+        let mut subtrees = Vec::new();
+        loop {
+            let mut subtree = Tnf::with_symmetries(symmetries);
+            subtree.choices = prefix.choices;
+            subtree.fixed = depth;
+            subtrees.push(subtree);
+            if !prefix.advance_within(0, depth) {
+                return subtrees;
+            }
+        }
+    }
+
+    // Drops everything in `subtrees` up to and including `machine`, so that
+    // running what's left carries on an enumeration that stopped after it.
+    // Fails if the subtrees never get to `machine`.
+    pub fn resume_after(
+        subtrees: Vec<Tnf<N>>,
+        machine: &PartialMachine<N>,
+    ) -> Result<Vec<Tnf<N>>, String> {
+        let missing = || format!("{} is not part of this enumeration", machine);
+        let mut position = Tnf::new();
+        for (slot, step) in machine.transitions.iter().flatten().enumerate() {
+            if step.is_some_and(|step| step.next_state == HALT) {
+                return Err(missing());
+            }
+            position.choices[slot / 2][slot % 2] = encode(*step);
+        }
+        let reached = position.reached();
+        for slot in 0..2 * N {
+            let (state, symbol) = (slot / 2, slot % 2);
+            if position.choices[state][symbol] >= options(state, reached[state][symbol], N) {
+                return Err(missing());
+            }
+        }
+
+        let start = subtrees
+            .iter()
+            .position(|subtree| {
+                (0..subtree.fixed).all(|slot| {
+                    subtree.choices[slot / 2][slot % 2] == position.choices[slot / 2][slot % 2]
+                })
+            })
+            .ok_or_else(missing)?;
+        let mut rest = subtrees.into_iter().skip(start).collect::<Vec<_>>();
+        rest[0].choices = position.choices;
+        rest[0].started = true;
+        Ok(rest)
+    }
+
+    // Whether the machine starts with the slots this subtree holds fixed
+    pub fn holds(&self, machine: &PartialMachine<N>) -> bool {
+        (0..self.fixed).all(|slot| {
+            self.choices[slot / 2][slot % 2] == encode(machine.transitions[slot / 2][slot % 2])
+        })
+    }
+
+    // How many states have been reached before each slot
+    fn reached(&self) -> [[usize; 2]; N] {
+        let mut reached = 1;
+        let mut before = [[0; 2]; N];
+        for (state, slots) in self.choices.iter().enumerate() {
+            for (symbol, choice) in slots.iter().enumerate() {
+                before[state][symbol] = reached;
+                if let Some(TuringStep {
+                    next_state: Index(next),
+                    ..
+                }) = decode(*choice)
+                {
+                    reached = reached.max(next + 1);
+                }
+            }
+        }
+        before
+    }
+
+    // Steps the rightmost slot that still has options left, resetting the
+    // ones after it. False once every combination has been seen.
+    fn advance(&mut self) -> bool {
+        self.advance_within(self.fixed, 2 * N)
+    }
+
+    // Like `advance`, but only moving slots in start..end
+    fn advance_within(&mut self, start: usize, end: usize) -> bool {
+        let reached = self.reached();
+        for slot in (start..end).rev() {
+            let (state, symbol) = (slot / 2, slot % 2);
+            if self.choices[state][symbol] + 1 < options(state, reached[state][symbol], N) {
+                self.choices[state][symbol] += 1;
+                for later in slot + 1..2 * N {
+                    self.choices[later / 2][later % 2] = 0;
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    fn current(&self) -> PartialMachine<N> {
+        PartialMachine {
+            transitions: self.choices.map(|[zero, one]| [decode(zero), decode(one)]),
+        }
+    }
+}
+
+impl<const N: usize> Iterator for Tnf<N> {
+    type Item = PartialMachine<N>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if !self.started || self.skipped {
+                self.started = true;
+                self.skipped = false;
+            } else if !self.advance() {
+                return None;
+            }
+            let machine = self.current();
+            if !self.filter.is_empty() {
+                // The shortest run of slots that rules the machine out, if
+                // any, and with it everything else that starts that way
+                if let Some(slots) = (1..=2 * N).find(|&n| !self.filter.allows(&machine, n)) {
+                    if !self.advance_within(self.fixed, slots) {
+                        return None;
+                    }
+                    self.skipped = true;
+                    continue;
+                }
+            }
+            if machine.undefined() > 0
+                && (self.symmetries == Symmetries::NONE || is_canonical(&machine, self.symmetries))
+            {
+                return Some(machine);
+            }
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::HashSet;
+use std::collections::VecDeque;
+
+use crate::enumerate::PartialMachine;
+use crate::enumerate::HALT_STEP;
+use crate::selftest::input_tape;
+use crate::standard::format_step;
+use crate::standard::state_letter;
+use crate::Bit;
+use crate::Bit::*;
+use crate::State::*;
+use crate::TapeMotion::*;
+use crate::TuringStep;
+
+// Whether two machines behave the same, such as a machine and the one a
+// state reduction made of it. Only what can be seen of a run is compared:
+// what each step writes, which way it moves and whether it halts. The
+// states themselves can be numbered differently, or be fewer in one, so
+// long as the machines are always in states that do the same thing.
+// Undefined transitions are the halting ones they're run as.
+//
+// Both are run on the same tape, since as long as they agree their tapes
+// are the same, and the first step they disagree on is reported.
+pub struct Divergence {
+    pub input: Vec<Bit>,
+    // Steps taken before the one they disagree on
+    pub step: u64,
+    pub state_a: usize,
+    pub state_b: usize,
+    pub read: Bit,
+    pub step_a: TuringStep,
+    pub step_b: TuringStep,
+}
+
+impl Divergence {
+    pub fn describe(&self) -> String {
+        let input: String = self.input.iter().map(|&bit| (bit as u8).to_string()).collect();
+        format!(
+            "from {} after {} steps: {}{} is {} in the first machine, but {}{} is {} in the second",
+            match input.is_empty() {
+                true => String::from("a blank tape"),
+                false => format!("input {}", input),
+            },
+            self.step,
+            state_letter(self.state_a),
+            self.read as u8,
+            format_step(&self.step_a),
+            state_letter(self.state_b),
+            self.read as u8,
+            format_step(&self.step_b)
+        )
+    }
+}
+
+fn transition<const N: usize>(machine: &PartialMachine<N>, state: usize, read: Bit) -> TuringStep {
+    machine.transitions[state][read as usize].unwrap_or(HALT_STEP)
+}
+
+// Same cell written, same way moved, and both halting or neither
+fn agree(a: &TuringStep, b: &TuringStep) -> bool {
+    a.print == b.print
+        && a.motion == b.motion
+        && matches!(a.next_state, HALT) == matches!(b.next_state, HALT)
+}
+
+// The machine in the standard format with undefined states added up to
+// `states`, which it never reaches, so machines of different sizes can be
+// parsed as the same N
+pub fn pad(machine: &str, states: usize) -> String {
+    let mut padded = machine.trim().to_string();
+    for _ in padded.split('_').count()..states {
+        padded.push_str("_------");
+    }
+    padded
+}
+
+// Runs the machines in lockstep from a blank tape and then from each input,
+// for at most `steps` steps each, returning where they first disagree, or
+// if they never do, the step they both halted after from each start, or
+// None where they were both still going
+pub fn equivalent_up_to<const N: usize>(
+    a: &PartialMachine<N>,
+    b: &PartialMachine<N>,
+    steps: u64,
+    inputs: &[Vec<Bit>],
+) -> Result<Vec<Option<u64>>, Divergence> {
+    let mut halts = Vec::new();
+    for input in starts(inputs) {
+        let mut halted = None;
+        let mut tape = input_tape::<u64>(input);
+        let (mut state_a, mut state_b) = (0, 0);
+        for step in 0..steps {
+            let read = tape.get();
+            let step_a = transition(a, state_a, read);
+            let step_b = transition(b, state_b, read);
+            if !agree(&step_a, &step_b) {
+                return Err(Divergence {
+                    input: input.clone(),
+                    step,
+                    state_a,
+                    state_b,
+                    read,
+                    step_a,
+                    step_b,
+                });
+            }
+            let (Index(next_a), Index(next_b)) = (step_a.next_state, step_b.next_state) else {
+                halted = Some(step + 1);
+                break;
+            };
+            tape.set(step_a.print);
+            tape.move_tape(step_a.motion);
+            (state_a, state_b) = (next_a, next_b);
+        }
+        halts.push(halted);
+    }
+    Ok(halts)
+}
+
+// A blank tape, then each input that isn't one
+fn starts(inputs: &[Vec<Bit>]) -> impl Iterator<Item = &Vec<Bit>> + '_ {
+    const BLANK: &Vec<Bit> = &Vec::new();
+    std::iter::once(BLANK).chain(inputs.iter().filter(|input| !input.is_empty()))
+}
+
+// Where the head starts in a window of `window` cells
+fn window_start(window: u32) -> u32 {
+    window / 2
+}
+
+// Whether `bisimulate` can be given the window and inputs
+pub fn check_window(window: u32, inputs: &[Vec<Bit>]) -> Result<(), String> {
+    if !(1..=64).contains(&window) {
+        return Err(format!("a window of {} cells doesn't fit in a word", window));
+    }
+    let room = (window - window_start(window)) as usize;
+    match inputs.iter().find(|input| input.len() > room) {
+        Some(input) => Err(format!(
+            "an input of {} cells doesn't fit in a window of {}, which has room for {}",
+            input.len(),
+            window,
+            room
+        )),
+        None => Ok(()),
+    }
+}
+
+// What the bisimulation found out about every run from the inputs
+pub enum Bisimulation {
+    // They agree on every configuration reachable without leaving the
+    // window, and no run leaves it, so they agree however long they run
+    Equivalent { configurations: usize },
+    // They agree on every configuration reachable without leaving the
+    // window, but runs leave it, so past that nothing is known
+    Escapes {
+        configurations: usize,
+        escaped: usize,
+    },
+    Differ(Divergence),
+    // Gave up after `MAX_CONFIGURATIONS` without finding a difference
+    TooMany,
+}
+
+pub const MAX_CONFIGURATIONS: usize = 1 << 24;
+
+// A pair of states, the cells of the window as the bits of a word, the
+// lowest bit the leftmost cell, and the head's place in it
+type Configuration = (usize, usize, u64, u32);
+
+// Explores every configuration the pair of machines can reach from the
+// inputs with the head inside a window of `window` cells, up to 64, where
+// the head starts halfway along. Each pair of states met with the same cell
+// under the head has to do the same thing, and since the configurations
+// reached form a closed set, agreeing on all of them with no run leaving
+// the window means agreeing on those runs forever, not only for some number
+// of steps.
+pub fn bisimulate<const N: usize>(
+    a: &PartialMachine<N>,
+    b: &PartialMachine<N>,
+    window: u32,
+    inputs: &[Vec<Bit>],
+) -> Result<Bisimulation, String> {
+    check_window(window, inputs)?;
+    let start = window_start(window);
+    let inputs: Vec<&Vec<Bit>> = starts(inputs).collect();
+    let mut seen: HashSet<Configuration> = HashSet::new();
+    // Each with the input it was reached from and the steps taken to reach it
+    let mut queue: VecDeque<(Configuration, usize, u64)> = VecDeque::new();
+    for (i, input) in inputs.iter().enumerate() {
+        let cells = input
+            .iter()
+            .enumerate()
+            .filter(|(_, &bit)| bit == One)
+            .fold(0, |cells, (j, _)| cells | 1 << (start as usize + j));
+        let configuration = (0, 0, cells, start);
+        if seen.insert(configuration) {
+            queue.push_back((configuration, i, 0));
+        }
+    }
+    let mut escaped = 0;
+    while let Some(((state_a, state_b, cells, head), input, steps)) = queue.pop_front() {
+        let read = if cells >> head & 1 == 1 { One } else { Zero };
+        let step_a = transition(a, state_a, read);
+        let step_b = transition(b, state_b, read);
+        if !agree(&step_a, &step_b) {
+            return Ok(Bisimulation::Differ(Divergence {
+                input: inputs[input].clone(),
+                step: steps,
+                state_a,
+                state_b,
+                read,
+                step_a,
+                step_b,
+            }));
+        }
+        let (Index(next_a), Index(next_b)) = (step_a.next_state, step_b.next_state) else {
+            continue;
+        };
+        let cells = match step_a.print {
+            One => cells | 1 << head,
+            Zero => cells & !(1 << head),
+        };
+        let head = match step_a.motion {
+            Left => head.checked_sub(1),
+            Right => Some(head + 1).filter(|&head| head < window),
+        };
+        let Some(head) = head else {
+            escaped += 1;
+            continue;
+        };
+        let configuration = (next_a, next_b, cells, head);
+        if seen.insert(configuration) {
+            if seen.len() > MAX_CONFIGURATIONS {
+                return Ok(Bisimulation::TooMany);
+            }
+            queue.push_back((configuration, input, steps + 1));
+        }
+    }
+    Ok(match escaped {
+        0 => Bisimulation::Equivalent {
+            configurations: seen.len(),
+        },
+        _ => Bisimulation::Escapes {
+            configurations: seen.len(),
+            escaped,
+        },
+    })
+}
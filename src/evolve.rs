@@ -0,0 +1,137 @@
+use std::io::Write;
+
+use crate::enumerate::decode;
+use crate::enumerate::PartialMachine;
+use crate::search::simulate;
+use crate::search::Leaderboard;
+use crate::search::Outcome;
+use crate::TuringStep;
+
+// What evolution selects for: ones left on the tape or steps taken to halt.
+// Machines still running at the step limit score nothing.
+#[derive(Clone, Copy)]
+pub enum Score {
+    Sigma,
+    Steps,
+}
+
+pub struct Evolution {
+    pub population: usize,
+    pub generations: u64,
+    pub seed: u64,
+    // Step limit for scoring each machine
+    pub limit: u64,
+    pub score: Score,
+}
+
+// SplitMix64, which is plenty for picking mutations and keeps runs
+// reproducible from the seed
+pub struct Random(pub u64);
+
+impl Random {
+    pub fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+// Any transition, undefined included, with the numbering of `encode`
+fn random_step<const N: usize>(random: &mut Random) -> Option<TuringStep> {
+    decode(random.below(4 * N + 1))
+}
+
+pub fn random_machine<const N: usize>(random: &mut Random) -> PartialMachine<N> {
+    PartialMachine {
+        transitions: std::array::from_fn(|_| [random_step::<N>(random), random_step::<N>(random)]),
+    }
+}
+
+// Replaces one transition, and now and again another one after it
+fn mutate<const N: usize>(machine: &mut PartialMachine<N>, random: &mut Random) {
+    loop {
+        let slot = random.below(2 * N);
+        machine.transitions[slot / 2][slot % 2] = random_step::<N>(random);
+        if random.below(3) != 0 {
+            return;
+        }
+    }
+}
+
+// Takes each state's transitions from one parent or the other
+fn crossover<const N: usize>(
+    a: &PartialMachine<N>,
+    b: &PartialMachine<N>,
+    random: &mut Random,
+) -> PartialMachine<N> {
+    PartialMachine {
+        transitions: std::array::from_fn(|state| match random.below(2) {
+            0 => a.transitions[state],
+            _ => b.transitions[state],
+        }),
+    }
+}
+
+// Three at random, the best of them wins
+fn tournament(fitness: &[u128], random: &mut Random) -> usize {
+    (0..3)
+        .map(|_| random.below(fitness.len()))
+        .max_by_key(|&i| fitness[i])
+        .unwrap()
+}
+
+// Evolves a population of N-state machines, offering every machine scored to
+// the leaderboard. Each generation keeps its best tenth as they are and
+// breeds the rest from tournament winners. Returns how many machines were
+// scored.
+pub fn evolve<const N: usize, W: Write>(
+    config: &Evolution,
+    leaderboard: &mut Leaderboard<W>,
+) -> std::io::Result<u64> {
+    let mut random = Random(config.seed);
+    let size = config.population.max(2);
+    let mut population: Vec<PartialMachine<N>> =
+        (0..size).map(|_| random_machine(&mut random)).collect();
+    let mut scored = 0;
+    for _ in 0..config.generations {
+        let mut fitness = Vec::with_capacity(size);
+        for machine in &population {
+            let outcome = simulate(machine, config.limit);
+            fitness.push(match (outcome, config.score) {
+                (Outcome::Halted { sigma, .. }, Score::Sigma) => sigma as u128,
+                (Outcome::Halted { steps, .. }, Score::Steps) => steps,
+                (Outcome::Undecided, _) => 0,
+            });
+            leaderboard.offer_outcome(scored, machine, outcome)?;
+            scored += 1;
+        }
+
+        let mut ranked: Vec<usize> = (0..size).collect();
+        ranked.sort_by_key(|&i| std::cmp::Reverse(fitness[i]));
+        let mut next: Vec<PartialMachine<N>> = ranked[..size.div_ceil(10)]
+            .iter()
+            .map(|&i| population[i].clone())
+            .collect();
+        while next.len() < size {
+            let a = &population[tournament(&fitness, &mut random)];
+            let mut child = match random.below(2) {
+                0 => crossover(
+                    a,
+                    &population[tournament(&fitness, &mut random)],
+                    &mut random,
+                ),
+                _ => a.clone(),
+            };
+            mutate(&mut child, &mut random);
+            next.push(child);
+        }
+        population = next;
+    }
+    Ok(scored)
+}
@@ -0,0 +1,159 @@
+use std::io::Read;
+use std::io::Write;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::certificate::parse_certificate;
+use crate::certificate::verify_certificate;
+use crate::decider::Decider;
+use crate::decider::Verdict;
+use crate::enumerate::PartialMachine;
+use crate::json::Json;
+
+// A decider in another program, named in a pipeline as external:PATH. The
+// program is run once for each machine, gets the machine in the standard
+// format and a newline on its standard input, and answers with a JSON
+// object on its standard output:
+//
+//     {"verdict": "halts", "steps": 21, "sigma": 6}
+//     {"verdict": "non-halting", "certificate": "translated-cycler 1 4 2"}
+//     {"verdict": "undecided"}
+//
+// Certificates are in the forms `certificate::format_certificate` writes.
+// Nothing it says is taken on trust: halting is checked by simulation and
+// non-halting by the certificate, and a verdict that doesn't check out is
+// reported and counts as undecided, as does a program that fails or takes
+// longer than TIMEOUT. Certificates are checked within the pipeline's step
+// limit, and one that needs more counts as undecided too.
+pub struct External {
+    pub program: String,
+    pub limit: u64,
+    name: &'static str,
+}
+
+// How long the program gets to answer for one machine
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+// Pipelines are built for every machine, so each name is only made static
+// once
+fn intern(name: String) -> &'static str {
+    static NAMES: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+    let mut names = NAMES.lock().unwrap();
+    match names.iter().find(|&&known| known == name) {
+        Some(&known) => known,
+        None => {
+            let name = Box::leak(name.into_boxed_str());
+            names.push(name);
+            name
+        }
+    }
+}
+
+impl External {
+    pub fn new(program: &str, limit: u64) -> External {
+        External {
+            program: program.to_string(),
+            limit,
+            name: intern(format!("external:{}", program)),
+        }
+    }
+
+    fn ask<const N: usize>(&self, machine: &PartialMachine<N>) -> Result<Verdict, String> {
+        let mut child = Command::new(&self.program)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| e.to_string())?;
+        let mut stdin = child.stdin.take().unwrap();
+        stdin
+            .write_all(format!("{}\n", machine).as_bytes())
+            .map_err(|e| e.to_string())?;
+        drop(stdin);
+        // Read on a thread of its own, so a program with a lot to say
+        // doesn't block on a full pipe while it's waited for
+        let mut stdout = child.stdout.take().unwrap();
+        let reader = thread::spawn(move || {
+            let mut answer = Vec::new();
+            stdout.read_to_end(&mut answer).map(|_| answer)
+        });
+        let started = Instant::now();
+        let status = loop {
+            if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+                break status;
+            }
+            if started.elapsed() > TIMEOUT {
+                // The reader is left to finish by itself, since anything
+                // the program started may still hold the pipe open
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("gave no answer in {} s", TIMEOUT.as_secs()));
+            }
+            thread::sleep(Duration::from_millis(1));
+        };
+        let answer = reader.join().unwrap().map_err(|e| e.to_string())?;
+        if !status.success() {
+            return Err(format!("exited with {}", status));
+        }
+        let verdict = parse_answer(&String::from_utf8_lossy(&answer))?;
+        if matches!(verdict, Verdict::Undecided) {
+            return Ok(verdict);
+        }
+        verify_certificate(machine, &verdict, self.limit)
+            .map_err(|e| format!("the verdict does not check out: {}", e))?;
+        Ok(verdict)
+    }
+}
+
+impl<const N: usize> Decider<N> for External {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn decide(&self, machine: &PartialMachine<N>) -> Verdict {
+        self.ask(machine).unwrap_or_else(|e| {
+            eprintln!("{} on {}: {}", self.program, machine, e);
+            Verdict::Undecided
+        })
+    }
+
+    fn budget(&self) -> String {
+        format!("limit={}", self.limit)
+    }
+}
+
+// Reads an answer in the protocol's JSON
+pub fn parse_answer(answer: &str) -> Result<Verdict, String> {
+    let answer = Json::parse(answer.trim())?;
+    let number = |key: &str| {
+        answer
+            .get(key)
+            .and_then(Json::as_u64)
+            .ok_or_else(|| format!("no number for {:?}", key))
+    };
+    let verdict = answer
+        .get("verdict")
+        .and_then(Json::as_str)
+        .ok_or("no verdict")?;
+    Ok(match verdict {
+        "halts" => Verdict::Halts {
+            steps: answer
+                .get("steps")
+                .and_then(Json::as_u128)
+                .ok_or("no number for \"steps\"")?,
+            sigma: number("sigma")?,
+        },
+        "non-halting" => match answer.get("certificate").and_then(Json::as_str) {
+            Some(certificate) => match parse_certificate(certificate)? {
+                Verdict::NonHalting(proof) => Verdict::NonHalting(proof),
+                _ => return Err(String::from("the certificate is not for non-halting")),
+            },
+            None => return Err(String::from("non-halting needs a certificate")),
+        },
+        "undecided" => Verdict::Undecided,
+        _ => return Err(format!("unknown verdict {:?}", verdict)),
+    })
+}
@@ -0,0 +1,255 @@
+use crate::decider::Decider;
+use crate::decider::Proof;
+use crate::decider::Verdict;
+use crate::enumerate::PartialMachine;
+use crate::standard::state_letter;
+use crate::symmetry::mirror;
+use crate::State::*;
+use crate::TapeMotion::*;
+use crate::TuringStep;
+
+// Proves non-halting by finite automata reduction: a pair of automata that
+// recognise a set of configurations holding every one about to halt,
+// holding everything that steps into it, and not holding the start. The
+// machine can then never get from the start to a halt.
+//
+// A configuration is read from left to right. A DFA reads the cells left
+// of the head, from blank tape on, and ends in some state q. Then an NFA
+// starts in its state (q, s) for the machine's state s and reads the cell
+// under the head and everything to the right of it, as far as any number
+// of blanks after the last one. The configuration is recognised if the NFA
+// can end up in its last state, ⊤, which stays ⊤ whatever it reads. With
+// the DFA fixed, the conditions on the NFA only ever ask for more
+// transitions, so the least NFA meeting them can be found by adding them
+// until nothing changes. The DFAs are tried in order up to `max_states`
+// states, for the machine and its mirror image (which reads from the right).
+pub struct FiniteAutomataReduction {
+    pub max_states: usize,
+}
+
+// The automata found. The DFA goes to dfa[q][cell] from state q, and NFA
+// state (q, s) is numbered q * N + s, with sets of them as bits:
+// nfa[cell][x] is where NFA state x goes on reading cell.
+pub struct Automata {
+    pub dfa: Vec<[usize; 2]>,
+    pub nfa: [Vec<u64>; 2],
+}
+
+impl Automata {
+    fn top(&self) -> usize {
+        self.nfa[0].len() - 1
+    }
+
+    // Where a set of NFA states goes on reading one cell
+    fn image(&self, set: u64, cell: usize) -> u64 {
+        (0..self.nfa[cell].len())
+            .filter(|&x| set >> x & 1 == 1)
+            .fold(0, |image, x| image | self.nfa[cell][x])
+    }
+}
+
+enum Transition {
+    Halts,
+    Moves(usize, bool, usize),
+}
+
+fn transition<const N: usize>(
+    machine: &PartialMachine<N>,
+    state: usize,
+    read: usize,
+) -> Transition {
+    match machine.transitions[state][read] {
+        Some(TuringStep {
+            print,
+            motion,
+            next_state: Index(next),
+        }) => Transition::Moves(print as usize, matches!(motion, Right), next),
+        _ => Transition::Halts,
+    }
+}
+
+// Whether the start of the machine, A on blank tape, is recognised. The
+// NFA reads at least the blank under the head, then any number more.
+fn recognises_start(automata: &Automata) -> bool {
+    let top = automata.top();
+    let mut reached = automata.image(1, 0);
+    loop {
+        if reached >> top & 1 == 1 {
+            return true;
+        }
+        let more = reached | automata.image(reached, 0);
+        if more == reached {
+            return false;
+        }
+        reached = more;
+    }
+}
+
+// The least NFA to go with this DFA
+fn least_nfa<const N: usize>(machine: &PartialMachine<N>, dfa: &[[usize; 2]]) -> Automata {
+    let top = dfa.len() * N;
+    let mut automata = Automata {
+        dfa: dfa.to_vec(),
+        nfa: [vec![0; top + 1], vec![0; top + 1]],
+    };
+    for cell in 0..2 {
+        automata.nfa[cell][top] = 1 << top;
+    }
+    // Halting and moving right only ask for fixed transitions
+    for (q, targets) in dfa.iter().enumerate() {
+        for s in 0..N {
+            for read in 0..2 {
+                automata.nfa[read][q * N + s] |= match transition(machine, s, read) {
+                    Transition::Halts => 1 << top,
+                    Transition::Moves(print, true, next) => 1 << (targets[print] * N + next),
+                    Transition::Moves(_, false, _) => 0,
+                };
+            }
+        }
+    }
+    // Moving left from s reading `read`, with x just left of the head, asks
+    // that (q x, s) reading `read` gets to everything (q, next) reading x
+    // and then `print` does
+    loop {
+        let mut changed = false;
+        for s in 0..N {
+            for read in 0..2 {
+                let Transition::Moves(print, false, next) = transition(machine, s, read) else {
+                    continue;
+                };
+                for (q, targets) in dfa.iter().enumerate() {
+                    for (x, target) in targets.iter().enumerate() {
+                        let after = automata.image(automata.nfa[x][q * N + next], print);
+                        let slot = &mut automata.nfa[read][target * N + s];
+                        if after & !*slot != 0 {
+                            *slot |= after;
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+        if !changed {
+            return automata;
+        }
+    }
+}
+
+// Checks automata found for the machine already mirrored, if need be,
+// against every condition directly
+pub fn check<const N: usize>(
+    machine: &PartialMachine<N>,
+    automata: &Automata,
+) -> Result<(), String> {
+    let dfa = &automata.dfa;
+    let states = dfa.len();
+    if states == 0 || states * N + 1 > 64 {
+        return Err(String::from("the automata are too small or too big"));
+    }
+    if dfa.iter().flatten().any(|&q| q >= states) || dfa[0][0] != 0 {
+        return Err(String::from(
+            "the DFA must stay in its first state on blank tape",
+        ));
+    }
+    let top = states * N;
+    if automata
+        .nfa
+        .iter()
+        .any(|nfa| nfa.len() != top + 1 || nfa.iter().any(|&set| set >> top >> 1 != 0))
+    {
+        return Err(format!("the NFA must have {} states", top + 1));
+    }
+    if automata.nfa.iter().any(|nfa| nfa[top] >> top & 1 == 0) {
+        return Err(String::from("the NFA must stay in its last state"));
+    }
+    let has = |set: u64, x: usize| set >> x & 1 == 1;
+    let includes = |big: u64, small: u64| small & !big == 0;
+    for (q, targets) in dfa.iter().enumerate() {
+        for s in 0..N {
+            for read in 0..2 {
+                let from = automata.nfa[read][q * N + s];
+                let fine = match transition(machine, s, read) {
+                    Transition::Halts => has(from, top),
+                    Transition::Moves(print, true, next) => has(from, targets[print] * N + next),
+                    Transition::Moves(print, false, next) => (0..2).all(|x| {
+                        let after = automata.image(automata.nfa[x][q * N + next], print);
+                        includes(automata.nfa[read][targets[x] * N + s], after)
+                    }),
+                };
+                if !fine {
+                    return Err(format!(
+                        "the automata miss what comes before DFA state {} with {}{}",
+                        q,
+                        state_letter(s),
+                        read
+                    ));
+                }
+            }
+        }
+    }
+    match recognises_start(automata) {
+        true => Err(String::from("the automata recognise the start")),
+        false => Ok(()),
+    }
+}
+
+// DFAs with `states` states, numbered in the order their transitions first
+// reach them, staying in the first state on blank tape. Stops at the first
+// that `found` accepts.
+fn search_dfas(states: usize, found: &mut impl FnMut(&[[usize; 2]]) -> bool) -> bool {
+    fn fill(
+        dfa: &mut Vec<[usize; 2]>,
+        slot: usize,
+        used: usize,
+        found: &mut impl FnMut(&[[usize; 2]]) -> bool,
+    ) -> bool {
+        let states = dfa.len();
+        if slot == 2 * states {
+            return used == states && found(dfa);
+        }
+        for target in 0..(used + 1).min(states) {
+            dfa[slot / 2][slot % 2] = target;
+            if fill(dfa, slot + 1, used.max(target + 1), found) {
+                return true;
+            }
+        }
+        false
+    }
+    let mut dfa = vec![[0; 2]; states];
+    fill(&mut dfa, 1, 1, found)
+}
+
+impl<const N: usize> Decider<N> for FiniteAutomataReduction {
+    fn name(&self) -> &'static str {
+        "finite-automata-reduction"
+    }
+
+    fn budget(&self) -> String {
+        format!("states={}", self.max_states)
+    }
+
+    fn decide(&self, machine: &PartialMachine<N>) -> Verdict {
+        let mirrored = mirror(machine);
+        for states in 1..=self.max_states.min(63 / N) {
+            for (machine, mirrored) in [(machine, false), (&mirrored, true)] {
+                let mut result = None;
+                search_dfas(states, &mut |dfa| {
+                    let automata = least_nfa(machine, dfa);
+                    if recognises_start(&automata) {
+                        return false;
+                    }
+                    result = Some(automata);
+                    true
+                });
+                if let Some(automata) = result {
+                    return Verdict::NonHalting(Proof::FiniteAutomataReduction {
+                        mirrored,
+                        dfa: automata.dfa,
+                        nfa: automata.nfa,
+                    });
+                }
+            }
+        }
+        Verdict::Undecided
+    }
+}
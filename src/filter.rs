@@ -0,0 +1,182 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::enumerate::PartialMachine;
+use crate::standard::format_step;
+use crate::standard::state_letter;
+use crate::State::*;
+
+// Restrictions on the machines an enumeration generates, written as clauses
+// separated by commas, all of which have to hold:
+//
+//     A0:1??      the transition in slot A0 matches the pattern: symbol
+//                 written, direction and next state, ? for anything, or
+//                 --- for undefined
+//     halts=1     how many transitions are undefined (halting), with =, <=
+//                 or >=
+//     into:A<=2   how many transitions go to state A, likewise
+//
+// Filters are checked on partly filled tables, so that a subtree of the
+// enumeration can be skipped as soon as nothing in it could match.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    clauses: Vec<Clause>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Compare {
+    Equal,
+    AtMost,
+    AtLeast,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum Clause {
+    // State, symbol read, and the pattern's three characters
+    Slot(usize, usize, [u8; 3]),
+    Halts(Compare, usize),
+    Into(usize, Compare, usize),
+}
+
+impl Compare {
+    // Whether a count somewhere in low..=high can satisfy it
+    fn possible(self, low: usize, high: usize, value: usize) -> bool {
+        match self {
+            Compare::Equal => low <= value && value <= high,
+            Compare::AtMost => low <= value,
+            Compare::AtLeast => high >= value,
+        }
+    }
+}
+
+fn matches(pattern: &[u8; 3], step: &str) -> bool {
+    pattern
+        .iter()
+        .zip(step.bytes())
+        .all(|(&want, got)| want == b'?' || want == got)
+}
+
+impl Filter {
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    // Whether some way of filling in the slots from `filled` on, in the
+    // order A0 A1 B0 B1 and so on, could give a machine that passes
+    pub fn allows<const N: usize>(&self, machine: &PartialMachine<N>, filled: usize) -> bool {
+        let slots: Vec<_> = machine.transitions.iter().flatten().take(filled).collect();
+        let open = 2 * N - slots.len();
+        self.clauses.iter().all(|clause| match *clause {
+            Clause::Slot(state, read, ref pattern) => match slots.get(2 * state + read) {
+                Some(step) => {
+                    let step = step.as_ref().map_or(String::from("---"), format_step);
+                    matches(pattern, &step)
+                }
+                None => 2 * state + read < 2 * N,
+            },
+            Clause::Halts(compare, value) => {
+                let halts = slots
+                    .iter()
+                    .filter(|step| step.is_none_or(|step| step.next_state == HALT))
+                    .count();
+                compare.possible(halts, halts + open, value)
+            }
+            Clause::Into(target, compare, value) => {
+                let into = slots
+                    .iter()
+                    .filter(|step| step.is_some_and(|step| step.next_state == Index(target)))
+                    .count();
+                compare.possible(into, into + open, value)
+            }
+        })
+    }
+}
+
+fn parse_state(s: &str) -> Option<usize> {
+    match s.as_bytes() {
+        &[letter @ b'A'..=b'Z'] => Some((letter - b'A') as usize),
+        _ => None,
+    }
+}
+
+fn parse_count(s: &str) -> Option<(Compare, usize)> {
+    let (compare, value) = if let Some(value) = s.strip_prefix("<=") {
+        (Compare::AtMost, value)
+    } else if let Some(value) = s.strip_prefix(">=") {
+        (Compare::AtLeast, value)
+    } else {
+        (Compare::Equal, s.strip_prefix('=')?)
+    };
+    Some((compare, value.parse().ok()?))
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let clauses = s
+            .split(',')
+            .filter(|clause| !clause.is_empty())
+            .map(|clause| {
+                let bad = || format!("bad filter clause {:?}", clause);
+                if let Some(count) = clause.strip_prefix("halts") {
+                    let (compare, value) = parse_count(count).ok_or_else(bad)?;
+                    return Ok(Clause::Halts(compare, value));
+                }
+                if let Some(rest) = clause.strip_prefix("into:") {
+                    let state = rest.get(..1).and_then(parse_state).ok_or_else(bad)?;
+                    let (compare, value) = parse_count(&rest[1..]).ok_or_else(bad)?;
+                    return Ok(Clause::Into(state, compare, value));
+                }
+                let (slot, pattern) = clause.split_once(':').ok_or_else(bad)?;
+                let state = slot.get(..1).and_then(parse_state).ok_or_else(bad)?;
+                let read = match &slot[1..] {
+                    "0" => 0,
+                    "1" => 1,
+                    _ => return Err(bad()),
+                };
+                let pattern: [u8; 3] = pattern.as_bytes().try_into().map_err(|_| bad())?;
+                let [print, motion, next] = pattern;
+                let valid = pattern == *b"---"
+                    || (matches!(print, b'0' | b'1' | b'?')
+                        && matches!(motion, b'L' | b'R' | b'?')
+                        && (next.is_ascii_uppercase() || next == b'?'));
+                match valid {
+                    true => Ok(Clause::Slot(state, read, pattern)),
+                    false => Err(bad()),
+                }
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Filter { clauses })
+    }
+}
+
+impl Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let show = |compare: Compare, value: usize| {
+            let op = match compare {
+                Compare::Equal => "=",
+                Compare::AtMost => "<=",
+                Compare::AtLeast => ">=",
+            };
+            format!("{}{}", op, value)
+        };
+        let clauses: Vec<String> = self
+            .clauses
+            .iter()
+            .map(|clause| match *clause {
+                Clause::Slot(state, read, pattern) => format!(
+                    "{}{}:{}",
+                    state_letter(state),
+                    read,
+                    String::from_utf8_lossy(&pattern)
+                ),
+                Clause::Halts(compare, value) => format!("halts{}", show(compare, value)),
+                Clause::Into(state, compare, value) => {
+                    format!("into:{}{}", state_letter(state), show(compare, value))
+                }
+            })
+            .collect();
+        f.write_str(&clauses.join(","))
+    }
+}
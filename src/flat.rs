@@ -0,0 +1,74 @@
+use num_traits::PrimInt;
+use num_traits::Unsigned;
+
+use crate::get_bit;
+use crate::set_bit;
+use crate::Bit::*;
+use crate::State;
+use crate::State::*;
+use crate::Tape;
+use crate::TapeMotion::*;
+use crate::TuringMachine;
+use crate::TuringStep;
+
+// The transition table flattened for the step loop: a byte per transition,
+// indexed by the state and the symbol read as (state << 1) | symbol, holding
+// the symbol to write in bit 0, whether to move left in bit 1 and the next
+// state in the rest, with HALT_CODE for the halt. A step is then one load
+// and a few shifts, instead of matching on the symbol, the step's fields and
+// the next state's enum.
+pub struct FlatMachine<const N: usize> {
+    table: [[u8; 2]; N],
+}
+
+const HALT_CODE: usize = 0x3f;
+
+fn encode(step: &TuringStep) -> u8 {
+    let next = match step.next_state {
+        Index(next) => next,
+        HALT => HALT_CODE,
+    };
+    (next << 2) as u8 | ((step.motion == Left) as u8) << 1 | step.print as u8
+}
+
+impl<const N: usize> TuringMachine<N> {
+    pub fn flatten(&self) -> FlatMachine<N> {
+        assert!(N < HALT_CODE);
+        FlatMachine {
+            table: self
+                .states
+                .map(|state| [encode(&state.zero), encode(&state.one)]),
+        }
+    }
+}
+
+impl<const N: usize> FlatMachine<N> {
+    // Runs from `state` for at most `limit` steps, returning the steps taken
+    // and the state it ends in
+    pub fn run<T: Unsigned + PrimInt>(
+        &self,
+        tape: &mut Tape<T>,
+        state: usize,
+        limit: u64,
+    ) -> (u64, State) {
+        let table = self.table.as_flattened();
+        let mut state = state;
+        let mut steps = 0;
+        while state != HALT_CODE && steps < limit {
+            let bit = tape.bit_index;
+            let word = tape.word_mut();
+            let code = table[(state << 1) | get_bit(*word, bit) as usize];
+            set_bit(word, bit, if code & 1 == 0 { Zero } else { One });
+            tape.move_tape(if code & 2 == 0 { Right } else { Left });
+            state = (code >> 2) as usize;
+            steps += 1;
+        }
+        (
+            steps,
+            match state {
+                HALT_CODE => HALT,
+                state => Index(state),
+            },
+        )
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::BTreeSet;
+
+use num_traits::PrimInt;
+use num_traits::Unsigned;
+
+use crate::chunked::Growth;
+use crate::enumerate::PartialMachine;
+use crate::evolve::Random;
+use crate::interop::from_csv;
+use crate::interop::from_turingmachine_io;
+use crate::json::Json;
+use crate::Bit;
+use crate::Bit::*;
+use crate::Tape;
+use crate::TapeMotion::*;
+
+// Fuzz targets, each taking any bytes at all and panicking if they find a
+// bug. They're run by the `fuzz` command, on random inputs or on files of
+// them, such as a fuzzer's corpus or the input of a crash it found.
+
+// The parsers must turn down anything they can't read with an error rather
+// than a panic, and whatever they do read must come back the same from
+// being written out and read again
+pub fn parsers(data: &[u8]) {
+    let text = String::from_utf8_lossy(data);
+    machine_round_trip::<1>(&text);
+    machine_round_trip::<2>(&text);
+    machine_round_trip::<3>(&text);
+    machine_round_trip::<4>(&text);
+    machine_round_trip::<5>(&text);
+    machine_round_trip::<6>(&text);
+    if let Ok(json) = Json::parse(&text) {
+        let written = json.to_string();
+        let again = Json::parse(&written)
+            .unwrap_or_else(|e| panic!("JSON {:?} was written as {:?}: {}", text, written, e));
+        assert_eq!(written, again.to_string(), "JSON {:?} doesn't round-trip", text);
+    }
+    // These only have to not panic, since nothing writes them back out
+    let _ = from_turingmachine_io(&text);
+    let _ = from_csv(&text);
+}
+
+fn machine_round_trip<const N: usize>(text: &str) {
+    if let Ok(machine) = text.parse::<PartialMachine<N>>() {
+        let written = machine.to_string();
+        let again: PartialMachine<N> = written
+            .parse()
+            .unwrap_or_else(|e| panic!("machine {:?} was written as {:?}: {}", text, written, e));
+        assert_eq!(written, again.to_string(), "machine {:?} doesn't round-trip", text);
+    }
+}
+
+// The tape as simply as it can be kept, a set of the cells holding a one and
+// the head's index, for checking `Tape` against
+#[derive(Default)]
+pub struct TapeModel {
+    pub ones: BTreeSet<i64>,
+    pub head: i64,
+    // The furthest the head has been either way
+    pub low: i64,
+    pub high: i64,
+}
+
+impl TapeModel {
+    pub fn get(&self) -> Bit {
+        match self.ones.contains(&self.head) {
+            true => One,
+            false => Zero,
+        }
+    }
+
+    pub fn set(&mut self, bit: Bit) {
+        match bit {
+            One => self.ones.insert(self.head),
+            Zero => self.ones.remove(&self.head),
+        };
+    }
+
+    pub fn move_head(&mut self, right: bool) {
+        self.head += if right { 1 } else { -1 };
+        self.low = self.low.min(self.head);
+        self.high = self.high.max(self.head);
+    }
+
+    // Panics unless the tape has the same head and cells as the model
+    pub fn check<T: Unsigned + PrimInt>(&self, tape: &Tape<T>) {
+        let (low, high) = tape.bounds();
+        assert_eq!(tape.head_index(), self.head, "head index");
+        assert_eq!(tape.get(), self.get(), "cell under the head at {}", self.head);
+        assert!(
+            low <= self.low && self.high < high,
+            "cells {}..={} visited but {}..{} allocated",
+            self.low,
+            self.high,
+            low,
+            high
+        );
+        assert_eq!(
+            tape.get_display_index() as i64,
+            self.head - low,
+            "display index with the head at {} and the tape from {}",
+            self.head,
+            low
+        );
+    }
+}
+
+// Each byte is an operation on a tape of bytes in chunks of two, so that
+// words and chunks are crossed often: the low two bits pick moving left or
+// right, writing, or a snapshot, and the rest say how far to move, what to
+// write, or whether to carry on from the snapshot or the tape it was taken
+// of. The tape is checked against a `TapeModel` after every one.
+pub fn tape(data: &[u8]) {
+    let mut tape = Tape::<u8>::with_growth(Growth {
+        chunk_bits: 1,
+        ..Growth::default()
+    });
+    let mut model = TapeModel::default();
+    model.check(&tape);
+    for &byte in data {
+        let amount = byte >> 2;
+        match byte & 3 {
+            0 | 1 => {
+                for _ in 0..=amount {
+                    tape.move_tape(if byte & 1 == 1 { Right } else { Left });
+                    model.move_head(byte & 1 == 1);
+                }
+            }
+            2 => {
+                let bit = if amount & 1 == 1 { One } else { Zero };
+                tape.set(bit);
+                model.set(bit);
+            }
+            _ => {
+                let snapshot = tape.snapshot();
+                if amount & 1 == 1 {
+                    tape = Tape::restore(&snapshot);
+                }
+            }
+        }
+        model.check(&tape);
+        let ones = (model.low..=model.high)
+            .filter(|&i| tape.cell_at(i) == One)
+            .count();
+        assert_eq!(ones, model.ones.len(), "ones on the tape");
+    }
+}
+
+// Inputs for the targets when there's no corpus to replay, of the bytes the
+// parsers look for more often than not, so that some of them get past the
+// first character
+pub fn random_input(random: &mut Random) -> Vec<u8> {
+    const ALPHABET: &[u8] = b"01LRZABCDEF_-\"{}[]:,.e+ \n\\utrnfalsey";
+    let len = random.below(64);
+    (0..len)
+        .map(|_| match random.below(8) {
+            0 => random.next() as u8,
+            _ => ALPHABET[random.below(ALPHABET.len())],
+        })
+        .collect()
+}
@@ -0,0 +1,198 @@
+use num_traits::PrimInt;
+use num_traits::Unsigned;
+
+use crate::enumerate::PartialMachine;
+use crate::header::fingerprint;
+use crate::header::header;
+use crate::header::parse_header;
+use crate::selftest::input_tape;
+use crate::standard::state_letter;
+use crate::Bit;
+use crate::Bit::*;
+use crate::State::*;
+use crate::Tape;
+use crate::TuringMachine;
+
+// Golden traces: a run written out a line per step, to be kept in a file
+// and checked against later, so a change to the step loop or the tape that
+// changes what a machine does shows up as a diff.
+//
+// Each line is the step, the state with Z for the halt, the head's index
+// and the cells from the leftmost one or the head, whichever comes first,
+// to the rightmost, with the head's bracketed:
+//
+//     3 B -1 [1]11
+//
+// after a header (see `header`) with the fingerprint of the machine, and a
+// line with the machine itself:
+//
+//     turing-sim-rs trace 2 9e4b07c2d5a1f386
+//     # 1RB1LB_1LA1RZ
+//
+// Version 1 had only the machine's line, and is read as if it had the
+// header.
+//
+// The traces in golden/ are checked by the tests at the end of this file,
+// and rewritten with `golden <machine> <file> --update` when a change to a
+// run is intended.
+pub const VERSION: u32 = 2;
+
+fn line<T: Unsigned + PrimInt, const N: usize>(
+    step: u64,
+    tm: &TuringMachine<N>,
+    tape: &Tape<T>,
+) -> String {
+    let (low, high) = tape.bounds();
+    let head = tape.head_index();
+    let ones: Vec<i64> = (low..high).filter(|&i| tape.cell_at(i) == One).collect();
+    let first = ones.first().map_or(head, |&i| i.min(head));
+    let last = ones.last().map_or(head, |&i| i.max(head));
+    let mut cells = String::new();
+    for i in first..=last {
+        let cell = if tape.cell_at(i) == One { '1' } else { '0' };
+        match i == head {
+            true => cells.push_str(&format!("[{}]", cell)),
+            false => cells.push(cell),
+        }
+    }
+    let state = match tm.state {
+        Index(state) => state_letter(state),
+        HALT => 'Z',
+    };
+    format!("{} {} {} {}", step, state, head, cells)
+}
+
+fn headers<const N: usize>(machine: &PartialMachine<N>) -> [String; 2] {
+    let text = machine.to_string();
+    [
+        header("trace", VERSION, &fingerprint(&text)),
+        format!("# {}", text),
+    ]
+}
+
+// The trace for at most `limit` steps from the input, with the machine in
+// a header
+pub fn trace<const N: usize>(
+    machine: &PartialMachine<N>,
+    input: &[Bit],
+    limit: u64,
+) -> Vec<String> {
+    let mut tm = machine.to_machine();
+    // Small words, so runs cross from word to word and half to half often
+    let mut tape = input_tape::<u8>(input);
+    let mut lines = headers(machine).to_vec();
+    lines.push(line(0, &tm, &tape));
+    let mut steps = 0;
+    while let Index(state) = tm.state {
+        if steps == limit {
+            break;
+        }
+        tm.step(&mut tape, state);
+        steps += 1;
+        lines.push(line(steps, &tm, &tape));
+    }
+    lines
+}
+
+// The lines of a trace file as the current version writes them, or an
+// error if it isn't a trace of the machine in a version this reads
+pub fn load<const N: usize>(
+    text: &str,
+    machine: &PartialMachine<N>,
+) -> Result<Vec<String>, String> {
+    let mut lines: Vec<String> = text.lines().map(String::from).collect();
+    let first = lines.first().map_or("", String::as_str);
+    let found = parse_header(first, "trace", VERSION)?;
+    if found.is_none() {
+        // Version 1
+        lines.insert(0, headers(machine)[0].clone());
+    }
+    let named = lines.get(1).and_then(|line| line.strip_prefix("# "));
+    let different = match found {
+        Some((_, found)) => found != fingerprint(&machine.to_string()),
+        None => named != Some(&machine.to_string()),
+    };
+    if different {
+        return Err(format!(
+            "it's a trace of {}, not {}",
+            named.unwrap_or("another machine"),
+            machine
+        ));
+    }
+    Ok(lines)
+}
+
+// Lines either side of the first difference to show
+const CONTEXT: usize = 3;
+
+// None if the traces are the same, or else the first place they differ,
+// with the lines around it, - for expected and + for actual
+pub fn diff(expected: &[&str], actual: &[String]) -> Option<String> {
+    let first = (0..expected.len().max(actual.len()))
+        .find(|&i| expected.get(i).copied() != actual.get(i).map(String::as_str))?;
+    let mut output = format!("first difference at line {}\n", first + 1);
+    for line in &expected[first.saturating_sub(CONTEXT)..first] {
+        output.push_str(&format!("  {}\n", line));
+    }
+    let end = |len: usize| len.min(first + CONTEXT + 1);
+    for line in &expected[first.min(expected.len())..end(expected.len())] {
+        output.push_str(&format!("- {}\n", line));
+    }
+    for line in &actual[first.min(actual.len())..end(actual.len())] {
+        output.push_str(&format!("+ {}\n", line));
+    }
+    if expected.len() != actual.len() {
+        output.push_str(&format!(
+            "expected {} lines, got {}\n",
+            expected.len(),
+            actual.len()
+        ));
+    }
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The machine, its input and the trace checked in for it
+    fn check<const N: usize>(machine: &str, input: &[Bit], text: &str) {
+        let machine: PartialMachine<N> = machine.parse().unwrap();
+        let expected = load(text, &machine).unwrap();
+        let expected: Vec<&str> = expected.iter().map(String::as_str).collect();
+        if let Some(diff) = diff(&expected, &trace(&machine, input, 10_000)) {
+            panic!("{} no longer runs as traced:\n{}", machine, diff);
+        }
+    }
+
+    #[test]
+    fn busy_beavers_match_their_traces() {
+        check::<2>("1RB1LB_1LA1RZ", &[], include_str!("../golden/bb2.trace"));
+        check::<3>(
+            "1RB1RZ_1LB0RC_1LC1LA",
+            &[],
+            include_str!("../golden/bb3.trace"),
+        );
+    }
+
+    #[test]
+    fn copy_matches_its_trace() {
+        check::<5>(
+            "0RZ0RB_0RC1RB_1LD1RC_0LE1LD_1RA1LE",
+            &[One, One, One],
+            include_str!("../golden/copy-3.trace"),
+        );
+    }
+
+    #[test]
+    fn a_changed_run_shows_up_in_the_diff() {
+        let machine: PartialMachine<2> = "1RB1LB_1LA1RZ".parse().unwrap();
+        let text = include_str!("../golden/bb2.trace");
+        let expected: Vec<&str> = text.lines().collect();
+        let mut actual = trace(&machine, &[], 10_000);
+        actual[4] = String::from("2 B 2 1[1]");
+        let diff = diff(&expected, &actual).unwrap();
+        assert!(diff.starts_with("first difference at line 5\n"));
+        assert!(diff.contains("+ 2 B 2 1[1]\n"));
+    }
+}
@@ -0,0 +1,57 @@
+use crate::sha256::hex;
+use crate::sha256::sha256;
+
+// The first line of the text files that are written to be read back later,
+// cursors and golden traces, saying what the file is, the version of its
+// format, and a fingerprint of the machine or search it belongs to:
+//
+//     turing-sim-rs cursor 3 5d1f0e9a3c7b2a48
+//
+// Files from before these had a header are version 1, and each reader
+// either still reads those or says why it can't. A file with a newer
+// version than the reader knows is turned down rather than half read.
+//
+// (Machines saved in the packed binary format have their own magic and
+// version, see `packed`, and LUTs are only ever built in memory.)
+const MAGIC: &str = "turing-sim-rs";
+
+// The first 16 hex digits of the SHA-256 of the text, enough to tell
+// machines or searches apart without being a line of its own
+pub fn fingerprint(text: &str) -> String {
+    hex(&sha256(text.as_bytes()))[..16].to_string()
+}
+
+pub fn header(kind: &str, version: u32, fingerprint: &str) -> String {
+    format!("{} {} {} {}", MAGIC, kind, version, fingerprint)
+}
+
+// The version and fingerprint from the first line of a file of the kind,
+// None if the line isn't a header at all, so the file is from version 1,
+// or an error if it's a header of another kind of file or of a version
+// newer than `current`
+pub fn parse_header(
+    line: &str,
+    kind: &str,
+    current: u32,
+) -> Result<Option<(u32, String)>, String> {
+    let words: Vec<&str> = line.split(' ').collect();
+    match words[..] {
+        [MAGIC, found, version, fingerprint] => {
+            if found != kind {
+                return Err(format!("expected a {} but found a {}", kind, found));
+            }
+            let version: u32 = version
+                .parse()
+                .map_err(|_| format!("bad {} version {:?}", kind, version))?;
+            if version > current {
+                return Err(format!(
+                    "{} version {} is from a newer turing-sim-rs, this one reads up to version {}",
+                    kind, version, current
+                ));
+            }
+            Ok(Some((version, fingerprint.to_string())))
+        }
+        [MAGIC, ..] => Err(format!("bad header {:?}", line)),
+        _ => Ok(None),
+    }
+}
@@ -0,0 +1,248 @@
+use std::io::Write;
+
+use num_traits::PrimInt;
+use num_traits::Unsigned;
+
+use crate::Bit::*;
+use crate::State::*;
+use crate::Tape;
+use crate::TuringMachine;
+
+// A run as the history of a one-dimensional cellular automaton, a row of
+// cells for each step, so it can be loaded into the same viewers as Rule 110.
+//
+// Cells are 0 blank and 1 one, except the one under the head, which is
+// 2 + 2 * state + the symbol, with the halt state counted as state N. That
+// keeps the whole configuration in the row, the way a CA simulating the
+// machine would have to.
+//
+// The rows aren't kept that way though, since a byte a cell for every step
+// of a long run on a wide tape would take gigabytes. Each row's cells are
+// packed a bit each into words, with the head and state kept beside them,
+// and only the words that differ from the row before are stored, as the
+// XOR of the two. A step changes a single cell, so most rows are a word.
+// A row whose first cell isn't where the last one's was, because the tape
+// grew to the left, is stored against a blank row instead.
+pub struct History {
+    rows: Vec<Row>,
+    // Every row's changed words, with their index in the row
+    words: Vec<(usize, u64)>,
+    // The last row recorded, to tell what the next one changes
+    last: Vec<u64>,
+    states: usize,
+}
+
+// Kept small, since there's one for every step
+struct Row {
+    // Tape index of the first cell, since the tape grows, and the number of
+    // cells
+    low: i64,
+    cells: u32,
+    // Counted from the first cell
+    head: u32,
+    state: u8,
+    // Whether `words` are only the row's, rather than its changes from the
+    // one before
+    full: bool,
+    // Where the row's words start in `History::words`, running up to the
+    // next row's start
+    start: usize,
+}
+
+impl History {
+    pub fn new(states: usize) -> History {
+        // Cell states have to fit in a byte, and in Golly's 256
+        assert!(2 + 2 * (states + 1) <= 256);
+        History {
+            rows: Vec::new(),
+            words: Vec::new(),
+            last: Vec::new(),
+            states,
+        }
+    }
+
+    pub fn record<T: Unsigned + PrimInt, const N: usize>(
+        &mut self,
+        tm: &TuringMachine<N>,
+        tape: &Tape<T>,
+    ) {
+        let (low, high) = tape.bounds();
+        let head = tape.head_index();
+        let state = match tm.state {
+            Index(state) => state,
+            HALT => self.states,
+        };
+        let cells = (high - low) as usize;
+        let mut row = vec![0; cells.div_ceil(64)];
+        for i in low..high {
+            if tape.cell_at(i) == One {
+                let cell = (i - low) as usize;
+                row[cell / 64] |= 1 << (cell % 64);
+            }
+        }
+        let full = self.rows.last().is_none_or(|last| last.low != low);
+        if full {
+            self.last.clear();
+        }
+        let start = self.words.len();
+        for (i, word) in row.iter().enumerate() {
+            let changes = word ^ self.last.get(i).unwrap_or(&0);
+            if changes != 0 {
+                self.words.push((i, changes));
+            }
+        }
+        self.rows.push(Row {
+            low,
+            cells: cells as u32,
+            head: (head - low) as u32,
+            state: state as u8,
+            full,
+            start,
+        });
+        self.last = row;
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    // Heap bytes held by the rows
+    pub fn memory_usage(&self) -> usize {
+        self.rows.capacity() * std::mem::size_of::<Row>()
+            + self.words.capacity() * std::mem::size_of::<(usize, u64)>()
+            + self.last.capacity() * std::mem::size_of::<u64>()
+    }
+
+    // Each row with the tape index of its first cell, as cell states,
+    // unpacked in turn from the first
+    fn rows(&self) -> impl Iterator<Item = (i64, Vec<u8>)> + '_ {
+        let mut words = Vec::new();
+        let ends = self.rows.iter().skip(1).map(|row| row.start);
+        self.rows
+            .iter()
+            .zip(ends.chain([self.words.len()]))
+            .map(move |(row, end)| {
+                if row.full {
+                    words.clear();
+                }
+                words.resize((row.cells as usize).div_ceil(64), 0);
+                for &(i, changes) in &self.words[row.start..end] {
+                    words[i] ^= changes;
+                }
+                let cells = (0..row.cells as usize)
+                    .map(|cell| {
+                        let one = (words[cell / 64] >> (cell % 64) & 1) as u8;
+                        if cell == row.head as usize {
+                            2 + 2 * row.state + one
+                        } else {
+                            one
+                        }
+                    })
+                    .collect();
+                (row.low, cells)
+            })
+    }
+
+    // Number of cell states in use, counting the blank one
+    pub fn cell_states(&self) -> usize {
+        2 + 2 * (self.states + 1)
+    }
+
+    // The cells every row covers, as tape indices of the first and one past
+    // the last
+    fn bounds(&self) -> (i64, i64) {
+        self.rows.iter().fold((0, 0), |(low, high), row| {
+            (low.min(row.low), high.max(row.low + row.cells as i64))
+        })
+    }
+
+    // Golly's run length encoding: a header with the size, then rows
+    // separated by '$' and ended by '!', with blank cells at the end of a row
+    // left out. Lines are kept under 70 characters as the format asks.
+    pub fn write_rle<W: Write>(&self, out: &mut W, rule: Option<&str>) -> std::io::Result<()> {
+        let (low, high) = self.bounds();
+        write!(out, "x = {}, y = {}", high - low, self.rows.len())?;
+        if let Some(rule) = rule {
+            write!(out, ", rule = {}", rule)?;
+        }
+        writeln!(out)?;
+
+        let mut line = String::new();
+        let mut push = |item: String, out: &mut W| -> std::io::Result<()> {
+            if line.len() + item.len() > 70 {
+                writeln!(out, "{}", line)?;
+                line.clear();
+            }
+            line.push_str(&item);
+            Ok(())
+        };
+        // Empty rows are folded into the count of the '$' that ends them
+        let mut ends = 0;
+        for (start, row) in self.rows() {
+            let padding = (start - low) as usize;
+            let cells: Vec<u8> = std::iter::repeat_n(0, padding)
+                .chain(row.iter().copied())
+                .collect();
+            let used = cells.iter().rposition(|x| *x != 0).map_or(0, |i| i + 1);
+            if used > 0 && ends > 0 {
+                push(run(ends, "$"), out)?;
+                ends = 0;
+            }
+            let mut i = 0;
+            while i < used {
+                let count = cells[i..used]
+                    .iter()
+                    .take_while(|x| **x == cells[i])
+                    .count();
+                push(run(count, &rle_state(cells[i])), out)?;
+                i += count;
+            }
+            ends += 1;
+        }
+        push(String::from("!"), out)?;
+        writeln!(out, "{}", line)
+    }
+}
+
+fn run(count: usize, item: &str) -> String {
+    match count {
+        1 => item.to_string(),
+        _ => format!("{}{}", count, item),
+    }
+}
+
+// '.' for the blank state, then A to X, then pA to pX, qA and so on
+fn rle_state(state: u8) -> String {
+    if state == 0 {
+        return String::from(".");
+    }
+    let n = state - 1;
+    let letter = (b'A' + n % 24) as char;
+    match n / 24 {
+        0 => letter.to_string(),
+        prefix => format!("{}{}", (b'o' + prefix) as char, letter),
+    }
+}
+
+impl<const N: usize> TuringMachine<N> {
+    // Runs for at most `limit` steps, adding a row to the history for each.
+    // Returns the number of steps taken.
+    pub fn history<T: Unsigned + PrimInt>(
+        &mut self,
+        tape: &mut Tape<T>,
+        limit: u64,
+        history: &mut History,
+    ) -> u64 {
+        let mut steps = 0;
+        history.record(self, tape);
+        while let Index(state) = self.state {
+            if steps == limit {
+                break;
+            }
+            self.step(tape, state);
+            steps += 1;
+            history.record(self, tape);
+        }
+        steps
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+// The machines a search still hasn't settled, kept across classification
+// runs along with what has been tried on each of them. Saved as tab
+// separated lines of a machine and the decider attempts made on it so far,
+// separated by spaces:
+//
+//     1RB1LC_0LA0RB_1LA---   simulation(limit=1000000) translated-cycler(limit=10000)
+//
+// Lines starting with # are comments.
+pub struct HoldoutList {
+    pub entries: Vec<Holdout>,
+}
+
+pub struct Holdout {
+    pub machine: String,
+    // Deciders with their limits (see `decider::attempt`), in the order
+    // they were first tried
+    pub attempts: Vec<String>,
+}
+
+impl HoldoutList {
+    // A file that isn't there yet is an empty list
+    pub fn read(path: &Path) -> Result<HoldoutList, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("{}: {}", path.display(), e)),
+        };
+        let entries = text
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let (machine, attempts) = line.split_once('\t').unwrap_or((line, ""));
+                Holdout {
+                    machine: machine.trim().to_string(),
+                    attempts: attempts.split_whitespace().map(String::from).collect(),
+                }
+            })
+            .collect();
+        Ok(HoldoutList { entries })
+    }
+
+    // Replaces the file in one go, like cursors
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let mut text = String::new();
+        for entry in &self.entries {
+            text += &format!("{}\t{}\n", entry.machine, entry.attempts.join(" "));
+        }
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, text)?;
+        fs::rename(&temporary, path)
+    }
+
+    // Adds machines not already in the list, returning how many were new
+    pub fn import<'a>(&mut self, machines: impl IntoIterator<Item = &'a str>) -> usize {
+        let before = self.entries.len();
+        let mut known: HashSet<String> = self.entries.iter().map(|e| e.machine.clone()).collect();
+        for machine in machines {
+            if known.insert(machine.to_string()) {
+                self.entries.push(Holdout {
+                    machine: machine.to_string(),
+                    attempts: Vec::new(),
+                });
+            }
+        }
+        self.entries.len() - before
+    }
+}
@@ -0,0 +1,210 @@
+use crate::enumerate::PartialMachine;
+use crate::enumerate::HALT_STEP;
+use crate::standard::state_letter;
+use crate::State::*;
+use crate::TapeMotion::*;
+use crate::TuringStep;
+
+// Hard 5-state machines from Skelet's list of holdouts, the machines his
+// 2003 search for BB(5) could not decide, by his numbering
+pub const HOLDOUTS: &[(&str, &str)] = &[
+    // A translated cycler, but only after a very long run
+    ("skelet1", "1RB1RD_1LC0RC_1RA1LD_0RE0LB_---1RC"),
+    // Counts in a Gray code
+    ("skelet17", "1RB---_0LC1RE_0LD1LC_1RA1LB_0RB0RA"),
+];
+
+pub fn holdout(name: &str) -> Option<&'static str> {
+    HOLDOUTS
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map(|(_, machine)| *machine)
+}
+
+// A tape stored as runs of equal cells, which lets long sweeps through a run
+// be taken in one go. Each side is a stack with the run next to the head on
+// top, and blank beyond the bottom.
+pub struct RunTape {
+    pub left: Vec<(u8, u64)>,
+    pub head: u8,
+    pub right: Vec<(u8, u64)>,
+}
+
+impl RunTape {
+    pub fn ones(&self) -> u64 {
+        let ones = |side: &[(u8, u64)]| -> u64 {
+            side.iter()
+                .filter(|&&(symbol, _)| symbol == 1)
+                .map(|&(_, n)| n)
+                .sum()
+        };
+        ones(&self.left) + ones(&self.right) + self.head as u64
+    }
+}
+
+// Blanks pushed onto an empty side just join the blank tape beyond it
+pub fn push(side: &mut Vec<(u8, u64)>, symbol: u8, count: u64) {
+    match side.last_mut() {
+        None if symbol == 0 => {}
+        Some((top, n)) if *top == symbol => *n += count,
+        _ => side.push((symbol, count)),
+    }
+}
+
+pub fn pop(side: &mut Vec<(u8, u64)>) -> u8 {
+    match side.last_mut() {
+        None => 0,
+        Some((symbol, n)) => {
+            let symbol = *symbol;
+            *n -= 1;
+            if *n == 0 {
+                side.pop();
+            }
+            symbol
+        }
+    }
+}
+
+pub enum Chain {
+    Running,
+    Halted,
+    // Sweeping forever into blank tape
+    Escaped,
+}
+
+// Simulation with chain steps: when a transition keeps the state, moves on
+// and meets a run of the symbol it reads, it would go all the way through
+// the run writing the same thing, so that is done at once.
+pub struct ChainRun<'a, const N: usize> {
+    machine: &'a PartialMachine<N>,
+    pub tape: RunTape,
+    pub state: usize,
+    pub head: i64,
+    // In u128, since a chain step can take more steps than fit in a u64
+    // after far fewer of them
+    pub steps: u128,
+    // Steps of the simulation itself, chain steps counting once
+    pub chain_steps: u64,
+}
+
+impl<'a, const N: usize> ChainRun<'a, N> {
+    pub fn new(machine: &'a PartialMachine<N>) -> ChainRun<'a, N> {
+        ChainRun {
+            machine,
+            tape: RunTape {
+                left: Vec::new(),
+                head: 0,
+                right: Vec::new(),
+            },
+            state: 0,
+            head: 0,
+            steps: 0,
+            chain_steps: 0,
+        }
+    }
+
+    pub fn step(&mut self) -> Chain {
+        let read = self.tape.head;
+        let step = self.machine.transitions[self.state][read as usize].unwrap_or(HALT_STEP);
+        let TuringStep {
+            print,
+            motion,
+            next_state,
+        } = step;
+        let Index(next) = next_state else {
+            // The halting step still counts, writes and moves, as it does on
+            // a `Tape`
+            let (behind, ahead, direction) = match motion {
+                Right => (&mut self.tape.left, &mut self.tape.right, 1),
+                Left => (&mut self.tape.right, &mut self.tape.left, -1),
+            };
+            push(behind, print as u8, 1);
+            self.tape.head = pop(ahead);
+            self.head += direction;
+            self.steps += 1;
+            self.chain_steps += 1;
+            return Chain::Halted;
+        };
+        let (behind, ahead, direction) = match motion {
+            Right => (&mut self.tape.left, &mut self.tape.right, 1),
+            Left => (&mut self.tape.right, &mut self.tape.left, -1),
+        };
+        // The cells ahead that will be gone through along with this one
+        let mut run = 0;
+        if next == self.state {
+            match ahead.last() {
+                Some(&(symbol, count)) if symbol == read => {
+                    run = count;
+                    ahead.pop();
+                }
+                None if read == 0 => return Chain::Escaped,
+                _ => {}
+            }
+        }
+        push(behind, print as u8, run + 1);
+        self.tape.head = pop(ahead);
+        self.state = next;
+        self.head += direction * (run as i64 + 1);
+        self.steps += run as u128 + 1;
+        self.chain_steps += 1;
+        Chain::Running
+    }
+}
+
+// Writes runs as symbol^count, and sequences of runs that repeat as
+// (runs)^times, so a tape like 1110 1110 1110 comes out as (1^3 0)^3
+pub fn repeat_structure(runs: &[(u8, u64)]) -> String {
+    let show = |&(symbol, count): &(u8, u64)| match count {
+        1 => symbol.to_string(),
+        _ => format!("{}^{}", symbol, count),
+    };
+    let mut words = Vec::new();
+    let mut i = 0;
+    while i < runs.len() {
+        // The unit of runs that covers the most by repeating, if any does
+        let mut best = (1, 1);
+        for unit in 2..=8.min(runs.len() - i) {
+            let mut times = 1;
+            while runs[i + times * unit..].starts_with(&runs[i..i + unit]) {
+                times += 1;
+                if i + (times + 1) * unit > runs.len() {
+                    break;
+                }
+            }
+            if times > 1 && unit * times > best.0 * best.1 {
+                best = (unit, times);
+            }
+        }
+        match best {
+            (1, _) => words.push(show(&runs[i])),
+            (unit, times) => words.push(format!(
+                "({})^{}",
+                runs[i..i + unit]
+                    .iter()
+                    .map(show)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                times
+            )),
+        }
+        i += best.0 * best.1;
+    }
+    words.join(" ")
+}
+
+// The configuration as state, then the tape left to right with the head's
+// cell in brackets, both sides in repeat structure
+pub fn format_configuration(state: usize, tape: &RunTape) -> String {
+    let right: Vec<(u8, u64)> = tape.right.iter().rev().copied().collect();
+    let mut text = format!("{} ", state_letter(state));
+    if !tape.left.is_empty() {
+        text += &repeat_structure(&tape.left);
+        text += " ";
+    }
+    text += &format!("[{}]", tape.head);
+    if !right.is_empty() {
+        text += " ";
+        text += &repeat_structure(&right);
+    }
+    text
+}
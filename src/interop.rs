@@ -0,0 +1,748 @@
+use std::collections::VecDeque;
+
+use crate::enumerate::PartialMachine;
+use crate::enumerate::HALT_STEP;
+use crate::standard::format_step;
+use crate::standard::state_letter;
+use crate::wide::WideMachine;
+use crate::wide::WideStep;
+use crate::Bit;
+use crate::Bit::*;
+use crate::State::*;
+use crate::TapeMotion::*;
+use crate::TuringStep;
+
+// Machines written for, and read from, other simulators
+
+// Morphett's simulator, at morphett.info/turing: a line per rule of
+//
+//     <state> <symbol read> <symbol written> <l or r> <next state>
+//
+// where _ is the blank symbol, 0 the starting state and reaching a state
+// named halt stops the run. States keep their numbers from A as 0, and
+// undefined transitions are written out as the halting ones they're run
+// as. Comments start with ';', and the input goes in a field of its own,
+// so it's given in one.
+pub fn to_morphett<const N: usize>(machine: &PartialMachine<N>, input: &[Bit]) -> String {
+    let symbol = |bit: Bit| match bit {
+        Zero => '_',
+        One => '1',
+    };
+    let mut output = format!("; {}\n", machine);
+    if !input.is_empty() {
+        let input: String = input.iter().map(|bit| symbol(*bit)).collect();
+        output.push_str(&format!("; initial input: {}\n", input));
+    }
+    for (state, slots) in machine.transitions.iter().enumerate() {
+        for (read, step) in [Zero, One].into_iter().zip(slots) {
+            let step: TuringStep = step.unwrap_or(HALT_STEP);
+            output.push_str(&format!(
+                "{} {} {} {} {}\n",
+                state,
+                symbol(read),
+                symbol(step.print),
+                match step.motion {
+                    Left => 'l',
+                    Right => 'r',
+                },
+                match step.next_state {
+                    Index(next) => next.to_string(),
+                    HALT => String::from("halt"),
+                }
+            ));
+        }
+    }
+    output
+}
+
+// turingmachinesimulator.com's programs: a name, the initial and accepting
+// states, and each rule as two lines,
+//
+//     <state>,<symbol read>
+//     <next state>,<symbol written>,< or >
+//
+// with _ as blank. States keep their letters, with Z the accepting one, and
+// undefined transitions are written out as the halting ones they're run as.
+// The input goes in a field of its own, so it's given in a comment. The
+// site's rules for more tapes list a symbol and move for each, but machines
+// here only have the one.
+pub fn to_turingmachinesimulator<const N: usize>(
+    machine: &PartialMachine<N>,
+    input: &[Bit],
+) -> String {
+    let symbol = |bit: Bit| match bit {
+        Zero => '_',
+        One => '1',
+    };
+    let mut output = format!("name: {}\ninit: A\naccept: Z\n", machine);
+    if !input.is_empty() {
+        let input: String = input.iter().map(|bit| symbol(*bit)).collect();
+        output.push_str(&format!("// Input: {}\n", input));
+    }
+    for (state, slots) in machine.transitions.iter().enumerate() {
+        for (read, step) in [Zero, One].into_iter().zip(slots) {
+            let step: TuringStep = step.unwrap_or(HALT_STEP);
+            output.push_str(&format!(
+                "\n{},{}\n{},{},{}\n",
+                state_letter(state),
+                symbol(read),
+                match step.next_state {
+                    Index(next) => state_letter(next),
+                    HALT => 'Z',
+                },
+                symbol(step.print),
+                match step.motion {
+                    Left => '<',
+                    Right => '>',
+                }
+            ));
+        }
+    }
+    output
+}
+
+// An HTML table of the transitions, a row for each state and a column for
+// each symbol read, with the machine and its input as the caption, for
+// showing it in a notebook or on a web page. Undefined transitions are
+// shown as --- as in the standard format.
+pub fn to_html<const N: usize>(machine: &PartialMachine<N>, input: &[Bit]) -> String {
+    let mut output = format!("<table>\n<caption><code>{}</code>", machine);
+    if !input.is_empty() {
+        let input: String = input
+            .iter()
+            .map(|bit| (b'0' + *bit as u8) as char)
+            .collect();
+        output.push_str(&format!(" on <code>{}</code>", input));
+    }
+    output.push_str("</caption>\n<tr><th></th><th>0</th><th>1</th></tr>\n");
+    for (state, slots) in machine.transitions.iter().enumerate() {
+        output.push_str(&format!("<tr><th>{}</th>", state_letter(state)));
+        for step in slots {
+            let step = match step {
+                Some(step) => format_step(step),
+                None => String::from("---"),
+            };
+            output.push_str(&format!("<td><code>{}</code></td>", step));
+        }
+        output.push_str("</tr>\n");
+    }
+    output.push_str("</table>\n");
+    output
+}
+
+// A machine read from another simulator's format, over its own symbols
+pub struct Imported {
+    pub machine: WideMachine,
+    // The names of the states, start first. Transitions to states without
+    // any of their own halt instead, and those are `halting`.
+    pub states: Vec<String>,
+    pub halting: Vec<String>,
+    // The symbols in the order they're numbered, blank first
+    pub symbols: Vec<char>,
+    // The tape to start on, with the head on its first cell
+    pub input: VecDeque<u8>,
+}
+
+// The lines that matter, with comments and trailing space taken off
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+    text: &'a str,
+}
+
+fn yaml_lines(text: &str) -> Result<Vec<Line<'_>>, String> {
+    let mut lines = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let number = i + 1;
+        let mut quote = None;
+        let mut end = line.len();
+        for (j, c) in line.char_indices() {
+            match (quote, c) {
+                (None, '\'' | '"') => quote = Some(c),
+                (Some(q), _) if c == q => quote = None,
+                (None, '#') if j == 0 || line[..j].ends_with(' ') => {
+                    end = j;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let line = line[..end].trim_end();
+        let text = line.trim_start_matches(' ');
+        if text.starts_with('\t') {
+            return Err(format!("line {}: tabs can't be used to indent", number));
+        }
+        if text.is_empty() || text == "---" {
+            continue;
+        }
+        lines.push(Line {
+            number,
+            indent: line.len() - text.len(),
+            text,
+        });
+    }
+    Ok(lines)
+}
+
+// Splits at each `separator` outside of quotes
+fn split_unquoted(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, _) if c == separator => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+// A key and what follows it, for a line of a mapping, or None if the line
+// isn't one
+fn split_key(text: &str) -> Option<(&str, &str)> {
+    let mut quote = None;
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '[' | '{') => depth += 1,
+            (None, ']' | '}') => depth -= 1,
+            (None, ':') if depth == 0 && text[i + 1..].chars().next().is_none_or(|c| c == ' ') => {
+                return Some((text[..i].trim(), text[i + 1..].trim()));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// A plain or quoted string
+fn scalar(s: &str, number: usize) -> Result<String, String> {
+    let s = s.trim();
+    for quote in ['\'', '"'] {
+        if let Some(inner) = s.strip_prefix(quote) {
+            let inner = inner
+                .strip_suffix(quote)
+                .ok_or(format!("line {}: unterminated string {}", number, s))?;
+            return Ok(match quote {
+                '\'' => inner.replace("''", "'"),
+                _ => inner.replace("\\\"", "\"").replace("\\\\", "\\"),
+            });
+        }
+    }
+    if s.starts_with(['[', '{', '&', '*', '!', '|', '>']) {
+        return Err(format!("line {}: {} isn't supported here", number, s));
+    }
+    Ok(s.to_string())
+}
+
+fn symbol(s: &str, number: usize) -> Result<char, String> {
+    let s = scalar(s, number)?;
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(format!(
+            "line {}: symbols have to be one character, not {:?}",
+            number, s
+        )),
+    }
+}
+
+// What a state does on reading a symbol
+#[derive(Clone)]
+struct Action {
+    write: Option<char>,
+    right: bool,
+    // The same state if None
+    next: Option<String>,
+    // Where it's written, for errors
+    number: usize,
+}
+
+fn action<'a>(
+    entries: impl Iterator<Item = (&'a str, Option<&'a str>)>,
+    number: usize,
+) -> Result<Action, String> {
+    let mut write = None;
+    let mut motion = None;
+    for (key, value) in entries {
+        match key {
+            "write" => {
+                let value = value.ok_or(format!("line {}: write needs a symbol", number))?;
+                write = Some(symbol(value, number)?);
+            }
+            "L" | "R" if motion.is_some() => {
+                return Err(format!("line {}: only one move is allowed", number));
+            }
+            "L" | "R" => {
+                let next = match value {
+                    Some(value) => Some(scalar(value, number)?),
+                    None => None,
+                };
+                motion = Some((key == "R", next));
+            }
+            _ => {
+                return Err(format!(
+                    "line {}: {} isn't supported, only write, L and R",
+                    number, key
+                ))
+            }
+        }
+    }
+    let (right, next) = motion.ok_or(format!("line {}: a move, L or R, is needed", number))?;
+    Ok(Action {
+        write,
+        right,
+        next,
+        number,
+    })
+}
+
+// The entries of a flow mapping, {write: 1, L: done}
+fn flow_entries(s: &str, number: usize) -> Result<Vec<(&str, Option<&str>)>, String> {
+    let inner = s
+        .strip_prefix('{')
+        .and_then(|s| s.strip_suffix('}'))
+        .ok_or(format!("line {}: bad mapping {}", number, s))?;
+    Ok(split_unquoted(inner, ',')
+        .into_iter()
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match split_key(entry) {
+            Some((key, value)) => (key, Some(value).filter(|v| !v.is_empty())),
+            None => (entry, None),
+        })
+        .collect())
+}
+
+// turingmachine.io's YAML specs, of the form
+//
+//     input: '1011'
+//     blank: ' '
+//     start state: right
+//     table:
+//       right:
+//         [1, 0]: R
+//         ' ': {L: carry}
+//       carry:
+//         1: {write: 0, L}
+//         [0, ' ']: {write: 1, L: done}
+//       done:
+//
+// Only as much YAML as these are written in is understood: block mappings,
+// flow mappings and sequences of symbols, and quoted strings. A state with
+// nothing under it halts, as does a symbol without a transition, which is
+// left undefined.
+pub fn from_turingmachine_io(text: &str) -> Result<Imported, String> {
+    let lines = yaml_lines(text)?;
+    let mut input = String::new();
+    let mut blank = None;
+    let mut start = None;
+    let mut table = None;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = &lines[i];
+        let number = line.number;
+        if line.indent > 0 {
+            return Err(format!("line {}: unexpected indentation", number));
+        }
+        let (key, value) =
+            split_key(line.text).ok_or(format!("line {}: expected a key", number))?;
+        i += 1;
+        match key {
+            "input" => input = scalar(value, number)?,
+            "blank" => blank = Some(symbol(value, number)?),
+            "start state" => start = Some(scalar(value, number)?),
+            "table" if value.is_empty() => {
+                let end = i + lines[i..].iter().take_while(|l| l.indent > 0).count();
+                table = Some(&lines[i..end]);
+                i = end;
+            }
+            "table" => return Err(format!("line {}: the table has to be a block", number)),
+            _ => return Err(format!("line {}: {} isn't supported", number, key)),
+        }
+    }
+    let blank = blank.ok_or("the spec needs a blank symbol")?;
+    let start = start.ok_or("the spec needs a start state")?;
+    let table = table.ok_or("the spec needs a table")?;
+
+    let mut parsed: Parsed = Vec::new();
+    let mut symbols = vec![blank];
+    let state_indent = table.first().map_or(0, |line| line.indent);
+    let mut i = 0;
+    while i < table.len() {
+        let line = &table[i];
+        let number = line.number;
+        i += 1;
+        if line.indent != state_indent {
+            return Err(format!("line {}: unexpected indentation", number));
+        }
+        let (name, value) =
+            split_key(line.text).ok_or(format!("line {}: expected a state", number))?;
+        if !value.is_empty() && value != "{}" {
+            return Err(format!(
+                "line {}: a state's transitions go under it",
+                number
+            ));
+        }
+        let name = scalar(name, number)?;
+        if parsed.iter().any(|(other, _)| *other == name) {
+            return Err(format!("line {}: {} is defined twice", number, name));
+        }
+        let mut transitions = Vec::new();
+        let entry_indent = table.get(i).map_or(0, |line| line.indent);
+        while i < table.len() && table[i].indent > state_indent {
+            let line = &table[i];
+            let number = line.number;
+            i += 1;
+            if line.indent != entry_indent {
+                return Err(format!("line {}: unexpected indentation", number));
+            }
+            let (key, value) =
+                split_key(line.text).ok_or(format!("line {}: expected a transition", number))?;
+            let reads = match key.strip_prefix('[') {
+                Some(list) => {
+                    let list = list
+                        .strip_suffix(']')
+                        .ok_or(format!("line {}: bad list {}", number, key))?;
+                    split_unquoted(list, ',')
+                        .into_iter()
+                        .map(|s| symbol(s, number))
+                        .collect::<Result<Vec<_>, _>>()?
+                }
+                None => vec![symbol(key, number)?],
+            };
+            let action = match value {
+                "L" | "R" => action([(value, None)].into_iter(), number)?,
+                _ if value.starts_with('{') => {
+                    action(flow_entries(value, number)?.into_iter(), number)?
+                }
+                "" => {
+                    // A block mapping, a line for each entry
+                    let end = i + table[i..]
+                        .iter()
+                        .take_while(|l| l.indent > entry_indent)
+                        .count();
+                    let entries = table[i..end]
+                        .iter()
+                        .map(|l| match split_key(l.text) {
+                            Some((key, value)) => (key, Some(value).filter(|v| !v.is_empty())),
+                            None => (l.text, None),
+                        })
+                        .collect::<Vec<_>>();
+                    i = end;
+                    action(entries.into_iter(), number)?
+                }
+                _ => return Err(format!("line {}: bad transition {}", number, value)),
+            };
+            for &read in &reads {
+                add_symbol(read, &mut symbols);
+                if let Some(write) = action.write {
+                    add_symbol(write, &mut symbols);
+                }
+                if transitions.iter().any(|(other, _)| *other == read) {
+                    return Err(format!(
+                        "line {}: {} has two transitions for {:?}",
+                        number, name, read
+                    ));
+                }
+                transitions.push((read, action.clone()));
+            }
+        }
+        parsed.push((name, transitions));
+    }
+    assemble(&parsed, symbols, &start, &input)
+}
+
+fn add_symbol(c: char, symbols: &mut Vec<char>) {
+    if !symbols.contains(&c) {
+        symbols.push(c);
+    }
+}
+
+// Each state's transitions, in order, numbered with the symbols seen so far
+type Parsed = Vec<(String, Vec<(char, Action)>)>;
+
+fn assemble(
+    parsed: &Parsed,
+    mut symbols: Vec<char>,
+    start: &str,
+    input: &str,
+) -> Result<Imported, String> {
+    for c in input.chars() {
+        add_symbol(c, &mut symbols);
+    }
+    if symbols.len() > u8::MAX as usize {
+        return Err(format!("{} symbols is too many", symbols.len()));
+    }
+
+    // Number the states that do something, start first
+    let (running, halting): (Vec<_>, Vec<_>) = parsed.iter().partition(|(_, t)| !t.is_empty());
+    let mut states: Vec<String> = running.iter().map(|(name, _)| name.clone()).collect();
+    let halting: Vec<String> = halting.iter().map(|(name, _)| name.clone()).collect();
+    let at = states.iter().position(|name| *name == start).ok_or(
+        match halting.iter().any(|name| name == start) {
+            true => format!("the start state {} has no transitions", start),
+            false => format!("the start state {} isn't in the table", start),
+        },
+    )?;
+    let first = states.remove(at);
+    states.insert(0, first);
+
+    let index = |c: char| symbols.iter().position(|&s| s == c).unwrap();
+    let mut transitions = vec![None; states.len() * symbols.len()];
+    for (state, name) in states.iter().enumerate() {
+        let (_, actions) = parsed.iter().find(|(other, _)| other == name).unwrap();
+        for (read, action) in actions {
+            let next = match &action.next {
+                None => state,
+                Some(next) => match states.iter().position(|s| s == next) {
+                    Some(next) => next,
+                    None if halting.contains(next) => states.len(),
+                    None => {
+                        return Err(format!("line {}: there's no state {}", action.number, next))
+                    }
+                },
+            };
+            transitions[state * symbols.len() + index(*read)] = Some(WideStep {
+                print: index(action.write.unwrap_or(*read)) as u8,
+                right: action.right,
+                next,
+            });
+        }
+    }
+    Ok(Imported {
+        machine: WideMachine {
+            symbols: symbols.len(),
+            transitions,
+        },
+        input: input.chars().map(|c| index(c) as u8).collect(),
+        states,
+        halting,
+        symbols,
+    })
+}
+
+// The fields of a CSV line, unquoting any in double quotes
+fn csv_fields(line: &str, separator: char, number: usize) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        let mut field = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => {
+                        return Err(format!(
+                            "line {}, column {}: unterminated quote",
+                            number,
+                            fields.len() + 1
+                        ))
+                    }
+                }
+            }
+            match chars.next() {
+                None => {
+                    fields.push(field);
+                    return Ok(fields);
+                }
+                Some(c) if c == separator => {}
+                Some(_) => {
+                    return Err(format!(
+                        "line {}, column {}: text after a closing quote",
+                        number,
+                        fields.len() + 1
+                    ))
+                }
+            }
+        } else {
+            loop {
+                match chars.next() {
+                    None => {
+                        fields.push(field.trim().to_string());
+                        return Ok(fields);
+                    }
+                    Some(c) if c == separator => break,
+                    Some(c) => field.push(c),
+                }
+            }
+            field = field.trim().to_string();
+        }
+        fields.push(field);
+    }
+}
+
+const COLUMNS: [&str; 5] = ["state", "read", "write", "move", "next"];
+
+// Transition tables from spreadsheets, a row per transition:
+//
+//     state,read,write,move,next
+//     A,_,1,R,B
+//     A,1,1,L,B
+//     B,_,1,L,A
+//     B,1,,R,halt
+//
+// The columns can come in any order but have to be the ones above, named in
+// a header. Rows are separated by commas, or by semicolons if the header
+// is, quoted fields are allowed, and blank lines and ones starting with #
+// are skipped. An empty cell, a space or _ is the blank symbol, an empty
+// write leaves the symbol as it is, moves are L or R, and a state without
+// rows of its own halts. 0 is a symbol like any other, so tables that use
+// it for blank cells need to say _ instead. The start state is the first
+// row's, and the input is empty.
+//
+// Two rows for one state and symbol are an error, even if they agree, as
+// are symbols written differently that are the same once trimmed, since
+// either is more likely a slip than meant.
+pub fn from_csv(text: &str) -> Result<Imported, String> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'));
+    let (number, header) = lines.next().ok_or("the table is empty")?;
+    let separator = match header.contains(';') && !header.contains(',') {
+        true => ';',
+        false => ',',
+    };
+    let header = csv_fields(header, separator, number)?;
+    let mut order = Vec::new();
+    for column in COLUMNS {
+        let found: Vec<usize> = (0..header.len())
+            .filter(|&i| header[i].eq_ignore_ascii_case(column))
+            .collect();
+        match found[..] {
+            [i] => order.push(i),
+            [] => return Err(format!("line {}: there's no {} column", number, column)),
+            _ => return Err(format!("line {}: there are two {} columns", number, column)),
+        }
+    }
+    if let Some(other) = header
+        .iter()
+        .find(|name| !COLUMNS.iter().any(|c| name.eq_ignore_ascii_case(c)))
+    {
+        return Err(format!("line {}: unknown column {:?}", number, other));
+    }
+
+    let mut parsed: Parsed = Vec::new();
+    let mut symbols = vec![' '];
+    // Where each state and symbol's row is, and how each symbol was written
+    let mut rows: Vec<(String, char, usize)> = Vec::new();
+    let mut spellings: Vec<(char, String, usize)> = Vec::new();
+    let mut targets: Vec<String> = Vec::new();
+    for (number, line) in lines {
+        let fields = csv_fields(line, separator, number)?;
+        if fields.len() != header.len() {
+            return Err(format!(
+                "line {}: {} fields, but the header has {}",
+                number,
+                fields.len(),
+                header.len()
+            ));
+        }
+        let at = |column: usize| {
+            format!(
+                "line {}, column {} ({})",
+                number,
+                order[column] + 1,
+                COLUMNS[column]
+            )
+        };
+        let field = |column: usize| &fields[order[column]];
+        let mut cell = |column: usize| -> Result<Option<char>, String> {
+            let text = field(column);
+            let trimmed = text.trim();
+            let c = match trimmed {
+                "" if text.is_empty() && column == 2 => return Ok(None),
+                "" | "_" => ' ',
+                _ => {
+                    let mut chars = trimmed.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => c,
+                        _ => {
+                            return Err(format!(
+                                "{}: symbols have to be one character, not {:?}",
+                                at(column),
+                                text
+                            ))
+                        }
+                    }
+                }
+            };
+            match spellings.iter().find(|(other, _, _)| *other == c) {
+                Some((_, spelling, line)) if spelling != text && c != ' ' => {
+                    return Err(format!(
+                        "{}: {:?} is the same symbol as {:?} on line {}",
+                        at(column),
+                        text,
+                        spelling,
+                        line
+                    ))
+                }
+                Some(_) => {}
+                None => spellings.push((c, text.to_string(), number)),
+            }
+            Ok(Some(c))
+        };
+        let read = cell(1)?.unwrap();
+        let write = cell(2)?;
+        let right = match field(3).to_ascii_uppercase().as_str() {
+            "R" => true,
+            "L" => false,
+            _ => return Err(format!("{}: moves are L or R, not {:?}", at(3), field(3))),
+        };
+        let state = field(0).clone();
+        let next = field(4).clone();
+        for (column, name) in [(0, &state), (4, &next)] {
+            if name.is_empty() {
+                return Err(format!("{}: a state is needed", at(column)));
+            }
+        }
+        if let Some((_, _, line)) = rows.iter().find(|(s, r, _)| *s == state && *r == read) {
+            return Err(format!(
+                "line {}: {} already has a transition for {:?}, on line {}",
+                number, state, read, line
+            ));
+        }
+        rows.push((state.clone(), read, number));
+        add_symbol(read, &mut symbols);
+        if let Some(write) = write {
+            add_symbol(write, &mut symbols);
+        }
+        if !targets.contains(&next) {
+            targets.push(next.clone());
+        }
+        let action = Action {
+            write,
+            right,
+            next: Some(next),
+            number,
+        };
+        match parsed.iter_mut().find(|(name, _)| *name == state) {
+            Some((_, transitions)) => transitions.push((read, action)),
+            None => parsed.push((state, vec![(read, action)])),
+        }
+    }
+    let start = parsed.first().ok_or("the table has no rows")?.0.clone();
+    for next in targets {
+        if !parsed.iter().any(|(name, _)| *name == next) {
+            parsed.push((next, Vec::new()));
+        }
+    }
+    assemble(&parsed, symbols, &start, "")
+}
@@ -0,0 +1,225 @@
+use std::fmt::Display;
+
+// Just enough JSON for talking to other programs. Numbers are kept as
+// written, so whole numbers of any size come through exactly.
+#[derive(Clone)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn parse(s: &str) -> Result<Json, String> {
+        let mut parser = Parser {
+            chars: s.chars().peekable(),
+        };
+        let value = parser.value().ok_or_else(|| format!("bad JSON {:?}", s))?;
+        parser.skip_space();
+        match parser.chars.next() {
+            None => Ok(value),
+            Some(_) => Err(format!("bad JSON {:?}", s)),
+        }
+    }
+
+    pub fn number(n: impl Display) -> Json {
+        Json::Number(n.to_string())
+    }
+
+    pub fn string(s: impl Into<String>) -> Json {
+        Json::String(s.into())
+    }
+
+    pub fn object<const F: usize>(fields: [(&str, Json); F]) -> Json {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    // The value of a field, if this is an object that has it
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Json::Number(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_u128(&self) -> Option<u128> {
+        match self {
+            Json::Number(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip_space(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn value(&mut self) -> Option<Json> {
+        self.skip_space();
+        Some(match *self.chars.peek()? {
+            '"' => {
+                self.chars.next();
+                Json::String(self.string()?)
+            }
+            '[' => {
+                self.chars.next();
+                let mut items = Vec::new();
+                self.skip_space();
+                if self.chars.next_if_eq(&']').is_none() {
+                    loop {
+                        items.push(self.value()?);
+                        self.skip_space();
+                        match self.chars.next()? {
+                            ',' => continue,
+                            ']' => break,
+                            _ => return None,
+                        }
+                    }
+                }
+                Json::Array(items)
+            }
+            '{' => {
+                self.chars.next();
+                let mut fields = Vec::new();
+                self.skip_space();
+                if self.chars.next_if_eq(&'}').is_none() {
+                    loop {
+                        self.skip_space();
+                        if self.chars.next()? != '"' {
+                            return None;
+                        }
+                        let key = self.string()?;
+                        self.skip_space();
+                        if self.chars.next()? != ':' {
+                            return None;
+                        }
+                        fields.push((key, self.value()?));
+                        self.skip_space();
+                        match self.chars.next()? {
+                            ',' => continue,
+                            '}' => break,
+                            _ => return None,
+                        }
+                    }
+                }
+                Json::Object(fields)
+            }
+            c if c == '-' || c.is_ascii_digit() => {
+                let number: String = std::iter::from_fn(|| {
+                    self.chars
+                        .next_if(|&c| c.is_ascii_digit() || "+-.eE".contains(c))
+                })
+                .collect();
+                number.parse::<f64>().ok()?;
+                Json::Number(number)
+            }
+            _ => {
+                let word: String =
+                    std::iter::from_fn(|| self.chars.next_if(|c| c.is_alphabetic())).collect();
+                match word.as_str() {
+                    "true" => Json::Bool(true),
+                    "false" => Json::Bool(false),
+                    "null" => Json::Null,
+                    _ => return None,
+                }
+            }
+        })
+    }
+
+    // The rest of a string, after its opening quote
+    fn string(&mut self) -> Option<String> {
+        let mut text = String::new();
+        loop {
+            match self.chars.next()? {
+                '"' => return Some(text),
+                '\\' => text.push(match self.chars.next()? {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    'u' => {
+                        let code: String = self.chars.by_ref().take(4).collect();
+                        char::from_u32(u32::from_str_radix(&code, 16).ok()?)?
+                    }
+                    c => c,
+                }),
+                c => text.push(c),
+            }
+        }
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(b) => f.write_fmt(format_args!("{}", b)),
+            Json::Number(n) => f.write_str(n),
+            Json::String(s) => {
+                f.write_str("\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\t' => f.write_str("\\t")?,
+                        c if (c as u32) < 0x20 => {
+                            f.write_fmt(format_args!("\\u{:04x}", c as u32))?
+                        }
+                        c => f.write_fmt(format_args!("{}", c))?,
+                    }
+                }
+                f.write_str("\"")
+            }
+            Json::Array(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    f.write_fmt(format_args!("{}", item))?;
+                }
+                f.write_str("]")
+            }
+            Json::Object(fields) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    f.write_fmt(format_args!("{}:{}", Json::string(key.as_str()), value))?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
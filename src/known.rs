@@ -0,0 +1,91 @@
+// The busy beaver values that have been proven, with machines attaining
+// them in the standard format (more symbols are written as more digits).
+// `steps` is the maximum shift count S and `sigma` the maximum number of
+// ones, or of non-blank symbols with more than two, Σ.
+//
+// Everything from 6 states on is only known to be astronomically large,
+// far beyond what fits in a u128, so there's nothing to compare with there.
+pub struct Known {
+    pub states: usize,
+    pub symbols: usize,
+    pub steps: u128,
+    pub sigma: u64,
+    pub steps_champion: &'static str,
+    pub sigma_champion: &'static str,
+}
+
+pub const KNOWN: &[Known] = &[
+    Known {
+        states: 1,
+        symbols: 2,
+        steps: 1,
+        sigma: 1,
+        steps_champion: "1RZ---",
+        sigma_champion: "1RZ---",
+    },
+    Known {
+        states: 2,
+        symbols: 2,
+        steps: 6,
+        sigma: 4,
+        steps_champion: "1RB1LB_1LA1RZ",
+        sigma_champion: "1RB1LB_1LA1RZ",
+    },
+    Known {
+        states: 3,
+        symbols: 2,
+        steps: 21,
+        sigma: 6,
+        steps_champion: "1RB1RZ_1LB0RC_1LC1LA",
+        sigma_champion: "1RB1RZ_0RC1RB_1LC1LA",
+    },
+    Known {
+        states: 4,
+        symbols: 2,
+        steps: 107,
+        sigma: 13,
+        steps_champion: "1RB1LB_1LA0LC_1RZ1LD_1RD0RA",
+        sigma_champion: "1RB1LB_1LA0LC_1RZ1LD_1RD0RA",
+    },
+    Known {
+        states: 5,
+        symbols: 2,
+        steps: 47_176_870,
+        sigma: 4098,
+        steps_champion: "1RB1LC_1RC1RB_1RD0LE_1LA1LD_1RZ0LA",
+        sigma_champion: "1RB1LC_1RC1RB_1RD0LE_1LA1LD_1RZ0LA",
+    },
+    Known {
+        states: 2,
+        symbols: 3,
+        steps: 38,
+        sigma: 9,
+        steps_champion: "1RB2LB1RZ_2LA2RB1LB",
+        sigma_champion: "1RB2LB1RZ_2LA2RB1LB",
+    },
+    Known {
+        states: 2,
+        symbols: 4,
+        steps: 3_932_964,
+        sigma: 2050,
+        steps_champion: "1RB2LA1RA1RA_1LB1LA3RB1RZ",
+        sigma_champion: "1RB2LA1RA1RA_1LB1LA3RB1RZ",
+    },
+];
+
+pub fn known(states: usize, symbols: usize) -> Option<&'static Known> {
+    KNOWN
+        .iter()
+        .find(|known| known.states == states && known.symbols == symbols)
+}
+
+// Whether a machine with `n_states` states and `symbols` symbols halting
+// with this score would beat the known values. Where they're proven, that
+// can only mean a bug. Everywhere else nothing that fits in a u128 comes
+// close, or nothing is proven to compare with.
+pub fn is_new_record(n_states: usize, symbols: usize, sigma: u64, steps: u128) -> bool {
+    match known(n_states, symbols) {
+        Some(known) => sigma > known.sigma || steps > known.steps,
+        None => false,
+    }
+}
@@ -0,0 +1,1537 @@
+mod aligned;
+mod beep;
+mod bench;
+mod bf;
+// What its functions expect of their pointers is said in its header
+#[cfg(feature = "capi")]
+#[allow(clippy::missing_safety_doc)]
+pub mod capi;
+mod cast;
+mod chunked;
+mod certificate;
+mod commands;
+mod compare;
+mod counter;
+#[cfg(feature = "crossval")]
+mod crossval;
+mod ctl;
+mod cursor;
+mod decider;
+mod enumerate;
+mod equivalence;
+mod evolve;
+mod external;
+mod far;
+mod filter;
+mod flat;
+mod fuzz;
+mod golden;
+mod header;
+mod history;
+mod holdout_list;
+mod holdouts;
+mod interop;
+mod json;
+mod known;
+mod modelcheck;
+mod models;
+mod packed;
+mod parallel;
+mod progress;
+mod reachable;
+mod rpc;
+mod rwl;
+mod sample;
+mod search;
+mod selftest;
+mod seed;
+mod sha256;
+mod shard;
+mod sliced;
+mod smt;
+mod spacetime;
+mod standard;
+mod strategy;
+mod symmetry;
+mod terminal;
+mod tree;
+mod utm;
+mod verify;
+mod wang;
+mod wide;
+
+use std::fmt::Display;
+use std::fmt::Write as _;
+use std::io::BufWriter;
+use std::mem::size_of;
+use std::ops::Index;
+use std::time::Duration;
+use std::time::Instant;
+
+use num_traits::NumCast;
+use num_traits::PrimInt;
+use num_traits::Unsigned;
+
+use chunked::Chunked;
+use chunked::Frozen;
+use chunked::Growth;
+use commands::Failure;
+use Bit::*;
+use State::*;
+use TapeMotion::*;
+
+fn as_bits<T: PrimInt>(x: T) -> String {
+    (0..8 * size_of::<T>())
+        .rev()
+        .map(|i| {
+            // These are guaranteed to be either 1 or 0 so no need for double-checking
+            if (x >> i) & T::one() == T::one() {
+                '1'
+            } else {
+                '0'
+            }
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Bit {
+    Zero,
+    One,
+}
+
+fn get_bit<T: PrimInt>(x: T, pos: usize) -> Bit {
+    if x & (T::one() << pos) == T::zero() {
+        Zero
+    } else {
+        One
+    }
+}
+
+fn set_bit<T: PrimInt>(x: &mut T, pos: usize, b: Bit) {
+    match b {
+        Zero => *x = *x & !(T::one() << pos),
+        One => *x = *x | (T::one() << pos),
+    }
+}
+
+impl Display for Bit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Zero => f.write_str("0"),
+            One => f.write_str("1"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum TapeMotion {
+    Left,
+    Right,
+}
+
+impl Display for TapeMotion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Left => f.write_str("<-"),
+            Right => f.write_str("->"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+enum State {
+    HALT,
+    Index(usize),
+}
+
+impl From<usize> for State {
+    fn from(value: usize) -> Self {
+        Index(value)
+    }
+}
+
+impl Display for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HALT => f.write_str("HALT"),
+            Index(i) => f.write_fmt(format_args!("s{}", i)),
+        }
+    }
+}
+
+//const HALT: isize = -1;
+
+#[derive(Clone, Copy)]
+struct TuringStep {
+    print: Bit,
+    motion: TapeMotion,
+    next_state: State,
+}
+
+#[derive(Clone, Copy)]
+struct TuringState {
+    zero: TuringStep,
+    one: TuringStep,
+}
+
+#[derive(Clone)]
+struct TuringMachine<const N: usize> {
+    states: [TuringState; N],
+    state: State,
+}
+
+macro_rules! turing_machine {
+    ( $(($print0:expr, $motion0:expr, $state0:expr; $print1:expr, $motion1:expr, $state1:expr)),+ ) => {
+        TuringMachine {
+            states: [$(
+                TuringState {
+                    zero: TuringStep {
+                        print: $print0,
+                        motion: $motion0,
+                        next_state: $state0.into(),
+                    },
+                    one: TuringStep {
+                        print: $print1,
+                        motion: $motion1,
+                        next_state: $state1.into(),
+                    },
+                },
+            )*],
+            state: 0.into(),
+        }
+    };
+}
+
+struct Tape<T: Unsigned + PrimInt> {
+    right: Chunked<T>,
+    left: Chunked<T>,
+    vec_index: usize,
+    bit_index: usize,
+    half: TapeMotion,
+    // The head's tape index, kept up to date by `move_tape` rather than
+    // worked out from the three fields above each time it's shown
+    head: i64,
+}
+
+// A tape as it was at some point in a run, see `Tape::snapshot`
+#[derive(Clone, PartialEq, Eq)]
+struct TapeSnapshot<T: Unsigned + PrimInt> {
+    right: Frozen<T>,
+    left: Frozen<T>,
+    vec_index: usize,
+    bit_index: usize,
+    half: TapeMotion,
+}
+
+impl<T: Unsigned + PrimInt> Tape<T> {
+    fn new() -> Tape<T> {
+        Tape::with_growth(Growth::default())
+    }
+
+    // A blank tape whose halves grow as `growth` says, see `chunked`
+    fn with_growth(growth: Growth) -> Tape<T> {
+        Tape {
+            right: Chunked::with_growth(T::zero(), growth),
+            left: Chunked::with_growth(T::zero(), growth),
+            vec_index: 0,
+            bit_index: 0,
+            half: Right,
+            head: 0,
+        }
+    }
+
+    // Allocates room for `cells` on each side of cell 0, so a run that stays
+    // within them never grows the tape. This sets aside memory only, and
+    // doesn't change the cells that are shown or counted as allocated.
+    fn reserve(&mut self, cells: usize) {
+        let words = cells.div_ceil(size_of::<T>() * 8);
+        self.right.reserve(words);
+        self.left.reserve(words);
+    }
+
+    // A copy of the tape and the head that shares every chunk that hasn't
+    // changed since the last snapshot, see `chunked`
+    fn snapshot(&mut self) -> TapeSnapshot<T> {
+        let snapshot = TapeSnapshot {
+            right: self.right.snapshot(),
+            left: self.left.snapshot(),
+            vec_index: self.vec_index,
+            bit_index: self.bit_index,
+            half: self.half,
+        };
+        // The head can go on writing where it is without crossing a word
+        self.touch();
+        if cfg!(debug_assertions) {
+            let restored = Tape::restore(&snapshot);
+            assert!(
+                restored.right == self.right
+                    && restored.left == self.left
+                    && restored.head_index() == self.head_index(),
+                "a snapshot doesn't restore to the tape it was taken of"
+            );
+        }
+        snapshot
+    }
+
+    // A tape to carry on from where a snapshot was taken, whose own
+    // snapshots share chunks with it
+    fn restore(snapshot: &TapeSnapshot<T>) -> Tape<T> {
+        let mut tape = Tape {
+            right: snapshot.right.thaw(),
+            left: snapshot.left.thaw(),
+            vec_index: snapshot.vec_index,
+            bit_index: snapshot.bit_index,
+            half: snapshot.half,
+            head: 0,
+        };
+        tape.head = tape.compute_index();
+        tape.touch();
+        tape.check_invariants();
+        tape
+    }
+
+    // Marks the chunk under the head as written to, which it has to be
+    // before the head writes there, see `chunked`
+    fn touch(&mut self) {
+        match self.half {
+            Left => self.left.touch(self.vec_index),
+            Right => self.right.touch(self.vec_index),
+        }
+    }
+
+    fn get(&self) -> Bit {
+        // bytes * 8 = bits
+        let vec = match self.half {
+            Left => &self.left,
+            Right => &self.right,
+        };
+        let vec_value = vec[self.vec_index];
+        get_bit(vec_value, self.bit_index)
+    }
+
+    fn set(&mut self, b: Bit) {
+        let vec = match self.half {
+            Left => &mut self.left,
+            Right => &mut self.right,
+        };
+        set_bit(&mut vec[self.vec_index], self.bit_index, b)
+    }
+
+    // The word under the head, to read and write the cell with one lookup
+    fn word_mut(&mut self) -> &mut T {
+        let vec = match self.half {
+            Left => &mut self.left,
+            Right => &mut self.right,
+        };
+        &mut vec[self.vec_index]
+    }
+
+    fn move_tape(&mut self, motion: TapeMotion) {
+        self.head = match motion {
+            Left => self.head.checked_sub(1),
+            Right => self.head.checked_add(1),
+        }
+        .expect("the head went past the last tape index");
+        let bits = 8 * size_of::<T>();
+        match (self.half, motion) {
+            (Left, Left) | (Right, Right) => {
+                if self.bit_index == bits - 1 {
+                    self.bit_index = 0;
+                    self.vec_index += 1;
+                    let vec = match self.half {
+                        Left => &mut self.left,
+                        Right => &mut self.right,
+                    };
+                    if self.vec_index == vec.len() {
+                        vec.push(T::zero());
+                    }
+                    vec.cross(self.vec_index);
+                } else {
+                    self.bit_index += 1;
+                }
+            }
+            (Left, Right) | (Right, Left) => {
+                if self.bit_index == 0 {
+                    if self.vec_index == 0 {
+                        self.half = match self.half {
+                            Left => Right,
+                            Right => Left,
+                        }
+                    } else {
+                        self.bit_index = bits - 1;
+                        self.vec_index -= 1;
+                    }
+                    match self.half {
+                        Left => self.left.cross(self.vec_index),
+                        Right => self.right.cross(self.vec_index),
+                    }
+                } else {
+                    self.bit_index -= 1;
+                }
+            }
+        }
+        self.check_invariants();
+    }
+
+    // Checks in debug builds that the head is somewhere on the tape and that
+    // its cached index agrees with where it is, so that a bug in moving it
+    // shows up on the step it happens rather than as a wrong tape much later.
+    // Release builds check nothing.
+    fn check_invariants(&self) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+        let bits = size_of::<T>() * 8;
+        let len = match self.half {
+            Left => self.left.len(),
+            Right => self.right.len(),
+        };
+        assert!(self.bit_index < bits, "bit index {} of {}", self.bit_index, bits);
+        assert!(
+            self.vec_index < len,
+            "word {} of {} on the {:?} half",
+            self.vec_index,
+            len,
+            self.half
+        );
+        assert_eq!(self.head, self.compute_index(), "the cached head index is stale");
+        assert_eq!(
+            self.head < 0,
+            matches!(self.half, Left),
+            "head at {} on the {:?} half",
+            self.head,
+            self.half
+        );
+    }
+
+    // Moves the head within the word it's on, for runs that step through a
+    // whole word at once
+    fn set_bit_index(&mut self, bit_index: usize) {
+        self.bit_index = bit_index;
+        self.head = self.compute_index();
+        self.check_invariants();
+    }
+
+    // The head's tape index, with cell 0 where it started and negative to
+    // its left
+    fn head_index(&self) -> i64 {
+        self.head
+    }
+
+    // Worked out in u128, which even a usize of words times the bits in each
+    // can't overflow, and checked into an i64, which no tape that fits in
+    // memory can overflow
+    fn compute_index(&self) -> i64 {
+        let bits = size_of::<T>() * 8;
+        let cell = self.vec_index as u128 * bits as u128 + self.bit_index as u128;
+        let index = match self.half {
+            Right => cell as i128,
+            // (Left, 0, 0) is -1
+            Left => -1 - cell as i128,
+        };
+        i64::try_from(index).expect("the head is past the last tape index")
+    }
+
+    // Tape cells in `words` words of a half, checked as in `compute_index`
+    fn cells_in(words: usize) -> i64 {
+        let bits = size_of::<T>() * 8;
+        i64::try_from(words as u128 * bits as u128).expect("the tape is too long to index")
+    }
+
+    // How many cells the head is from the leftmost one allocated
+    fn get_display_index(&self) -> u64 {
+        // The head is never left of the allocation, so this is never negative
+        (Tape::<T>::cells_in(self.left.len()) + self.head) as u64
+    }
+
+    // The cell at a tape index, with everything outside the allocation blank
+    fn cell_at(&self, index: i64) -> Bit {
+        let bits = size_of::<T>() * 8;
+        let (vec, i) = if index < 0 {
+            (&self.left, !index as u64)
+        } else {
+            (&self.right, index as u64)
+        };
+        // Past the last usize is past the allocation
+        let Ok(i) = usize::try_from(i) else {
+            return Zero;
+        };
+        match vec.get(i / bits) {
+            Some(x) => get_bit(*x, i % bits),
+            None => Zero,
+        }
+    }
+
+    // Tape indices of the first allocated cell and one past the last
+    fn bounds(&self) -> (i64, i64) {
+        (
+            -Tape::<T>::cells_in(self.left.len()),
+            Tape::<T>::cells_in(self.right.len()),
+        )
+    }
+
+    // Number of ones on the tape, the busy beaver score
+    fn ones(&self) -> u64 {
+        self.left
+            .iter()
+            .chain(self.right.iter())
+            .map(|x| x.count_ones() as u64)
+            .sum()
+    }
+
+    fn allocated_cells(&self) -> usize {
+        (self.left.len() + self.right.len()) * size_of::<T>() * 8
+    }
+
+    // Heap bytes held by both halves, including spare capacity, see
+    // `Chunked::memory_usage`
+    fn memory_usage(&self) -> usize {
+        self.left.memory_usage() + self.right.memory_usage()
+    }
+}
+
+impl<T: Unsigned + PrimInt> Display for Tape<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.render_into(f, &DisplayOptions::default())
+    }
+}
+
+// Width of the step numbers in the left margin
+const MARGIN: usize = 9;
+
+// Shared by every renderer, so a run looks the same however it is printed.
+#[derive(Clone)]
+struct DisplayOptions {
+    zero: char,
+    one: char,
+    // Filler for the line underneath the tape that carries the head marker
+    blank: char,
+    head: char,
+    // Put `separator` between every `group` cells
+    group: Option<usize>,
+    separator: char,
+    // Print the whole transition table after each step
+    table: bool,
+    // Start each row with the step it shows
+    step_numbers: bool,
+    // Print a ruler every this many rows
+    ruler: Option<usize>,
+    // Only show every this many steps, and the halt and any marked steps
+    trace_every: Option<u64>,
+    // Steps flagged with a marker in the margin
+    marks: Vec<u64>,
+    // Pack two cells into each character with block elements. The glyphs
+    // above don't apply, and groups count characters rather than cells.
+    blocks: bool,
+}
+
+// Block elements indexed by the quadrants they fill: bit 0 top left,
+// bit 1 top right, bit 2 bottom left, bit 3 bottom right
+const QUADRANTS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+impl Default for DisplayOptions {
+    fn default() -> Self {
+        DisplayOptions {
+            zero: '0',
+            one: '1',
+            blank: ' ',
+            head: '^',
+            group: None,
+            separator: ' ',
+            table: false,
+            step_numbers: false,
+            ruler: None,
+            trace_every: None,
+            marks: Vec::new(),
+            blocks: false,
+        }
+    }
+}
+
+impl DisplayOptions {
+    fn glyph(&self, b: Bit) -> char {
+        match b {
+            Zero => self.zero,
+            One => self.one,
+        }
+    }
+
+    // Whether a separator goes just before the character at `column`
+    fn separated(&self, column: usize) -> bool {
+        matches!(self.group, Some(group) if group > 0 && column > 0 && column.is_multiple_of(group))
+    }
+
+    // Where a cell ends up in the rendered string, counting separators
+    fn column(&self, cell: usize) -> usize {
+        let column = if self.blocks { cell / 2 } else { cell };
+        match self.group {
+            Some(group) if group > 0 => column + column / group,
+            _ => column,
+        }
+    }
+
+    // Empty unless step numbers or marks are on. Rows that don't start a step,
+    // like the head line, get a blank margin of the same width.
+    fn margin(&self, step: Option<u64>, marked: bool) -> String {
+        let mut output = String::new();
+        self.margin_into(&mut output, step, marked);
+        output
+    }
+
+    fn margin_into(&self, output: &mut String, step: Option<u64>, marked: bool) {
+        if !self.step_numbers && self.marks.is_empty() {
+            return;
+        }
+        let mark = if marked { '>' } else { ' ' };
+        match step {
+            Some(step) if self.step_numbers => write!(output, "{:>MARGIN$}{} ", step, mark),
+            _ => write!(output, "{:>MARGIN$}{} ", "", mark),
+        }
+        .unwrap();
+    }
+
+    fn ruler_due(&self, row: u64) -> bool {
+        matches!(self.ruler, Some(every) if every > 0 && row.is_multiple_of(every as u64))
+    }
+
+    // A tick every 8 cells counted from cell `zero`, which gets a '+'
+    fn ruler(&self, cells: usize, zero: usize) -> String {
+        let mut output = String::new();
+        self.ruler_into(&mut output, cells, zero);
+        output
+    }
+
+    fn ruler_into(&self, output: &mut String, cells: usize, zero: usize) {
+        let per_char = if self.blocks { 2 } else { 1 };
+        for i in 0..cells.div_ceil(per_char) {
+            if self.separated(i) {
+                output.push(self.separator);
+            }
+            let first = i * per_char;
+            output.push(match first.abs_diff(zero) {
+                _ if (first..first + per_char).contains(&zero) => '+',
+                d if d % 8 == 0 => '|',
+                _ => '-',
+            });
+        }
+    }
+}
+
+impl<T: Unsigned + PrimInt> Tape<T> {
+    // Every allocated cell, leftmost first
+    fn cells(&self) -> impl Iterator<Item = Bit> + '_ {
+        let bits = size_of::<T>() * 8;
+        self.left
+            .iter()
+            .rev()
+            .flat_map(move |x| (0..bits).rev().map(move |i| get_bit(*x, i)))
+            .chain(
+                self.right
+                    .iter()
+                    .flat_map(move |x| (0..bits).map(move |i| get_bit(*x, i))),
+            )
+    }
+
+    fn render(&self, options: &DisplayOptions) -> String {
+        let mut output = String::new();
+        self.render_into(&mut output, options).unwrap();
+        output
+    }
+
+    // Writes the cells straight into `output`, for frames built up in a
+    // reused buffer and for `Display`, without a string of their own
+    fn render_into(
+        &self,
+        output: &mut impl std::fmt::Write,
+        options: &DisplayOptions,
+    ) -> std::fmt::Result {
+        if options.blocks {
+            let mut cells = self.cells();
+            let mut i = 0;
+            while let Some(first) = cells.next() {
+                if options.separated(i) {
+                    output.write_char(options.separator)?;
+                }
+                // Both rows of the quadrant the same, so each cell is a half
+                let left = if first == One { 5 } else { 0 };
+                let right = if cells.next() == Some(One) { 10 } else { 0 };
+                output.write_char(QUADRANTS[left | right])?;
+                i += 1;
+            }
+            return Ok(());
+        }
+        for (i, b) in self.cells().enumerate() {
+            if options.separated(i) {
+                output.write_char(options.separator)?;
+            }
+            output.write_char(options.glyph(b))?;
+        }
+        Ok(())
+    }
+}
+
+// Alternative Display for a tape: the raw words in hex, which stays readable
+// long after the bit string has outgrown the terminal.
+struct HexDump<'a, T: Unsigned + PrimInt>(&'a Tape<T>);
+
+impl<T: Unsigned + PrimInt> Tape<T> {
+    fn hex(&self) -> HexDump<'_, T> {
+        HexDump(self)
+    }
+}
+
+impl<T: Unsigned + PrimInt> Display for HexDump<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let tape = self.0;
+        let bits = size_of::<T>() * 8;
+        let width = bits / 4;
+        let words = tape
+            .left
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, x)| (Left, i, x))
+            .chain(tape.right.iter().enumerate().map(|(i, x)| (Right, i, x)));
+        for (n, (half, i, x)) in words.enumerate() {
+            if n > 0 {
+                f.write_str(" ")?;
+            }
+            // Label each word with the tape index of its leftmost cell
+            let offset = match half {
+                Left => -Tape::<T>::cells_in(i + 1),
+                Right => Tape::<T>::cells_in(i),
+            };
+            // Right-half words hold their leftmost cell in bit 0, so they're
+            // turned around to read left to right like the left half's
+            let value = match half {
+                Left => x.to_u128().unwrap(),
+                Right => x.reverse_bits().to_u128().unwrap(),
+            };
+            let is_head = i == tape.vec_index
+                && matches!((half, tape.half), (Left, Left) | (Right, Right));
+            if is_head {
+                f.write_fmt(format_args!("{}:[{:0width$x}]", offset, value))?;
+            } else {
+                f.write_fmt(format_args!("{}:{:0width$x}", offset, value))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// One step of verbose output: the tape, the head line and whatever
+// annotations the options ask for, as the `row`th frame shown. The frame
+// is built up in `buffer`, which is kept from one frame to the next, so
+// once the tape stops growing a step costs no allocations, only the
+// formatting and a copy into `out`.
+fn write_frame<const N: usize, T: Unsigned + PrimInt>(
+    out: &mut impl std::io::Write,
+    buffer: &mut String,
+    tm: &TuringMachine<N>,
+    tape: &Tape<T>,
+    step: u64,
+    row: u64,
+    options: &DisplayOptions,
+) -> std::io::Result<()> {
+    buffer.clear();
+    let (low, high) = tape.bounds();
+    if options.ruler_due(row) {
+        options.margin_into(buffer, None, false);
+        options.ruler_into(buffer, (high - low) as usize, -low as usize);
+        buffer.push('\n');
+    }
+    options.margin_into(buffer, Some(step), options.marks.contains(&step));
+    tape.render_into(buffer, options).unwrap();
+    buffer.push('\n');
+    options.margin_into(buffer, None, false);
+    head_line_into(buffer, tm, tape, options);
+    buffer.push('\n');
+    if options.table {
+        for line in tm.render_table(active_cell(tm, tape), options).lines() {
+            options.margin_into(buffer, None, false);
+            buffer.push_str(line);
+            buffer.push('\n');
+        }
+    }
+    out.write_all(buffer.as_bytes())
+}
+
+// The transition about to be taken, for `render_table` to bracket
+fn active_cell<const N: usize, T: Unsigned + PrimInt>(
+    tm: &TuringMachine<N>,
+    tape: &Tape<T>,
+) -> Option<(usize, Bit)> {
+    match tm.state {
+        Index(state) => Some((state, tape.get())),
+        HALT => None,
+    }
+}
+
+// The head marker under its cell, then where it is, the state and the
+// transition about to be taken
+fn head_line_into<const N: usize, T: Unsigned + PrimInt>(
+    output: &mut String,
+    tm: &TuringMachine<N>,
+    tape: &Tape<T>,
+    options: &DisplayOptions,
+) {
+    let column = options.column(
+        usize::try_from(tape.get_display_index()).expect("the head is too far along to show"),
+    );
+    output.extend(std::iter::repeat_n(options.blank, column));
+    write!(output, "{}{} \t{}", options.head, tape.head_index(), tm.state).unwrap();
+    if let Index(state) = tm.state {
+        let bit = tape.get();
+        let step = match bit {
+            Zero => &tm.states[state].zero,
+            One => &tm.states[state].one,
+        };
+        write!(
+            output,
+            ".{}: {} {} {}",
+            options.glyph(bit),
+            options.glyph(step.print),
+            step.motion,
+            step.next_state
+        )
+        .unwrap();
+    }
+    // In the halt state there is nothing after the state name
+}
+
+// The head line printed under the tape, plus the transition table if enabled
+fn format_state<const N: usize, T: Unsigned + PrimInt>(
+    tm: &TuringMachine<N>,
+    tape: &Tape<T>,
+    options: &DisplayOptions,
+) -> String {
+    let mut output = String::new();
+    head_line_into(&mut output, tm, tape, options);
+    output.push('\n');
+    if options.table {
+        output.push_str(&tm.render_table(active_cell(tm, tape), options));
+    }
+    output
+}
+
+impl<const N: usize> TuringMachine<N> {
+    // One row per state, one column per symbol read. The `active` cell is
+    // bracketed so it can be picked out while following a run.
+    fn render_table(&self, active: Option<(usize, Bit)>, options: &DisplayOptions) -> String {
+        let cell = |step: &TuringStep| {
+            format!(
+                "{} {} {}",
+                options.glyph(step.print),
+                step.motion,
+                step.next_state
+            )
+        };
+        let cells: Vec<[String; 2]> = self
+            .states
+            .iter()
+            .map(|s| [cell(&s.zero), cell(&s.one)])
+            .collect();
+        let width = cells
+            .iter()
+            .flatten()
+            .map(|c| c.chars().count())
+            .max()
+            .unwrap_or(0);
+        let label_width = format!("s{}", N.saturating_sub(1)).len();
+
+        let mut output = format!(
+            "{:label_width$}  {:width$}   {}\n",
+            "",
+            options.zero,
+            options.one
+        );
+        for (i, row) in cells.iter().enumerate() {
+            let mut line = format!("{:label_width$}", format!("s{}", i));
+            for (b, c) in [Zero, One].into_iter().zip(row) {
+                let is_active = matches!(
+                    (active, b),
+                    (Some((state, Zero)), Zero) | (Some((state, One)), One) if state == i
+                );
+                let (open, close) = if is_active { ('[', ']') } else { (' ', ' ') };
+                line.push_str(&format!(" {}{:width$}{}", open, c, close));
+            }
+            output.push_str(line.trim_end());
+            output.push('\n');
+        }
+        output
+    }
+}
+
+fn show_progress<T: Unsigned + PrimInt>(steps: u64, rate: f64, start: Instant, tape: &Tape<T>) {
+    let average = steps as f64 / start.elapsed().as_secs_f64();
+    // \x1b[K clears whatever the previous, possibly longer, line left behind
+    eprint!(
+        "\r{} steps \t{:.0} steps/s (avg {:.0}) \t{} cells \t{} KiB\x1b[K",
+        steps,
+        rate,
+        average,
+        tape.allocated_cells(),
+        tape.memory_usage() / 1024
+    );
+}
+
+impl<const N: usize> TuringMachine<N> {
+    fn step<T: Unsigned + PrimInt>(&mut self, tape: &mut Tape<T>, state: usize) {
+        let step = match tape.get() {
+            Zero => &self.states[state].zero,
+            One => &self.states[state].one,
+        };
+        tape.set(step.print);
+        tape.move_tape(step.motion);
+        self.state = step.next_state;
+    }
+
+    // The number of steps to halt, or None if the machine is still going
+    // after `limit` of them
+    fn run_limited<T: Unsigned + PrimInt>(&mut self, tape: &mut Tape<T>, limit: u64) -> Option<u64> {
+        let Index(state) = self.state else {
+            return Some(0);
+        };
+        let (steps, end) = self.flatten().run(tape, state, limit);
+        self.state = end;
+        match end {
+            HALT => Some(steps),
+            Index(_) => None,
+        }
+    }
+
+    // Headless run that keeps a single status line on stderr up to date,
+    // refreshed at most once per `interval`. Returns the number of steps
+    // taken if it halted within `limit` of them, as `run_limited` does.
+    fn run_with_progress<T: Unsigned + PrimInt>(
+        &mut self,
+        tape: &mut Tape<T>,
+        limit: u64,
+        interval: Duration,
+    ) -> Option<u64> {
+        let flat = self.flatten();
+        let start = Instant::now();
+        let mut last = start;
+        let mut last_steps: u64 = 0;
+        let mut steps: u64 = 0;
+        while let Index(state) = self.state {
+            if steps == limit {
+                break;
+            }
+            // Reading the clock every step would cost more than the step
+            // itself, so the flattened table takes them a slice at a time
+            let (taken, end) = flat.run(tape, state, (limit - steps).min(0x10000));
+            steps += taken;
+            self.state = end;
+            let now = Instant::now();
+            if now - last >= interval {
+                let rate = (steps - last_steps) as f64 / (now - last).as_secs_f64();
+                show_progress(steps, rate, start, tape);
+                last = now;
+                last_steps = steps;
+            }
+        }
+        let rate = (steps - last_steps) as f64 / last.elapsed().as_secs_f64();
+        show_progress(steps, rate, start, tape);
+        match self.state {
+            HALT => {
+                eprintln!(" \thalted");
+                Some(steps)
+            }
+            Index(_) => {
+                eprintln!(" \tstill running");
+                None
+            }
+        }
+    }
+
+    // Prints every step through one buffered lock on stdout, rather than a
+    // println per line, which flushed and made a write call for each of them
+    fn run_verbose_with<T: Unsigned + PrimInt>(
+        &mut self,
+        tape: &mut Tape<T>,
+        options: &DisplayOptions,
+    ) {
+        let mut out = BufWriter::new(std::io::stdout().lock());
+        self.run_verbose_into(&mut out, tape, options, u64::MAX)
+            .and_then(|_| std::io::Write::flush(&mut out))
+            .expect("failed printing to stdout");
+    }
+
+    // Writes a frame for the start and for each of at most `limit` steps,
+    // returning the steps taken. With --trace-every, the steps between the
+    // frames shown go through the flattened table as a headless run would,
+    // so a sampled trace of a long run costs little more than the run.
+    fn run_verbose_into<T: Unsigned + PrimInt>(
+        &mut self,
+        out: &mut impl std::io::Write,
+        tape: &mut Tape<T>,
+        options: &DisplayOptions,
+        limit: u64,
+    ) -> std::io::Result<u64> {
+        let flat = self.flatten();
+        let every = options.trace_every.unwrap_or(1);
+        let mut buffer = String::new();
+        let mut steps = 0;
+        let mut rows = 0;
+        write_frame(out, &mut buffer, self, tape, steps, rows, options)?;
+        while let Index(state) = self.state {
+            if steps == limit {
+                break;
+            }
+            let due = options
+                .marks
+                .iter()
+                .copied()
+                .filter(|&mark| mark > steps)
+                .fold((steps / every).saturating_add(1).saturating_mul(every), u64::min)
+                .min(limit);
+            let (taken, end) = flat.run(tape, state, due - steps);
+            self.state = end;
+            steps += taken;
+            rows += 1;
+            write_frame(out, &mut buffer, self, tape, steps, rows, options)?;
+        }
+        Ok(steps)
+    }
+}
+
+/*
+// I think this is literally impossible?
+// Because there's no way I could link a bit to the vec value without a very quirky return type
+impl<T: Unsigned + PrimInt> IndexMut<isize> for Tape<T> {
+    fn index_mut(&mut self, index: isize) -> &mut Self::Output {
+        todo!()
+    }
+}
+*/
+
+/*
+static BB2_MACH: TuringMachine<2> = turing_machine!(
+    (One, Right, 1; One, Left, 1),
+    (One, Left, 0; One, Right, HALT)
+);
+*/
+
+/*
+static BB3_MACH: TuringMachine<3> = turing_machine!(
+    (One, Right, 1; One, Left, 2),
+    (One, Left, 0; One, Right, 1),
+    (One, Left, 1; One, Right, HALT),
+);
+*/
+
+/*
+static COPY_MACH: TuringMachine<5> = turing_machine!(
+    (Zero, Right, HALT; Zero, Right, 1),
+    (Zero, Right, 2; One, Right, 1),
+    (One, Left, 3; One, Right, 2),
+    (Zero, Left, 4; One, Left, 3),
+    (One, Right, 0; One, Left, 4),
+);
+*/
+
+#[derive(Clone, Copy, Debug)]
+struct CompiledStep<T: Unsigned + PrimInt> {
+    tape: T,
+    direction_state: u8,
+}
+
+impl<T: Unsigned + PrimInt> CompiledStep<T> {
+    fn get_direction(&self) -> TapeMotion {
+        if self.direction_state & 1 == 0 {
+            Left
+        } else {
+            Right
+        }
+    }
+
+    fn get_state(&self) -> i8 {
+        let result = self.direction_state >> 1;
+        // all 1s except for the most significant, negative bit
+        if result == !0 >> 1 {
+            -1
+        } else {
+            result as i8
+        }
+    }
+}
+
+struct CompiledTuringMachine<T: Unsigned + PrimInt, const N: usize> {
+    tm: TuringMachine<N>,
+    lut: Vec<CompiledStep<T>>,
+    // The steps each entry takes, kept apart from the entries so that they
+    // stay as small as the word
+    lengths: Vec<u32>,
+}
+
+impl<const N: usize> TuringMachine<N> {
+    fn compile<T: Unsigned + PrimInt>(mut self) -> CompiledTuringMachine<T, N> {
+        assert!(N < i8::MAX as usize);
+        let start = self.state;
+        let bits: usize = size_of::<T>() * 8;
+        let num_steps: usize = (N * 2) << bits;
+        // Padded to a power of two, so that a lookup can mask its index into
+        // range instead of checking it, see `CompiledTuringMachine::run_limited`
+        let mut steps: Vec<CompiledStep<T>> = vec![
+            CompiledStep {
+                tape: T::zero(),
+                direction_state: 0,
+            };
+            (N * 2).next_power_of_two() << bits
+        ];
+        let mut lengths = vec![0; steps.len()];
+        for (i, (entry, length)) in steps
+            .iter_mut()
+            .zip(lengths.iter_mut())
+            .take(num_steps)
+            .enumerate()
+        {
+            (*entry, *length) = self.compile_entry(i);
+        }
+
+        self.state = start;
+        CompiledTuringMachine {
+            tm: self,
+            lut: steps,
+            lengths,
+        }
+    }
+}
+
+impl<const N: usize> TuringMachine<N> {
+    // Runs the LUT entry at index i until the head leaves its word or the
+    // machine halts, leaving `self.state` wherever that ends, and returns it
+    // with the steps it took. A machine still inside the word after more
+    // steps than the word has configurations never leaves it, and its entry
+    // is made a halting one, which the run hands to the step loop as it does
+    // a word the machine really halts in.
+    fn compile_entry<T: Unsigned + PrimInt>(&mut self, i: usize) -> (CompiledStep<T>, u32) {
+        let bits: usize = size_of::<T>() * 8;
+        let configurations = (N * bits) << bits;
+        let mut steps = 0;
+        let state_mask = !0 >> (usize::BITS as usize - bits);
+        // the index i is in the form of
+        // [state_index: remaining bits] [entryDirection: 1 bit] [tape: size(T) bits]
+        // from most significant to least significant, or left to right.
+        // The mask is to make sure that the numcast will never fail.
+        let mut tape: T = NumCast::from(i & state_mask).unwrap();
+        // 0 means we entered from the left,
+        // anything else means we entered from the right
+        let mut position = match i & (1 << bits) {
+            0 => bits - 1,
+            _ => 0,
+        };
+        self.state = Index(i >> (bits + 1));
+        let mut exited: Option<TapeMotion> = None;
+        while exited.is_none() {
+            if steps > configurations {
+                self.state = HALT;
+                break;
+            }
+            steps += 1;
+            if let Index(state) = self.state {
+                let step = match get_bit(tape, position) {
+                    Zero => &self.states[state].zero,
+                    One => &self.states[state].one,
+                };
+                set_bit(&mut tape, position, step.print);
+                match step.motion {
+                    Left if position == bits - 1 => exited = Some(Left),
+                    Right if position == 0 => exited = Some(Right),
+                    Left => position += 1,
+                    Right => position -= 1,
+                }
+                self.state = step.next_state;
+            } else {
+                break;
+            }
+        }
+        let direction_state: u8 = match self.state {
+            Index(state) => (state as u8) << 1,
+            HALT => !0 << 1,
+        } | match exited {
+            None | Some(Right) => 0,
+            Some(Left) => 1,
+        };
+        (
+            CompiledStep {
+                tape,
+                direction_state,
+            },
+            steps as u32,
+        )
+    }
+}
+
+impl<T: Unsigned + PrimInt, const N: usize> Index<CompiledStep<T>> for CompiledTuringMachine<T, N> {
+    type Output = CompiledStep<T>;
+
+    fn index(&self, index: CompiledStep<T>) -> &Self::Output {
+        let bits = size_of::<T>() * 8;
+        let vec_index: usize =
+            index.tape.to_usize().unwrap() | ((index.direction_state as usize) << bits);
+        &self.lut[vec_index & (self.lut.len() - 1)]
+    }
+}
+
+impl<T: Unsigned + PrimInt, const N: usize> CompiledTuringMachine<T, N> {
+    // Heap bytes held by the LUT, padding included
+    fn memory_usage(&self) -> usize {
+        self.lut.capacity() * size_of::<CompiledStep<T>>()
+            + self.lengths.capacity() * size_of::<u32>()
+    }
+
+    // Runs a word at a time, returning the number of steps to halt, or None
+    // if the machine is still going after `limit` of them, as
+    // `TuringMachine::run_limited` does. A word can take the run past the
+    // limit if it's going to be None anyway. The LUT doesn't say where in a
+    // word the machine halts, so from a halting entry on the rest of the run
+    // goes through the step loop instead.
+    fn run_limited(&mut self, tape: &mut Tape<T>, limit: u64) -> Option<u64> {
+        let bits = size_of::<T>() * 8;
+        // The LUT's length is a power of two, so masking an index keeps it in
+        // range, and the lookups need no bounds checks after this one
+        let mask = self.lut.len() - 1;
+        let lut = &self.lut[..=mask];
+        let lengths = &self.lengths[..=mask];
+        // On the right half the lowest bit is the leftmost cell, and on the
+        // left half the rightmost, but the LUT's words have the leftmost cell
+        // in the highest bit
+        let at_left = |tape: &Tape<T>| match tape.half {
+            Right => tape.bit_index == 0,
+            Left => tape.bit_index == bits - 1,
+        };
+        // Lookups start at the edge of a word
+        let mut steps = 0;
+        while let Index(state) = self.tm.state {
+            if tape.bit_index == 0 || tape.bit_index == bits - 1 {
+                break;
+            }
+            if steps == limit {
+                return None;
+            }
+            self.tm.step(tape, state);
+            steps += 1;
+        }
+        while let Index(state) = self.tm.state {
+            if steps >= limit {
+                return None;
+            }
+            let entered = (!at_left(tape) as usize) << bits;
+            let half = tape.half;
+            let vec = match half {
+                Left => &mut tape.left,
+                Right => &mut tape.right,
+            };
+            let word = match half {
+                Left => vec[tape.vec_index],
+                Right => vec[tape.vec_index].reverse_bits(),
+            };
+            let index = (state << (bits + 1)) | entered | word.to_usize().unwrap();
+            // Laid out as `compile_entry` reads it, and in range without the
+            // mask
+            debug_assert!(
+                index >> (bits + 1) == state
+                    && (index >> bits & 1) << bits == entered
+                    && index & ((1 << bits) - 1) == word.to_usize().unwrap()
+                    && index <= mask,
+                "LUT index {:#x} for state {} and word {:#x}",
+                index,
+                state,
+                word.to_usize().unwrap()
+            );
+            let step = lut[index & mask];
+            if step.get_state() < 0 {
+                return self
+                    .tm
+                    .run_limited(tape, limit - steps)
+                    .map(|rest| steps + rest);
+            }
+            steps += lengths[index & mask] as u64;
+            vec[tape.vec_index] = match half {
+                Left => step.tape,
+                Right => step.tape.reverse_bits(),
+            };
+            // The LUT gives the side the next word is entered from, the
+            // opposite of the way the head moved out of this one
+            let exit = match step.get_direction() {
+                Left => Right,
+                Right => Left,
+            };
+            tape.set_bit_index(match (half, exit) {
+                (Right, Left) | (Left, Right) => 0,
+                (Right, Right) | (Left, Left) => bits - 1,
+            });
+            tape.move_tape(exit);
+            self.tm.state = Index(step.get_state() as usize);
+        }
+        Some(steps)
+    }
+}
+
+// Takes `arg` if it's one of the flags for annotating a trace, along with
+// its value from `args`, returning whether it was, so commands with flags of
+// their own can accept these too
+fn parse_display_option(
+    options: &mut DisplayOptions,
+    arg: &str,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<bool, String> {
+    let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+    match arg {
+        "--step-numbers" => options.step_numbers = true,
+        "--table" => options.table = true,
+        "--blocks" => options.blocks = true,
+        "--ruler" => {
+            let every = value()?;
+            options.ruler = Some(every.parse().map_err(|_| format!("bad --ruler {}", every))?);
+        }
+        "--trace-every" => {
+            let every = value()?;
+            options.trace_every = Some(
+                every
+                    .parse()
+                    .ok()
+                    .filter(|&every| every > 0)
+                    .ok_or(format!("bad --trace-every {}", every))?,
+            );
+        }
+        "--mark-steps" => {
+            let steps = value()?;
+            options.marks = steps
+                .split(',')
+                .map(|s| s.trim().parse().map_err(|_| format!("bad step {:?}", s)))
+                .collect::<Result<_, _>>()?;
+        }
+        "--glyphs" => {
+            let glyphs = value()?;
+            match glyphs.chars().collect::<Vec<char>>()[..] {
+                [zero, one] => (options.zero, options.one) = (zero, one),
+                [zero, one, blank, head] => {
+                    (options.zero, options.one) = (zero, one);
+                    (options.blank, options.head) = (blank, head);
+                }
+                _ => {
+                    return Err(format!(
+                        "bad --glyphs {:?}, it takes 0 and 1, and then blank and head",
+                        glyphs
+                    ))
+                }
+            }
+        }
+        "--group" => {
+            let group = value()?;
+            options.group = Some(
+                group
+                    .parse()
+                    .ok()
+                    .filter(|&group| group > 0)
+                    .ok_or(format!("bad --group {}", group))?,
+            );
+        }
+        "--separator" => {
+            let separator = value()?;
+            let mut chars = separator.chars();
+            options.separator = match (chars.next(), chars.next()) {
+                (Some(separator), None) => separator,
+                _ => {
+                    return Err(format!(
+                        "bad --separator {:?}, it takes a single character",
+                        separator
+                    ))
+                }
+            };
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+// Flags for annotating the verbose trace printed by main
+fn parse_display_options(mut args: impl Iterator<Item = String>) -> Result<DisplayOptions, String> {
+    let mut options = DisplayOptions::default();
+    while let Some(arg) = args.next() {
+        if !parse_display_option(&mut options, &arg, &mut args)? {
+            return Err(format!("unknown argument {}", arg));
+        }
+    }
+    Ok(options)
+}
+
+const USAGE: &str = "usage:
+    turing-sim-rs [--step-numbers] [--ruler N] [--mark-steps 1000,2500] [--trace-every N] [--table]
+        [--glyphs 01[ ^] | --blocks] [--group N] [--separator C]
+    turing-sim-rs trace <machine> [--step-numbers] [--ruler N] [--mark-steps ..] [--trace-every N]
+        [--table] [--glyphs 01[ ^] | --blocks] [--group N] [--separator C]
+    turing-sim-rs watch <machine> [--speed STEPS] [--limit N] [--cast FILE]
+        [--glyphs 01[ ^] | --blocks] [--group N] [--separator C]
+    turing-sim-rs spacetime <machine> [--limit N] [--width W] [--rows R] [--out FILE]
+        [--aggregate any|density] [--step-numbers] [--ruler N] [--mark-steps ..]
+        [--glyphs 01[ ^] | --blocks] [--group N] [--separator C]
+    turing-sim-rs run <machine> [--limit N] [--strategy auto|flat|lut|chain | --progress SECONDS]
+        [--reserve CELLS] [--growth double|+N|chunk=BITS|huge]
+    turing-sim-rs sweep <machine> (--input BITS.. | --all K) [--limit N]
+    turing-sim-rs enumerate <states> [--limit N] [--leaderboard FILE] [--mirror]
+        [--filter CLAUSES] [--threads N] [--shard K/N [--dir DIR]]
+        [--cursor FILE | --resume-cursor FILE] [--checkpoint-every SECONDS]
+        [--progress SECONDS] [--progress-log FILE] [--early-halt | --static]
+    turing-sim-rs enumerate <states> --symbols 3..10 [--limit N] [--leaderboard FILE] [--mirror]
+        (the tree on one thread only, and no deciders take more than two symbols)
+    turing-sim-rs merge <dir>
+    turing-sim-rs evolve <states> [--population N] [--generations N] [--seed N] [--limit N]
+        [--score sigma|steps] [--leaderboard FILE]
+    turing-sim-rs sample <states> [--count N] [--seed N] [--budgets 100,10000,..]
+        [--deciders a,b,..]
+    turing-sim-rs decide <machine> [--deciders a,b,..] [--limit N] [--certificate]
+    turing-sim-rs classify --in FILE [--out FILE] [--deciders a,b,..] [--limit N]
+        [--holdouts FILE] [--holdouts-seed FILE] [--staged FIRST]
+    turing-sim-rs seed <file> [index... | --all]
+    turing-sim-rs pack <machine> --out FILE | --in FILE --out FILE
+    turing-sim-rs unpack <file> [index...] [--count]
+    turing-sim-rs known
+    turing-sim-rs bench [--corpus FILE] [--only NAME,..] [--time SECONDS]
+        [--glyphs 01[ ^] | --blocks] [--group N] [--separator C]
+    turing-sim-rs analyze <machine or holdout name> [--steps N] [--records N]
+    turing-sim-rs beep <machine> [--beep A0,B1,..] [--limit N]
+    turing-sim-rs verify-claims <claims.csv> [--out FILE] [--limit N] [--key FILE]
+    turing-sim-rs verify-cert <machine> <certificate> [--limit N]
+    turing-sim-rs rpc
+    turing-sim-rs tag <machine> [--limit N] [--productions]
+    turing-sim-rs counter <machine> [--limit N] [--program]
+    turing-sim-rs wang <program> [--limit N]
+    turing-sim-rs bf <program> [--limit N]
+    turing-sim-rs history <machine> [--steps N] [--out FILE] [--rule NAME]
+    turing-sim-rs utm <machine> [--input BITS] [--limit N] [--table]
+    turing-sim-rs export <machine> --format morphett|turingmachinesimulator|smtlib|nusmv|tla|html|svg
+        [--input BITS] [--unroll STEPS] [--window CELLS]
+    turing-sim-rs import <file> --format turingmachine.io|csv [--limit N]
+    turing-sim-rs golden <machine> <file> [--input BITS] [--steps N] [--update]
+    turing-sim-rs selftest [<machine>..] [--input BITS] [--limit N] [--corpus FILE]
+    turing-sim-rs fuzz parsers|tape [<file>..] [--runs N] [--seed N]
+    turing-sim-rs assert <machine or file> [--halts | --runs] [--steps N] [--sigma N]
+        [--within N] [--input BITS]
+    turing-sim-rs equivalent <machine> <machine> [--steps N] [--input BITS].. [--all K]
+        [--window CELLS]
+    turing-sim-rs compare <machine> <machine> [--limit N] [--step-numbers] [--ruler N] [--mark-steps ..]
+        [--glyphs 01[ ^] | --blocks] [--group N] [--separator C]
+    turing-sim-rs crossval --in FILE --sim COMMAND [--limit N]   (with the crossval feature)
+    turing-sim-rs canonicalize <machine...> | --in FILE [--mirror] [--swap-symbols] [--unique]
+    turing-sim-rs holdouts import <list> <file...>
+    turing-sim-rs holdouts run <list> [--deciders a,b,..] [--limit N] [--report FILE]
+    turing-sim-rs holdouts diff <old list> <new list>";
+
+// The copy machine, traced on a tape holding 11
+fn demo(args: impl Iterator<Item = String>) -> Result<(), String> {
+    let options = parse_display_options(args)?;
+    let mut tm = turing_machine!(
+        (Zero, Right, HALT; Zero, Right, 1),
+        (Zero, Right, 2; One, Right, 1),
+        (One, Left, 3; One, Right, 2),
+        (Zero, Left, 4; One, Left, 3),
+        (One, Right, 0; One, Left, 4)
+    );
+    let mut tape = Tape::<u8>::new();
+    tape.right[0] = 0x03;
+    tm.run_verbose_with(&mut tape, &options);
+    println!("{}", tape.hex());
+    let comp = tm.compile::<u16>();
+    let test_index = 0b01111111111111100;
+    println!(
+        "{}\t{:?}\t{}\t{}",
+        comp.lut.len(),
+        comp.lut[test_index],
+        as_bits(comp.lut[test_index].tape),
+        size_of::<CompiledStep<u32>>()
+    );
+    Ok(())
+}
+
+// The command line, run by the binary, src/main.rs
+pub fn main() {
+    let mut args = std::env::args().skip(1).peekable();
+    let result = match args.peek().map(String::as_str) {
+        Some("enumerate") => commands::enumerate(args.skip(1)),
+        Some("merge") => commands::merge(args.skip(1)),
+        Some("evolve") => commands::evolve_command(args.skip(1)),
+        Some("sample") => commands::sample_command(args.skip(1)),
+        Some("decide") => commands::decide(args.skip(1)),
+        Some("classify") => commands::classify(args.skip(1)),
+        Some("seed") => commands::seed(args.skip(1)),
+        Some("pack") => commands::pack(args.skip(1)),
+        Some("unpack") => commands::unpack(args.skip(1)),
+        Some("known") => commands::known(args.skip(1)),
+        Some("bench") => commands::bench(args.skip(1)),
+        Some("analyze") => commands::analyze(args.skip(1)),
+        Some("beep") => commands::beep(args.skip(1)),
+        Some("verify-claims") => commands::verify_claims(args.skip(1)),
+        Some("verify-cert") => commands::verify_cert(args.skip(1)),
+        Some("rpc") => commands::rpc_command(args.skip(1)),
+        Some("tag") => commands::tag(args.skip(1)),
+        Some("counter") => commands::counter(args.skip(1)),
+        Some("wang") => commands::wang_command(args.skip(1)),
+        Some("bf") => commands::bf_command(args.skip(1)),
+        Some("history") => commands::history(args.skip(1)),
+        Some("trace") => commands::trace_command(args.skip(1)),
+        Some("watch") => commands::watch(args.skip(1)),
+        Some("spacetime") => commands::spacetime(args.skip(1)),
+        Some("run") => commands::run_command(args.skip(1)),
+        Some("sweep") => commands::sweep(args.skip(1)),
+        Some("utm") => commands::utm(args.skip(1)),
+        Some("export") => commands::export(args.skip(1)),
+        Some("import") => commands::import(args.skip(1)),
+        Some("golden") => commands::golden(args.skip(1)),
+        Some("selftest") => commands::selftest_command(args.skip(1)),
+        Some("fuzz") => commands::fuzz_command(args.skip(1)),
+        Some("assert") => commands::assert_command(args.skip(1)),
+        Some("equivalent") => commands::equivalent(args.skip(1)),
+        Some("compare") => commands::compare(args.skip(1)),
+        #[cfg(feature = "crossval")]
+        Some("crossval") => commands::crossval(args.skip(1)),
+        Some("canonicalize") => commands::canonicalize(args.skip(1)),
+        Some("holdouts") => commands::holdouts(args.skip(1)),
+        _ => demo(args).map_err(Failure::Usage),
+    };
+    match result {
+        Ok(()) => {}
+        Err(Failure::Usage(e)) => {
+            eprintln!("{}", e);
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+        Err(Failure::Error(e)) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/*
+
+0:
+    0 1 -> 1
+    1 1 <- 1
+1:
+    0 1 <- 0
+    1 1 -> HALT
+
+000000
+  0^
+000100
+   1^
+000110
+  0^
+000110
+ 1^
+001110
+0^
+011110
+ 1^
+011110
+   ^HALT
+
+*/
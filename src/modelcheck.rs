@@ -0,0 +1,173 @@
+use crate::enumerate::PartialMachine;
+use crate::enumerate::HALT_STEP;
+use crate::standard::state_letter;
+use crate::Bit;
+use crate::Bit::*;
+use crate::State::*;
+use crate::TapeMotion::*;
+use crate::TuringStep;
+
+// A machine as a finite transition system for model checkers, NuSMV and
+// TLC. Only a window of cells from -window to window is kept, with the
+// input from cell 0 and the head starting there. A step that would take the
+// head outside goes to the state escaped instead, where the system stays,
+// as it does once halted. Undefined transitions are the halting ones
+// they're run as.
+//
+// Each comes with the property that the machine never halts, so a
+// counterexample is a run that halts without leaving the window, and if
+// the property holds the machine either never halts or needs more tape.
+
+fn state_name(step: &TuringStep) -> String {
+    match step.next_state {
+        Index(next) => state_letter(next).to_string(),
+        HALT => String::from("halt"),
+    }
+}
+
+// Every transition, as the state, the symbol read and the step taken
+fn transitions<const N: usize>(
+    machine: &PartialMachine<N>,
+) -> impl Iterator<Item = (usize, Bit, TuringStep)> + '_ {
+    machine
+        .transitions
+        .iter()
+        .enumerate()
+        .flat_map(|(state, slots)| {
+            [Zero, One]
+                .into_iter()
+                .zip(slots)
+                .map(move |(read, step)| (state, read, step.unwrap_or(HALT_STEP)))
+        })
+}
+
+pub fn to_nusmv<const N: usize>(
+    machine: &PartialMachine<N>,
+    input: &[Bit],
+    window: usize,
+) -> String {
+    let w = window as isize;
+    let cells = -w..=w;
+    let states: Vec<String> = (0..N).map(|q| state_letter(q).to_string()).collect();
+    let mut output = format!(
+        "-- {}, on cells {} to {}\nMODULE main\nVAR\n",
+        machine, -w, w
+    );
+    output.push_str(&format!(
+        "  state : {{{}, halt, escaped}};\n",
+        states.join(", ")
+    ));
+    output.push_str(&format!("  head : {}..{};\n", -w, w));
+    output.push_str(&format!("  tape : array {}..{} of boolean;\n", -w, w));
+
+    let when = |state: usize, read: Bit| {
+        format!(
+            "state = {} & {}read",
+            state_letter(state),
+            if read == Zero { "!" } else { "" }
+        )
+    };
+    output.push_str("DEFINE\n  read := case\n");
+    for i in cells.clone() {
+        output.push_str(&format!("    head = {} : tape[{}];\n", i, i));
+    }
+    output.push_str("  esac;\n  write := case\n");
+    for (state, read, step) in transitions(machine) {
+        output.push_str(&format!(
+            "    {} : {};\n",
+            when(state, read),
+            if step.print == One { "TRUE" } else { "FALSE" }
+        ));
+    }
+    output.push_str("    TRUE : read;\n  esac;\n  right := case\n");
+    for (state, read, step) in transitions(machine) {
+        output.push_str(&format!(
+            "    {} : {};\n",
+            when(state, read),
+            if step.motion == Right {
+                "TRUE"
+            } else {
+                "FALSE"
+            }
+        ));
+    }
+    output.push_str("    TRUE : FALSE;\n  esac;\n  target := case\n");
+    for (state, read, step) in transitions(machine) {
+        output.push_str(&format!(
+            "    {} : {};\n",
+            when(state, read),
+            state_name(&step)
+        ));
+    }
+    output.push_str("    TRUE : state;\n  esac;\n");
+    output.push_str("  running := state != halt & state != escaped;\n");
+    output.push_str(&format!(
+        "  escapes := running & (right & head = {} | !right & head = {});\n",
+        w, -w
+    ));
+
+    output.push_str("ASSIGN\n");
+    output.push_str(&format!(
+        "  init(state) := {};\n  init(head) := 0;\n",
+        states[0]
+    ));
+    for i in cells.clone() {
+        let one = usize::try_from(i).ok().and_then(|i| input.get(i)) == Some(&One);
+        output.push_str(&format!(
+            "  init(tape[{}]) := {};\n",
+            i,
+            if one { "TRUE" } else { "FALSE" }
+        ));
+    }
+    output.push_str("  next(state) := case\n    escapes : escaped;\n    running : target;\n    TRUE : state;\n  esac;\n");
+    output.push_str("  next(head) := case\n    !running | escapes : head;\n    right : head + 1;\n    TRUE : head - 1;\n  esac;\n");
+    for i in cells {
+        output.push_str(&format!(
+            "  next(tape[{0}]) := case\n    running & head = {0} : write;\n    TRUE : tape[{0}];\n  esac;\n",
+            i
+        ));
+    }
+    output.push_str("-- Never halts, within the window\nLTLSPEC G !(state = halt)\n");
+    output
+}
+
+// A TLA+ module, to be saved as Machine.tla
+pub fn to_tla<const N: usize>(
+    machine: &PartialMachine<N>,
+    input: &[Bit],
+    window: usize,
+) -> String {
+    let ones: Vec<String> = input
+        .iter()
+        .enumerate()
+        .filter(|(_, bit)| **bit == One)
+        .map(|(i, _)| i.to_string())
+        .collect();
+    let mut output = format!(
+        "---- MODULE Machine ----\n\\* {}, on cells -{} to {}\nEXTENDS Integers\n\nVARIABLES state, head, tape\nvars == <<state, head, tape>>\nWindow == -{}..{}\n\n",
+        machine, window, window, window, window
+    );
+    output.push_str(&format!(
+        "Init ==\n    /\\ state = \"{}\"\n    /\\ head = 0\n    /\\ tape = [i \\in Window |-> i \\in {{{}}}]\n\n",
+        state_letter(0),
+        ones.join(", ")
+    ));
+    output.push_str(
+        "Step(s, read, write, move, next) ==\n    /\\ state = s\n    /\\ tape[head] = read\n    /\\ tape' = [tape EXCEPT ![head] = write]\n    /\\ IF head + move \\in Window\n          THEN state' = next /\\ head' = head + move\n          ELSE state' = \"escaped\" /\\ head' = head\n\n",
+    );
+    output.push_str("Next ==\n");
+    for (state, read, step) in transitions(machine) {
+        output.push_str(&format!(
+            "    \\/ Step(\"{}\", {}, {}, {}, \"{}\")\n",
+            state_letter(state),
+            if read == One { "TRUE" } else { "FALSE" },
+            if step.print == One { "TRUE" } else { "FALSE" },
+            if step.motion == Right { "1" } else { "-1" },
+            state_name(&step)
+        ));
+    }
+    output.push_str(
+        "    \\/ state \\in {\"halt\", \"escaped\"} /\\ UNCHANGED vars\n\nSpec == Init /\\ [][Next]_vars\n\n\\* Never halts, within the window\nNeverHalts == state # \"halt\"\n====\n",
+    );
+    output
+}
@@ -0,0 +1,844 @@
+use std::collections::VecDeque;
+use std::fmt::Display;
+
+use num_traits::PrimInt;
+use num_traits::Unsigned;
+
+use crate::enumerate::PartialMachine;
+use crate::enumerate::HALT_STEP;
+use crate::standard::state_letter;
+use crate::wide::WideMachine;
+use crate::wide::WideStep;
+use crate::Bit::*;
+use crate::State::*;
+use crate::Tape;
+use crate::TapeMotion::*;
+
+// Other models of computation, and the classic reductions between them and
+// Turing machines.
+//
+// A tag system reads the first symbol of its word, deletes the first
+// `deletion` symbols and appends the production of the one it read. It
+// halts once the word is shorter than `deletion`, or on reading a symbol
+// without a production.
+pub struct TagSystem {
+    pub deletion: usize,
+    pub names: Vec<String>,
+    pub productions: Vec<Option<Vec<usize>>>,
+}
+
+impl TagSystem {
+    // Takes one step, or returns false if the system has halted
+    pub fn step(&self, word: &mut VecDeque<usize>) -> bool {
+        if word.len() < self.deletion {
+            return false;
+        }
+        let Some(production) = &self.productions[word[0]] else {
+            return false;
+        };
+        word.drain(..self.deletion);
+        word.extend(production);
+        true
+    }
+}
+
+// One production to a line
+impl Display for TagSystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, production) in self.names.iter().zip(&self.productions) {
+            match production {
+                Some(production) => {
+                    let word: Vec<&str> =
+                        production.iter().map(|&s| self.names[s].as_str()).collect();
+                    f.write_fmt(format_args!("{} -> {}\n", name, word.join(" ")))?
+                }
+                None => f.write_fmt(format_args!("{} halts\n", name))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// A 2-tag system that runs a Turing machine, after Cocke and Minsky. The
+// tape is split at the head into two numbers, m for the cells left of it,
+// nearest first, and n for the cell under it and the ones to the right, so
+// the symbol read is n's lowest bit. A configuration in state q is the word
+//
+//     A0 A1 (a0 a1)^m B0 B1 (b0 b1)^n
+//
+// with symbols for q. Either symbol of a pair can come first in a pass
+// over the word, depending on how the deletions are lined up, and the 1
+// versions' productions start with a filler symbol to line the next pass
+// up again. A step takes three or four passes:
+//
+// 1. The m part is copied, and n is halved by giving each b a production of
+//    one symbol, so the next pass reads every other one of them.
+// 2. Everything is copied into pairs of symbols for either bit read, and as
+//    halving n leaves the deletions lined up or not depending on its lowest
+//    bit, the next pass reads the symbols for that bit.
+// 3. Knowing the transition, a move right writes the new word with m
+//    doubled plus the bit printed, and n halved. A move left needs the
+//    lowest bit of m as well, so m is halved as n was, with the n part
+//    copied into pairs for either bit of m.
+// 4. (Left only) The new word is written with m halved, and with n doubled
+//    twice, plus twice the bit printed and the bit of m that is now under
+//    the head.
+//
+// Halting is moving to a state Z whose A symbols halt the tag system.
+pub struct TagEncoding {
+    pub system: TagSystem,
+    // Symbols by kind, then state, with Z as the last state
+    a_start: [Vec<usize>; 2],
+    a: [Vec<usize>; 2],
+    b_start: [Vec<usize>; 2],
+    b: [Vec<usize>; 2],
+}
+
+// Where the word stands in the simulation: at the start of a step, in the
+// state given (with None for Z), with m and n as above
+pub struct TagConfiguration {
+    pub state: Option<usize>,
+    pub m: u64,
+    pub n: u64,
+}
+
+impl TagEncoding {
+    pub fn new<const N: usize>(machine: &PartialMachine<N>) -> TagEncoding {
+        let mut names = Vec::new();
+        let mut symbol = |name: String| {
+            names.push(name);
+            names.len() - 1
+        };
+        let letter = |q: usize| match q {
+            _ if q == N => 'Z',
+            _ => state_letter(q),
+        };
+        let filler = symbol(String::from("x"));
+        let per_state = |symbol: &mut dyn FnMut(String) -> usize, kind: &str| -> Vec<usize> {
+            (0..=N)
+                .map(|q| symbol(format!("{}{}", kind, letter(q))))
+                .collect()
+        };
+        let a_start = [per_state(&mut symbol, "A0"), per_state(&mut symbol, "A1")];
+        let a = [per_state(&mut symbol, "a0"), per_state(&mut symbol, "a1")];
+        let b_start = [per_state(&mut symbol, "B0"), per_state(&mut symbol, "B1")];
+        let b = [per_state(&mut symbol, "b0"), per_state(&mut symbol, "b1")];
+        let c_start = per_state(&mut symbol, "C");
+        let c = per_state(&mut symbol, "c");
+        let d_start = per_state(&mut symbol, "D");
+        let d = per_state(&mut symbol, "d");
+        // Symbols for state q reading bit s, at [q * 2 + s]
+        let per_read = |symbol: &mut dyn FnMut(String) -> usize, kind: &str| -> Vec<usize> {
+            (0..2 * N)
+                .map(|i| symbol(format!("{}{}{}", kind, letter(i / 2), i % 2)))
+                .collect()
+        };
+        let s_start = per_read(&mut symbol, "S");
+        let t = per_read(&mut symbol, "t");
+        let u_start = per_read(&mut symbol, "U");
+        let v = per_read(&mut symbol, "v");
+        let h_start = per_read(&mut symbol, "H");
+        let h = per_read(&mut symbol, "h");
+        // And for the lowest bit of m as well, at [bit][q * 2 + s]
+        let j_start = [per_read(&mut symbol, "J0"), per_read(&mut symbol, "J1")];
+        let w = [per_read(&mut symbol, "V0"), per_read(&mut symbol, "V1")];
+
+        let mut productions = vec![Some(Vec::new()); names.len()];
+        let mut set =
+            |symbol: usize, production: Vec<usize>| productions[symbol] = Some(production);
+        let pair = |symbols: &[Vec<usize>; 2], i: usize| [symbols[0][i], symbols[1][i]];
+        for q in 0..N {
+            set(a_start[0][q], vec![c_start[q], filler]);
+            set(a_start[1][q], vec![filler, c_start[q], filler]);
+            for o in 0..2 {
+                set(a[o][q], vec![c[q], filler]);
+                set(b_start[o][q], vec![d_start[q]]);
+                set(b[o][q], vec![d[q]]);
+            }
+            // Reading a 1 leaves the deletions lined up for the first symbol
+            // of each pair, and a 0 for the second
+            let by_bit = |symbols: &[usize]| vec![symbols[2 * q + 1], symbols[2 * q]];
+            set(c_start[q], by_bit(&s_start));
+            set(c[q], by_bit(&t));
+            set(d_start[q], by_bit(&u_start));
+            set(d[q], by_bit(&v));
+            for s in 0..2 {
+                let i = 2 * q + s;
+                let step = machine.transitions[q][s].unwrap_or(HALT_STEP);
+                let next = match step.next_state {
+                    Index(next) => next,
+                    HALT => N,
+                };
+                let print = matches!(step.print, One) as usize;
+                let new_a = pair(&a_start, next);
+                let new_b = pair(&b_start, next);
+                let a_pair = pair(&a, next);
+                let b_pair = pair(&b, next);
+                // Reading a 0 leaves the deletions out of line
+                let lined_up = |mut production: Vec<usize>| {
+                    if s == 0 {
+                        production.insert(0, filler);
+                    }
+                    production
+                };
+                match step.motion {
+                    Right => {
+                        set(s_start[i], [new_a.to_vec(), a_pair.repeat(print)].concat());
+                        set(t[i], a_pair.repeat(2));
+                        set(u_start[i], new_b.to_vec());
+                        set(v[i], b_pair.to_vec());
+                    }
+                    Left => {
+                        set(s_start[i], lined_up(vec![h_start[i]]));
+                        set(t[i], vec![h[i]]);
+                        // And the same for m's lowest bit
+                        set(u_start[i], vec![j_start[1][i], j_start[0][i]]);
+                        set(v[i], vec![w[1][i], w[0][i]]);
+                        set(h_start[i], new_a.to_vec());
+                        set(h[i], a_pair.to_vec());
+                        for (bit, j) in j_start.iter().enumerate() {
+                            set(
+                                j[i],
+                                [new_b.to_vec(), b_pair.repeat(2 * print + bit)].concat(),
+                            );
+                            set(w[bit][i], b_pair.repeat(4));
+                        }
+                    }
+                }
+            }
+        }
+        for start in &a_start {
+            productions[start[N]] = None;
+        }
+        TagEncoding {
+            system: TagSystem {
+                deletion: 2,
+                names,
+                productions,
+            },
+            a_start,
+            a,
+            b_start,
+            b,
+        }
+    }
+
+    // The word for state A on blank tape
+    pub fn start(&self) -> VecDeque<usize> {
+        self.word(0, 0, 0)
+    }
+
+    // The word for a configuration, with m and n as above
+    pub fn word(&self, state: usize, m: u64, n: u64) -> VecDeque<usize> {
+        let mut word = VecDeque::from([self.a_start[0][state], self.a_start[1][state]]);
+        for _ in 0..m {
+            word.extend([self.a[0][state], self.a[1][state]]);
+        }
+        word.extend([self.b_start[0][state], self.b_start[1][state]]);
+        for _ in 0..n {
+            word.extend([self.b[0][state], self.b[1][state]]);
+        }
+        word
+    }
+
+    // The configuration, if the word is at the start of a step
+    pub fn decode(&self, word: &VecDeque<usize>) -> Option<TagConfiguration> {
+        let states = self.a_start[0].len();
+        let front = *word.front()?;
+        let q = (0..states).find(|&q| self.a_start.iter().any(|start| start[q] == front))?;
+        let count = |symbol: usize| word.iter().filter(|&&s| s == symbol).count() as u64;
+        Some(TagConfiguration {
+            state: (q < states - 1).then_some(q),
+            m: count(self.a[0][q]),
+            n: count(self.b[0][q]),
+        })
+    }
+}
+
+// The two numbers for a tape as `TagEncoding` splits it, or None if either
+// doesn't fit in 64 bits
+pub fn split_tape<T: Unsigned + PrimInt>(tape: &Tape<T>) -> Option<(u64, u64)> {
+    let (low, high) = tape.bounds();
+    let head = tape.head_index();
+    let number = |cells: &mut dyn Iterator<Item = i64>| {
+        let mut number = 0u64;
+        for (bit, index) in cells.enumerate() {
+            if tape.cell_at(index) == One {
+                if bit >= 64 {
+                    return None;
+                }
+                number |= 1 << bit;
+            }
+        }
+        Some(number)
+    };
+    Some((
+        number(&mut (low..head).rev())?,
+        number(&mut (head..high.max(head + 1)))?,
+    ))
+}
+
+// A Turing machine over more symbols that runs a 2-tag system, with the
+// word on the tape, blank as 0 and tag symbol i as i + 1. Starting at the
+// first symbol, state 0 reads it and erases it, then erases the next one,
+// goes past the end of the word to write the production, and comes back.
+// The machine halts where the tag system does, with the word left on the
+// tape, by going to the state one past the last.
+pub fn tag_to_turing(system: &TagSystem) -> Result<WideMachine, String> {
+    if system.deletion != 2 {
+        return Err(String::from("only 2-tag systems are turned into machines"));
+    }
+    if system.names.len() > u8::MAX as usize - 1 {
+        return Err(format!("{} tag symbols is too many", system.names.len()));
+    }
+    let symbols = system.names.len() + 1;
+    // State 0 reads, 1 returns, then each symbol goes through deleting,
+    // restoring, seeking the end and writing all but the first of its
+    // production
+    let mut first = vec![0; symbols - 1];
+    let mut states = 2;
+    for (i, production) in system.productions.iter().enumerate() {
+        first[i] = states;
+        states += production
+            .as_ref()
+            .map_or(0, |p| 3 + p.len().saturating_sub(1));
+    }
+    let (read, back, halt) = (0, 1, states);
+    let mut transitions = vec![None; states * symbols];
+    let mut set = |state: usize, cell: usize, print: usize, right: bool, next: usize| {
+        transitions[state * symbols + cell] = Some(WideStep {
+            print: print as u8,
+            right,
+            next,
+        })
+    };
+    set(read, 0, 0, true, halt);
+    for cell in 1..symbols {
+        set(back, cell, cell, false, back);
+    }
+    set(back, 0, 0, true, read);
+    for (i, production) in system.productions.iter().enumerate() {
+        let (delete, restore, seek) = (first[i], first[i] + 1, first[i] + 2);
+        let Some(production) = production else {
+            set(read, i + 1, i + 1, true, halt);
+            continue;
+        };
+        set(read, i + 1, 0, true, delete);
+        // A word of one symbol halts the tag system, so put it back
+        set(delete, 0, 0, false, restore);
+        set(restore, 0, i + 1, true, halt);
+        for cell in 1..symbols {
+            set(delete, cell, 0, true, seek);
+            set(seek, cell, cell, true, seek);
+        }
+        let mut state = seek;
+        for (k, &symbol) in production.iter().enumerate() {
+            let last = k + 1 == production.len();
+            let next = if last { back } else { seek + 1 + k };
+            set(state, 0, symbol + 1, !last, next);
+            state = next;
+        }
+        if production.is_empty() {
+            set(seek, 0, 0, false, back);
+        }
+    }
+    Ok(WideMachine {
+        symbols,
+        transitions,
+    })
+}
+
+// A run of a `WideMachine` where going to the state past the last halts
+pub struct WideRun<'a> {
+    machine: &'a WideMachine,
+    pub tape: VecDeque<u8>,
+    pub head: usize,
+    pub state: usize,
+}
+
+impl<'a> WideRun<'a> {
+    pub fn new(machine: &'a WideMachine, tape: VecDeque<u8>) -> WideRun<'a> {
+        WideRun {
+            machine,
+            tape,
+            head: 0,
+            state: 0,
+        }
+    }
+
+    pub fn halted(&self) -> bool {
+        self.state * self.machine.symbols >= self.machine.transitions.len()
+    }
+
+    // Whether the transition for the cell under the head is undefined
+    pub fn stuck(&self) -> bool {
+        let cell = self.tape[self.head] as usize;
+        self.machine.transitions[self.state * self.machine.symbols + cell].is_none()
+    }
+
+    // Takes one step, or returns an error at an undefined transition
+    pub fn step(&mut self) -> Result<(), String> {
+        let cell = self.tape[self.head];
+        let Some(step) =
+            self.machine.transitions[self.state * self.machine.symbols + cell as usize]
+        else {
+            return Err(format!(
+                "no transition for state {} reading {}",
+                self.state, cell
+            ));
+        };
+        self.tape[self.head] = step.print;
+        self.state = step.next;
+        if step.right {
+            self.head += 1;
+            if self.head == self.tape.len() {
+                self.tape.push_back(0);
+            }
+        } else if self.head == 0 {
+            self.tape.push_front(0);
+        } else {
+            self.head -= 1;
+        }
+        Ok(())
+    }
+}
+
+// A Minsky machine: counters holding whole numbers and a program that
+// starts at its first instruction with all of them at 0
+#[derive(Clone, Copy)]
+pub enum Instruction {
+    // Adds one to the counter, then goes on to the instruction given
+    Inc(usize, usize),
+    // Takes one from the counter and goes to the first instruction given,
+    // or goes to the second if the counter is 0
+    Dec(usize, usize, usize),
+    Halt,
+}
+
+pub struct CounterMachine {
+    pub counters: usize,
+    pub program: Vec<Instruction>,
+}
+
+impl CounterMachine {
+    // Takes the step from instruction `at`, or returns None at a halt
+    pub fn step(&self, counters: &mut [u64], at: usize) -> Option<usize> {
+        match self.program[at] {
+            Instruction::Inc(counter, next) => {
+                counters[counter] += 1;
+                Some(next)
+            }
+            Instruction::Dec(counter, next, _) if counters[counter] > 0 => {
+                counters[counter] -= 1;
+                Some(next)
+            }
+            Instruction::Dec(_, _, zero) => Some(zero),
+            Instruction::Halt => None,
+        }
+    }
+}
+
+// One instruction to a line
+impl Display for CounterMachine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (at, instruction) in self.program.iter().enumerate() {
+            match instruction {
+                Instruction::Inc(counter, next) => {
+                    f.write_fmt(format_args!("{}: inc {} -> {}\n", at, counter, next))?
+                }
+                Instruction::Dec(counter, next, zero) => f.write_fmt(format_args!(
+                    "{}: dec {} -> {}, or {} if 0\n",
+                    at, counter, next, zero
+                ))?,
+                Instruction::Halt => f.write_fmt(format_args!("{}: halt\n", at))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// Builds programs a piece at a time, each piece going on to an instruction
+// given and returning its own first one
+struct Assembler {
+    program: Vec<Instruction>,
+}
+
+impl Assembler {
+    // An instruction to fill in later
+    fn reserve(&mut self) -> usize {
+        self.program.push(Instruction::Halt);
+        self.program.len() - 1
+    }
+
+    fn emit(&mut self, instruction: Instruction) -> usize {
+        self.program.push(instruction);
+        self.program.len() - 1
+    }
+
+    fn incs(&mut self, counter: usize, times: u64, then: usize) -> usize {
+        (0..times).fold(then, |next, _| self.emit(Instruction::Inc(counter, next)))
+    }
+
+    // Empties one counter into another, adding `times` for each one
+    fn transfer(&mut self, from: usize, to: usize, times: u64, then: usize) -> usize {
+        let head = self.reserve();
+        let body = self.incs(to, times, head);
+        self.program[head] = Instruction::Dec(from, body, then);
+        head
+    }
+
+    // Empties one counter into another in `divisor`s, going to then[r] with
+    // the remainder r left over
+    fn divide(&mut self, from: usize, to: usize, divisor: usize, then: &[usize]) -> usize {
+        let takes: Vec<usize> = (0..divisor).map(|_| self.reserve()).collect();
+        let add = self.emit(Instruction::Inc(to, takes[0]));
+        for (r, &take) in takes.iter().enumerate() {
+            let next = takes.get(r + 1).copied().unwrap_or(add);
+            self.program[take] = Instruction::Dec(from, next, then[r]);
+        }
+        takes[0]
+    }
+}
+
+// A 4-counter machine that runs a Turing machine, with the tape split into
+// m and n as for `TagEncoding` in counters 0 and 1 and the other two for
+// working. Each state's code halves n into counter 2, the remainder being
+// the symbol read, then a move right doubles m through counter 3 and adds
+// the bit printed, and a move left halves m through counter 3 and puts
+// four n plus twice the bit printed plus the remainder back into n.
+pub struct CounterEncoding {
+    pub machine: CounterMachine,
+    // Where each state's code starts, with Z's halt last
+    pub starts: Vec<usize>,
+}
+
+impl CounterEncoding {
+    pub fn new<const N: usize>(machine: &PartialMachine<N>) -> CounterEncoding {
+        let mut assembler = Assembler {
+            program: Vec::new(),
+        };
+        let mut starts: Vec<usize> = (0..N).map(|_| assembler.reserve()).collect();
+        starts.push(assembler.emit(Instruction::Halt));
+        for q in 0..N {
+            let mut reads = [0; 2];
+            for (s, read) in reads.iter_mut().enumerate() {
+                let step = machine.transitions[q][s].unwrap_or(HALT_STEP);
+                let next = match step.next_state {
+                    Index(next) => starts[next],
+                    HALT => starts[N],
+                };
+                let print = matches!(step.print, One) as u64;
+                *read = match step.motion {
+                    Right => {
+                        let n = assembler.transfer(2, 1, 1, next);
+                        let m = assembler.incs(0, print, n);
+                        let m = assembler.transfer(3, 0, 1, m);
+                        assembler.transfer(0, 3, 2, m)
+                    }
+                    Left => {
+                        let under: Vec<usize> = (0..2)
+                            .map(|bit| {
+                                let n = assembler.incs(1, 2 * print + bit, next);
+                                let n = assembler.transfer(2, 1, 4, n);
+                                assembler.transfer(3, 0, 1, n)
+                            })
+                            .collect();
+                        assembler.divide(0, 3, 2, &under)
+                    }
+                };
+            }
+            let code = assembler.divide(1, 2, 2, &reads);
+            assembler.program[starts[q]] = Instruction::Dec(3, code, code);
+        }
+        CounterEncoding {
+            machine: CounterMachine {
+                counters: 4,
+                program: assembler.program,
+            },
+            starts,
+        }
+    }
+}
+
+const PRIMES: [u64; 8] = [2, 3, 5, 7, 11, 13, 17, 19];
+
+// A 2-counter machine that runs a counter machine, after Minsky. Counter 0
+// holds 2^c0 3^c1 5^c2 and so on, over the first primes, and counter 1 is
+// for working. Adding to a counter multiplies by its prime, and taking from
+// it divides, putting the number back together if there is a remainder,
+// which means the counter was 0. Returns the start of each instruction's
+// code as well.
+pub fn to_two_counters(machine: &CounterMachine) -> Result<(CounterMachine, Vec<usize>), String> {
+    if machine.counters > PRIMES.len() {
+        return Err(format!("{} counters is too many", machine.counters));
+    }
+    let mut assembler = Assembler {
+        program: Vec::new(),
+    };
+    // The first instruction starts by setting counter 0 to 1
+    let first = assembler.reserve();
+    let starts: Vec<usize> = machine
+        .program
+        .iter()
+        .map(|_| assembler.reserve())
+        .collect();
+    assembler.program[first] = Instruction::Inc(0, starts[0]);
+    for (at, &instruction) in machine.program.iter().enumerate() {
+        let code = match instruction {
+            Instruction::Inc(counter, next) => {
+                let back = assembler.transfer(1, 0, 1, starts[next]);
+                assembler.transfer(0, 1, PRIMES[counter], back)
+            }
+            Instruction::Dec(counter, next, zero) => {
+                let prime = PRIMES[counter];
+                let mut then = vec![assembler.transfer(1, 0, 1, starts[next])];
+                for remainder in 1..prime {
+                    let back = assembler.incs(0, remainder, starts[zero]);
+                    then.push(assembler.transfer(1, 0, prime, back));
+                }
+                assembler.divide(0, 1, prime as usize, &then)
+            }
+            Instruction::Halt => {
+                assembler.program[starts[at]] = Instruction::Halt;
+                continue;
+            }
+        };
+        assembler.program[starts[at]] = Instruction::Dec(1, code, code);
+    }
+    Ok((
+        CounterMachine {
+            counters: 2,
+            program: assembler.program,
+        },
+        starts,
+    ))
+}
+
+// What counter 0 of the 2-counter machine holds for these counters, if it
+// fits in 64 bits
+pub fn godel_number(counters: &[u64]) -> Option<u64> {
+    counters
+        .iter()
+        .zip(PRIMES)
+        .try_fold(1u64, |number, (&c, prime)| {
+            number.checked_mul(prime.checked_pow(c.try_into().ok()?)?)
+        })
+}
+
+// A Turing machine over 4 symbols that runs a counter machine. The tape
+// holds each counter in unary, 1s after a separator 2, with one more
+// separator at the end, and each instruction starts on the first separator.
+// Adding to a counter writes a 1 at the start of its block and shifts the
+// rest of the tape right, and taking from it marks its first 1 with a 3 and
+// shifts the rest of the tape from the end back left over the mark. Returns
+// the state each instruction starts in as well, with the state past the
+// last halting.
+pub fn counters_to_turing(machine: &CounterMachine) -> (WideMachine, Vec<usize>) {
+    const SYMBOLS: usize = 4;
+    let program = &machine.program;
+    // Each instruction seeks the separator before its counter, acts, and
+    // carries the displaced symbol or shifts the tape, then returns
+    let sizes: Vec<usize> = program
+        .iter()
+        .map(|instruction| match instruction {
+            Instruction::Inc(counter, _) => counter + 1 + 1 + 2,
+            Instruction::Dec(counter, _, _) => counter + 1 + 1 + 2 + 2,
+            Instruction::Halt => 0,
+        })
+        .collect();
+    let mut first = Vec::with_capacity(program.len());
+    let mut states = 0;
+    for size in &sizes {
+        first.push(states);
+        states += size + 1;
+    }
+    let halt = states;
+    let starts: Vec<usize> = program
+        .iter()
+        .zip(&first)
+        .map(|(instruction, &first)| match instruction {
+            Instruction::Halt => halt,
+            _ => first,
+        })
+        .collect();
+    // Returning to the first separator, then going to the instruction
+    let back = |at: usize| match program[at] {
+        Instruction::Halt => halt,
+        _ => first[at] + sizes[at],
+    };
+    let mut transitions = vec![None; states * SYMBOLS];
+    let mut set = |state: usize, cell: usize, print: usize, right: bool, next: usize| {
+        transitions[state * SYMBOLS + cell] = Some(WideStep {
+            print: print as u8,
+            right,
+            next,
+        })
+    };
+    for (at, instruction) in program.iter().enumerate() {
+        let counter = match *instruction {
+            Instruction::Inc(counter, _) | Instruction::Dec(counter, _, _) => counter,
+            Instruction::Halt => continue,
+        };
+        let seek = first[at];
+        let act = seek + counter + 1;
+        for skipped in 0..=counter {
+            set(seek + skipped, 1, 1, true, seek + skipped);
+            set(seek + skipped, 2, 2, true, seek + skipped + 1);
+        }
+        match *instruction {
+            Instruction::Inc(_, next) => {
+                // Carrying a 1 or a 2 to the right
+                let carry = |cell: usize| act + cell;
+                for cell in 1..3 {
+                    set(act, cell, 1, true, carry(cell));
+                    for read in 1..3 {
+                        set(carry(cell), read, cell, true, carry(read));
+                    }
+                    set(carry(cell), 0, cell, false, back(next));
+                }
+            }
+            Instruction::Dec(_, next, zero) => {
+                let to_end = act + 1;
+                let last = act + 2;
+                // Carrying a 1 or a 2 to the left
+                let carry = |cell: usize| act + 2 + cell;
+                set(act, 2, 2, false, back(zero));
+                set(act, 1, 3, true, to_end);
+                for cell in 1..3 {
+                    set(to_end, cell, cell, true, to_end);
+                    set(last, cell, 0, false, carry(cell));
+                    for read in 1..3 {
+                        set(carry(cell), read, cell, false, carry(read));
+                    }
+                    set(carry(cell), 3, cell, false, back(next));
+                }
+                set(to_end, 0, 0, false, last);
+            }
+            Instruction::Halt => unreachable!(),
+        }
+    }
+    for (at, &size) in sizes.iter().enumerate() {
+        if size == 0 {
+            continue;
+        }
+        let state = first[at] + size;
+        for cell in 1..SYMBOLS {
+            set(state, cell, cell, false, state);
+        }
+        set(state, 0, 0, true, starts[at]);
+    }
+    (
+        WideMachine {
+            symbols: SYMBOLS,
+            transitions,
+        },
+        starts,
+    )
+}
+
+// The blank tape for a counter machine started on it: a separator for each
+// counter and one at the end
+pub fn counter_tape(counters: usize) -> VecDeque<u8> {
+    VecDeque::from(vec![2; counters + 1])
+}
+
+// The counters on a tape written by `counters_to_turing`
+pub fn read_counters(tape: &VecDeque<u8>) -> Vec<u64> {
+    let mut counters = Vec::new();
+    for &cell in tape {
+        match cell {
+            2 => counters.push(0),
+            1 | 3 => *counters.last_mut().unwrap() += 1,
+            _ => {}
+        }
+    }
+    counters.pop();
+    counters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Runs the machine and its tag system to the halt, checking the word
+    // stands for the machine's configuration at the start of each step, and
+    // returns the machine's steps and the tag steps they took
+    fn through_tag_system<const N: usize>(machine: &str) -> (u64, u64) {
+        let machine: PartialMachine<N> = machine.parse().unwrap();
+        let encoding = TagEncoding::new(&machine);
+        let mut tm = machine.to_machine();
+        let mut tape = Tape::<u64>::new();
+        let mut word = encoding.start();
+        let (mut steps, mut tag_steps) = (0, 0);
+        loop {
+            let configuration = encoding.decode(&word).unwrap();
+            let state = match tm.state {
+                Index(state) => Some(state),
+                HALT => None,
+            };
+            assert_eq!(configuration.state, state, "state after {} steps", steps);
+            assert_eq!(
+                split_tape(&tape),
+                Some((configuration.m, configuration.n)),
+                "tape after {} steps",
+                steps
+            );
+            let Some(state) = state else {
+                return (steps, tag_steps);
+            };
+            loop {
+                assert!(encoding.system.step(&mut word), "halted at step {}", steps);
+                tag_steps += 1;
+                if encoding.decode(&word).is_some() {
+                    break;
+                }
+            }
+            tm.step(&mut tape, state);
+            steps += 1;
+        }
+    }
+
+    // Runs the machine's tag system under the machine `tag_to_turing` makes
+    // of it to the halt, checking the tape holds the word after each tag
+    // step, and returns the tag steps
+    fn through_turing_machine<const N: usize>(machine: &str) -> u64 {
+        let machine: PartialMachine<N> = machine.parse().unwrap();
+        let encoding = TagEncoding::new(&machine);
+        let turing = tag_to_turing(&encoding.system).unwrap();
+        let mut word = encoding.start();
+        let mut run = WideRun::new(&turing, word.iter().map(|&s| s as u8 + 1).collect());
+        let mut tag_steps = 0;
+        loop {
+            let on_tape: Vec<usize> = run
+                .tape
+                .iter()
+                .filter(|&&cell| cell != 0)
+                .map(|&cell| cell as usize - 1)
+                .collect();
+            assert_eq!(on_tape, Vec::from(word.clone()), "after {} tag steps", tag_steps);
+            if run.halted() {
+                return tag_steps;
+            }
+            let going = encoding.system.step(&mut word);
+            loop {
+                run.step().unwrap();
+                if run.state == 0 || run.halted() {
+                    break;
+                }
+            }
+            assert_eq!(going, !run.halted(), "after {} tag steps", tag_steps);
+            tag_steps += going as u64;
+        }
+    }
+
+    #[test]
+    fn busy_beavers_through_tag_systems_and_back() {
+        let (steps, tag_steps) = through_tag_system::<2>("1RB1LB_1LA1RZ");
+        assert_eq!(steps, 6);
+        assert_eq!(through_turing_machine::<2>("1RB1LB_1LA1RZ"), tag_steps);
+
+        let (steps, tag_steps) = through_tag_system::<3>("1RB1RZ_1LB0RC_1LC1LA");
+        assert_eq!(steps, 21);
+        assert_eq!(through_turing_machine::<3>("1RB1RZ_1LB0RC_1LC1LA"), tag_steps);
+    }
+}
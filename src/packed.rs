@@ -0,0 +1,237 @@
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+
+use crate::enumerate::PartialMachine;
+use crate::Bit::*;
+use crate::State::*;
+use crate::TapeMotion::*;
+use crate::TuringStep;
+
+// A compact binary format for machines, a byte per transition: 0 if it's
+// undefined, or else 0x80, with 0x40 set for writing a one, 0x20 for moving
+// left, and the next state from 0 for A in the low five bits, with 31 for
+// the halt. Transitions go A0 A1 B0 B1 and so on, so a machine is twice as
+// many bytes as it has states, and up to 30 states fit.
+//
+// On its own, a machine is
+//
+//     "TM", the version, the number of states,
+//     the transitions, and the CRC-32 of everything before it
+//
+// with the checksum little endian. A container of many, for collections of
+// millions, has a header of
+//
+//     "TMBC", the version, the number of states, two zero bytes,
+//     the number of machines as a u64 and the CRC-32 of the records as a u32
+//
+// all little endian, followed by the transitions of each machine with
+// nothing between them. Every record is the same size, so a machine can be
+// found by its index without reading those before it, and the whole file
+// can be memory mapped and scanned in place with `Container`.
+pub const VERSION: u8 = 1;
+pub const CONTAINER_HEADER_LEN: usize = 20;
+const HALT_CODE: u8 = 31;
+
+// CRC-32 as in zlib and PNG, carried on over more data
+fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    data.iter().fold(crc, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            (crc >> 1) ^ (0xedb88320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    !crc32_update(!0, data)
+}
+
+pub fn encode_transition(step: &Option<TuringStep>) -> u8 {
+    let Some(step) = step else {
+        return 0;
+    };
+    let next = match step.next_state {
+        Index(next) => next as u8,
+        HALT => HALT_CODE,
+    };
+    0x80 | (step.print as u8) << 6 | (matches!(step.motion, Left) as u8) << 5 | next
+}
+
+pub fn decode_transition(byte: u8, states: usize) -> Result<Option<TuringStep>, String> {
+    if byte == 0 {
+        return Ok(None);
+    }
+    let next = byte & 0x1f;
+    if byte & 0x80 == 0 || (next != HALT_CODE && next as usize >= states) {
+        return Err(format!("bad transition {:#04x}", byte));
+    }
+    Ok(Some(TuringStep {
+        print: if byte & 0x40 == 0 { Zero } else { One },
+        motion: if byte & 0x20 == 0 { Right } else { Left },
+        next_state: match next {
+            HALT_CODE => HALT,
+            next => Index(next as usize),
+        },
+    }))
+}
+
+fn check_states(states: usize) -> Result<(), String> {
+    match states {
+        1..=30 => Ok(()),
+        _ => Err(format!("{} states don't fit in the format", states)),
+    }
+}
+
+// A machine's transitions, as in a container's records
+pub fn encode_record<const N: usize>(machine: &PartialMachine<N>, record: &mut Vec<u8>) {
+    record.extend(machine.transitions.iter().flatten().map(encode_transition));
+}
+
+pub fn decode_record<const N: usize>(record: &[u8]) -> Result<PartialMachine<N>, String> {
+    if record.len() != 2 * N {
+        return Err(format!(
+            "{} bytes aren't a machine with {} states",
+            record.len(),
+            N
+        ));
+    }
+    let mut transitions = [[None; 2]; N];
+    for (slot, &byte) in record.iter().enumerate() {
+        transitions[slot / 2][slot % 2] = decode_transition(byte, N)?;
+    }
+    Ok(PartialMachine { transitions })
+}
+
+pub fn encode<const N: usize>(machine: &PartialMachine<N>) -> Vec<u8> {
+    let mut bytes = vec![b'T', b'M', VERSION, N as u8];
+    encode_record(machine, &mut bytes);
+    let checksum = crc32(&bytes);
+    bytes.extend(checksum.to_le_bytes());
+    bytes
+}
+
+// The number of states of an encoded machine, to choose what to decode it as
+pub fn encoded_states(bytes: &[u8]) -> Result<usize, String> {
+    match bytes {
+        [b'T', b'M', VERSION, states, ..] => Ok(*states as usize),
+        [b'T', b'M', version, ..] => Err(format!("unknown version {}", version)),
+        _ => Err(String::from("not an encoded machine")),
+    }
+}
+
+pub fn decode<const N: usize>(bytes: &[u8]) -> Result<PartialMachine<N>, String> {
+    let states = encoded_states(bytes)?;
+    if states != N {
+        return Err(format!("the machine has {} states, not {}", states, N));
+    }
+    if bytes.len() != 2 * N + 8 {
+        return Err(format!("{} bytes is the wrong length", bytes.len()));
+    }
+    let (body, checksum) = bytes.split_at(bytes.len() - 4);
+    if crc32(body) != u32::from_le_bytes(checksum.try_into().unwrap()) {
+        return Err(String::from("the checksum doesn't match"));
+    }
+    decode_record(&body[4..])
+}
+
+// Writes a container of the machines. The header is written last, once the
+// count and checksum are known, so the output has to be seekable.
+pub fn write_container<const N: usize>(
+    out: &mut (impl Write + Seek),
+    machines: impl IntoIterator<Item = PartialMachine<N>>,
+) -> Result<u64, String> {
+    check_states(N)?;
+    let write_error = |e: std::io::Error| e.to_string();
+    let start = out.stream_position().map_err(write_error)?;
+    out.write_all(&[0; CONTAINER_HEADER_LEN])
+        .map_err(write_error)?;
+    let mut count = 0u64;
+    let mut crc = !0u32;
+    let mut record = Vec::with_capacity(2 * N);
+    for machine in machines {
+        record.clear();
+        encode_record(&machine, &mut record);
+        crc = crc32_update(crc, &record);
+        out.write_all(&record).map_err(write_error)?;
+        count += 1;
+    }
+    let mut header = Vec::with_capacity(CONTAINER_HEADER_LEN);
+    header.extend(b"TMBC");
+    header.extend([VERSION, N as u8, 0, 0]);
+    header.extend(count.to_le_bytes());
+    header.extend((!crc).to_le_bytes());
+    let end = out.stream_position().map_err(write_error)?;
+    out.seek(SeekFrom::Start(start)).map_err(write_error)?;
+    out.write_all(&header).map_err(write_error)?;
+    out.seek(SeekFrom::Start(end)).map_err(write_error)?;
+    out.flush().map_err(write_error)?;
+    Ok(count)
+}
+
+// A container in memory, or mapped into it, read in place
+pub struct Container<'a> {
+    pub states: usize,
+    pub len: u64,
+    checksum: u32,
+    records: &'a [u8],
+}
+
+impl<'a> Container<'a> {
+    // Checks the header and the size, but not the checksum, which means
+    // reading every record, see `verify`
+    pub fn new(bytes: &'a [u8]) -> Result<Container<'a>, String> {
+        if bytes.len() < CONTAINER_HEADER_LEN || &bytes[0..4] != b"TMBC" {
+            return Err(String::from("not a container of machines"));
+        }
+        if bytes[4] != VERSION {
+            return Err(format!("unknown version {}", bytes[4]));
+        }
+        let states = bytes[5] as usize;
+        check_states(states)?;
+        let len = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
+        let checksum = u32::from_le_bytes(bytes[16..20].try_into().unwrap());
+        let records = &bytes[CONTAINER_HEADER_LEN..];
+        if len.checked_mul(2 * states as u64) != Some(records.len() as u64) {
+            return Err(format!(
+                "{} bytes of records aren't {} machines with {} states",
+                records.len(),
+                len,
+                states
+            ));
+        }
+        Ok(Container {
+            states,
+            len,
+            checksum,
+            records,
+        })
+    }
+
+    pub fn verify(&self) -> Result<(), String> {
+        match crc32(self.records) == self.checksum {
+            true => Ok(()),
+            false => Err(String::from("the checksum doesn't match")),
+        }
+    }
+
+    // A machine's transitions, undecoded
+    pub fn record(&self, index: u64) -> Option<&'a [u8]> {
+        let size = 2 * self.states;
+        let at = usize::try_from(index).ok().filter(|_| index < self.len)? * size;
+        Some(&self.records[at..at + size])
+    }
+
+    pub fn get<const N: usize>(&self, index: u64) -> Result<PartialMachine<N>, String> {
+        let record = self
+            .record(index)
+            .ok_or(format!("no machine {}, there are {}", index, self.len))?;
+        decode_record(record).map_err(|e| format!("machine {}: {}", index, e))
+    }
+
+    // Every machine in order, paired with its index
+    pub fn iter<const N: usize>(
+        &self,
+    ) -> impl Iterator<Item = Result<(u64, PartialMachine<N>), String>> + '_ {
+        (0..self.len).map(move |index| self.get(index).map(|machine| (index, machine)))
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+
+use crate::enumerate::Tnf;
+use crate::search::simulate;
+use crate::search::Batch;
+use crate::search::Outcome;
+
+pub struct Parallel {
+    pub threads: usize,
+    // Step limit each worker gives every machine it simulates
+    pub limit: u64,
+    // Tallied by the workers as they go, see `Counters`
+    pub counters: Arc<Counters>,
+}
+
+// Running totals the workers add to as they simulate, for showing progress
+// while a search is going. Results reach the calling thread in enumeration
+// order, so a slow subtree early on holds back everything finished after it,
+// and counting there would show the search stalled when it isn't. These are
+// counted where the work is done instead, with relaxed atomics rather than
+// a lock the workers would queue up behind, and read now and then by
+// whoever is showing them. Nothing is ordered by them, so a read can be a
+// moment behind some workers, but never by more than one batch of theirs.
+#[derive(Default)]
+pub struct Counters {
+    machines: AtomicU64,
+    steps: AtomicU64,
+    // New bests seen by any worker, which can be more than the leaderboard
+    // ends up with, since workers find them out of order
+    champions: AtomicU64,
+    best_sigma: AtomicU64,
+    best_steps: AtomicU64,
+}
+
+// What the counters added up to when they were read
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Tally {
+    pub machines: u64,
+    pub steps: u64,
+    pub champions: u64,
+}
+
+// Machines a worker counts up on its own before adding them to the shared
+// counters, so that they aren't bouncing a cache line between cores on every
+// machine
+const BATCH: u64 = 4096;
+
+impl Counters {
+    pub fn read(&self) -> Tally {
+        Tally {
+            machines: self.machines.load(Relaxed),
+            steps: self.steps.load(Relaxed),
+            champions: self.champions.load(Relaxed),
+        }
+    }
+
+    fn add(&self, tally: &mut Tally) {
+        self.machines.fetch_add(tally.machines, Relaxed);
+        self.steps.fetch_add(tally.steps, Relaxed);
+        *tally = Tally::default();
+    }
+
+    // Counts a record as soon as a worker beats the best so far, which
+    // unlike the other totals can't wait for the end of a batch. Workers
+    // only offer what beats their own best, which few machines do, so this
+    // is rare enough not to need batching
+    fn offer(&self, sigma: u64, steps: u64) {
+        if self.best_sigma.fetch_max(sigma, Relaxed) < sigma {
+            self.champions.fetch_add(1, Relaxed);
+        }
+        if self.best_steps.fetch_max(steps, Relaxed) < steps {
+            self.champions.fetch_add(1, Relaxed);
+        }
+    }
+}
+
+// Every worker owns a deque of subtrees. It works from the front of its own
+// and, once that runs dry, steals from the back of someone else's.
+fn next_task<const N: usize>(
+    deques: &[Mutex<VecDeque<(usize, Tnf<N>)>>],
+    me: usize,
+) -> Option<(usize, Tnf<N>)> {
+    if let Some(task) = deques[me].lock().unwrap().pop_front() {
+        return Some(task);
+    }
+    (1..deques.len())
+        .map(|offset| (me + offset) % deques.len())
+        .find_map(|victim| deques[victim].lock().unwrap().pop_back())
+}
+
+// Enumerates and simulates subtrees of the table enumeration (see
+// `Tnf::subtrees`) on several threads. Each subtree is handed to `emit` on
+// the calling thread as a `Batch`, in exactly the order running them one
+// after another would give, whatever order they finish in, along with the
+// position of its first machine. Only the summary of a subtree is kept
+// while it waits for those before it, not its machines, so a search holds
+// on to little more than a few records per subtree however big the
+// subtrees are.
+pub fn enumerate_parallel<const N: usize>(
+    subtrees: Vec<Tnf<N>>,
+    config: &Parallel,
+    mut emit: impl FnMut(u64, Batch<N>),
+) {
+    let threads = config.threads.max(1);
+    let deques: Vec<Mutex<VecDeque<(usize, Tnf<N>)>>> =
+        (0..threads).map(|_| Mutex::new(VecDeque::new())).collect();
+    // Dealt out round robin so that the early subtrees, which the output is
+    // waiting on, are at the front of every deque
+    for (i, subtree) in subtrees.into_iter().enumerate() {
+        deques[i % threads].lock().unwrap().push_back((i, subtree));
+    }
+
+    let (sender, receiver) = mpsc::channel::<(usize, Batch<N>)>();
+    thread::scope(|scope| {
+        for me in 0..threads {
+            let sender = sender.clone();
+            let deques = &deques;
+            let limit = config.limit;
+            let counters = &*config.counters;
+            scope.spawn(move || {
+                let mut tally = Tally::default();
+                // This worker's best sigma and steps
+                let mut best = (0, 0);
+                while let Some((i, subtree)) = next_task(deques, me) {
+                    let mut batch = Batch::new();
+                    for machine in subtree {
+                        let outcome = simulate(&machine, limit);
+                        tally.machines += 1;
+                        tally.steps += match outcome {
+                            Outcome::Halted { steps, sigma } => {
+                                let steps = u64::try_from(steps).unwrap_or(u64::MAX);
+                                if sigma > best.0 || steps > best.1 {
+                                    best = (best.0.max(sigma), best.1.max(steps));
+                                    counters.offer(sigma, steps);
+                                }
+                                steps
+                            }
+                            Outcome::Undecided => limit,
+                        };
+                        if tally.machines == BATCH {
+                            counters.add(&mut tally);
+                        }
+                        batch.push(&machine, outcome);
+                    }
+                    counters.add(&mut tally);
+                    if sender.send((i, batch)).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        // Hold on to finished subtrees until everything before them is out
+        let mut pending = BTreeMap::new();
+        let mut next = 0;
+        let mut index = 0;
+        for (i, batch) in receiver {
+            pending.insert(i, batch);
+            while let Some(batch) = pending.remove(&next) {
+                let machines = batch.machines;
+                emit(index, batch);
+                index += machines;
+                next += 1;
+            }
+        }
+    });
+}
@@ -0,0 +1,183 @@
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::enumerate::Tnf;
+use crate::parallel::Counters;
+use crate::search::Champion;
+use crate::search::Batch;
+
+// Keeps track of how an enumeration is getting on, and every `interval`
+// shows it on stderr and appends it to the log, if there is one, as a tab
+// separated line of seconds elapsed, machines, halted, undecided, subtrees
+// done, subtrees in all, best sigma and best steps.
+//
+// How far along it is comes from counting the subtrees it was split into
+// that have been finished, which is rough since they vary a lot in size.
+pub struct Progress<const N: usize> {
+    subtrees: Vec<Tnf<N>>,
+    // The subtree the latest machine came from
+    current: usize,
+    machines: u64,
+    halted: u64,
+    undecided: u64,
+    // Machines by how many transitions they define
+    defined: [u64; 13],
+    started: Instant,
+    interval: Duration,
+    shown: Instant,
+    log: Option<File>,
+    // The workers' own totals, for a search run on several threads
+    counters: Option<Arc<Counters>>,
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds {
+        0..60 => format!("{}s", seconds),
+        60..3600 => format!("{}m {}s", seconds / 60, seconds % 60),
+        _ => format!("{}h {}m", seconds / 3600, seconds / 60 % 60),
+    }
+}
+
+impl<const N: usize> Progress<N> {
+    pub fn new(
+        subtrees: &[Tnf<N>],
+        interval: Duration,
+        log: Option<&Path>,
+    ) -> std::io::Result<Progress<N>> {
+        let log = match log {
+            Some(path) => Some(OpenOptions::new().create(true).append(true).open(path)?),
+            None => None,
+        };
+        Ok(Progress {
+            subtrees: subtrees.to_vec(),
+            current: 0,
+            machines: 0,
+            halted: 0,
+            undecided: 0,
+            defined: [0; 13],
+            started: Instant::now(),
+            interval,
+            shown: Instant::now(),
+            log,
+            counters: None,
+        })
+    }
+
+    // Also shows what the workers of a parallel search have done, which can
+    // be well ahead of the machines that have been passed on in order
+    pub fn watch(&mut self, counters: Arc<Counters>) {
+        self.counters = Some(counters);
+    }
+
+    // Called with each batch of machines in turn, along with the champions
+    // so far
+    pub fn passed(
+        &mut self,
+        batch: &Batch<N>,
+        best_sigma: Option<&Champion>,
+        best_steps: Option<&Champion>,
+    ) -> std::io::Result<()> {
+        let Some(last) = &batch.last else {
+            return Ok(());
+        };
+        while self.current + 1 < self.subtrees.len() && !self.subtrees[self.current].holds(last) {
+            self.current += 1;
+        }
+        self.machines += batch.machines;
+        self.halted += batch.halted;
+        self.undecided += batch.machines - batch.halted;
+        for (count, defined) in self.defined.iter_mut().zip(batch.defined) {
+            *count += defined;
+        }
+        if self.shown.elapsed() >= self.interval {
+            self.show(best_sigma, best_steps)?;
+            self.shown = Instant::now();
+        }
+        Ok(())
+    }
+
+    // Shows the end result, everything done
+    pub fn finish(
+        &mut self,
+        best_sigma: Option<&Champion>,
+        best_steps: Option<&Champion>,
+    ) -> std::io::Result<()> {
+        self.current = self.subtrees.len();
+        self.show(best_sigma, best_steps)
+    }
+
+    fn show(
+        &mut self,
+        best_sigma: Option<&Champion>,
+        best_steps: Option<&Champion>,
+    ) -> std::io::Result<()> {
+        let elapsed = self.started.elapsed();
+        let done = self.current as f64 / self.subtrees.len() as f64;
+        let left = match done > 0.0 {
+            true => format!(
+                ", about {} left",
+                format_duration(elapsed.mul_f64((1.0 - done) / done))
+            ),
+            false => String::new(),
+        };
+        eprintln!(
+            "{} machines in {} ({:.0}/s), {} of {} subtrees done{}",
+            self.machines,
+            format_duration(elapsed),
+            self.machines as f64 / elapsed.as_secs_f64().max(1e-9),
+            self.current,
+            self.subtrees.len(),
+            left
+        );
+        eprintln!(
+            "    {} halted, {} still running at the step limit",
+            self.halted, self.undecided
+        );
+        let depths: Vec<String> = (0..=2 * N)
+            .filter(|&defined| self.defined[defined] > 0)
+            .map(|defined| format!("{}: {}", defined, self.defined[defined]))
+            .collect();
+        eprintln!("    by transitions defined: {}", depths.join(", "));
+        if let Some(counters) = &self.counters {
+            let tally = counters.read();
+            eprintln!(
+                "    workers: {} machines simulated ({:.0}/s), {} steps, {} records",
+                tally.machines,
+                tally.machines as f64 / elapsed.as_secs_f64().max(1e-9),
+                tally.steps,
+                tally.champions
+            );
+        }
+        let show = |champion: Option<&Champion>| match champion {
+            Some(champion) => format!(
+                "{} steps and {} ones by {}",
+                champion.steps, champion.sigma, champion.machine
+            ),
+            None => String::from("none yet"),
+        };
+        eprintln!("    best sigma: {}", show(best_sigma));
+        eprintln!("    best steps: {}", show(best_steps));
+
+        if let Some(log) = &mut self.log {
+            writeln!(
+                log,
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                elapsed.as_secs(),
+                self.machines,
+                self.halted,
+                self.undecided,
+                self.current,
+                self.subtrees.len(),
+                best_sigma.map_or(0, |champion| champion.sigma),
+                best_steps.map_or(0, |champion| champion.steps)
+            )?;
+        }
+        Ok(())
+    }
+}
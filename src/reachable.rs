@@ -0,0 +1,107 @@
+use std::mem::size_of;
+
+use num_traits::PrimInt;
+use num_traits::Unsigned;
+
+use crate::CompiledStep;
+use crate::CompiledTuringMachine;
+use crate::State::*;
+use crate::TapeMotion::*;
+use crate::TuringMachine;
+
+// Which LUT entries a run from a blank tape can look up, found before
+// building any of the rest. An entry is a state and the side a word is
+// entered from, paired with the word's contents. A word holds zero until
+// the head first reaches it, and after that whatever the last entry to
+// leave it wrote, so every word ever entered is zero or the output of some
+// reachable entry, and every state and side it's entered with is where some
+// reachable entry left off. Closing those two sets over each other from the
+// start gives every entry the run could need, and usually far fewer than
+// all of them. The rest of the table is left blank, so it stays the same
+// size and lookups stay a mask and a load.
+//
+// Nothing is said about where on the tape each word is, so this takes in
+// more than the run can reach, never less.
+pub struct Reachability {
+    // Entries compiled
+    pub reachable: usize,
+    // Entries a full compile would build
+    pub total: usize,
+}
+
+impl Reachability {
+    // How many times fewer entries were built than a full compile's
+    pub fn ratio(&self) -> f64 {
+        self.total as f64 / self.reachable.max(1) as f64
+    }
+}
+
+impl<const N: usize> TuringMachine<N> {
+    // Compiles only the entries reachable from the current state on a blank
+    // tape, with the head where `Tape::new` puts it. Runs from anywhere else
+    // may look up entries that were never built.
+    pub fn compile_reachable<T: Unsigned + PrimInt>(
+        mut self,
+    ) -> (CompiledTuringMachine<T, N>, Reachability) {
+        assert!(N < i8::MAX as usize);
+        let start = self.state;
+        let bits: usize = size_of::<T>() * 8;
+        let mut lut: Vec<CompiledStep<T>> = vec![
+            CompiledStep {
+                tape: T::zero(),
+                direction_state: 0,
+            };
+            (N * 2).next_power_of_two() << bits
+        ];
+        // Entries are indexed as in `compile`, by (state << 1 | side) << bits
+        // | word, where the state and side are what's called an entry here
+        let mut entries = Vec::new();
+        let mut words = vec![0];
+        let mut seen_entries = vec![false; N * 2];
+        let mut seen_words = vec![false; 1 << bits];
+        seen_words[0] = true;
+        let mut queue = Vec::new();
+        // The head starts on the leftmost cell of the first word
+        if let Index(state) = start {
+            seen_entries[state << 1] = true;
+            entries.push(state << 1);
+            queue.push(state << (bits + 1));
+        }
+        let mut lengths = vec![0; lut.len()];
+        let mut reachable = 0;
+        while let Some(i) = queue.pop() {
+            let step;
+            (step, lengths[i]) = self.compile_entry::<T>(i);
+            lut[i] = step;
+            reachable += 1;
+            if step.get_state() < 0 {
+                continue;
+            }
+            let entry = (step.get_state() as usize) << 1 | (step.get_direction() == Right) as usize;
+            if !seen_entries[entry] {
+                seen_entries[entry] = true;
+                entries.push(entry);
+                queue.extend(words.iter().map(|word| entry << bits | word));
+            }
+            let word = step.tape.to_usize().unwrap();
+            if !seen_words[word] {
+                seen_words[word] = true;
+                words.push(word);
+                queue.extend(entries.iter().map(|entry| entry << bits | word));
+            }
+        }
+
+        self.state = start;
+        (
+            CompiledTuringMachine {
+                tm: self,
+                lut,
+                lengths,
+            },
+            Reachability {
+                reachable,
+                total: (N * 2) << bits,
+            },
+        )
+    }
+}
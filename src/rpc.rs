@@ -0,0 +1,128 @@
+use std::io::BufRead;
+use std::io::Write;
+
+use crate::json::Json;
+
+// JSON-RPC 2.0, framed the way the Language Server Protocol frames it: each
+// message is a Content-Length header, a blank line, and that many bytes of
+// JSON. Requests get an answer with the same id, notifications (requests
+// without one) don't, and an "exit" notification ends the session as the
+// end of the input does.
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+
+// The longest message read, far longer than any request needs, so that a
+// Content-Length can't have the whole of memory set aside for it
+const MAX_LENGTH: usize = 1 << 20;
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> RpcError {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+// The next message, or None at the end of the input
+fn read_message(input: &mut impl BufRead) -> Result<Option<String>, String> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+            return match length {
+                None => Ok(None),
+                Some(_) => Err(String::from("the input ends in a header")),
+            };
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            match length {
+                Some(_) => break,
+                // Blank lines between messages
+                None => continue,
+            }
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(format!("bad header {:?}", line));
+        };
+        if name.eq_ignore_ascii_case("content-length") {
+            length = Some(
+                value
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| format!("bad header {:?}", line))?,
+            );
+        }
+    }
+    let length = length.unwrap();
+    if length > MAX_LENGTH {
+        return Err(format!(
+            "a message of {} bytes is longer than the limit of {}",
+            length, MAX_LENGTH
+        ));
+    }
+    let mut body = vec![0; length];
+    input.read_exact(&mut body).map_err(|e| e.to_string())?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|_| String::from("a message is not UTF-8"))
+}
+
+fn write_message(output: &mut impl Write, message: &Json) -> Result<(), String> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)
+        .and_then(|_| output.flush())
+        .map_err(|e| e.to_string())
+}
+
+// Answers requests with `handle`, which is given the method and its params
+pub fn serve(
+    mut input: impl BufRead,
+    mut output: impl Write,
+    mut handle: impl FnMut(&str, &Json) -> Result<Json, RpcError>,
+) -> Result<(), String> {
+    while let Some(message) = read_message(&mut input)? {
+        let request = match Json::parse(&message) {
+            Ok(request) => request,
+            Err(e) => {
+                write_message(
+                    &mut output,
+                    &answer(Json::Null, Err(RpcError::new(PARSE_ERROR, e))),
+                )?;
+                continue;
+            }
+        };
+        let id = request.get("id").cloned();
+        let result = match request.get("method").and_then(Json::as_str) {
+            Some("exit") => return Ok(()),
+            Some(method) => handle(method, request.get("params").unwrap_or(&Json::Null)),
+            None => Err(RpcError::new(INVALID_REQUEST, "no method")),
+        };
+        if let Some(id) = id {
+            write_message(&mut output, &answer(id, result))?;
+        }
+    }
+    Ok(())
+}
+
+fn answer(id: Json, result: Result<Json, RpcError>) -> Json {
+    let (key, value) = match result {
+        Ok(result) => ("result", result),
+        Err(error) => (
+            "error",
+            Json::object([
+                ("code", Json::number(error.code)),
+                ("message", Json::string(error.message)),
+            ]),
+        ),
+    };
+    Json::object([("jsonrpc", Json::string("2.0")), ("id", id), (key, value)])
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use crate::decider::Decider;
+use crate::decider::Proof;
+use crate::decider::Verdict;
+use crate::enumerate::PartialMachine;
+use crate::State::*;
+use crate::TapeMotion::*;
+use crate::TuringStep;
+
+// Proves non-halting with a repeated word list: the tape is cut into words
+// of `block` cells, and each side of the head is kept as a list of words
+// with how many times each repeats, like (011)^5 (1)^2. Counts from
+// `threshold` on are all treated as one, "threshold or more", which makes
+// the number of such tapes finite when the structure of the tape is, no
+// matter how long it gets. Taking one word off a count of that kind leaves
+// either one fewer than the threshold or still the threshold or more, so
+// both are explored. If every configuration reachable this way from the
+// blank tape is found without any of them halting, the machine can't halt,
+// since each of its real configurations is among them.
+//
+// The head always sits between words facing one of them, takes it off its
+// list, and runs within it until it leaves by one side or the other. Blank
+// tape is an empty list, so a blank word is never put at the end of one.
+pub struct RepeatedWordList {
+    // The largest number of cells in a word to try
+    pub max_block: usize,
+    pub threshold: u32,
+    // How many configurations to allow at each word size before giving up
+    pub max_configs: usize,
+}
+
+// Words are kept as bits, the leftmost cell in the lowest. The last entry
+// of either list is the one next to the head.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Config {
+    state: usize,
+    facing_right: bool,
+    left: Vec<(u32, u32)>,
+    right: Vec<(u32, u32)>,
+}
+
+enum Crossing {
+    // Leaves the word in this state having written it over, by the right
+    // side if true
+    Leaves(usize, u32, bool),
+    // Runs around inside the word forever
+    Stays,
+    Halts,
+}
+
+// Runs the machine on one word, entering it from the left or the right
+fn cross<const N: usize>(
+    machine: &PartialMachine<N>,
+    block: usize,
+    mut state: usize,
+    mut word: u32,
+    from_left: bool,
+) -> Crossing {
+    let mut head = if from_left { 0 } else { block as i64 - 1 };
+    // More steps than there are configurations within the word means one
+    // of them has come round again
+    let mut steps = 0u64;
+    let configurations = ((N * block) as u64) << block;
+    while (0..block as i64).contains(&head) {
+        if steps > configurations {
+            return Crossing::Stays;
+        }
+        let read = word >> head & 1;
+        let Some(TuringStep {
+            print,
+            motion,
+            next_state: Index(next),
+        }) = machine.transitions[state][read as usize]
+        else {
+            return Crossing::Halts;
+        };
+        word = word & !(1 << head) | (print as u32) << head;
+        state = next;
+        head += match motion {
+            Right => 1,
+            Left => -1,
+        };
+        steps += 1;
+    }
+    Crossing::Leaves(state, word, head >= 0)
+}
+
+fn push(list: &mut Vec<(u32, u32)>, word: u32, threshold: u32) {
+    match list.last_mut() {
+        None if word == 0 => {}
+        Some((top, count)) if *top == word => *count = (*count + 1).min(threshold),
+        _ => list.push((word, 1)),
+    }
+}
+
+// The word next to the head with each list that can be left behind
+fn pop(list: &[(u32, u32)], threshold: u32) -> Vec<(u32, Vec<(u32, u32)>)> {
+    let Some(&(word, count)) = list.last() else {
+        return vec![(0, Vec::new())];
+    };
+    let rest = &list[..list.len() - 1];
+    let with = |count: u32| {
+        let mut list = rest.to_vec();
+        if count > 0 {
+            list.push((word, count));
+        }
+        (word, list)
+    };
+    match count == threshold {
+        true => vec![with(threshold - 1), with(threshold)],
+        false => vec![with(count - 1)],
+    }
+}
+
+// A tape that keeps getting more words isn't going to close up, so
+// configurations with more than this give up early
+const MAX_WORDS: usize = 32;
+
+// Finds every configuration reachable from the blank tape with words of
+// `block` cells. Fails if one of them halts or there get to be more than
+// `max_configs`, or one with more than `MAX_WORDS` words.
+pub fn explore<const N: usize>(
+    machine: &PartialMachine<N>,
+    block: usize,
+    threshold: u32,
+    max_configs: usize,
+) -> Result<usize, String> {
+    if !(1..=16).contains(&block) || threshold == 0 {
+        return Err(String::from(
+            "words need 1 to 16 cells and a threshold above 0",
+        ));
+    }
+    let mut crossings = HashMap::new();
+    let start = Config {
+        state: 0,
+        facing_right: true,
+        left: Vec::new(),
+        right: Vec::new(),
+    };
+    let mut seen = HashSet::from([start.clone()]);
+    let mut pending = vec![start];
+    while let Some(config) = pending.pop() {
+        let ahead = match config.facing_right {
+            true => &config.right,
+            false => &config.left,
+        };
+        for (word, rest) in pop(ahead, threshold) {
+            let key = (config.state, word, config.facing_right);
+            let crossing = crossings
+                .entry(key)
+                .or_insert_with(|| cross(machine, block, config.state, word, config.facing_right));
+            let (state, word, exit_right) = match *crossing {
+                Crossing::Leaves(state, word, exit_right) => (state, word, exit_right),
+                Crossing::Stays => continue,
+                Crossing::Halts => return Err(String::from("a reachable configuration halts")),
+            };
+            let (mut left, mut right) = match config.facing_right {
+                true => (config.left.clone(), rest),
+                false => (rest, config.right.clone()),
+            };
+            match exit_right {
+                true => push(&mut left, word, threshold),
+                false => push(&mut right, word, threshold),
+            }
+            let next = Config {
+                state,
+                facing_right: exit_right,
+                left,
+                right,
+            };
+            if seen.insert(next.clone()) {
+                if seen.len() > max_configs {
+                    return Err(String::from("too many configurations"));
+                }
+                if next.left.len() + next.right.len() > MAX_WORDS {
+                    return Err(String::from("too many words on the tape"));
+                }
+                pending.push(next);
+            }
+        }
+    }
+    Ok(seen.len())
+}
+
+impl<const N: usize> Decider<N> for RepeatedWordList {
+    fn name(&self) -> &'static str {
+        "repeated-word-list"
+    }
+
+    fn budget(&self) -> String {
+        format!(
+            "block={},threshold={},configs={}",
+            self.max_block, self.threshold, self.max_configs
+        )
+    }
+
+    fn decide(&self, machine: &PartialMachine<N>) -> Verdict {
+        for block in 1..=self.max_block {
+            if explore(machine, block, self.threshold, self.max_configs).is_ok() {
+                return Verdict::NonHalting(Proof::RepeatedWordList {
+                    block,
+                    threshold: self.threshold,
+                });
+            }
+        }
+        Verdict::Undecided
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::decider::Pipeline;
+use crate::decider::Verdict;
+use crate::evolve::random_machine;
+use crate::evolve::Random;
+use crate::search::simulate;
+use crate::search::Outcome;
+
+// An experiment on what machines of some size typically do: `count` of
+// them, with every transition (undefined included) picked at random, are
+// run for each budget in turn until they halt. After the first budget runs
+// out, the deciders get a go at proving that a machine never halts, which
+// spares the larger budgets the machines that obviously don't.
+pub struct Sampling {
+    pub count: u64,
+    pub seed: u64,
+    // Step limits, in increasing order
+    pub budgets: Vec<u64>,
+}
+
+#[derive(Default)]
+pub struct Statistics {
+    pub machines: u64,
+    // Halting machines by the first budget they halted within
+    pub halted: Vec<u64>,
+    // Halting times by the power of two they're under, and sigma values
+    pub steps: BTreeMap<u32, u64>,
+    pub sigma: BTreeMap<u64, u64>,
+    // Machines proven never to halt, by decider
+    pub non_halting: BTreeMap<&'static str, u64>,
+    pub undecided: u64,
+    budgets: Vec<u64>,
+}
+
+pub fn sample<const N: usize>(sampling: &Sampling, pipeline: &Pipeline<N>) -> Statistics {
+    let mut random = Random(sampling.seed);
+    let mut statistics = Statistics {
+        halted: vec![0; sampling.budgets.len()],
+        budgets: sampling.budgets.clone(),
+        ..Statistics::default()
+    };
+    for _ in 0..sampling.count {
+        let machine = random_machine::<N>(&mut random);
+        statistics.machines += 1;
+        let mut settled = false;
+        for (stage, &budget) in sampling.budgets.iter().enumerate() {
+            if let Outcome::Halted { steps, sigma } = simulate(&machine, budget) {
+                statistics.halted[stage] += 1;
+                *statistics.steps.entry(steps.ilog2()).or_default() += 1;
+                *statistics.sigma.entry(sigma).or_default() += 1;
+                settled = true;
+                break;
+            }
+            if stage == 0 {
+                let decision = pipeline.decide(&machine);
+                if let (Verdict::NonHalting(_), Some(decider)) =
+                    (decision.verdict, decision.decider)
+                {
+                    *statistics.non_halting.entry(decider).or_default() += 1;
+                    settled = true;
+                    break;
+                }
+            }
+        }
+        if !settled {
+            statistics.undecided += 1;
+        }
+    }
+    statistics
+}
+
+impl Display for Statistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let share = |n: u64| 100.0 * n as f64 / self.machines.max(1) as f64;
+        let halted: u64 = self.halted.iter().sum();
+        let non_halting: u64 = self.non_halting.values().sum();
+        f.write_fmt(format_args!(
+            "{:>10}  halted ({:.1}%)\n",
+            halted,
+            share(halted)
+        ))?;
+        for (budget, count) in self.budgets.iter().zip(&self.halted) {
+            f.write_fmt(format_args!("{:>10}    within {} steps\n", count, budget))?;
+        }
+        f.write_str("            halting times\n")?;
+        for (&power, count) in &self.steps {
+            let range = match power {
+                0 => String::from("1 step"),
+                _ => format!("{} to {} steps", 1u128 << power, (2u128 << power) - 1),
+            };
+            f.write_fmt(format_args!("{:>10}    {}\n", count, range))?;
+        }
+        f.write_str("            sigma\n")?;
+        for (sigma, count) in &self.sigma {
+            f.write_fmt(format_args!("{:>10}    {}\n", count, sigma))?;
+        }
+        f.write_fmt(format_args!(
+            "{:>10}  never halt ({:.1}%)\n",
+            non_halting,
+            share(non_halting)
+        ))?;
+        for (decider, count) in &self.non_halting {
+            f.write_fmt(format_args!("{:>10}    {}\n", count, decider))?;
+        }
+        f.write_fmt(format_args!(
+            "{:>10}  undecided ({:.1}%)\n",
+            self.undecided,
+            share(self.undecided)
+        ))
+    }
+}
@@ -0,0 +1,259 @@
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use crate::enumerate::PartialMachine;
+use crate::known::is_new_record;
+use crate::known::known;
+use crate::Tape;
+
+#[derive(Clone)]
+pub struct Champion {
+    pub machine: String,
+    pub steps: u128,
+    pub sigma: u64,
+    // Position in the enumeration of the machine that set the record
+    pub found_at: u64,
+}
+
+// Keeps the best score (ones left on the tape) and the best shift count
+// (steps to halt) seen so far. Every new record is printed and appended to
+// `out` as a tab separated line: which record, machine, steps, sigma and
+// where it was found.
+pub struct Leaderboard<W: Write> {
+    pub best_sigma: Option<Champion>,
+    pub best_steps: Option<Champion>,
+    out: W,
+}
+
+// Reads back a line written by `Leaderboard::record`, giving which record
+// it is and the champion
+pub fn parse_record(line: &str) -> Option<(String, Champion)> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    match fields[..] {
+        [kind, machine, steps, sigma, found_at] => Some((
+            kind.to_string(),
+            Champion {
+                machine: machine.to_string(),
+                steps: steps.parse().ok()?,
+                sigma: sigma.parse().ok()?,
+                found_at: found_at.parse().ok()?,
+            },
+        )),
+        _ => None,
+    }
+}
+
+impl Leaderboard<File> {
+    pub fn create(path: impl AsRef<Path>) -> std::io::Result<Leaderboard<File>> {
+        Ok(Leaderboard::new(File::create(path)?))
+    }
+
+    // Opens an unfinished leaderboard to add to, starting from the records
+    // already in it
+    pub fn reopen(path: impl AsRef<Path>) -> Result<Leaderboard<File>, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut best_sigma = None;
+        let mut best_steps = None;
+        for line in text.lines() {
+            if line.starts_with("done\t") {
+                return Err(format!("{} is from a finished search", path.display()));
+            }
+            match parse_record(line) {
+                Some((kind, champion)) if kind == "sigma" => best_sigma = Some(champion),
+                Some((kind, champion)) if kind == "steps" => best_steps = Some(champion),
+                _ => return Err(format!("{}: bad line {:?}", path.display(), line)),
+            }
+        }
+        let out = OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(Leaderboard {
+            best_sigma,
+            best_steps,
+            out,
+        })
+    }
+}
+
+impl<W: Write> Leaderboard<W> {
+    pub fn new(out: W) -> Leaderboard<W> {
+        Leaderboard {
+            best_sigma: None,
+            best_steps: None,
+            out,
+        }
+    }
+
+    fn record(&mut self, kind: &str, champion: &Champion) -> std::io::Result<()> {
+        let line = format!(
+            "{}\t{}\t{}\t{}\t{}",
+            kind, champion.machine, champion.steps, champion.sigma, champion.found_at
+        );
+        // Say so on the terminal when a record reaches the known value. The
+        // file is left as it is for merging.
+        let states = champion.machine.split('_').count();
+        // Three characters to a transition
+        let symbols = champion.machine.find('_').unwrap_or(champion.machine.len()) / 3;
+        let score = match kind {
+            "sigma" => champion.sigma as u128,
+            _ => champion.steps,
+        };
+        let note = match known(states, symbols) {
+            _ if is_new_record(states, symbols, champion.sigma, champion.steps) => {
+                "\t(beyond the proven value, something is wrong)"
+            }
+            Some(known)
+                if score
+                    == match kind {
+                        "sigma" => known.sigma as u128,
+                        _ => known.steps,
+                    } =>
+            {
+                "\t(the known value)"
+            }
+            _ => "",
+        };
+        println!("{}{}", line, note);
+        writeln!(self.out, "{}", line)?;
+        // A search can be killed at any point, so don't sit on records
+        self.out.flush()
+    }
+
+    // Marks the leaderboard as complete, after `tried` machines
+    pub fn finish(&mut self, tried: u64) -> std::io::Result<()> {
+        writeln!(self.out, "done\t{}", tried)?;
+        self.out.flush()
+    }
+
+    pub fn offer(&mut self, champion: Champion) -> std::io::Result<()> {
+        if self
+            .best_sigma
+            .as_ref()
+            .is_none_or(|best| champion.sigma > best.sigma)
+        {
+            self.record("sigma", &champion)?;
+            self.best_sigma = Some(champion.clone());
+        }
+        if self
+            .best_steps
+            .as_ref()
+            .is_none_or(|best| champion.steps > best.steps)
+        {
+            self.record("steps", &champion)?;
+            self.best_steps = Some(champion);
+        }
+        Ok(())
+    }
+}
+
+// Consecutive machines of an enumeration, as much of them as the
+// leaderboard, the progress and the cursor need: how many there were, how
+// many halted and defined how many transitions, the last of them, and the
+// ones that beat every machine before them in the batch on sigma or steps,
+// with their positions in it. Those are the only ones that can set a record
+// on the leaderboard, so a worker can hand over a whole subtree as one of
+// these rather than every machine in it.
+pub struct Batch<const N: usize> {
+    pub machines: u64,
+    pub halted: u64,
+    // Machines by how many transitions they define
+    pub defined: [u64; 13],
+    pub records: Vec<(u64, PartialMachine<N>, Outcome)>,
+    pub last: Option<PartialMachine<N>>,
+    best_sigma: Option<u64>,
+    best_steps: Option<u128>,
+}
+
+impl<const N: usize> Batch<N> {
+    pub fn new() -> Batch<N> {
+        Batch {
+            machines: 0,
+            halted: 0,
+            defined: [0; 13],
+            records: Vec::new(),
+            last: None,
+            best_sigma: None,
+            best_steps: None,
+        }
+    }
+
+    pub fn of(machine: &PartialMachine<N>, outcome: Outcome) -> Batch<N> {
+        let mut batch = Batch::new();
+        batch.push(machine, outcome);
+        batch
+    }
+
+    pub fn push(&mut self, machine: &PartialMachine<N>, outcome: Outcome) {
+        if let Outcome::Halted { steps, sigma } = outcome {
+            self.halted += 1;
+            let sigma_record = self.best_sigma.is_none_or(|best| sigma > best);
+            let steps_record = self.best_steps.is_none_or(|best| steps > best);
+            if sigma_record {
+                self.best_sigma = Some(sigma);
+            }
+            if steps_record {
+                self.best_steps = Some(steps);
+            }
+            if sigma_record || steps_record {
+                self.records.push((self.machines, machine.clone(), outcome));
+            }
+        }
+        self.defined[2 * N - machine.undefined()] += 1;
+        self.machines += 1;
+        self.last = Some(machine.clone());
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Outcome {
+    Halted { steps: u128, sigma: u64 },
+    // Still running when the step limit ran out
+    Undecided,
+}
+
+// Runs the machine from a blank tape for up to `limit` steps
+pub fn simulate<const N: usize>(machine: &PartialMachine<N>, limit: u64) -> Outcome {
+    let mut tape = Tape::<u64>::new();
+    match machine.to_machine().run_limited(&mut tape, limit) {
+        Some(steps) => Outcome::Halted {
+            steps: steps as u128,
+            sigma: tape.ones(),
+        },
+        None => Outcome::Undecided,
+    }
+}
+
+impl<W: Write> Leaderboard<W> {
+    // Offers the result of the machine at position `index` of the enumeration
+    pub fn offer_outcome<const N: usize>(
+        &mut self,
+        index: u64,
+        machine: &PartialMachine<N>,
+        outcome: Outcome,
+    ) -> std::io::Result<()> {
+        match outcome {
+            Outcome::Halted { steps, sigma } => self.offer(Champion {
+                // With the undefined transitions filled in, as they were run
+                machine: machine.to_machine().to_string(),
+                steps,
+                sigma,
+                found_at: index,
+            }),
+            Outcome::Undecided => Ok(()),
+        }
+    }
+
+    // Offers the records of a batch whose first machine is at position
+    // `first` of the enumeration
+    pub fn offer_batch<const N: usize>(&mut self, first: u64, batch: &Batch<N>) -> std::io::Result<()> {
+        for (position, machine, outcome) in &batch.records {
+            self.offer_outcome(first + position, machine, *outcome)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::io::Error;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::path::Path;
+
+use crate::enumerate::PartialMachine;
+use crate::Bit::*;
+use crate::State::*;
+use crate::TapeMotion::*;
+use crate::TuringStep;
+
+// The bbchallenge seed database: the 5-state machines left undecided by its
+// initial enumeration. A 30 byte header is followed by one 30 byte record
+// per machine. Each record is the machine's ten transitions, A0 A1 B0 B1 and
+// so on, as three bytes: the symbol written, the direction (0 for right, 1
+// for left) and the next state (1 to 5 for A to E, 0 for undefined).
+pub const SEED_STATES: usize = 5;
+pub const HEADER_LEN: u64 = 30;
+pub const RECORD_LEN: u64 = 30;
+
+// The header's counts are big endian. Machines undecided because of the time
+// limit come first in the database, then those undecided because of the
+// space limit.
+pub struct SeedHeader {
+    pub undecided_time: u32,
+    pub undecided_space: u32,
+    pub total: u32,
+    pub sorted: bool,
+}
+
+pub struct SeedDatabase<R: Read + Seek> {
+    pub header: SeedHeader,
+    reader: R,
+    len: u64,
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+pub fn decode_record(
+    record: &[u8; RECORD_LEN as usize],
+) -> Result<PartialMachine<SEED_STATES>, String> {
+    let mut transitions = [[None; 2]; SEED_STATES];
+    for (slot, bytes) in record.chunks(3).enumerate() {
+        let step = match *bytes {
+            [_, _, 0] => None,
+            [print, motion, next @ 1..=5] if print <= 1 && motion <= 1 => Some(TuringStep {
+                print: if print == 0 { Zero } else { One },
+                motion: if motion == 0 { Right } else { Left },
+                next_state: Index(next as usize - 1),
+            }),
+            _ => return Err(format!("bad transition {:?}", bytes)),
+        };
+        transitions[slot / 2][slot % 2] = step;
+    }
+    Ok(PartialMachine { transitions })
+}
+
+impl SeedDatabase<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<SeedDatabase<BufReader<File>>> {
+        SeedDatabase::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> SeedDatabase<R> {
+    pub fn new(mut reader: R) -> std::io::Result<SeedDatabase<R>> {
+        let size = reader.seek(SeekFrom::End(0))?;
+        if size < HEADER_LEN || !(size - HEADER_LEN).is_multiple_of(RECORD_LEN) {
+            return Err(invalid(format!(
+                "{} bytes is not a whole number of records",
+                size
+            )));
+        }
+        reader.seek(SeekFrom::Start(0))?;
+        let mut header = [0; HEADER_LEN as usize];
+        reader.read_exact(&mut header)?;
+        let count = |at: usize| u32::from_be_bytes(header[at..at + 4].try_into().unwrap());
+        Ok(SeedDatabase {
+            header: SeedHeader {
+                undecided_time: count(0),
+                undecided_space: count(4),
+                total: count(8),
+                sorted: header[12] == 1,
+            },
+            reader,
+            len: (size - HEADER_LEN) / RECORD_LEN,
+        })
+    }
+
+    // The number of machines, going by the size of the file
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn get(&mut self, index: u64) -> std::io::Result<PartialMachine<SEED_STATES>> {
+        if index >= self.len {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("no machine {}, there are {}", index, self.len),
+            ));
+        }
+        self.reader
+            .seek(SeekFrom::Start(HEADER_LEN + index * RECORD_LEN))?;
+        self.next_record(index)
+    }
+
+    fn next_record(&mut self, index: u64) -> std::io::Result<PartialMachine<SEED_STATES>> {
+        let mut record = [0; RECORD_LEN as usize];
+        self.reader.read_exact(&mut record)?;
+        decode_record(&record).map_err(|e| invalid(format!("machine {}: {}", index, e)))
+    }
+
+    // Every machine in order, paired with its index
+    pub fn iter(
+        &mut self,
+    ) -> std::io::Result<
+        impl Iterator<Item = std::io::Result<(u64, PartialMachine<SEED_STATES>)>> + '_,
+    > {
+        self.reader.seek(SeekFrom::Start(HEADER_LEN))?;
+        Ok((0..self.len).map(move |index| self.next_record(index).map(|machine| (index, machine))))
+    }
+}
+
+// The inverse of `decode_record`. Halting transitions become undefined ones.
+pub fn encode_record(machine: &PartialMachine<SEED_STATES>) -> [u8; RECORD_LEN as usize] {
+    let mut record = [0; RECORD_LEN as usize];
+    for (slot, step) in machine.transitions.iter().flatten().enumerate() {
+        if let Some(TuringStep {
+            print,
+            motion,
+            next_state: Index(next),
+        }) = step
+        {
+            record[3 * slot] = *print as u8;
+            record[3 * slot + 1] = matches!(motion, Left) as u8;
+            record[3 * slot + 2] = *next as u8 + 1;
+        }
+    }
+    record
+}
+
+// Writes a database of the machines, all counted as undecided because of
+// the time limit
+pub fn write_database(
+    out: &mut impl Write,
+    machines: &[PartialMachine<SEED_STATES>],
+) -> std::io::Result<()> {
+    let count = u32::try_from(machines.len())
+        .map_err(|_| invalid(format!("{} machines is too many", machines.len())))?;
+    let mut header = [0; HEADER_LEN as usize];
+    header[0..4].copy_from_slice(&count.to_be_bytes());
+    header[8..12].copy_from_slice(&count.to_be_bytes());
+    out.write_all(&header)?;
+    for machine in machines {
+        out.write_all(&encode_record(machine))?;
+    }
+    out.flush()
+}
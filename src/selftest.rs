@@ -0,0 +1,399 @@
+use std::fmt;
+
+use num_traits::PrimInt;
+use num_traits::Unsigned;
+
+use crate::enumerate::PartialMachine;
+use crate::holdouts::push;
+use crate::holdouts::Chain;
+use crate::holdouts::ChainRun;
+use crate::verify::Verify;
+use crate::Bit;
+use crate::Bit::*;
+use crate::State::*;
+use crate::Tape;
+use crate::TapeMotion::*;
+
+// Differential testing of the ways a machine can be run. Each engine runs
+// the machine from a blank tape with the same step limit, and they should
+// all agree on whether it halts, after how many steps, where the head ends
+// up and what's left on the tape. A machine that's still going at the limit
+// is only checked for that much, since the engines that take several steps
+// at a time stop wherever past the limit their last one took them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Engine {
+    // One step at a time through `TuringMachine::step`
+    Naive,
+    // The flattened table headless runs go through, see `flat`
+    Compiled,
+    // The LUT of u8 words, a word of steps at a time, with every entry
+    // checked against the step loop first, see `verify`
+    Macro,
+    // Chain steps on a run-length tape, see `holdouts`
+    Chain,
+}
+
+pub const ENGINES: [Engine; 4] = [Engine::Naive, Engine::Compiled, Engine::Macro, Engine::Chain];
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Engine::Naive => "naive",
+            Engine::Compiled => "compiled",
+            Engine::Macro => "macro",
+            Engine::Chain => "chain",
+        })
+    }
+}
+
+// How a halting run ended, with the tape as the cells holding a one, in
+// order, so that tapes stored differently compare equal
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Ending {
+    pub steps: u128,
+    pub head: i64,
+    pub ones: Vec<i64>,
+}
+
+impl Ending {
+    fn cell(&self, index: i64) -> u8 {
+        self.ones.binary_search(&index).is_ok() as u8
+    }
+}
+
+fn tape_ending<T: Unsigned + PrimInt>(steps: u64, tape: &Tape<T>) -> Ending {
+    let (low, high) = tape.bounds();
+    Ending {
+        steps: steps as u128,
+        head: tape.head_index(),
+        ones: (low..high)
+            .filter(|&i| tape.cell_at(i) == One)
+            .collect(),
+    }
+}
+
+fn chain_ending<const N: usize>(run: &ChainRun<N>) -> Ending {
+    let mut ones = Vec::new();
+    // Each side's runs are stacked with the one next to the head on top
+    let mut at = run.head;
+    for &(symbol, count) in run.tape.left.iter().rev() {
+        if symbol == 1 {
+            ones.extend(at - count as i64..at);
+        }
+        at -= count as i64;
+    }
+    if run.tape.head == 1 {
+        ones.push(run.head);
+    }
+    let mut at = run.head + 1;
+    for &(symbol, count) in run.tape.right.iter().rev() {
+        if symbol == 1 {
+            ones.extend(at..at + count as i64);
+        }
+        at += count as i64;
+    }
+    ones.sort_unstable();
+    Ending {
+        steps: run.steps,
+        head: run.head,
+        ones,
+    }
+}
+
+// A blank tape but for the input, which starts under the head and goes
+// rightwards
+pub fn input_tape<T: Unsigned + PrimInt>(input: &[Bit]) -> Tape<T> {
+    let mut tape = Tape::new();
+    for &bit in input {
+        tape.set(bit);
+        tape.move_tape(Right);
+    }
+    for _ in input {
+        tape.move_tape(Left);
+    }
+    tape
+}
+
+// None if the machine was still going at the limit
+pub fn run<const N: usize>(
+    machine: &PartialMachine<N>,
+    input: &[Bit],
+    engine: Engine,
+    limit: u64,
+) -> Option<Ending> {
+    match engine {
+        Engine::Naive => {
+            let mut tm = machine.to_machine();
+            let mut tape = input_tape::<u64>(input);
+            let mut steps = 0;
+            while let Index(state) = tm.state {
+                if steps == limit {
+                    return None;
+                }
+                tm.step(&mut tape, state);
+                steps += 1;
+            }
+            Some(tape_ending(steps, &tape))
+        }
+        Engine::Compiled => {
+            let mut tape = input_tape::<u64>(input);
+            let steps = machine.to_machine().run_limited(&mut tape, limit)?;
+            Some(tape_ending(steps, &tape))
+        }
+        Engine::Macro => {
+            let mut compiled = machine
+                .to_machine()
+                .compile_verified::<u8>(Verify::for_word::<u8>());
+            let mut tape = input_tape::<u8>(input);
+            let steps = compiled.run_limited(&mut tape, limit)?;
+            Some(tape_ending(steps, &tape))
+        }
+        Engine::Chain => {
+            let mut run = ChainRun::new(machine);
+            if let Some((&first, rest)) = input.split_first() {
+                run.tape.head = first as u8;
+                for &bit in rest.iter().rev() {
+                    push(&mut run.tape.right, bit as u8, 1);
+                }
+            }
+            while run.steps < limit as u128 {
+                match run.step() {
+                    Chain::Running => {}
+                    Chain::Halted if run.steps <= limit as u128 => return Some(chain_ending(&run)),
+                    Chain::Halted | Chain::Escaped => return None,
+                }
+            }
+            None
+        }
+    }
+}
+
+fn describe(result: &Option<Ending>) -> String {
+    match result {
+        Some(ending) => format!(
+            "halts after {} steps with the head at {} and {} ones",
+            ending.steps,
+            ending.head,
+            ending.ones.len()
+        ),
+        None => String::from("still running at the limit"),
+    }
+}
+
+// Cells this far either side of the first difference are shown
+const CONTEXT: i64 = 16;
+
+// The engines' results, a line each, and if two halting runs left different
+// tapes, the cells around the first place they differ
+pub fn diff(results: &[(Engine, Option<Ending>)]) -> String {
+    let mut text = String::new();
+    for (engine, result) in results {
+        text += &format!("    {:8} {}\n", engine, describe(result));
+    }
+    let endings: Vec<(Engine, &Ending)> = results
+        .iter()
+        .filter_map(|(engine, result)| result.as_ref().map(|ending| (*engine, ending)))
+        .collect();
+    let Some(&(first, expected)) = endings.first() else {
+        return text;
+    };
+    for &(engine, ending) in &endings[1..] {
+        if ending.ones == expected.ones {
+            continue;
+        }
+        let differ = |&i: &i64| expected.cell(i) != ending.cell(i);
+        let at = expected
+            .ones
+            .iter()
+            .chain(&ending.ones)
+            .copied()
+            .filter(differ)
+            .min()
+            .unwrap();
+        text += &format!("    tapes first differ at cell {}:\n", at);
+        for (engine, ending) in [(first, expected), (engine, ending)] {
+            let cells: String = (at - CONTEXT..=at + CONTEXT)
+                .map(|i| match (i == at, ending.cell(i)) {
+                    (true, cell) => format!("[{}]", cell),
+                    (false, cell) => cell.to_string(),
+                })
+                .collect();
+            text += &format!("    {:8} {}..{} {}\n", engine, at - CONTEXT, at + CONTEXT, cells);
+        }
+    }
+    text
+}
+
+// Runs the machine under every engine, returning what each made of it if
+// they don't all agree
+pub fn check<const N: usize>(
+    machine: &PartialMachine<N>,
+    input: &[Bit],
+    limit: u64,
+) -> Result<Option<Ending>, Vec<(Engine, Option<Ending>)>> {
+    let results: Vec<(Engine, Option<Ending>)> = ENGINES
+        .into_iter()
+        .map(|engine| (engine, run(machine, input, engine, limit)))
+        .collect();
+    match results.iter().all(|(_, result)| *result == results[0].1) {
+        true => Ok(results[0].1.clone()),
+        false => Err(results),
+    }
+}
+
+// What a machine of the known-answer corpus is expected to do
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Expected {
+    Halts { steps: u128, sigma: u64 },
+    // Still running at the limit
+    Runs,
+}
+
+// A machine to run under every engine, from `input` for `limit` steps, and
+// what it should do if that's known
+pub struct Case {
+    pub name: String,
+    pub machine: String,
+    pub input: Vec<Bit>,
+    pub limit: u64,
+    pub expected: Option<Expected>,
+}
+
+impl Case {
+    // What's wrong with a result all the engines agree on, if anything
+    pub fn mismatch(&self, result: &Option<Ending>) -> Option<String> {
+        let expected = self.expected?;
+        let ok = match (expected, result) {
+            (Expected::Halts { steps, sigma }, Some(ending)) => {
+                ending.steps == steps && ending.ones.len() as u64 == sigma
+            }
+            (Expected::Runs, None) => true,
+            _ => false,
+        };
+        let expected = match expected {
+            Expected::Halts { steps, sigma } => {
+                format!("halts after {} steps with {} ones", steps, sigma)
+            }
+            Expected::Runs => format!("is still running after {} steps", self.limit),
+        };
+        (!ok).then(|| format!("expected it {}, but it {}", expected, describe(result)))
+    }
+}
+
+// Machines with what they're known to do, to check the engines against what
+// they should get and not only each other. A line per machine, of its name,
+// the machine, the input from the head rightwards or - for a blank tape, and
+// either `halts` with the steps and the ones left, or `runs` with a number
+// of steps it's still going after. The copy machine turns a block of n ones
+// into two separated by a blank.
+pub const CORPUS: &str = "\
+bb1 1RZ--- - halts 1 1
+bb2 1RB1LB_1LA1RZ - halts 6 4
+bb3 1RB1RZ_1LB0RC_1LC1LA - halts 21 5
+bb3-sigma 1RB1RZ_0RC1RB_1LC1LA - halts 14 6
+bb4 1RB1LB_1LA0LC_1RZ1LD_1RD0RA - halts 107 13
+bb5 1RB1LC_1RC1RB_1RD0LE_1LA1LD_1RZ0LA - halts 47176870 4098
+copy-1 0RZ0RB_0RC1RB_1LD1RC_0LE1LD_1RA1LE 1 halts 6 2
+copy-2 0RZ0RB_0RC1RB_1LD1RC_0LE1LD_1RA1LE 11 halts 15 4
+copy-3 0RZ0RB_0RC1RB_1LD1RC_0LE1LD_1RA1LE 111 halts 28 6
+cycler 1RB0RB_1LA1LA - runs 1000000
+translated 1RB0LA_1LA1RB - runs 1000000
+bouncer 1RB0LB_1LA0RA - runs 1000000
+binary 1RB1LA_0LA0RB - runs 1000000
+skelet1 1RB1RD_1LC0RC_1RA1LD_0RE0LB_---1RC - runs 1000000
+";
+
+// Reads a corpus in the format of `CORPUS`, skipping blank lines and ones
+// starting with #
+pub fn load_corpus(text: &str) -> Result<Vec<Case>, String> {
+    let mut corpus = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let bad = |what: &str| format!("line {}: bad {}", i + 1, what);
+        let words: Vec<&str> = line.split_whitespace().collect();
+        // A halting machine is run for exactly as many steps as it should take
+        let (expected, limit) = match words[..] {
+            [_, _, _, "halts", steps, sigma] => {
+                let steps: u64 = steps.parse().map_err(|_| bad("step count"))?;
+                let sigma = sigma.parse().map_err(|_| bad("sigma"))?;
+                let expected = Expected::Halts {
+                    steps: steps as u128,
+                    sigma,
+                };
+                (expected, steps)
+            }
+            [_, _, _, "runs", limit] => {
+                (Expected::Runs, limit.parse().map_err(|_| bad("step count"))?)
+            }
+            _ => {
+                return Err(format!(
+                    "line {}: expected a name, machine, input and halts STEPS SIGMA or runs STEPS",
+                    i + 1
+                ))
+            }
+        };
+        let input = match words[2] {
+            "-" => Vec::new(),
+            bits => bits
+                .chars()
+                .map(|c| match c {
+                    '0' => Ok(Zero),
+                    '1' => Ok(One),
+                    _ => Err(bad("input")),
+                })
+                .collect::<Result<_, _>>()?,
+        };
+        corpus.push(Case {
+            name: words[0].to_string(),
+            machine: words[1].to_string(),
+            input,
+            limit,
+            expected: Some(expected),
+        });
+    }
+    Ok(corpus)
+}
+
+pub fn corpus() -> Vec<Case> {
+    load_corpus(CORPUS).expect("the built in corpus parses")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // What's wrong with the case, if anything, as `selftest` would say it
+    fn failure<const N: usize>(case: &Case) -> Option<String> {
+        let machine: PartialMachine<N> = case.machine.parse().expect("corpus machines parse");
+        match check(&machine, &case.input, case.limit) {
+            Ok(result) => case.mismatch(&result),
+            Err(results) => Some(diff(&results)),
+        }
+    }
+
+    #[test]
+    fn corpus_runs_as_expected_under_every_engine() {
+        for case in corpus() {
+            let failure = match case.machine.split('_').count() {
+                1 => failure::<1>(&case),
+                2 => failure::<2>(&case),
+                3 => failure::<3>(&case),
+                4 => failure::<4>(&case),
+                5 => failure::<5>(&case),
+                n => panic!("{} has {} states", case.name, n),
+            };
+            assert_eq!(failure, None, "{}", case.name);
+        }
+    }
+
+    #[test]
+    fn corpus_lines_need_an_answer() {
+        assert!(load_corpus("bb2 1RB1LB_1LA1RZ -").is_err());
+        assert!(load_corpus("bb2 1RB1LB_1LA1RZ - halts six 4").is_err());
+        assert!(load_corpus("bb2 1RB1LB_1LA1RZ 012 halts 6 4").is_err());
+        assert_eq!(load_corpus("# nothing\n\n").map(|corpus| corpus.len()), Ok(0));
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::enumerate::Tnf;
+use crate::search::parse_record;
+use crate::search::Champion;
+use crate::symmetry::Symmetries;
+
+// How many slots are fixed to cut the tree into subtrees for sharding. Every
+// shard of a search has to agree on this, so it isn't configurable.
+pub const SHARD_DEPTH: usize = 6;
+
+// One of `count` disjoint pieces of an enumeration, written "index/count"
+// with index counting from 0. Shard k gets every subtree whose position is
+// k modulo count, which spreads the expensive parts of the tree around.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+}
+
+impl FromStr for Shard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || format!("bad shard {:?}, expected index/count", s);
+        let (index, count) = s.split_once('/').ok_or_else(bad)?;
+        let shard = Shard {
+            index: index.parse().map_err(|_| bad())?,
+            count: count.parse().map_err(|_| bad())?,
+        };
+        if shard.index >= shard.count {
+            return Err(format!("shard index must be below {}", shard.count));
+        }
+        Ok(shard)
+    }
+}
+
+impl Display for Shard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}/{}", self.index, self.count))
+    }
+}
+
+impl Shard {
+    pub fn subtrees<const N: usize>(&self, symmetries: Symmetries) -> Vec<Tnf<N>> {
+        Tnf::<N>::subtrees(symmetries, SHARD_DEPTH)
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| i % self.count == self.index)
+            .map(|(_, subtree)| subtree)
+            .collect()
+    }
+
+    // Where this shard's leaderboard goes in the directory shared by a search
+    pub fn path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("shard-{}-of-{}.tsv", self.index, self.count))
+    }
+}
+
+// A record line from a shard's leaderboard
+struct Record {
+    kind: String,
+    champion: Champion,
+    shard: usize,
+}
+
+pub struct Merged {
+    pub best_sigma: Option<String>,
+    pub best_steps: Option<String>,
+    pub tried: u64,
+}
+
+// The shard count of the search in `dir`, going by its file names
+fn shard_count(dir: &Path) -> Result<usize, String> {
+    let mut counts = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))? {
+        let name = entry.map_err(|e| e.to_string())?.file_name();
+        let name = name.to_string_lossy();
+        if let Some(count) = name
+            .strip_prefix("shard-")
+            .and_then(|rest| rest.strip_suffix(".tsv"))
+            .and_then(|rest| rest.split_once("-of-"))
+            .and_then(|(_, count)| count.parse::<usize>().ok())
+        {
+            if !counts.contains(&count) {
+                counts.push(count);
+            }
+        }
+    }
+    match counts[..] {
+        [count] => Ok(count),
+        [] => Err(format!("no shards in {}", dir.display())),
+        _ => Err(format!("shards of different searches in {}", dir.display())),
+    }
+}
+
+// Combines the leaderboards of all the shards in `dir`. Fails unless every
+// shard is there and ran to completion. Equal scores go to the record from
+// the lowest shard, then the earliest in that shard, so the result doesn't
+// depend on which shard finished first.
+pub fn merge(dir: &Path) -> Result<Merged, String> {
+    let count = shard_count(dir)?;
+    let mut records = Vec::new();
+    let mut tried = 0;
+    for index in 0..count {
+        let shard = Shard { index, count };
+        let path = shard.path(dir);
+        let text = fs::read_to_string(&path)
+            .map_err(|e| format!("shard {}: {}: {}", shard, path.display(), e))?;
+        let mut done = false;
+        for line in text.lines() {
+            if let Some(count) = line.strip_prefix("done\t") {
+                tried += count
+                    .parse::<u64>()
+                    .map_err(|_| format!("shard {}: bad line {:?}", shard, line))?;
+                done = true;
+            } else {
+                let (kind, champion) =
+                    parse_record(line).ok_or(format!("shard {}: bad line {:?}", shard, line))?;
+                records.push(Record {
+                    kind,
+                    champion,
+                    shard: index,
+                });
+            }
+        }
+        if !done {
+            return Err(format!("shard {} has not finished", shard));
+        }
+    }
+
+    let best = |kind: &str, score: fn(&Champion) -> u128| {
+        records
+            .iter()
+            .filter(|r| r.kind == kind)
+            .min_by_key(|r| {
+                (
+                    std::cmp::Reverse(score(&r.champion)),
+                    r.shard,
+                    r.champion.found_at,
+                )
+            })
+            .map(|r| {
+                format!(
+                    "{}\t{}\t{}\t{}\t{}:{}",
+                    r.kind,
+                    r.champion.machine,
+                    r.champion.steps,
+                    r.champion.sigma,
+                    Shard {
+                        index: r.shard,
+                        count
+                    },
+                    r.champion.found_at
+                )
+            })
+    };
+    Ok(Merged {
+        best_sigma: best("sigma", |c| c.sigma as u128),
+        best_steps: best("steps", |c| c.steps),
+        tried,
+    })
+}
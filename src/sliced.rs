@@ -0,0 +1,182 @@
+use crate::Bit;
+use crate::Bit::*;
+use crate::State::*;
+use crate::TapeMotion;
+use crate::TapeMotion::*;
+use crate::TuringMachine;
+
+// One machine run on up to 64 tapes at once, for sweeping it over inputs.
+// The tapes are sliced into bits: each of WIDTH cells is a u64 holding that
+// cell of every tape, one bit per lane, and the head and the state are kept
+// the same way, as a u64 per cell with a bit set for each lane whose head is
+// there and a u64 per state with a bit set for each lane in it. A step is
+// then the same word operations for every lane whatever each is doing, about
+// 2B + 4S of them for a machine of B states with the heads spread over S
+// cells, where running the lanes one at a time would take 64 steps.
+//
+// The tapes are bounded, so a lane whose head walks off either end stops
+// there, as do lanes that halt. The heads only spread a cell a step, so only
+// the cells between the leftmost and rightmost of them are gone through.
+pub const WIDTH: usize = 64;
+
+pub const LANES: usize = 64;
+
+// Where the head starts, with the input from it rightwards
+pub const START: usize = WIDTH / 2;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Lane {
+    Running,
+    Halted { steps: u64, sigma: u64 },
+    // The head went past the end of the tape on that side
+    FellOff { steps: u64, side: TapeMotion },
+}
+
+pub struct SlicedRun<'a, const N: usize> {
+    machine: &'a TuringMachine<N>,
+    tape: [u64; WIDTH],
+    head: [u64; WIDTH],
+    state: [u64; N],
+    // Cells that any head can be on
+    lo: usize,
+    hi: usize,
+    pub steps: u64,
+    pub lanes: Vec<Lane>,
+}
+
+impl<'a, const N: usize> SlicedRun<'a, N> {
+    // A lane for each input, started in the machine's state at START
+    pub fn new(machine: &'a TuringMachine<N>, inputs: &[Vec<Bit>]) -> SlicedRun<'a, N> {
+        assert!(inputs.len() <= LANES);
+        let mut tape = [0; WIDTH];
+        for (lane, input) in inputs.iter().enumerate() {
+            assert!(input.len() <= WIDTH - START);
+            for (i, &bit) in input.iter().enumerate() {
+                tape[START + i] |= (bit as u64) << lane;
+            }
+        }
+        let all = match inputs.len() {
+            LANES => !0,
+            lanes => (1 << lanes) - 1,
+        };
+        let mut head = [0; WIDTH];
+        head[START] = all;
+        let mut state = [0; N];
+        if let Index(start) = machine.state {
+            state[start] = all;
+        }
+        SlicedRun {
+            machine,
+            tape,
+            head,
+            state,
+            lo: START,
+            hi: START,
+            steps: 0,
+            lanes: vec![Lane::Running; inputs.len()],
+        }
+    }
+
+    // Lanes still running
+    pub fn running(&self) -> u64 {
+        self.state.iter().fold(0, |running, lanes| running | lanes)
+    }
+
+    // Steps every running lane
+    pub fn step(&mut self) {
+        let active = self.running();
+        let cells = self.lo..=self.hi;
+        let read = cells
+            .clone()
+            .fold(0, |read, c| read | (self.tape[c] & self.head[c]));
+        let mut ones = 0;
+        let mut left = 0;
+        let mut halting = 0;
+        let mut next = [0; N];
+        for (state, lanes) in self.state.iter().enumerate() {
+            let transitions = &self.machine.states[state];
+            for (symbol, step) in [(!read, &transitions.zero), (read, &transitions.one)] {
+                let lanes = lanes & symbol;
+                if lanes == 0 {
+                    continue;
+                }
+                if step.print == One {
+                    ones |= lanes;
+                }
+                if step.motion == Left {
+                    left |= lanes;
+                }
+                match step.next_state {
+                    Index(next_state) => next[next_state] |= lanes,
+                    HALT => halting |= lanes,
+                }
+            }
+        }
+        let right = active & !left;
+        for c in cells {
+            let here = self.head[c] & active;
+            self.tape[c] = (self.tape[c] & !here) | (ones & here);
+        }
+        self.steps += 1;
+
+        let fell_left = self.head[0] & left & !halting;
+        let fell_right = self.head[WIDTH - 1] & right & !halting;
+        self.lo = self.lo.saturating_sub(1);
+        self.hi = (self.hi + 1).min(WIDTH - 1);
+        let mut head = [0; WIDTH];
+        for (c, lanes) in head.iter_mut().enumerate().take(self.hi + 1).skip(self.lo) {
+            *lanes = self.head[c] & !active;
+            if c > 0 {
+                *lanes |= self.head[c - 1] & right;
+            }
+            if c + 1 < WIDTH {
+                *lanes |= self.head[c + 1] & left;
+            }
+        }
+        self.head = head;
+        while self.lo < self.hi && self.head[self.lo] == 0 {
+            self.lo += 1;
+        }
+        while self.hi > self.lo && self.head[self.hi] == 0 {
+            self.hi -= 1;
+        }
+
+        for lanes in &mut next {
+            *lanes &= !(fell_left | fell_right);
+        }
+        self.state = next;
+        for lane in lanes(halting) {
+            let sigma = self.tape.iter().map(|cell| cell >> lane & 1).sum();
+            self.lanes[lane] = Lane::Halted {
+                steps: self.steps,
+                sigma,
+            };
+        }
+        for (fell, side) in [(fell_left, Left), (fell_right, Right)] {
+            for lane in lanes(fell) {
+                self.lanes[lane] = Lane::FellOff {
+                    steps: self.steps,
+                    side,
+                };
+            }
+        }
+    }
+
+    // Steps until every lane has stopped or `limit` steps have gone by
+    pub fn run(&mut self, limit: u64) {
+        while self.steps < limit && self.running() != 0 {
+            self.step();
+        }
+    }
+}
+
+// The lanes set in a mask
+fn lanes(mut mask: u64) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        (mask != 0).then(|| {
+            let lane = mask.trailing_zeros() as usize;
+            mask &= mask - 1;
+            lane
+        })
+    })
+}
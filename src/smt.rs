@@ -0,0 +1,87 @@
+use crate::enumerate::PartialMachine;
+use crate::enumerate::HALT_STEP;
+use crate::Bit;
+use crate::Bit::*;
+use crate::State::*;
+use crate::TapeMotion::*;
+
+// A machine's step relation in SMT-LIB, for Z3 or CVC5. A configuration is
+// a state as an integer, with N for the halt, a head position and the tape
+// as an array from positions to whether the cell is a one. `step` relates a
+// configuration to the next, and a halted machine stays where it is, so
+// that a run can be unrolled for a fixed number of steps whether or not it
+// halts before them. Undefined transitions are the halting ones they're
+// run as.
+//
+// Unrolled, the run gets a configuration s_t, h_t, t_t for each step t from
+// the input up to the last, and the goal that it has halted by then. The
+// answer is sat if and only if the machine halts within that many steps,
+// and the model is then the run.
+pub fn to_smtlib<const N: usize>(
+    machine: &PartialMachine<N>,
+    input: &[Bit],
+    unroll: Option<u64>,
+) -> String {
+    let mut output = format!("; {}\n(set-logic QF_AUFLIA)\n", machine);
+    output.push_str(&format!("(define-fun halt () Int {})\n", N));
+    output.push_str(
+        "(define-fun step ((s Int) (h Int) (t (Array Int Bool)) \
+         (s2 Int) (h2 Int) (t2 (Array Int Bool))) Bool\n  (or\n",
+    );
+    for (state, slots) in machine.transitions.iter().enumerate() {
+        for (read, step) in [Zero, One].into_iter().zip(slots) {
+            let step = step.unwrap_or(HALT_STEP);
+            output.push_str(&format!(
+                "    (and (= s {}) {} (= t2 (store t h {})) (= h2 ({} h 1)) (= s2 {}))\n",
+                state,
+                match read {
+                    Zero => "(not (select t h))",
+                    One => "(select t h)",
+                },
+                matches!(step.print, One),
+                match step.motion {
+                    Left => '-',
+                    Right => '+',
+                },
+                match step.next_state {
+                    Index(next) => next.to_string(),
+                    HALT => String::from("halt"),
+                }
+            ));
+        }
+    }
+    output.push_str("    (and (= s halt) (= s2 halt) (= h2 h) (= t2 t))))\n");
+
+    let Some(steps) = unroll else {
+        return output;
+    };
+    for t in 0..=steps {
+        output.push_str(&format!(
+            "(declare-const s_{0} Int)\n(declare-const h_{0} Int)\n(declare-const t_{0} (Array Int Bool))\n",
+            t
+        ));
+    }
+    // The input from the head rightwards, on an otherwise blank tape
+    let mut tape = String::from("((as const (Array Int Bool)) false)");
+    for (i, bit) in input.iter().enumerate() {
+        if *bit == One {
+            tape = format!("(store {} {} true)", tape, i);
+        }
+    }
+    output.push_str(&format!(
+        "(assert (and (= s_0 0) (= h_0 0) (= t_0 {})))\n",
+        tape
+    ));
+    for t in 0..steps {
+        output.push_str(&format!(
+            "(assert (step s_{0} h_{0} t_{0} s_{1} h_{1} t_{1}))\n",
+            t,
+            t + 1
+        ));
+    }
+    output.push_str(&format!(
+        "(assert (= s_{} halt))\n(check-sat)\n(get-value (h_{0} t_{0}))\n",
+        steps
+    ));
+    output
+}
@@ -0,0 +1,269 @@
+use std::io::Write;
+use std::mem::size_of;
+
+use num_traits::PrimInt;
+use num_traits::Unsigned;
+
+use crate::get_bit;
+use crate::Bit::*;
+use crate::DisplayOptions;
+use crate::QUADRANTS;
+use crate::State::*;
+use crate::Tape;
+use crate::TuringMachine;
+
+// How the cells that share a pixel are combined
+#[derive(Clone, Copy)]
+pub enum Aggregate {
+    // Set if any of the cells is a one (the max, for a binary tape)
+    Any,
+    // Shaded by the fraction of cells that are ones
+    Density,
+}
+
+// A space-time diagram with a fixed memory budget: `width` columns by at most
+// `capacity` rows, however long the run or wide the tape gets.
+//
+// In time, rows are taken every `stride` steps. When the diagram fills up,
+// every other row is dropped and the stride doubles.
+// In space, each column covers `scale` cells centred on cell 0. When the tape
+// outgrows the window, neighbouring columns are merged and the scale doubles.
+// Columns hold counts of ones, so both kinds of merge are exact.
+pub struct SpaceTime {
+    width: usize,
+    capacity: usize,
+    scale: usize,
+    stride: u64,
+    rows: Vec<(u64, Vec<u32>)>,
+}
+
+impl SpaceTime {
+    pub fn new(width: usize, capacity: usize) -> SpaceTime {
+        // Halving the window has to land on column boundaries
+        assert!(width >= 4 && width.is_multiple_of(4));
+        assert!(capacity >= 2);
+        SpaceTime {
+            width,
+            capacity,
+            scale: 1,
+            stride: 1,
+            rows: Vec::with_capacity(capacity),
+        }
+    }
+
+    // Cells either side of 0 that fit in the window
+    fn extent(&self) -> isize {
+        (self.width / 2 * self.scale) as isize
+    }
+
+    fn zoom_out(&mut self) {
+        let w = self.width;
+        for (_, row) in self.rows.iter_mut() {
+            let mut merged = vec![0; w];
+            for (j, pixel) in merged.iter_mut().enumerate().take(3 * w / 4).skip(w / 4) {
+                *pixel = row[2 * j - w / 2] + row[2 * j - w / 2 + 1];
+            }
+            *row = merged;
+        }
+        self.scale *= 2;
+    }
+
+    fn thin_out(&mut self) {
+        let mut i = 0;
+        self.rows.retain(|_| {
+            i += 1;
+            i % 2 == 1
+        });
+        self.stride *= 2;
+    }
+
+    pub fn record<T: Unsigned + PrimInt>(&mut self, step: u64, tape: &Tape<T>) {
+        if !step.is_multiple_of(self.stride) {
+            return;
+        }
+        if self.rows.len() == self.capacity {
+            self.thin_out();
+            if !step.is_multiple_of(self.stride) {
+                return;
+            }
+        }
+        let bits = size_of::<T>() * 8;
+        let (lowest, highest) = tape.bounds();
+        while lowest < -(self.extent() as i64) || highest > self.extent() as i64 {
+            self.zoom_out();
+        }
+
+        let extent = self.extent();
+        let scale = self.scale;
+        let mut row = vec![0; self.width];
+        let mut add_word = |first: isize, word: T, cell: &dyn Fn(usize) -> isize| {
+            if scale >= bits {
+                // Both are powers of two, so the word sits inside one column
+                row[((first + extent) as usize) / scale] += word.count_ones();
+            } else {
+                for i in 0..bits {
+                    if let One = get_bit(word, i) {
+                        row[((cell(i) + extent) as usize) / scale] += 1;
+                    }
+                }
+            }
+        };
+        for (k, word) in tape.right.iter().enumerate() {
+            let start = (k * bits) as isize;
+            add_word(start, *word, &|i| start + i as isize);
+        }
+        for (k, word) in tape.left.iter().enumerate() {
+            let start = (k * bits) as isize;
+            add_word(-start - bits as isize, *word, &|i| -start - i as isize - 1);
+        }
+        self.rows.push((step, row));
+    }
+
+    fn pixel(&self, ones: u32, aggregate: Aggregate) -> u8 {
+        match aggregate {
+            Aggregate::Any if ones > 0 => 0,
+            Aggregate::Any => 255,
+            Aggregate::Density => (255 - 255 * ones as usize / self.scale) as u8,
+        }
+    }
+
+    // Binary PGM, ones dark on a light background
+    pub fn write_pgm<W: Write>(&self, out: &mut W, aggregate: Aggregate) -> std::io::Result<()> {
+        write!(out, "P5\n{} {}\n255\n", self.width, self.rows.len())?;
+        for (_, row) in self.rows.iter() {
+            let pixels: Vec<u8> = row.iter().map(|x| self.pixel(*x, aggregate)).collect();
+            out.write_all(&pixels)?;
+        }
+        Ok(())
+    }
+
+    // SVG with a square of `cell` pixels for each column of each row, ones
+    // dark on a light background as in the PGM. Runs of set columns are one
+    // rectangle each, which keeps sparse diagrams small.
+    pub fn write_svg<W: Write>(
+        &self,
+        out: &mut W,
+        aggregate: Aggregate,
+        cell: usize,
+    ) -> std::io::Result<()> {
+        writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" shape-rendering=\"crispEdges\">",
+            self.width * cell,
+            self.rows.len() * cell
+        )?;
+        writeln!(out, "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>")?;
+        for (y, (_, row)) in self.rows.iter().enumerate() {
+            let mut x = 0;
+            while x < self.width {
+                let shade = self.pixel(row[x], aggregate);
+                let run = row[x..]
+                    .iter()
+                    .take_while(|ones| self.pixel(**ones, aggregate) == shade)
+                    .count();
+                if shade < 255 {
+                    writeln!(
+                        out,
+                        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"rgb({4},{4},{4})\"/>",
+                        x * cell,
+                        y * cell,
+                        run * cell,
+                        cell,
+                        shade
+                    )?;
+                }
+                x += run;
+            }
+        }
+        writeln!(out, "</svg>")
+    }
+
+    // Heap bytes held by the rows, which `capacity` and `width` bound
+    pub fn memory_usage(&self) -> usize {
+        self.rows.capacity() * std::mem::size_of::<(u64, Vec<u32>)>()
+            + self
+                .rows
+                .iter()
+                .map(|(_, row)| row.capacity() * std::mem::size_of::<u32>())
+                .sum::<usize>()
+    }
+
+    // One text row per sampled step, a column showing `one` if any of its
+    // cells is set. In block mode each character packs two columns of two
+    // consecutive rows.
+    pub fn render(&self, options: &DisplayOptions) -> String {
+        let mut output = String::new();
+        let blank = options.margin(None, false);
+        let per_line = if options.blocks { 2 } else { 1 };
+        let mut previous: Option<u64> = None;
+        for (n, lines) in self.rows.chunks(per_line).enumerate() {
+            if options.ruler_due(n as u64) {
+                output.push_str(&blank);
+                output.push_str(&options.ruler(self.width, self.width / 2));
+                output.push('\n');
+            }
+            let (first, top) = &lines[0];
+            let last = lines[lines.len() - 1].0;
+            // Only some steps have a row, so a mark goes on the first row at
+            // or after it
+            let marked = options
+                .marks
+                .iter()
+                .any(|mark| previous.is_none_or(|p| *mark > p) && *mark <= last);
+            output.push_str(&options.margin(Some(*first), marked));
+            previous = Some(last);
+            if options.blocks {
+                let bottom = lines.get(1).map(|(_, row)| row);
+                for i in 0..self.width / 2 {
+                    if options.separated(i) {
+                        output.push(options.separator);
+                    }
+                    let set = |row: &Vec<u32>, column: usize, quadrant: usize| {
+                        if row[column] > 0 {
+                            quadrant
+                        } else {
+                            0
+                        }
+                    };
+                    let mut index = set(top, 2 * i, 1) | set(top, 2 * i + 1, 2);
+                    if let Some(bottom) = bottom {
+                        index |= set(bottom, 2 * i, 4) | set(bottom, 2 * i + 1, 8);
+                    }
+                    output.push(QUADRANTS[index]);
+                }
+            } else {
+                for (i, x) in top.iter().enumerate() {
+                    if options.separated(i) {
+                        output.push(options.separator);
+                    }
+                    output.push(if *x > 0 { options.one } else { options.zero });
+                }
+            }
+            output.push('\n');
+        }
+        output
+    }
+}
+
+impl<const N: usize> TuringMachine<N> {
+    // Runs for at most `limit` steps, feeding the diagram as it goes.
+    // Returns the number of steps taken.
+    pub fn space_time<T: Unsigned + PrimInt>(
+        &mut self,
+        tape: &mut Tape<T>,
+        limit: u64,
+        diagram: &mut SpaceTime,
+    ) -> u64 {
+        let mut steps = 0;
+        diagram.record(steps, tape);
+        while let Index(state) = self.state {
+            if steps == limit {
+                break;
+            }
+            self.step(tape, state);
+            steps += 1;
+            diagram.record(steps, tape);
+        }
+        steps
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::enumerate::PartialMachine;
+use crate::Bit::*;
+use crate::State::*;
+use crate::TapeMotion::*;
+use crate::TuringMachine;
+use crate::TuringStep;
+
+// The text format used by bbchallenge and most busy beaver write-ups: the
+// states in order, separated by '_', each as its transitions on 0 and 1.
+// A transition is the symbol written, L or R, then the next state as a letter
+// with Z for halting, so the 2-state busy beaver is 1RB1LB_1LA1RZ.
+// Unspecified transitions are written ---.
+
+pub fn state_letter(state: usize) -> char {
+    (b'A' + state as u8) as char
+}
+
+pub fn format_step(step: &TuringStep) -> String {
+    format!(
+        "{}{}{}",
+        match step.print {
+            Zero => '0',
+            One => '1',
+        },
+        match step.motion {
+            Left => 'L',
+            Right => 'R',
+        },
+        match step.next_state {
+            HALT => 'Z',
+            Index(i) => state_letter(i),
+        }
+    )
+}
+
+impl<const N: usize> Display for TuringMachine<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, state) in self.states.iter().enumerate() {
+            if i > 0 {
+                f.write_str("_")?;
+            }
+            f.write_str(&format_step(&state.zero))?;
+            f.write_str(&format_step(&state.one))?;
+        }
+        Ok(())
+    }
+}
+
+// Parses a transition, with "---" for an undefined one
+pub fn parse_step(s: &str, states: usize) -> Result<Option<TuringStep>, String> {
+    if s == "---" {
+        return Ok(None);
+    }
+    let bad = || format!("bad transition {:?}", s);
+    let &[print, motion, next] = s.as_bytes() else {
+        return Err(bad());
+    };
+    Ok(Some(TuringStep {
+        print: match print {
+            b'0' => Zero,
+            b'1' => One,
+            _ => return Err(bad()),
+        },
+        motion: match motion {
+            b'L' => Left,
+            b'R' => Right,
+            _ => return Err(bad()),
+        },
+        next_state: match next {
+            b'Z' => HALT,
+            b'A'..=b'Y' if ((next - b'A') as usize) < states => Index((next - b'A') as usize),
+            _ => return Err(bad()),
+        },
+    }))
+}
+
+impl<const N: usize> FromStr for PartialMachine<N> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let states: Vec<&str> = s.trim().split('_').collect();
+        if states.len() != N {
+            return Err(format!("{:?} does not have {} states", s, N));
+        }
+        let mut transitions = [[None; 2]; N];
+        for (slots, state) in transitions.iter_mut().zip(states) {
+            if !state.is_ascii() || state.len() != 6 {
+                return Err(format!("bad state {:?}", state));
+            }
+            slots[0] = parse_step(&state[..3], N)?;
+            slots[1] = parse_step(&state[3..], N)?;
+        }
+        Ok(PartialMachine { transitions })
+    }
+}
+
+// Undefined transitions are read as halting ones, see `HALT_STEP`
+impl<const N: usize> FromStr for TuringMachine<N> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.parse::<PartialMachine<N>>()?.to_machine())
+    }
+}
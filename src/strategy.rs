@@ -0,0 +1,151 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::enumerate::PartialMachine;
+use crate::holdouts::Chain;
+use crate::holdouts::ChainRun;
+use crate::search::simulate;
+use crate::search::Outcome;
+use crate::Tape;
+
+// Which way of running a machine is fastest depends on the machine: the LUT
+// goes a word at a time but pays for building its table and falls back to
+// the step loop for a word it cycles inside, and chain steps go through a
+// block of equal cells at once, which helps a sweeping machine a lot and
+// others not at all. Rather than guess, `run_auto` runs the machine briefly
+// under each, and then runs it to the limit under the one that would get
+// there first, counting the time to build the LUT against it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Strategy {
+    // The flattened step loop, on a tape of u64 words
+    Flat,
+    // The LUT of u8 words reachable from a blank tape, see `reachable`
+    Lut,
+    // Chain steps on a run-length tape, see `holdouts`
+    Chain,
+}
+
+pub const STRATEGIES: [Strategy; 3] = [Strategy::Flat, Strategy::Lut, Strategy::Chain];
+
+// Steps each strategy is tried for, enough to take a few milliseconds at the
+// slowest
+pub const PROBE_STEPS: u64 = 1 << 22;
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Strategy::Flat => "flat",
+            Strategy::Lut => "lut",
+            Strategy::Chain => "chain",
+        })
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        STRATEGIES
+            .into_iter()
+            .find(|strategy| strategy.to_string() == s)
+            .ok_or(format!("unknown strategy {}", s))
+    }
+}
+
+// Runs from a blank tape for up to `limit` steps, returning the outcome if
+// it would be the same for any longer limit, and the time taken to set up
+// before the first step. Chain steps can tell that a machine sweeps into
+// blank tape forever, and then it's undecided however long it's run.
+fn run_timed<const N: usize>(
+    machine: &PartialMachine<N>,
+    strategy: Strategy,
+    limit: u64,
+) -> (Option<Outcome>, Duration) {
+    let start = Instant::now();
+    match strategy {
+        Strategy::Flat => match simulate(machine, limit) {
+            Outcome::Undecided => (None, Duration::ZERO),
+            halted => (Some(halted), Duration::ZERO),
+        },
+        Strategy::Lut => {
+            let mut compiled = machine.to_machine().compile_reachable::<u8>().0;
+            let setup = start.elapsed();
+            let mut tape = Tape::<u8>::new();
+            let outcome = compiled
+                .run_limited(&mut tape, limit)
+                .map(|steps| Outcome::Halted {
+                    steps: steps as u128,
+                    sigma: tape.ones(),
+                });
+            (outcome, setup)
+        }
+        Strategy::Chain => {
+            let mut run = ChainRun::new(machine);
+            // A chain step can go past the limit, and then it's as if the
+            // limit had run out first
+            while run.steps < limit as u128 {
+                match run.step() {
+                    Chain::Running => {}
+                    Chain::Halted if run.steps <= limit as u128 => {
+                        let outcome = Outcome::Halted {
+                            steps: run.steps,
+                            sigma: run.tape.ones(),
+                        };
+                        return (Some(outcome), Duration::ZERO);
+                    }
+                    Chain::Halted => break,
+                    Chain::Escaped => return (Some(Outcome::Undecided), Duration::ZERO),
+                }
+            }
+            (None, Duration::ZERO)
+        }
+    }
+}
+
+pub fn run_with<const N: usize>(
+    machine: &PartialMachine<N>,
+    strategy: Strategy,
+    limit: u64,
+) -> Outcome {
+    run_timed(machine, strategy, limit)
+        .0
+        .unwrap_or(Outcome::Undecided)
+}
+
+// Probes each strategy for PROBE_STEPS, or the limit if that's less, and then
+// runs from the start again under the fastest, logging the rates and the
+// choice to stderr. If a probe already has the outcome, that's the result.
+pub fn run_auto<const N: usize>(machine: &PartialMachine<N>, limit: u64) -> Outcome {
+    let probe = limit.min(PROBE_STEPS);
+    let mut best: Option<(Strategy, f64)> = None;
+    for strategy in STRATEGIES {
+        let start = Instant::now();
+        let (outcome, setup) = run_timed(machine, strategy, probe);
+        let elapsed = start.elapsed() - setup;
+        if let Some(outcome) = outcome {
+            eprintln!("probe {}: decided within {} steps", strategy, probe);
+            return outcome;
+        }
+        if probe == limit {
+            eprintln!("probe {}: ran the whole limit", strategy);
+            return Outcome::Undecided;
+        }
+        let rate = probe as f64 / elapsed.as_secs_f64().max(1e-9);
+        let estimate = setup.as_secs_f64() + limit as f64 / rate;
+        eprintln!(
+            "probe {}: {:.0} steps/s after {:.3} ms of setup, {:.3} s to the limit",
+            strategy,
+            rate,
+            setup.as_secs_f64() * 1000.0,
+            estimate
+        );
+        if best.is_none_or(|(_, fastest)| estimate < fastest) {
+            best = Some((strategy, estimate));
+        }
+    }
+    let (strategy, _) = best.unwrap();
+    eprintln!("running with {}", strategy);
+    run_with(machine, strategy, limit)
+}
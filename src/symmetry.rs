@@ -0,0 +1,211 @@
+use crate::enumerate::PartialMachine;
+use crate::Bit::*;
+use crate::State::*;
+use crate::TapeMotion::*;
+use crate::TuringStep;
+
+// Which transformations count as giving the same machine. Relabelling the
+// states other than A always does.
+//
+// Mirroring swaps L and R everywhere and behaves identically, reflected.
+// Swapping symbols exchanges 0 and 1 in what is read and written, which is
+// the same machine run on a tape of ones instead of blanks, so it only makes
+// sense to turn on when that distinction doesn't matter to the search.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Symmetries {
+    pub mirror: bool,
+    pub swap_symbols: bool,
+}
+
+impl Symmetries {
+    pub const NONE: Symmetries = Symmetries {
+        mirror: false,
+        swap_symbols: false,
+    };
+}
+
+pub fn mirror<const N: usize>(machine: &PartialMachine<N>) -> PartialMachine<N> {
+    PartialMachine {
+        transitions: machine.transitions.map(|slots| {
+            slots.map(|t| {
+                t.map(|step| TuringStep {
+                    motion: match step.motion {
+                        Left => Right,
+                        Right => Left,
+                    },
+                    ..step
+                })
+            })
+        }),
+    }
+}
+
+fn swap_symbols<const N: usize>(machine: &PartialMachine<N>) -> PartialMachine<N> {
+    PartialMachine {
+        transitions: machine.transitions.map(|[zero, one]| {
+            [one, zero].map(|t| {
+                t.map(|step| TuringStep {
+                    print: match step.print {
+                        Zero => One,
+                        One => Zero,
+                    },
+                    ..step
+                })
+            })
+        }),
+    }
+}
+
+// The states in the order the table first mentions them, reading A's
+// transitions, then those of the next state found, and so on, and how many
+// of them were found that way. States that are never mentioned keep their
+// relative order at the end.
+fn first_use<const N: usize>(machine: &PartialMachine<N>) -> (Vec<usize>, usize) {
+    let mut order: Vec<usize> = Vec::with_capacity(N);
+    if N > 0 {
+        order.push(0);
+    }
+    let mut i = 0;
+    while i < order.len() {
+        for step in machine.transitions[order[i]].iter().flatten() {
+            if let Index(next) = step.next_state {
+                if !order.contains(&next) {
+                    order.push(next);
+                }
+            }
+        }
+        i += 1;
+    }
+    let reached = order.len();
+    for state in 0..N {
+        if !order.contains(&state) {
+            order.push(state);
+        }
+    }
+    (order, reached)
+}
+
+// Renumbers the states in order of first use (see `first_use`)
+pub fn relabel<const N: usize>(machine: &PartialMachine<N>) -> PartialMachine<N> {
+    renumber(machine, &first_use(machine).0)
+}
+
+// The machine with state order[i] renamed to i
+fn renumber<const N: usize>(machine: &PartialMachine<N>, order: &[usize]) -> PartialMachine<N> {
+    let mut new_index = [0; N];
+    for (new, old) in order.iter().enumerate() {
+        new_index[*old] = new;
+    }
+    let renumber = |t: Option<TuringStep>| {
+        t.map(|step| TuringStep {
+            next_state: match step.next_state {
+                Index(next) => Index(new_index[next]),
+                HALT => HALT,
+            },
+            ..step
+        })
+    };
+    PartialMachine {
+        transitions: std::array::from_fn(|new| machine.transitions[order[new]].map(renumber)),
+    }
+}
+
+// Orders machines slot by slot: undefined first, then halting, then by next
+// state, symbol written and direction (rightwards first)
+fn key<const N: usize>(machine: &PartialMachine<N>) -> Vec<(usize, usize, usize, usize)> {
+    machine
+        .transitions
+        .iter()
+        .flatten()
+        .map(|t| match t {
+            None => (0, 0, 0, 0),
+            Some(step) => (
+                1,
+                match step.next_state {
+                    HALT => 0,
+                    Index(i) => i + 1,
+                },
+                matches!(step.print, One) as usize,
+                matches!(step.motion, Left) as usize,
+            ),
+        })
+        .collect()
+}
+
+// The representative of the machine's class under `symmetries`: of all the
+// transformed and relabelled versions of it, the one that orders first.
+pub fn canonicalize<const N: usize>(
+    machine: &PartialMachine<N>,
+    symmetries: Symmetries,
+) -> PartialMachine<N> {
+    let mut variants = vec![machine.clone()];
+    if symmetries.mirror {
+        variants.push(mirror(machine));
+    }
+    if symmetries.swap_symbols {
+        let swapped: Vec<_> = variants.iter().map(swap_symbols).collect();
+        variants.extend(swapped);
+    }
+    variants
+        .iter()
+        .map(relabel)
+        .min_by_key(key)
+        .unwrap()
+}
+
+pub fn is_canonical<const N: usize>(machine: &PartialMachine<N>, symmetries: Symmetries) -> bool {
+    key(&canonicalize(machine, symmetries)) == key(machine)
+}
+
+// A machine in the normal form `Tnf` generates, and how it was got from the
+// original
+pub struct Canonical<const N: usize> {
+    pub machine: PartialMachine<N>,
+    // The state each state of the original became
+    pub states: [usize; N],
+    pub mirrored: bool,
+    pub swapped: bool,
+}
+
+// Brings any machine into the normal form of `Tnf`: the variant under
+// `symmetries` that orders first, with the states renumbered in order of
+// first use, and the transitions of states that can never be reached left
+// undefined like the enumeration leaves them. Machines that only differ in
+// ways that don't matter come out the same.
+pub fn canonicalize_tnf<const N: usize>(
+    machine: &PartialMachine<N>,
+    symmetries: Symmetries,
+) -> Canonical<N> {
+    let mut variants = vec![(machine.clone(), false, false)];
+    if symmetries.mirror {
+        variants.push((mirror(machine), true, false));
+    }
+    if symmetries.swap_symbols {
+        let swapped: Vec<_> = variants
+            .iter()
+            .map(|(variant, mirrored, _)| (swap_symbols(variant), *mirrored, true))
+            .collect();
+        variants.extend(swapped);
+    }
+    variants
+        .into_iter()
+        .map(|(variant, mirrored, swapped)| {
+            let (order, reached) = first_use(&variant);
+            let mut machine = renumber(&variant, &order);
+            for slots in &mut machine.transitions[reached..] {
+                *slots = [None, None];
+            }
+            let mut states = [0; N];
+            for (new, old) in order.iter().enumerate() {
+                states[*old] = new;
+            }
+            Canonical {
+                machine,
+                states,
+                mirrored,
+                swapped,
+            }
+        })
+        .min_by_key(|canonical| key(&canonical.machine))
+        .unwrap()
+}
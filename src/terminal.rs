@@ -0,0 +1,86 @@
+use std::io::IsTerminal;
+use std::io::Read;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::thread;
+
+// Keys read from the terminal one at a time as they're pressed, for
+// `watch`. There's no terminal library here, so the terminal is switched
+// into a mode that hands over each key without echoing it, and back, by
+// running `stty` on it.
+pub enum Key {
+    Char(char),
+    Left,
+    Right,
+    Enter,
+    Backspace,
+    // Ctrl-C, which comes as a key rather than a signal while the terminal
+    // is switched, so it's always switched back
+    Interrupt,
+}
+
+// The terminal's settings from before it was switched, put back when this
+// is dropped
+pub struct Raw {
+    saved: String,
+}
+
+// What stty printed, or None if it couldn't be run on the terminal
+fn stty(args: &[&str]) -> Option<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let printed = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    output.status.success().then_some(printed)
+}
+
+impl Raw {
+    // None if stdin isn't a terminal, or it can't be switched
+    pub fn enter() -> Option<Raw> {
+        if !std::io::stdin().is_terminal() {
+            return None;
+        }
+        let saved = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "-isig", "min", "1"])?;
+        Some(Raw { saved })
+    }
+
+    // Keys as they're pressed, read on a thread of their own so a run can
+    // go on playing between them
+    pub fn keys(&self) -> Receiver<Key> {
+        let (send, receive) = mpsc::channel();
+        thread::spawn(move || {
+            let mut bytes = std::io::stdin().lock().bytes().map_while(Result::ok);
+            while let Some(byte) = bytes.next() {
+                let key = match byte {
+                    3 => Key::Interrupt,
+                    b'\r' | b'\n' => Key::Enter,
+                    8 | 127 => Key::Backspace,
+                    // The arrows are escape, [ and a letter
+                    27 => match (bytes.next(), bytes.next()) {
+                        (Some(b'['), Some(b'C')) => Key::Right,
+                        (Some(b'['), Some(b'D')) => Key::Left,
+                        _ => continue,
+                    },
+                    byte if byte.is_ascii_graphic() || byte == b' ' => Key::Char(byte as char),
+                    _ => continue,
+                };
+                if send.send(key).is_err() {
+                    break;
+                }
+            }
+        });
+        receive
+    }
+}
+
+impl Drop for Raw {
+    fn drop(&mut self) {
+        stty(&[&self.saved]);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::enumerate::PartialMachine;
+use crate::search::Outcome;
+use crate::Bit::*;
+use crate::State::*;
+use crate::TapeMotion::*;
+use crate::TuringStep;
+
+// Enumerates machines by running them. Starting from the machine with no
+// transitions at all, each machine is simulated until it first needs a
+// transition that isn't defined yet, where it halts, and only then is that
+// transition branched on. Nothing is ever generated for transitions the run
+// never gets to, so every machine comes out exactly when it halts, or when
+// it reaches the step limit without doing so, and the enumeration skips
+// everything that differs from it only in transitions it never used.
+//
+// A new transition may go to any state reached so far or to the next one,
+// so states are numbered in the order the run first enters them. With
+// `mirror`, the first transition only moves right, which leaves out the
+// mirror image of every machine.
+pub struct SimulatedTree<const N: usize> {
+    limit: u64,
+    mirror: bool,
+    pending: Vec<Node<N>>,
+}
+
+// A machine paused at the transition it is about to take
+struct Node<const N: usize> {
+    machine: PartialMachine<N>,
+    // The cells visited so far, the first of them at position `low`
+    tape: Vec<u8>,
+    low: i64,
+    head: i64,
+    state: usize,
+    steps: u64,
+    reached: usize,
+}
+
+impl<const N: usize> SimulatedTree<N> {
+    pub fn new(limit: u64, mirror: bool) -> SimulatedTree<N> {
+        SimulatedTree {
+            limit,
+            mirror,
+            pending: vec![Node {
+                machine: PartialMachine {
+                    transitions: [[None; 2]; N],
+                },
+                tape: vec![0],
+                low: 0,
+                head: 0,
+                state: 0,
+                steps: 0,
+                reached: 1,
+            }],
+        }
+    }
+}
+
+impl<const N: usize> Iterator for SimulatedTree<N> {
+    type Item = (PartialMachine<N>, Outcome);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut node = self.pending.pop()?;
+        // Run on until the next transition is undefined
+        let read = loop {
+            if node.steps == self.limit {
+                return Some((node.machine, Outcome::Undecided));
+            }
+            let i = (node.head - node.low) as usize;
+            let read = node.tape[i];
+            let Some(TuringStep {
+                print,
+                motion,
+                next_state: Index(next),
+            }) = node.machine.transitions[node.state][read as usize]
+            else {
+                break read;
+            };
+            node.tape[i] = print as u8;
+            node.state = next;
+            node.steps += 1;
+            node.head += match motion {
+                Right => 1,
+                Left => -1,
+            };
+            if node.head < node.low {
+                node.tape.insert(0, 0);
+                node.low -= 1;
+            } else if node.head == node.low + node.tape.len() as i64 {
+                node.tape.push(0);
+            }
+        };
+
+        // Halting there writes a one, as undefined transitions do
+        let ones = node.tape.iter().filter(|&&cell| cell == 1).count() as u64;
+        let outcome = Outcome::Halted {
+            steps: node.steps as u128 + 1,
+            sigma: ones + 1 - read as u64,
+        };
+        // A machine with every transition defined can never halt
+        if node.machine.undefined() > 1 {
+            let first = node.steps == 0;
+            let mut children = Vec::new();
+            for next in 0..(node.reached + 1).min(N) {
+                for print in [Zero, One] {
+                    for motion in [Right, Left] {
+                        if first && self.mirror && matches!(motion, Left) {
+                            continue;
+                        }
+                        let mut machine = node.machine.clone();
+                        machine.transitions[node.state][read as usize] = Some(TuringStep {
+                            print,
+                            motion,
+                            next_state: Index(next),
+                        });
+                        children.push(Node {
+                            machine,
+                            tape: node.tape.clone(),
+                            low: node.low,
+                            head: node.head,
+                            state: node.state,
+                            steps: node.steps,
+                            reached: node.reached.max(next + 1),
+                        });
+                    }
+                }
+            }
+            // Taken off the end, so the first child goes last
+            self.pending.extend(children.into_iter().rev());
+        }
+        Some((node.machine, outcome))
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::VecDeque;
+
+use crate::enumerate::PartialMachine;
+use crate::models::TagConfiguration;
+use crate::models::TagEncoding;
+use crate::models::TagSystem;
+use crate::wide::WideMachine;
+use crate::wide::WideStep;
+use crate::Bit;
+use crate::Bit::*;
+
+// A universal machine: one fixed table that runs any 2-tag system written
+// on its tape, and with `TagEncoding` any Turing machine. It's nowhere near
+// the smallest known, but each state does one thing and it's easy to follow.
+//
+// The tape holds the productions, last first, then the word:
+//
+//     | P(k-1) | ... | P1 | P0 H _ _ 1 1 , 1 ,
+//
+// A symbol i in the word is i + 1 ones then a comma, and in a production
+// i + 1 a's then a b. A production that halts is just h. Deleted symbols
+// are overwritten with _, so the word drifts right as it goes.
+//
+// A step marks the ones of the first symbol one at a time as x, marking a
+// separator | as ! for each, from the right. The leftmost ! then starts the
+// production that symbol has, which is copied a cell at a time to the end
+// of the word, marking a's and b's as A's and B's so the copy knows where
+// it got to. After unmarking the table the first two symbols are deleted.
+// The run halts where the tag system would, leaving the word on the tape
+// with the first symbol's ones marked.
+pub const BLANK: u8 = 0;
+pub const ONE: u8 = 1;
+pub const MARKED_ONE: u8 = 2;
+pub const COMMA: u8 = 3;
+pub const DELETED: u8 = 4;
+pub const HEAD: u8 = 5;
+pub const SEPARATOR: u8 = 6;
+pub const MARKED_SEPARATOR: u8 = 7;
+pub const DIGIT: u8 = 8;
+pub const MARKED_DIGIT: u8 = 9;
+pub const END: u8 = 10;
+pub const MARKED_END: u8 = 11;
+pub const HALTS: u8 = 12;
+const SYMBOLS: usize = 13;
+
+const READ: usize = 0;
+const TO_TABLE: usize = 1;
+const CHECK: usize = 2;
+const FIND: usize = 3;
+const TO_PRODUCTION: usize = 4;
+const COPY: usize = 5;
+const WRITE_ONE: usize = 6;
+const WRITE_COMMA: usize = 7;
+const BACK: usize = 8;
+const UNMARK_LEFT: usize = 9;
+const UNMARK_RIGHT: usize = 10;
+const DELETE_FIRST: usize = 11;
+const DELETE_SECOND: usize = 12;
+const HALT: usize = 13;
+
+pub fn universal() -> WideMachine {
+    let mut transitions = vec![None; HALT * SYMBOLS];
+    let mut set = |state: usize, cells: &[u8], print: Option<u8>, right: bool, next: usize| {
+        for &cell in cells {
+            transitions[state * SYMBOLS + cell as usize] = Some(WideStep {
+                print: print.unwrap_or(cell),
+                right,
+                next,
+            });
+        }
+    };
+    let table = [SEPARATOR, MARKED_SEPARATOR, DIGIT, END, HALTS];
+
+    // Past the table and what's been read to the next one of the symbol
+    set(READ, &table, None, true, READ);
+    set(READ, &[HEAD, DELETED, MARKED_ONE], None, true, READ);
+    set(READ, &[ONE], Some(MARKED_ONE), false, TO_TABLE);
+    set(READ, &[COMMA], None, true, CHECK);
+    // An empty word
+    set(READ, &[BLANK], None, true, HALT);
+    set(
+        TO_TABLE,
+        &[MARKED_ONE, DELETED, HEAD],
+        None,
+        false,
+        TO_TABLE,
+    );
+    set(
+        TO_TABLE,
+        &[MARKED_SEPARATOR, DIGIT, END, HALTS],
+        None,
+        false,
+        TO_TABLE,
+    );
+    set(TO_TABLE, &[SEPARATOR], Some(MARKED_SEPARATOR), true, READ);
+
+    // A word of one symbol halts
+    set(CHECK, &[BLANK], None, false, HALT);
+    set(CHECK, &[ONE], None, false, FIND);
+    set(FIND, &[COMMA, MARKED_ONE, DELETED, HEAD], None, false, FIND);
+    set(
+        FIND,
+        &[MARKED_SEPARATOR, DIGIT, END, HALTS],
+        None,
+        false,
+        FIND,
+    );
+    set(FIND, &[SEPARATOR, BLANK], None, true, TO_PRODUCTION);
+    set(
+        TO_PRODUCTION,
+        &[DIGIT, END, HALTS],
+        None,
+        true,
+        TO_PRODUCTION,
+    );
+    set(TO_PRODUCTION, &[MARKED_SEPARATOR], None, true, COPY);
+
+    set(COPY, &[DIGIT], Some(MARKED_DIGIT), true, WRITE_ONE);
+    set(COPY, &[END], Some(MARKED_END), true, WRITE_COMMA);
+    set(COPY, &[HALTS], None, true, HALT);
+    set(COPY, &[MARKED_SEPARATOR, HEAD], None, false, UNMARK_LEFT);
+    let passing = [
+        ONE,
+        MARKED_ONE,
+        COMMA,
+        DELETED,
+        HEAD,
+        MARKED_SEPARATOR,
+        DIGIT,
+        END,
+        HALTS,
+    ];
+    for (state, print) in [(WRITE_ONE, ONE), (WRITE_COMMA, COMMA)] {
+        set(state, &passing, None, true, state);
+        set(state, &[MARKED_DIGIT, MARKED_END], None, true, state);
+        set(state, &[BLANK], Some(print), false, BACK);
+    }
+    set(BACK, &passing, None, false, BACK);
+    set(BACK, &[MARKED_DIGIT, MARKED_END], None, true, COPY);
+
+    // Out to the left end of the table and back to H
+    for (state, right) in [(UNMARK_LEFT, false), (UNMARK_RIGHT, true)] {
+        set(state, &[MARKED_SEPARATOR], Some(SEPARATOR), right, state);
+        set(state, &[MARKED_DIGIT], Some(DIGIT), right, state);
+        set(state, &[MARKED_END], Some(END), right, state);
+        set(state, &[SEPARATOR, DIGIT, END, HALTS], None, right, state);
+    }
+    set(UNMARK_LEFT, &[BLANK], None, true, UNMARK_RIGHT);
+    set(UNMARK_RIGHT, &[HEAD], None, true, DELETE_FIRST);
+    set(DELETE_FIRST, &[DELETED], None, true, DELETE_FIRST);
+    set(
+        DELETE_FIRST,
+        &[MARKED_ONE],
+        Some(DELETED),
+        true,
+        DELETE_FIRST,
+    );
+    set(DELETE_FIRST, &[COMMA], Some(DELETED), true, DELETE_SECOND);
+    set(DELETE_SECOND, &[ONE], Some(DELETED), true, DELETE_SECOND);
+    set(DELETE_SECOND, &[COMMA], Some(DELETED), true, READ);
+    WideMachine {
+        symbols: SYMBOLS,
+        transitions,
+    }
+}
+
+// The tape for the universal machine to run a tag system on a word, with
+// the head to start on the first cell
+pub fn encode_tag(system: &TagSystem, word: &VecDeque<usize>) -> VecDeque<u8> {
+    let mut tape = VecDeque::new();
+    for production in system.productions.iter().rev() {
+        tape.push_back(SEPARATOR);
+        match production {
+            Some(production) => {
+                for &symbol in production {
+                    tape.extend(std::iter::repeat_n(DIGIT, symbol + 1));
+                    tape.push_back(END);
+                }
+            }
+            None => tape.push_back(HALTS),
+        }
+    }
+    tape.push_back(HEAD);
+    for &symbol in word {
+        tape.extend(std::iter::repeat_n(ONE, symbol + 1));
+        tape.push_back(COMMA);
+    }
+    tape
+}
+
+// The word on the universal machine's tape, or None if it stopped partway
+// through writing one
+pub fn decode_tag(tape: &VecDeque<u8>) -> Option<VecDeque<usize>> {
+    let head = tape.iter().position(|&cell| cell == HEAD)?;
+    let mut word = VecDeque::new();
+    let mut ones = 0;
+    for &cell in tape.iter().skip(head + 1) {
+        match cell {
+            DELETED | BLANK => {}
+            ONE | MARKED_ONE => ones += 1,
+            COMMA if ones > 0 => {
+                word.push_back(ones - 1);
+                ones = 0;
+            }
+            _ => return None,
+        }
+    }
+    (ones == 0).then_some(word)
+}
+
+// A machine and its input, the cells from the head rightwards with the
+// rest of the tape blank, turned into the tape for the universal machine
+pub fn encode_machine<const N: usize>(
+    machine: &PartialMachine<N>,
+    input: &[Bit],
+) -> Result<(TagEncoding, VecDeque<u8>), String> {
+    // The word is as long as the number the input makes
+    if input.len() > 16 {
+        return Err(String::from(
+            "inputs of more than 16 cells make too long a word",
+        ));
+    }
+    let n = input
+        .iter()
+        .enumerate()
+        .filter(|(_, bit)| **bit == One)
+        .fold(0u64, |n, (i, _)| n | 1 << i);
+    let encoding = TagEncoding::new(machine);
+    let tape = encode_tag(&encoding.system, &encoding.word(0, 0, n));
+    Ok((encoding, tape))
+}
+
+// Where the machine being run stands, read back off the universal
+// machine's tape by way of the tag system's word
+pub fn decode_machine(encoding: &TagEncoding, tape: &VecDeque<u8>) -> Option<TagConfiguration> {
+    encoding.decode(&decode_tag(tape)?)
+}
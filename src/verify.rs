@@ -0,0 +1,162 @@
+use std::mem::size_of;
+
+use num_traits::NumCast;
+use num_traits::PrimInt;
+use num_traits::Unsigned;
+
+use crate::evolve::Random;
+use crate::get_bit;
+use crate::set_bit;
+use crate::Bit::*;
+use crate::CompiledTuringMachine;
+use crate::State::*;
+use crate::Tape;
+use crate::TapeMotion::*;
+use crate::TuringMachine;
+
+// Checks LUT entries against the machine run a step at a time. Each entry
+// checked is decoded from its index as the layout says, a state above the
+// side the word is entered from above the word, and that word is written to
+// a `Tape` with its leftmost cell in the word's highest bit. The machine is
+// then stepped with `TuringMachine::step` until the head leaves the word,
+// and the entry must say the same about the word left behind, the state
+// and the side the next word is entered from, and the steps it took. An
+// entry for a machine that halts or never leaves the word only has to be a
+// halting one, since the run hands those to the step loop.
+//
+// This shares nothing with `compile_entry` but the machine, so a mistake in
+// building or indexing the table, such as an operator precedence slip
+// splitting up the index, shows up as an entry that disagrees.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Verify {
+    All,
+    // As many entries picked at random, from the seed
+    Sample { entries: usize, seed: u64 },
+}
+
+impl Verify {
+    // Every entry for words of up to a byte, where there are at most a few
+    // thousand, and a sample for wider ones, where there can be millions
+    pub fn for_word<T>() -> Verify {
+        match size_of::<T>() {
+            1 => Verify::All,
+            _ => Verify::Sample {
+                entries: 4096,
+                seed: 1,
+            },
+        }
+    }
+}
+
+impl<const N: usize> TuringMachine<N> {
+    // As `compile`, then panics if any entry checked disagrees with the
+    // step loop
+    pub fn compile_verified<T: Unsigned + PrimInt>(
+        self,
+        verify: Verify,
+    ) -> CompiledTuringMachine<T, N> {
+        let compiled = self.compile::<T>();
+        if let Err(e) = compiled.verify(verify) {
+            panic!("{}", e);
+        }
+        compiled
+    }
+}
+
+impl<T: Unsigned + PrimInt, const N: usize> CompiledTuringMachine<T, N> {
+    // The first entry checked that disagrees with the step loop, described
+    pub fn verify(&self, verify: Verify) -> Result<(), String> {
+        let bits = size_of::<T>() * 8;
+        // Entries past these are padding, see `compile`
+        let entries = (N * 2) << bits;
+        match verify {
+            Verify::All => (0..entries).try_for_each(|i| self.verify_entry(i)),
+            Verify::Sample { entries: n, seed } => {
+                let mut random = Random(seed);
+                (0..n).try_for_each(|_| self.verify_entry(random.below(entries)))
+            }
+        }
+    }
+
+    fn verify_entry(&self, i: usize) -> Result<(), String> {
+        let bits = size_of::<T>() * 8;
+        let state = i >> (bits + 1);
+        let from_right = (i >> bits) & 1 == 1;
+        let word = i & ((1 << bits) - 1);
+
+        let mut tape = Tape::<u64>::new();
+        for cell in 0..bits {
+            tape.set(get_bit(word, bits - 1 - cell));
+            tape.move_tape(Right);
+        }
+        // Back to the leftmost cell, or the rightmost if it's entered from
+        // that side
+        for _ in 0..if from_right { 1 } else { bits } {
+            tape.move_tape(Left);
+        }
+        let mut tm = self.tm.clone();
+        tm.state = Index(state);
+        let configurations = (N * bits) << bits;
+        let mut steps = 0;
+        let inside = |tape: &Tape<u64>| (0..bits as i64).contains(&tape.head_index());
+        while let Index(state) = tm.state {
+            if steps > configurations || !inside(&tape) {
+                break;
+            }
+            tm.step(&mut tape, state);
+            steps += 1;
+        }
+        let mut left: usize = 0;
+        for cell in 0..bits {
+            if tape.cell_at(cell as i64) == One {
+                set_bit(&mut left, bits - 1 - cell, One);
+            }
+        }
+
+        let entry = &self.lut[i];
+        let found: usize = NumCast::from(entry.tape).unwrap();
+        let halts = tm.state == HALT || inside(&tape);
+        let describe = || {
+            format!(
+                "LUT entry {:#x}, state {} entered from the {} with word {:#x}",
+                i,
+                state,
+                if from_right { "right" } else { "left" },
+                word
+            )
+        };
+        if halts {
+            if entry.get_state() >= 0 {
+                return Err(format!("{} halts, but the entry doesn't", describe()));
+            }
+            return Ok(());
+        }
+        // Leaving by the left enters the next word from its right
+        let entered = match tape.head_index() < 0 {
+            true => Right,
+            false => Left,
+        };
+        let Index(next) = tm.state else {
+            unreachable!()
+        };
+        if entry.get_state() != next as i8
+            || entry.get_direction() != entered
+            || found != left
+            || self.lengths[i] as usize != steps
+        {
+            return Err(format!(
+                "{} leaves word {:#x} in state {} after {} steps, entering the next from the {:?}, but the entry says word {:#x} in state {} after {} steps, from the {:?}",
+                describe(),
+                left,
+                next,
+                steps,
+                entered,
+                found,
+                entry.get_state(),
+                self.lengths[i],
+                entry.get_direction()
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::VecDeque;
+
+use crate::standard::state_letter;
+use crate::wide::WideMachine;
+use crate::wide::WideStep;
+
+// Programs for Wang's B-machine, with the erasing and stopping of Post's
+// machine besides, one instruction to a line or separated by ';'s:
+//
+//     mark, *        prints a 1
+//     erase, e       prints a 0 (Post)
+//     right, r       moves right
+//     left, l        moves left
+//     jump N, c N    goes to instruction N if the cell holds a 1
+//     ? N M          goes to instruction N on a 0 and M on a 1 (Post)
+//     halt, stop     stops (Post)
+//
+// Instructions are numbered from 1, and anything after a '#' is a comment.
+// A program runs from its first instruction until it stops or runs off the
+// end, with every instruction that doesn't jump going on to the next.
+#[derive(Clone, Copy)]
+pub enum Wang {
+    Print(u8),
+    Move(bool),
+    // Where to go on a 0 and on a 1, from 0
+    Branch(usize, usize),
+    Halt,
+}
+
+pub fn parse_program(text: &str) -> Result<Vec<Wang>, String> {
+    let lines = text.lines().map(|line| line.split('#').next().unwrap());
+    let words: Vec<Vec<&str>> = lines
+        .flat_map(|line| line.split(';'))
+        .map(|instruction| instruction.split_whitespace().collect::<Vec<_>>())
+        .filter(|words| !words.is_empty())
+        .collect();
+    let target = |word: &str| match word.parse::<usize>() {
+        Ok(n) if (1..=words.len() + 1).contains(&n) => Ok(n - 1),
+        _ => Err(format!("no instruction {:?} to go to", word)),
+    };
+    words
+        .iter()
+        .enumerate()
+        .map(|(at, words)| {
+            let next = at + 1;
+            Ok(match words[..] {
+                ["mark" | "*"] => Wang::Print(1),
+                ["erase" | "e"] => Wang::Print(0),
+                ["right" | "r"] => Wang::Move(true),
+                ["left" | "l"] => Wang::Move(false),
+                ["jump" | "c", n] => Wang::Branch(next, target(n)?),
+                ["?", n, m] => Wang::Branch(target(n)?, target(m)?),
+                ["halt" | "stop"] => Wang::Halt,
+                _ => return Err(format!("bad instruction {:?}", words.join(" "))),
+            })
+        })
+        .collect()
+}
+
+// Runs a program directly, for up to `limit` instructions, on a tape of
+// which the head starts at the first cell. Returns the instructions run, or
+// None if it's still going.
+pub fn run_program(
+    program: &[Wang],
+    tape: &mut VecDeque<u8>,
+    head: &mut usize,
+    limit: u64,
+) -> Option<u64> {
+    let mut at = 0;
+    let mut steps = 0;
+    while at < program.len() {
+        if steps == limit {
+            return None;
+        }
+        steps += 1;
+        at = match program[at] {
+            Wang::Print(bit) => {
+                tape[*head] = bit;
+                at + 1
+            }
+            Wang::Move(true) => {
+                *head += 1;
+                if *head == tape.len() {
+                    tape.push_back(0);
+                }
+                at + 1
+            }
+            Wang::Move(false) => {
+                match *head {
+                    0 => tape.push_front(0),
+                    _ => *head -= 1,
+                }
+                at + 1
+            }
+            Wang::Branch(zero, one) => match tape[*head] {
+                0 => zero,
+                _ => one,
+            },
+            Wang::Halt => break,
+        };
+    }
+    Some(steps)
+}
+
+// Compiles a program into a Turing machine, halting by going to the state
+// past the last. A Turing machine has to move on every step, so each
+// instruction is a state that moves right, and one that doesn't mean to is
+// followed by a state for its next instruction that moves back left first.
+// A print followed by a move is done in one step, though. Stopping also
+// moves right and back, so the head stays where the program left it.
+pub fn compile(program: &[Wang]) -> WideMachine {
+    // Running off the end stops as well
+    let program: Vec<Wang> = program.iter().copied().chain([Wang::Halt]).collect();
+    // The states moving back left before each instruction, and before the
+    // halt at the end
+    let halt = program.len();
+    let mut back = vec![None; program.len() + 1];
+    let mut states = program.len();
+    let mut back_to = |to: usize| {
+        *back[to].get_or_insert_with(|| {
+            states += 1;
+            states - 1
+        })
+    };
+    let mut transitions = Vec::new();
+    for (at, &instruction) in program.iter().enumerate() {
+        // What to print (if not what was read), which way to move and where
+        // to go, for each symbol read
+        let steps = match instruction {
+            Wang::Print(bit) => match program[at + 1] {
+                Wang::Move(right) => [(Some(bit), right, at + 2); 2],
+                _ => [(Some(bit), true, back_to(at + 1)); 2],
+            },
+            Wang::Move(right) => [(None, right, at + 1); 2],
+            Wang::Branch(zero, one) => [(None, true, back_to(zero)), (None, true, back_to(one))],
+            Wang::Halt => [(None, true, back_to(halt)); 2],
+        };
+        for (read, (print, right, next)) in steps.into_iter().enumerate() {
+            transitions.push(Some(WideStep {
+                print: print.unwrap_or(read as u8),
+                right,
+                next,
+            }));
+        }
+    }
+    transitions.resize(2 * states, None);
+    for (to, state) in back.iter().enumerate() {
+        let Some(state) = *state else {
+            continue;
+        };
+        let next = if to == halt { states } else { to };
+        for read in 0..2 {
+            transitions[2 * state + read] = Some(WideStep {
+                print: read as u8,
+                right: false,
+                next,
+            });
+        }
+    }
+    WideMachine {
+        symbols: 2,
+        transitions,
+    }
+}
+
+// A compiled program in the standard format, with Z for the halt. Works
+// for any machine that halts by going to the state past the last.
+pub fn format_compiled(machine: &WideMachine) -> Result<String, String> {
+    let states = machine.transitions.len() / machine.symbols;
+    if states > 25 {
+        return Err(format!(
+            "{} states are too many for the standard format",
+            states
+        ));
+    }
+    let mut s = String::new();
+    for (i, step) in machine.transitions.iter().enumerate() {
+        if i > 0 && i % machine.symbols == 0 {
+            s.push('_');
+        }
+        match step {
+            Some(step) => {
+                s += &format!(
+                    "{}{}{}",
+                    step.print,
+                    if step.right { 'R' } else { 'L' },
+                    match step.next {
+                        next if next == states => 'Z',
+                        next => state_letter(next),
+                    }
+                )
+            }
+            None => s += "---",
+        }
+    }
+    Ok(s)
+}
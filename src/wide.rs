@@ -0,0 +1,173 @@
+use std::fmt::Display;
+
+use crate::search::Outcome;
+use crate::standard::state_letter;
+
+// Machines over more than two symbols, with the numbers of states and
+// symbols only known at run time. The rest of the crate works on bits, so
+// these are only enumerated, by `WideTree`, and scored.
+#[derive(Clone)]
+pub struct WideMachine {
+    pub symbols: usize,
+    // Indexed by state * symbols + symbol read
+    pub transitions: Vec<Option<WideStep>>,
+}
+
+#[derive(Clone, Copy)]
+pub struct WideStep {
+    pub print: u8,
+    pub right: bool,
+    pub next: usize,
+}
+
+impl WideMachine {
+    // With the undefined transitions written as halting ones, 1RZ, the way
+    // they are run
+    pub fn halting(&self) -> String {
+        self.format("1RZ")
+    }
+
+    fn format(&self, undefined: &str) -> String {
+        let mut s = String::new();
+        for (i, step) in self.transitions.iter().enumerate() {
+            if i > 0 && i % self.symbols == 0 {
+                s.push('_');
+            }
+            match step {
+                Some(step) => {
+                    s += &format!(
+                        "{}{}{}",
+                        step.print,
+                        if step.right { 'R' } else { 'L' },
+                        state_letter(step.next)
+                    )
+                }
+                None => s += undefined,
+            }
+        }
+        s
+    }
+}
+
+impl Display for WideMachine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.format("---"))
+    }
+}
+
+// The same enumeration as `tree::SimulatedTree`: each machine runs until it
+// needs an undefined transition, which is then branched on. Besides states,
+// symbols are also numbered in the order they're first written, so that
+// machines that only differ by exchanging non-blank symbols come out once.
+pub struct WideTree {
+    states: usize,
+    symbols: usize,
+    limit: u64,
+    mirror: bool,
+    pending: Vec<Node>,
+}
+
+struct Node {
+    machine: WideMachine,
+    tape: Vec<u8>,
+    low: i64,
+    head: i64,
+    state: usize,
+    steps: u64,
+    reached: usize,
+    // One more than the largest symbol written so far
+    written: usize,
+}
+
+impl WideTree {
+    pub fn new(states: usize, symbols: usize, limit: u64, mirror: bool) -> WideTree {
+        WideTree {
+            states,
+            symbols,
+            limit,
+            mirror,
+            pending: vec![Node {
+                machine: WideMachine {
+                    symbols,
+                    transitions: vec![None; states * symbols],
+                },
+                tape: vec![0],
+                low: 0,
+                head: 0,
+                state: 0,
+                steps: 0,
+                reached: 1,
+                written: 1,
+            }],
+        }
+    }
+}
+
+impl Iterator for WideTree {
+    type Item = (WideMachine, Outcome);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut node = self.pending.pop()?;
+        let read = loop {
+            if node.steps == self.limit {
+                return Some((node.machine, Outcome::Undecided));
+            }
+            let i = (node.head - node.low) as usize;
+            let read = node.tape[i];
+            let Some(step) = node.machine.transitions[node.state * self.symbols + read as usize]
+            else {
+                break read;
+            };
+            node.tape[i] = step.print;
+            node.state = step.next;
+            node.steps += 1;
+            node.head += if step.right { 1 } else { -1 };
+            if node.head < node.low {
+                node.tape.insert(0, 0);
+                node.low -= 1;
+            } else if node.head == node.low + node.tape.len() as i64 {
+                node.tape.push(0);
+            }
+        };
+
+        // Halting writes a 1, so sigma counts the non-blank symbols after it
+        let non_blank = node.tape.iter().filter(|&&cell| cell != 0).count() as u64;
+        let outcome = Outcome::Halted {
+            steps: node.steps as u128 + 1,
+            sigma: non_blank + (read == 0) as u64,
+        };
+        let undefined = node.machine.transitions.iter().filter(|t| t.is_none());
+        if undefined.count() > 1 {
+            let first = node.steps == 0;
+            let mut children = Vec::new();
+            for next in 0..(node.reached + 1).min(self.states) {
+                for print in 0..(node.written + 1).min(self.symbols) {
+                    for right in [true, false] {
+                        if first && self.mirror && !right {
+                            continue;
+                        }
+                        let mut machine = node.machine.clone();
+                        machine.transitions[node.state * self.symbols + read as usize] =
+                            Some(WideStep {
+                                print: print as u8,
+                                right,
+                                next,
+                            });
+                        children.push(Node {
+                            machine,
+                            tape: node.tape.clone(),
+                            low: node.low,
+                            head: node.head,
+                            state: node.state,
+                            steps: node.steps,
+                            reached: node.reached.max(next + 1),
+                            written: node.written.max(print + 1),
+                        });
+                    }
+                }
+            }
+            self.pending.extend(children.into_iter().rev());
+        }
+        Some((node.machine, outcome))
+    }
+}